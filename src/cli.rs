@@ -1,21 +1,128 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
 
 use anyhow::{bail, Context};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read, Seek, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
-use tlparse::{parse_path, ParseConfig};
+use tlparse::{
+    diff_logs, diff_output_dirs, entry_value, json_schemas, parse_entries,
+    parse_entries_from_reader, parse_path, parse_path_incremental, parse_path_with_stats,
+    parse_reader_with_stats, render_checkpoint, render_single_file_report, serve_report,
+    write_outputs, Baseline, DirectoryWriter, KnownIssue, LinkRewrite, LogListing, OutputWriter,
+    ParseCheckpoint, ParseConfig, ParseOutput, ParseResult, ParsedEnvelope, ProgressSink, Query,
+    Stats, ZipWriter, DEFAULT_METRICS_COLUMNS, DEFAULT_RECOMPILE_STORM_THRESHOLD,
+    DEFAULT_RECOMPILE_STORM_WINDOW_S, DEFAULT_REGRESSION_THRESHOLD_PCT, DEFAULT_RENDER_BUDGET_S,
+};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FailOn {
+    /// A parser failed on an entry (including unparseable guards)
+    Parser,
+    /// A payload did not match its md5
+    Md5,
+    /// A line was not a glog line, or its JSON metadata did not parse
+    Malformed,
+    /// An entry was not recognized by any parser
+    Unknown,
+    /// A frame regressed from --baseline
+    Regression,
+    /// Never fail because of the log contents
+    None,
+}
+
+impl FailOn {
+    fn count(self, output: &ParseOutput) -> u64 {
+        let stats = &output.stats;
+        match self {
+            FailOn::Parser => stats.fail_parser + stats.fail_dynamo_guards_json,
+            FailOn::Md5 => stats.fail_payload_md5,
+            FailOn::Malformed => stats.fail_glog + stats.fail_json,
+            FailOn::Unknown => stats.unknown,
+            FailOn::Regression => output.regressions.len() as u64,
+            FailOn::None => 0,
+        }
+    }
+}
+
+// Exit code when the report was written, but the log had problems selected by --fail-on
+const EXIT_FAIL_ON: i32 = 2;
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the JSON Schema of the structured log entries tlparse reads, and of the JSON files it
+    /// writes
+    Schema {
+        /// Only print this schema (e.g., envelope), instead of all of them keyed by name
+        name: Option<String>,
+    },
+    /// Compare two report directories written by tlparse, listing the outputs that were added,
+    /// removed or changed for each compile id.  Exits with 1 if there are any differences
+    DiffOut { old: PathBuf, new: PathBuf },
+    /// Compare the runs in two logs, e.g., before and after a change when bisecting: the compile
+    /// ids added and removed, the compile time of each frame, the guard counts, and new failures.
+    /// Exits with 1 if there are any differences
+    Diff { old: PathBuf, new: PathBuf },
+    /// Parse a log and serve the report over HTTP instead of writing it out, e.g., to view it
+    /// through port forwarding from a remote host.  A report directory is served as is
+    Serve {
+        /// Log to parse, or a report directory written by tlparse
+        path: PathBuf,
+        #[arg(long, default_value_t = 8000)]
+        port: u16,
+        /// Address to listen on.  The default only accepts connections from this host, which
+        /// includes those forwarded with ssh -L
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// Print what a jq-like query selects from each entry of a log, as JSON lines, e.g.,
+    /// 'compilation_metrics | select(.fail_type) | .fail_reason'.  Stages are separated by |: a
+    /// name keeps the entries with that field and gives its value, a path like .a.b[0] or .a[]
+    /// gives what it leads to, and select(PATH) or select(PATH OP LITERAL) (with ==, !=, <, <=, >
+    /// or >= and a JSON literal) keeps what the path is true for.  Entries have their metadata
+    /// (with the compile id as frame_id, frame_compile_id and attempt), lineno and payload
+    Query {
+        query: String,
+        /// Log to query, or - to read it from stdin
+        path: PathBuf,
+    },
+    /// Render a report again from the checkpoint that --incremental or --watch saved, without
+    /// reparsing the log, e.g., after upgrading tlparse.  The report is rewritten in place
+    Render {
+        /// The checkpoint, or the report directory it is in
+        #[arg(long)]
+        from_json: PathBuf,
+    },
+}
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Log to parse (possibly .gz or .zst compressed), - to read it from stdin, or a directory of
+    /// per-rank logs (e.g., TORCH_TRACE) to merge into one report
+    #[arg(required = true)]
+    path: Option<PathBuf>,
     /// Parse most recent log
     #[arg(long)]
     latest: bool,
-    /// Output directory, defaults to `tl_out`
-    #[arg(short, default_value = "tl_out")]
+    /// Output directory, defaults to `tl_out`.  With a .zip extension, the report is written into a
+    /// single zip archive instead, e.g., for sharing
+    #[arg(short, long, default_value = "tl_out")]
     out: PathBuf,
     /// Delete out directory if it already exists
     #[arg(long)]
@@ -31,6 +138,17 @@ pub struct Cli {
     /// Don't open browser at the end
     #[arg(long)]
     no_browser: bool,
+    /// Print the file:// URL of the report on stdout, and nothing else, instead of opening a
+    /// browser.  For scripts
+    #[arg(long)]
+    print_url_only: bool,
+    /// Serve the report over HTTP once it is written, as tlparse serve does, instead of opening a
+    /// browser
+    #[arg(long)]
+    serve: bool,
+    /// Port to serve the report on with --serve
+    #[arg(long, default_value_t = 8000)]
+    port: u16,
     /// Some custom HTML to append to the top of report
     #[arg(long, default_value = "")]
     custom_header_html: String,
@@ -41,15 +159,444 @@ pub struct Cli {
     /// Enabiling this option will enforce output as plain text for easier diffing
     #[arg(short, long)]
     plain_text: bool,
+    /// Seconds highlighting a single output (e.g., the code of an enormous graph) may take before
+    /// it is written as plain text instead, with a warning, to keep the whole report's time bounded
+    #[arg(long, default_value_t = DEFAULT_RENDER_BUDGET_S)]
+    render_budget_s: f64,
     /// For export specific logs
     #[arg(short, long)]
     export: bool,
+    /// Split the build products listing in index.html into one page per frame when there are
+    /// more compile ids than this
+    #[arg(long, default_value_t = 1000)]
+    max_inline_compile_ids: usize,
+    /// Write every output except the top level index.html gzip compressed, with a .gz extension.
+    /// Links are left unchanged, so serve the report with something that transparently maps
+    /// foo.html to foo.html.gz (e.g., nginx gzip_static, or object storage Content-Encoding)
+    #[arg(long)]
+    gzip_output: bool,
+    /// Write the whole report as a single self-contained index.html, e.g., for attaching to a bug
+    #[arg(long)]
+    single_file: bool,
+    /// Save a checkpoint in the output directory, and if a checkpoint for the same log is already
+    /// there, only parse what has been appended to the log since, and only write its outputs
+    /// along with fresh summary pages.  Useful for a log that is still being written
+    #[arg(long)]
+    incremental: bool,
+    /// Keep following the log as it is written, e.g., by a training run, and update the report
+    /// whenever it grows, only parsing what was appended (as with --incremental).  Reload the page,
+    /// or serve it with --serve, to see the updates
+    #[arg(long)]
+    watch: bool,
+    /// How often to check whether the log has grown with --watch
+    #[arg(long, default_value_t = 5.0)]
+    watch_interval_s: f64,
+    /// Stop watching after updating the report this many times, counting the first, instead of
+    /// when stopped with Ctrl-C
+    #[arg(long)]
+    watch_rounds: Option<usize>,
+    /// Once the report adds up to more than this many megabytes, warn and stop writing low value
+    /// outputs (generic artifacts and file dumps)
+    #[arg(long)]
+    output_budget_mb: Option<u64>,
+    /// Parse the log and print statistics, but don't write any output.  Useful for checking
+    /// whether a huge log is worth rendering
+    #[arg(long)]
+    parse_only: bool,
+    /// List the entries of every compile id by kind, with the size of their payloads, without
+    /// rendering or writing anything, e.g., to pick filters like --max-compiles for a huge log
+    #[arg(long)]
+    list: bool,
+    /// Comma separated kinds of problems in the log that make the exit code non-zero (2), after
+    /// the report has been written anyway.  Unlike --strict, this does not stop the report from
+    /// being generated
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "none")]
+    fail_on: Vec<FailOn>,
+    /// Link the frame each compilation is for to its source, e.g.
+    /// https://github.com/pytorch/pytorch/blob/main/{filename}#L{line}
+    #[arg(long)]
+    source_url_template: Option<String>,
+    /// Warn at the top of index.html about frames that recompiled more than this many times
+    /// within --recompile-storm-window-s seconds
+    #[arg(long, default_value_t = DEFAULT_RECOMPILE_STORM_THRESHOLD)]
+    recompile_storm_threshold: usize,
+    /// Window for --recompile-storm-threshold, in seconds
+    #[arg(long, default_value_t = DEFAULT_RECOMPILE_STORM_WINDOW_S)]
+    recompile_storm_window_s: f64,
+    /// Write the compile time and graph count of every frame to this JSON file, e.g., from a known
+    /// good run in CI, for later runs to be checked against with --baseline
+    #[arg(long)]
+    emit_baseline: Option<PathBuf>,
+    /// Check the compile time and graph count of every frame against a file written by
+    /// --emit-baseline, and flag the frames that grew by more than --regression-threshold-pct at
+    /// the top of index.html.  Add regression to --fail-on to also fail on them
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// How much a frame's compile time or graph count may grow over --baseline, in percent, before
+    /// it is flagged
+    #[arg(long, default_value_t = DEFAULT_REGRESSION_THRESHOLD_PCT)]
+    regression_threshold_pct: f64,
+    /// In the build products listing, collapse the artifacts of compilation attempts that were
+    /// restarted (e.g., by a graph break) behind a toggle
+    #[arg(long)]
+    hide_abandoned_attempts: bool,
+    /// Comma separated CompilationMetrics fields to show in the metrics table of index.html and
+    /// in compilation_metrics.csv, e.g., guard_count,cache_size
+    #[arg(long, value_delimiter = ',', default_values = DEFAULT_METRICS_COLUMNS)]
+    metrics_columns: Vec<String>,
+    /// Don't check payloads against their MD5, which speeds up parsing huge logs.  Corrupted
+    /// payloads are then not counted in the stats, so this can't be combined with --fail-on md5
+    #[arg(long)]
+    no_verify_payload: bool,
+    /// Only check the MD5 of payloads of at least this many bytes.  Most of the hashing time goes
+    /// to big payloads, which are also the ones a crash is most likely to cut off
+    #[arg(long)]
+    verify_payload_min_bytes: Option<usize>,
+    /// Also write parsed.json, with the compile ids, metrics, guards, artifacts and failures of the
+    /// run, for post-processing from scripts
+    #[arg(long)]
+    json: bool,
+    /// Only render the artifacts of the first N compile ids, for a quick report on huge logs.  The
+    /// stack trie, failures, and metrics based summaries still cover every compilation
+    #[arg(long)]
+    max_compiles: Option<usize>,
+    /// Parse every rank in the log into its own report under rank_{rank}/, with an index.html
+    /// merging their compile ids and failures, instead of only the first rank seen
+    #[arg(long)]
+    all_ranks: bool,
+    /// Read the log twice: first for the entries that other entries refer to (file names, stacks,
+    /// specializations, and guards), then to render, so that nothing logged later is missing, e.g.,
+    /// the file names of stack frames in logs that were concatenated out of order
+    #[arg(long, alias = "prescan-interned-strings")]
+    two_pass: bool,
+    /// Leave frames whose file name matches this regex out of the stacks in the report (e.g.,
+    /// 'torch/_dynamo/.*'), to see only the model's own code.  Can be repeated.  Exports, e.g.,
+    /// --json, still have every frame
+    #[arg(long)]
+    hide_frames: Vec<String>,
+    /// Show at most this many frames in a row in stacks, with the outer ones behind a "N more
+    /// frames" expander, for stacks that are hundreds of frames deep
+    #[arg(long)]
+    max_stack_depth: Option<usize>,
+    /// Label each frame of the stack trie with the compile time of the compilations under it (and
+    /// its share of the total), and list the heaviest branches first
+    #[arg(long)]
+    stack_trie_times: bool,
+    /// Rewrite the URLs of links in the log and in --custom-header-html matching the regex PATTERN
+    /// with REPLACEMENT (which can refer to capture groups as $1), e.g., to map internal URLs to a
+    /// proxy when the report is viewed from outside.  Can be repeated; rewrites apply in order
+    #[arg(long, num_args = 2, value_names = ["PATTERN", "REPLACEMENT"])]
+    rewrite_link: Vec<String>,
+    /// TOML file with defaults for the options above, keyed by their long names with underscores
+    /// (e.g., strict_compile_id = true).  Options given on the command line take precedence.
+    /// Defaults to tlparse.toml in the current directory, if there is one
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+const DEFAULT_CONFIG_PATH: &str = "tlparse.toml";
+
+// Defaults for command line options, read from a config file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    latest: Option<bool>,
+    out: Option<PathBuf>,
+    overwrite: Option<bool>,
+    strict: Option<bool>,
+    strict_compile_id: Option<bool>,
+    no_browser: Option<bool>,
+    print_url_only: Option<bool>,
+    serve: Option<bool>,
+    port: Option<u16>,
+    custom_header_html: Option<String>,
+    verbose: Option<bool>,
+    plain_text: Option<bool>,
+    render_budget_s: Option<f64>,
+    export: Option<bool>,
+    max_inline_compile_ids: Option<usize>,
+    gzip_output: Option<bool>,
+    single_file: Option<bool>,
+    incremental: Option<bool>,
+    watch: Option<bool>,
+    watch_interval_s: Option<f64>,
+    watch_rounds: Option<usize>,
+    output_budget_mb: Option<u64>,
+    parse_only: Option<bool>,
+    list: Option<bool>,
+    fail_on: Option<Vec<FailOn>>,
+    source_url_template: Option<String>,
+    recompile_storm_threshold: Option<usize>,
+    recompile_storm_window_s: Option<f64>,
+    emit_baseline: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    regression_threshold_pct: Option<f64>,
+    hide_abandoned_attempts: Option<bool>,
+    metrics_columns: Option<Vec<String>>,
+    no_verify_payload: Option<bool>,
+    verify_payload_min_bytes: Option<usize>,
+    json: Option<bool>,
+    max_compiles: Option<usize>,
+    all_ranks: Option<bool>,
+    #[serde(alias = "prescan_interned_strings")]
+    two_pass: Option<bool>,
+    hide_frames: Option<Vec<String>>,
+    max_stack_depth: Option<usize>,
+    stack_trie_times: Option<bool>,
+    // Pairs of pattern and replacement, as with --rewrite-link
+    rewrite_link: Option<Vec<String>>,
+    // Only settable here, e.g., [[known_issues]] tables with pattern, explanation, and optionally
+    // suggestion and link
+    known_issues: Option<Vec<KnownIssue>>,
+    // Also only settable here, as [[link_rewrites]] tables with pattern and replacement, applied
+    // after those given with --rewrite-link
+    link_rewrites: Option<Vec<LinkRewrite>>,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read config file {}", path.display()))?;
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Couldn't parse config file {}", path.display()))?;
+        if config
+            .rewrite_link
+            .as_ref()
+            .is_some_and(|r| r.len() % 2 != 0)
+        {
+            bail!(
+                "rewrite_link in config file {} needs a replacement for every pattern",
+                path.display()
+            );
+        }
+        Ok(config)
+    }
+
+    // Fill in the options that weren't given on the command line
+    fn apply(self, cli: &mut Cli, matches: &ArgMatches) {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        // Optional options take the value as is, through Into<Option<T>>
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(if let Some(value) = self.$field {
+                    if unset(stringify!($field)) {
+                        cli.$field = value.into();
+                    }
+                })*
+            };
+        }
+        apply!(
+            latest,
+            out,
+            overwrite,
+            strict,
+            strict_compile_id,
+            no_browser,
+            print_url_only,
+            serve,
+            port,
+            custom_header_html,
+            verbose,
+            plain_text,
+            render_budget_s,
+            export,
+            max_inline_compile_ids,
+            gzip_output,
+            single_file,
+            incremental,
+            watch,
+            watch_interval_s,
+            watch_rounds,
+            output_budget_mb,
+            parse_only,
+            list,
+            fail_on,
+            source_url_template,
+            recompile_storm_threshold,
+            recompile_storm_window_s,
+            emit_baseline,
+            baseline,
+            regression_threshold_pct,
+            hide_abandoned_attempts,
+            metrics_columns,
+            no_verify_payload,
+            verify_payload_min_bytes,
+            json,
+            max_compiles,
+            all_ranks,
+            two_pass,
+            hide_frames,
+            max_stack_depth,
+            stack_trie_times,
+            rewrite_link
+        );
+    }
+}
+
+// A progress bar for the bytes parsed and a spinner with the parse statistics, with warnings
+// printed above them
+#[derive(Default)]
+struct ProgressBars {
+    multi: MultiProgress,
+    bars: RefCell<Option<(ProgressBar, ProgressBar)>>,
+}
+
+impl ProgressSink for ProgressBars {
+    fn start(&self, size: Option<u64>) {
+        let bar = self
+            .multi
+            .add(size.map_or_else(ProgressBar::hidden, ProgressBar::new));
+        bar.set_style(ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} [{bytes_per_sec}] ({eta})")
+            .unwrap()
+            .progress_chars("#>-"));
+        let spinner = self.multi.add(ProgressBar::new_spinner());
+        *self.bars.borrow_mut() = Some((bar, spinner));
+    }
+
+    fn bytes_read(&self, bytes: u64) {
+        if let Some((bar, _)) = &*self.bars.borrow() {
+            bar.set_position(bytes);
+        }
+    }
+
+    fn stats(&self, stats: &Stats) {
+        if let Some((_, spinner)) = &*self.bars.borrow() {
+            spinner.set_message(format!("{:?}", stats));
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        self.multi.suspend(|| eprintln!("{message}"));
+    }
+
+    fn finish(&self, stats: &Stats) {
+        if let Some((bar, spinner)) = self.bars.borrow_mut().take() {
+            bar.finish_with_message("done");
+            spinner.finish();
+        }
+        eprintln!("{:?}", stats);
+    }
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    let config_path = cli
+        .config
+        .clone()
+        .or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_PATH)).filter(|default| default.is_file()));
+    let mut known_issues = Vec::new();
+    let mut config_link_rewrites = Vec::new();
+    if let Some(config_path) = config_path {
+        let mut config_file = ConfigFile::load(&config_path)?;
+        known_issues = config_file.known_issues.take().unwrap_or_default();
+        config_link_rewrites = config_file.link_rewrites.take().unwrap_or_default();
+        config_file.apply(&mut cli, &matches);
+    }
+    if let Some(Command::DiffOut { old, new }) = &cli.command {
+        let diff = diff_output_dirs(old, new)?;
+        print!("{}", diff.summary());
+        if !diff.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Diff { old, new }) = &cli.command {
+        let diff = diff_logs(old, new)?;
+        print!("{}", diff.summary());
+        if !diff.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Query { query, path }) = &cli.command {
+        let query = Query::parse(query)?;
+        let entries: Box<dyn Iterator<Item = ParsedEnvelope>> = if path == Path::new("-") {
+            Box::new(parse_entries_from_reader(io::stdin().lock()))
+        } else {
+            Box::new(parse_entries(path)?)
+        };
+        let mut stdout = io::stdout().lock();
+        for entry in entries {
+            for value in query.run(entry_value(&entry)) {
+                match writeln!(stdout, "{}", serde_json::to_string(&value)?) {
+                    // E.g., piped into head
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                    result => result?,
+                }
+            }
+        }
+        return Ok(());
+    }
+    if let Some(Command::Serve { path, port, host }) = &cli.command {
+        let listener = bind(host, *port)?;
+        if path.join("index.html").is_file() {
+            return serve_directory(listener, path);
+        }
+        let config = ParseConfig {
+            progress: Some(Rc::new(ProgressBars::default())),
+            ..Default::default()
+        };
+        let files: HashMap<PathBuf, String> = parse_path(path, config)?.into_iter().collect();
+        eprintln!("Serving the report at http://{}/", listener.local_addr()?);
+        return serve_report(listener, |file| {
+            files.get(file).map(|contents| contents.as_bytes().to_vec())
+        });
+    }
+    if let Some(Command::Render { from_json }) = &cli.command {
+        let checkpoint_path = if from_json.is_dir() {
+            from_json.join(".tlparse_checkpoint.json")
+        } else {
+            from_json.clone()
+        };
+        let checkpoint: ParseCheckpoint = serde_json::from_slice(&fs::read(&checkpoint_path)?)
+            .map_err(|e| {
+                anyhow::anyhow!("{} isn't a checkpoint: {e}", checkpoint_path.display())
+            })?;
+        let out_path = checkpoint_path.parent().unwrap_or(Path::new("."));
+        let config = ParseConfig {
+            known_issues,
+            link_rewrites: config_link_rewrites,
+            ..Default::default()
+        };
+        let output = render_checkpoint(checkpoint, config, || {
+            fs::read_to_string(out_path.join("raw.log"))
+        })?;
+        write_outputs(
+            &mut DirectoryWriter::new(out_path.to_path_buf(), false)?,
+            output.files,
+        )?;
+        eprintln!("Rendered the report in {}", out_path.display());
+        return Ok(());
+    }
+    if let Some(Command::Schema { name }) = cli.command {
+        let schemas = json_schemas();
+        let json = match name {
+            Some(name) => {
+                let Some((_, schema)) = schemas.iter().find(|(n, _)| *n == name) else {
+                    let names: Vec<_> = schemas.iter().map(|(n, _)| *n).collect();
+                    bail!(
+                        "Unknown schema {name}, expected one of {}",
+                        names.join(", ")
+                    );
+                };
+                serde_json::to_string_pretty(schema)?
+            }
+            None => serde_json::to_string_pretty(
+                &schemas
+                    .into_iter()
+                    .map(|(n, schema)| (n.to_string(), schema))
+                    .collect::<serde_json::Map<_, _>>(),
+            )?,
+        };
+        println!("{json}");
+        return Ok(());
+    }
+    // Required unless there is a subcommand
+    let cli_path = cli.path.take().unwrap();
     let path = if cli.latest {
-        let input_path = cli.path;
+        let input_path = cli_path;
         // Path should be a directory
         if !input_path.is_dir() {
             bail!(
@@ -69,44 +616,459 @@ fn main() -> anyhow::Result<()> {
         };
         last_modified_file.path()
     } else {
-        cli.path
+        cli_path
     };
 
-    let out_path = cli.out;
+    // The log is piped in, e.g., from ssh or a fetch
+    let stdin = path == Path::new("-");
+    if cli.watch {
+        if stdin {
+            bail!("--watch needs a log file to follow, not stdin");
+        }
+        if path.is_dir() {
+            bail!("--watch needs a log file to follow, not a directory");
+        }
+        if cli.out.extension().is_some_and(|e| e == "zip") {
+            bail!("--watch updates the report in place, so it can't write a zip archive");
+        }
+        if cli.parse_only {
+            bail!("--watch updates the report, so it can't be used with --parse-only");
+        }
+        cli.incremental = true;
+    } else if cli.watch_rounds.is_some() {
+        bail!("--watch-rounds only applies with --watch");
+    }
+    if stdin && cli.incremental {
+        bail!("--incremental needs a log file to resume, not stdin");
+    }
+    let parse = |config| {
+        if stdin {
+            parse_reader_with_stats(io::stdin().lock(), config)
+        } else {
+            parse_path_with_stats(&path, config)
+        }
+    };
+
+    let out_path = cli.out.clone();
+    let zip_output = out_path.extension().is_some_and(|e| e == "zip");
+    if zip_output && cli.incremental {
+        bail!("--incremental keeps its checkpoint in the output directory, so it can't write a zip archive");
+    }
+    if zip_output && cli.gzip_output {
+        bail!("--gzip-output can't be used with a zip archive, which is compressed already");
+    }
+    if cli.serve && (zip_output || cli.gzip_output) {
+        bail!("--serve serves the files of the output directory as is, so it can't be used with a zip archive or --gzip-output");
+    }
+
+    if cli.no_verify_payload && cli.fail_on.contains(&FailOn::Md5) {
+        bail!("--fail-on md5 needs payloads to be verified, so it can't be used with --no-verify-payload");
+    }
+    if cli.fail_on.contains(&FailOn::Regression) && cli.baseline.is_none() {
+        bail!("--fail-on regression needs a --baseline to check against");
+    }
+    if cli.all_ranks && cli.emit_baseline.is_some() {
+        bail!("--emit-baseline records a single run, so it can't be used with --all-ranks");
+    }
+    let baseline: Option<Baseline> = match &cli.baseline {
+        Some(path) => Some(
+            serde_json::from_slice(&fs::read(path)?)
+                .with_context(|| format!("Couldn't read baseline {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    if cli.list {
+        if path.is_dir() {
+            bail!("--list reads a single log, not a directory of them");
+        }
+        let listing = if stdin {
+            LogListing::new(parse_entries_from_reader(io::stdin().lock()))
+        } else {
+            LogListing::new(parse_entries(&path)?)
+        };
+        print!("{}", listing.summary());
+        return Ok(());
+    }
+    if cli.parse_only {
+        let config = ParseConfig {
+            strict: cli.strict,
+            strict_compile_id: cli.strict_compile_id,
+            verbose: cli.verbose,
+            plain_text: cli.plain_text,
+            render_budget_s: Some(cli.render_budget_s),
+            export: cli.export,
+            no_verify_payload: cli.no_verify_payload,
+            verify_payload_min_bytes: cli.verify_payload_min_bytes,
+            baseline: baseline.clone(),
+            regression_threshold_pct: Some(cli.regression_threshold_pct),
+            progress: Some(Rc::new(ProgressBars::default())),
+            ..Default::default()
+        };
+        // parse_path already prints the parse statistics
+        let ParseResult { output, .. } = parse(config)?;
+        let total: usize = output
+            .files
+            .iter()
+            .map(|(_, contents)| contents.len())
+            .sum();
+        eprintln!(
+            "Would write {} files, {:.1} MiB in total",
+            output.files.len(),
+            total as f64 / (1024.0 * 1024.0)
+        );
+        emit_baseline(&cli, &output)?;
+        exit_for_fail_on(&cli.fail_on, &output);
+        return Ok(());
+    }
+    let checkpoint_name = ".tlparse_checkpoint.json";
+    // While watching, each round writes the report next to the one that is shown, and swaps it in
+    // once done, so that the report never disappears or is seen half written
+    let staging_path = cli
+        .watch
+        .then(|| sibling_path(&out_path, "tlparse_staging"));
+    let report_path = staging_path.as_ref().unwrap_or(&out_path);
+    let link_rewrites: Vec<LinkRewrite> = cli
+        .rewrite_link
+        .chunks(2)
+        .map(|r| LinkRewrite {
+            pattern: r[0].clone(),
+            replacement: r[1].clone(),
+        })
+        .chain(config_link_rewrites)
+        .collect();
+    let mut rounds = 0;
+    loop {
+        let first_round = rounds == 0;
+        let checkpoint = if cli.incremental {
+            fs::read(out_path.join(checkpoint_name))
+                .ok()
+                .and_then(|s| serde_json::from_slice::<ParseCheckpoint>(&s).ok())
+                .filter(|c| c.matches(&path).unwrap_or(false))
+        } else {
+            None
+        };
+
+        // When resuming, the old contents are overwritten in place
+        if checkpoint.is_none() && out_path.exists() {
+            if !first_round {
+                eprintln!("Parsing {} from the start", path.display());
+            } else if !cli.overwrite {
+                bail!(
+                    "{} {} already exists, use -o OUTDIR to write to another location or pass --overwrite to overwrite the old contents",
+                    if out_path.is_dir() { "Directory" } else { "File" },
+                    out_path.display()
+                );
+            }
+            if !cli.watch {
+                remove_path(&out_path)?;
+            }
+        }
+        if let Some(staging_path) = &staging_path {
+            // E.g., left by a round that failed
+            if staging_path.exists() {
+                remove_path(staging_path)?;
+            }
+            // Resuming only writes what changed, so start from the report so far
+            if checkpoint.is_some() {
+                link_report(&out_path, staging_path)?;
+            }
+        }
+
+        // Outputs are written on another thread as they are completed, except for a single file
+        // report, which needs all of them at once
+        let mut output_writer: Option<Box<dyn OutputWriter>> = Some(if zip_output {
+            Box::new(ZipWriter::new(&out_path)?)
+        } else {
+            Box::new(DirectoryWriter::new(report_path.clone(), cli.gzip_output)?)
+        });
+        let (output_sink, writer) = if cli.single_file {
+            (None, None)
+        } else {
+            let (sender, receiver) = mpsc::channel::<(PathBuf, String)>();
+            let mut output_writer = output_writer.take().unwrap();
+            let writer = thread::spawn(move || write_outputs(output_writer.as_mut(), receiver));
+            (Some(sender), Some(writer))
+        };
+
+        let config = ParseConfig {
+            strict: cli.strict,
+            strict_compile_id: cli.strict_compile_id,
+            custom_parsers: Vec::new(),
+            custom_header_html: cli.custom_header_html.clone(),
+            verbose: cli.verbose,
+            plain_text: cli.plain_text,
+            render_budget_s: Some(cli.render_budget_s),
+            export: cli.export,
+            max_inline_compile_ids: Some(cli.max_inline_compile_ids),
+            output_budget: cli.output_budget_mb.map(|mb| mb * 1024 * 1024),
+            source_url_template: cli.source_url_template.clone(),
+            known_issues: known_issues.clone(),
+            link_rewrites: link_rewrites.clone(),
+            recompile_storm_threshold: Some(cli.recompile_storm_threshold),
+            recompile_storm_window_s: Some(cli.recompile_storm_window_s),
+            baseline: baseline.clone(),
+            regression_threshold_pct: Some(cli.regression_threshold_pct),
+            output_sink,
+            hide_abandoned_attempts: cli.hide_abandoned_attempts,
+            metrics_columns: Some(cli.metrics_columns.clone()),
+            no_verify_payload: cli.no_verify_payload,
+            verify_payload_min_bytes: cli.verify_payload_min_bytes,
+            json: cli.json,
+            max_compiles: cli.max_compiles,
+            all_ranks: cli.all_ranks,
+            two_pass: cli.two_pass,
+            hide_frames: cli.hide_frames.clone(),
+            max_stack_depth: cli.max_stack_depth,
+            stack_trie_times: cli.stack_trie_times,
+            progress: Some(Rc::new(ProgressBars::default())),
+        };
+
+        let result = if cli.incremental {
+            parse_path_incremental(&path, config, checkpoint)
+        } else {
+            parse(config)
+        };
+        // The sink was dropped along with the config, so the writer finishes once it has caught
+        // up.  Wait for it even if parsing failed, so that what was written isn't left half done
+        if let Some(writer) = writer {
+            writer
+                .join()
+                .map_err(|_| anyhow::anyhow!("Output writer thread panicked"))??;
+        }
+        let ParseResult {
+            output,
+            checkpoint: new_checkpoint,
+            ..
+        } = result?;
+        // Left for a single file report
+        if let Some(mut output_writer) = output_writer {
+            let report = render_single_file_report(&output.files)?;
+            write_outputs(
+                output_writer.as_mut(),
+                [(PathBuf::from("index.html"), report)],
+            )?;
+        }
+
+        emit_baseline(&cli, &output)?;
+
+        if let Some(checkpoint) = &new_checkpoint {
+            let checkpoint_path = report_path.join(checkpoint_name);
+            // Replaced rather than written over, as it may be linked from the shown report
+            if checkpoint_path.exists() {
+                fs::remove_file(&checkpoint_path)?;
+            }
+            fs::write(&checkpoint_path, serde_json::to_vec(checkpoint)?)?;
+        }
+        if let Some(staging_path) = &staging_path {
+            swap_report(staging_path, &out_path)?;
+        }
+
+        if !cli.watch {
+            show_report(&cli, &out_path, zip_output)?;
+            exit_for_fail_on(&cli.fail_on, &output);
+            return Ok(());
+        }
+        // Later rounds only refresh the report, which is reloaded from the output directory
+        if first_round {
+            if cli.serve {
+                let listener = bind("127.0.0.1", cli.port)?;
+                let dir = out_path.clone();
+                thread::spawn(move || serve_directory(listener, &dir));
+            } else {
+                show_report(&cli, &out_path, zip_output)?;
+            }
+        }
+        rounds += 1;
+        if cli.watch_rounds.is_some_and(|n| rounds >= n) {
+            eprintln!("Updated {}, stopping", out_path.display());
+            return Ok(());
+        }
+        eprintln!(
+            "Updated {}, waiting for {} to grow (Ctrl-C to stop)",
+            out_path.display(),
+            path.display()
+        );
+        let interval = Duration::from_secs_f64(cli.watch_interval_s);
+        // A partial line at the end of the log was left for when it is complete, so only new
+        // complete lines start another round
+        let parsed = new_checkpoint.as_ref().map_or(0, ParseCheckpoint::offset);
+        while complete_len(&path)? == parsed {
+            thread::sleep(interval);
+        }
+    }
+}
+
+// Where to write something to put at path later, e.g., tl_out.tlparse_staging for tl_out
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+// Hard links the files of a report into another directory, so that a copy of the report can be
+// updated without touching the one that is shown
+fn link_report(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let to = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            link_report(&entry.path(), &to)?;
+        } else if fs::hard_link(entry.path(), &to).is_err() {
+            // E.g., on a file system without hard links
+            fs::copy(entry.path(), &to)?;
+        }
+    }
+    Ok(())
+}
 
+// Puts the report written at staging_path in place of the one at out_path.  A directory can't be
+// renamed over another that isn't empty, so the old report is moved aside first, and requests
+// served in between the two renames may fail
+fn swap_report(staging_path: &Path, out_path: &Path) -> io::Result<()> {
+    let old_path = sibling_path(out_path, "tlparse_old");
+    if old_path.exists() {
+        remove_path(&old_path)?;
+    }
     if out_path.exists() {
-        if !cli.overwrite {
-            bail!(
-                "Directory {} already exists, use -o OUTDIR to write to another location or pass --overwrite to overwrite the old contents",
-                out_path.display()
-            );
+        fs::rename(out_path, &old_path)?;
+    }
+    fs::rename(staging_path, out_path)?;
+    if old_path.exists() {
+        remove_path(&old_path)?;
+    }
+    Ok(())
+}
+
+// How long the log is up to the end of its last complete line
+fn complete_len(path: &Path) -> io::Result<u64> {
+    let mut file = fs::File::open(path)?;
+    let mut end = file.metadata()?.len();
+    let mut buf = vec![0; 64 * 1024];
+    while end > 0 {
+        let start = end.saturating_sub(buf.len() as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(io::SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(i) = chunk.iter().rposition(|b| *b == b'\n') {
+            return Ok(start + i as u64 + 1);
         }
-        fs::remove_dir_all(&out_path)?;
+        end = start;
     }
-    fs::create_dir(&out_path)?;
+    Ok(0)
+}
 
-    let config = ParseConfig {
-        strict: cli.strict,
-        strict_compile_id: cli.strict_compile_id,
-        custom_parsers: Vec::new(),
-        custom_header_html: cli.custom_header_html,
-        verbose: cli.verbose,
-        plain_text: cli.plain_text,
-        export: cli.export,
+// Prints, serves or opens the written report, depending on the options
+fn show_report(cli: &Cli, out_path: &Path, zip_output: bool) -> anyhow::Result<()> {
+    let report_path = if zip_output {
+        out_path.to_path_buf()
+    } else {
+        out_path.join("index.html")
     };
+    if cli.print_url_only {
+        println!("{}", file_url(&report_path)?);
+    } else if zip_output {
+        eprintln!("Wrote the report to {}", out_path.display());
+    } else if cli.serve {
+        // Runs until stopped, so there is no exit code for --fail-on to set
+        serve_directory(bind("127.0.0.1", cli.port)?, out_path)?;
+    } else if !cli.no_browser {
+        let opened = if is_headless() {
+            false
+        } else {
+            // E.g., no browser installed
+            opener::open(&report_path)
+                .map_err(|e| eprintln!("Couldn't open a browser: {e}"))
+                .is_ok()
+        };
+        if !opened {
+            eprintln!(
+                "The report is at {}\nTo view it from another machine, pass --serve, or run \
+                 tlparse serve {}, and forward port {} to it",
+                file_url(&report_path)?,
+                out_path.display(),
+                cli.port
+            );
+        }
+    }
+    Ok(())
+}
 
-    let output = parse_path(&path, config)?;
+// Whether there is no display for a browser to open on, e.g., in an ssh session or a container
+fn is_headless() -> bool {
+    if cfg!(any(target_os = "macos", windows)) {
+        std::env::var_os("SSH_CONNECTION").is_some()
+    } else {
+        std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none()
+    }
+}
 
-    for (filename, path) in output {
-        let out_file = out_path.join(filename);
-        if let Some(dir) = out_file.parent() {
-            fs::create_dir_all(dir)?;
+fn file_url(path: &Path) -> anyhow::Result<String> {
+    let path =
+        fs::canonicalize(path).with_context(|| format!("Couldn't resolve {}", path.display()))?;
+    let path = path.to_string_lossy();
+    // Windows paths come back as \\?\C:\...
+    let path = path
+        .strip_prefix(r"\\?\")
+        .unwrap_or(&path)
+        .replace('\\', "/");
+    let mut url = String::from("file://");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            url.push(b as char);
+        } else {
+            url.push_str(&format!("%{b:02X}"));
         }
-        fs::write(out_file, path)?;
     }
+    Ok(url)
+}
+
+fn bind(host: &str, port: u16) -> anyhow::Result<TcpListener> {
+    TcpListener::bind((host, port)).with_context(|| format!("Couldn't listen on {host}:{port}"))
+}
 
-    if !cli.no_browser {
-        opener::open(out_path.join("index.html"))?;
+fn serve_directory(listener: TcpListener, dir: &Path) -> anyhow::Result<()> {
+    eprintln!(
+        "Serving {} at http://{}/",
+        dir.display(),
+        listener.local_addr()?
+    );
+    serve_report(listener, |file| fs::read(dir.join(file)).ok())
+}
+
+fn emit_baseline(cli: &Cli, output: &ParseOutput) -> anyhow::Result<()> {
+    if let Some(path) = &cli.emit_baseline {
+        let baseline = Baseline::new(&output.metrics);
+        fs::write(path, serde_json::to_string_pretty(&baseline)?)
+            .with_context(|| format!("Couldn't write baseline {}", path.display()))?;
     }
     Ok(())
 }
+
+fn exit_for_fail_on(fail_on: &[FailOn], output: &ParseOutput) {
+    let failed: Vec<String> = fail_on
+        .iter()
+        .filter(|f| f.count(output) > 0)
+        .map(|f| f.to_possible_value().unwrap().get_name().to_string())
+        .collect();
+    if !failed.is_empty() {
+        eprintln!(
+            "Log had problems selected by --fail-on: {}",
+            failed.join(", ")
+        );
+        std::process::exit(EXIT_FAIL_ON);
+    }
+}
@@ -1,48 +1,343 @@
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use fxhash::{FxHashMap, FxHashSet};
 use md5::{Digest, Md5};
 use std::ffi::{OsStr, OsString};
 
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use regex::Regex;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fs::{self, File};
-use std::io::{self, BufRead};
-use std::path::PathBuf;
-use std::time::Instant;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
 use tinytemplate::TinyTemplate;
 
 use crate::parsers::default_parsers;
+use crate::parsers::format_stack;
+use crate::parsers::graph_node_counts;
 use crate::parsers::ParserOutput;
 use crate::parsers::StructuredLogParser;
 use crate::templates::*;
 use crate::types::*;
 mod parsers;
+mod query;
 mod templates;
 mod types;
 
+pub use query::{entry_value, Query};
+
+pub use types::{
+    ArtifactKind, Baseline, BaselineFrame, CompilationMetricsIndex, CompilationMetricsMetadata,
+    CompileId, Envelope, FrameTimeDelta, GuardCountDelta, JsonFailure, KnownIssue, LinkRewrite,
+    ListedKind, LogDiff, LogListing, OutputDiff, OutputFile, OutputFiles, ParseCheckpoint,
+    ParsedEnvelope, Regression, Stats,
+};
+
+#[derive(Default, Clone)]
 pub struct ParseConfig {
     pub strict: bool,
     pub strict_compile_id: bool,
-    pub custom_parsers: Vec<Box<dyn crate::parsers::StructuredLogParser>>,
+    pub custom_parsers: Vec<Rc<dyn crate::parsers::StructuredLogParser>>,
     pub custom_header_html: String,
     pub verbose: bool,
     pub plain_text: bool,
+    // Seconds highlighting one output may take before it is written as plain text instead, so that
+    // an enormous one doesn't hold up the whole report
+    pub render_budget_s: Option<f64>,
     pub export: bool,
+    // Split the build products listing into one page per frame when there are more compile ids
+    // than this, so index.html stays small for huge runs
+    pub max_inline_compile_ids: Option<usize>,
+    // Once generated outputs add up to more than this many bytes, warn and stop writing low value
+    // outputs (see StructuredLogParser::low_value)
+    pub output_budget: Option<u64>,
+    // Links the frame a compilation is for to its source, with {filename} and {line} placeholders
+    pub source_url_template: Option<String>,
+    // Explanations for failures and restarts, tried before the built in ones
+    pub known_issues: Vec<KnownIssue>,
+    // Warn at the top of index.html about frames that recompiled more than this many times
+    // within recompile_storm_window_s seconds
+    pub recompile_storm_threshold: Option<usize>,
+    pub recompile_storm_window_s: Option<f64>,
+    // Flag frames whose compile time or graph count grew by more than regression_threshold_pct
+    // percent over this baseline, at the top of index.html and in ParseOutput::regressions
+    pub baseline: Option<Baseline>,
+    pub regression_threshold_pct: Option<f64>,
+    // When set, outputs are sent here as soon as they are complete, instead of being returned, so
+    // that they can be written while the rest of the log is parsed
+    pub output_sink: Option<Sender<(PathBuf, String)>>,
+    // Collapse the artifacts of attempts that were restarted, e.g., by a graph break, in listings
+    pub hide_abandoned_attempts: bool,
+    // CompilationMetrics fields shown in the metrics table of index.html and compilation_metrics.csv
+    pub metrics_columns: Option<Vec<String>>,
+    // Don't check payloads against their md5, which is a noticeable part of parsing huge logs.
+    // fail_payload_md5 stays 0.
+    pub no_verify_payload: bool,
+    // Only check the md5 of payloads of at least this many bytes.  Big payloads dominate the
+    // hashing time and are the ones most likely to be cut off by a crash or a full disk.
+    pub verify_payload_min_bytes: Option<usize>,
+    // Also write JSON_OUTPUT_PATH, with the compile ids, metrics, guards, artifacts and failures of
+    // the run
+    pub json: bool,
+    // Only run parsers (which render the artifacts) for the first this many compile ids, to get a
+    // quick report out of huge logs.  Metrics based summaries still cover every compilation.
+    pub max_compiles: Option<usize>,
+    // Parse the entries of every rank into rank_{rank}/ (no_rank/ for entries without one), with an
+    // index.html linking them, instead of only the first rank seen
+    pub all_ranks: bool,
+    // Read the log twice, first for what entries refer to, e.g., the str entries of file names
+    // (so stack frames logged before them don't show (unknown)), stacks, and the specializations
+    // and guards shown on metrics pages, so that it can be logged after them
+    pub two_pass: bool,
+    // Regexes for the file names of frames to leave out of rendered stacks (exports keep them)
+    pub hide_frames: Vec<String>,
+    // Frames in a row shown in rendered stacks, with the outer ones behind an expander
+    pub max_stack_depth: Option<usize>,
+    // Label the frames of the stack trie with the compile time spent under them, heaviest first
+    pub stack_trie_times: bool,
+    // Applied in order to the URLs of link entries and of the links in custom_header_html, e.g.,
+    // to map internal URLs to ones that can be reached from outside
+    pub link_rewrites: Vec<LinkRewrite>,
+    // Where progress and warnings are reported, stderr (without progress bars) if None
+    pub progress: Option<Rc<dyn ProgressSink>>,
+}
+
+// Receives the progress of parsing, e.g., to draw a progress bar, and the warnings to show the user,
+// e.g., lines that failed to parse.  When parsing all ranks, every rank is parsed in turn, each
+// from start to finish.
+pub trait ProgressSink {
+    // size is the size of the log in bytes, if known
+    fn start(&self, _size: Option<u64>) {}
+    // How far into the log parsing is, in bytes
+    fn bytes_read(&self, _bytes: u64) {}
+    fn stats(&self, _stats: &Stats) {}
+    fn warn(&self, message: &str) {
+        eprintln!("{message}");
+    }
+    fn finish(&self, stats: &Stats) {
+        eprintln!("{stats:?}");
+    }
 }
 
-impl Default for ParseConfig {
-    fn default() -> Self {
-        Self {
-            strict: false,
-            strict_compile_id: false,
-            custom_parsers: Vec::default(),
-            custom_header_html: String::default(),
-            verbose: false,
-            plain_text: false,
-            export: false,
+// Reports warnings and the final statistics on stderr
+pub struct StderrProgress;
+
+impl ProgressSink for StderrProgress {}
+
+// Reports nothing, e.g., for embedding tlparse in a service
+pub struct SilentProgress;
+
+impl ProgressSink for SilentProgress {
+    fn warn(&self, _message: &str) {}
+    fn finish(&self, _stats: &Stats) {}
+}
+
+// Where the files of a report go, e.g., a directory, an archive or a remote store.  Paths are
+// relative to the root of the report, and finish is called once after the last file.
+pub trait OutputWriter: Send {
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()>;
+    // Called with the directory of a file before the first file in it is written
+    fn create_dir(&mut self, _path: &Path) -> anyhow::Result<()> {
+        Ok(())
+    }
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+// Writes the report into a directory on disk, optionally gzipping every file but index.html
+pub struct DirectoryWriter {
+    root: PathBuf,
+    gzip: bool,
+}
+
+impl DirectoryWriter {
+    pub fn new(root: PathBuf, gzip: bool) -> anyhow::Result<Self> {
+        fs::create_dir_all(&root)?;
+        Ok(DirectoryWriter { root, gzip })
+    }
+}
+
+impl OutputWriter for DirectoryWriter {
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let gzip = self.gzip && path != Path::new("index.html");
+        let mut out_file = self.root.join(path);
+        if gzip {
+            out_file.as_mut_os_string().push(".gz");
+        }
+        // A file that is there already is replaced rather than written over, since it may be a
+        // hard link to the report that --watch shows while it writes the next one
+        if out_file.exists() {
+            fs::remove_file(&out_file)?;
+        }
+        if gzip {
+            let mut encoder = GzEncoder::new(File::create(out_file)?, Compression::default());
+            encoder.write_all(contents)?;
+            encoder.finish()?;
+        } else {
+            fs::write(out_file, contents)?;
+        }
+        Ok(())
+    }
+
+    fn create_dir(&mut self, path: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(self.root.join(path))?;
+        Ok(())
+    }
+}
+
+// Writes the whole report into one zip archive, e.g., to share it as a single file.  index.html is
+// at the root of the archive, so the relative links between files work once it's extracted
+pub struct ZipWriter {
+    zip: Option<zip::ZipWriter<File>>,
+}
+
+impl ZipWriter {
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("Couldn't create {}", path.display()))?;
+        Ok(ZipWriter {
+            zip: Some(zip::ZipWriter::new(file)),
+        })
+    }
+
+    fn zip(&mut self) -> anyhow::Result<&mut zip::ZipWriter<File>> {
+        self.zip
+            .as_mut()
+            .context("Zip archive was already finished")
+    }
+}
+
+impl OutputWriter for ZipWriter {
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let zip = self.zip()?;
+        zip.start_file_from_path(path, zip::write::SimpleFileOptions::default())?;
+        zip.write_all(contents)?;
+        Ok(())
+    }
+
+    fn create_dir(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.zip()?
+            .add_directory_from_path(path, zip::write::SimpleFileOptions::default())?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if let Some(zip) = self.zip.take() {
+            zip.finish()?;
+        }
+        Ok(())
+    }
+}
+
+// Writes the files of a report, e.g., as they are received from ParseConfig::output_sink, and
+// finishes the writer
+pub fn write_outputs(
+    writer: &mut dyn OutputWriter,
+    outputs: impl IntoIterator<Item = (PathBuf, String)>,
+) -> anyhow::Result<()> {
+    let mut created = FxHashSet::default();
+    for (path, contents) in outputs {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            if created.insert(dir.to_path_buf()) {
+                writer.create_dir(dir)?;
+            }
         }
+        writer.write_file(&path, contents.as_bytes())?;
     }
+    writer.finish()
+}
+
+impl ParseConfig {
+    // The options of one rank's report in all_ranks mode
+    fn for_rank(&self) -> ParseConfig {
+        ParseConfig {
+            custom_parsers: Vec::new(),
+            output_sink: None,
+            all_ranks: false,
+            ..self.clone()
+        }
+    }
+
+    // Payloads shorter than this aren't checked against their md5, and none are if None
+    fn payload_check_min_bytes(&self) -> Option<usize> {
+        (!self.no_verify_payload).then(|| self.verify_payload_min_bytes.unwrap_or(0))
+    }
+
+    fn progress(&self) -> Rc<dyn ProgressSink> {
+        self.progress
+            .clone()
+            .unwrap_or_else(|| Rc::new(StderrProgress))
+    }
+}
+
+// Not compared by check_snapshot or diff_output_dirs, since it records when the report was made
+pub const REPORT_METADATA_PATH: &str = "tlparse_report.json";
+
+// Undefined intern table ids listed in index.html
+const TOP_MISSING_INTERNED_STRS: usize = 20;
+
+// Sources listed in the top guard sources table of index.html
+const TOP_GUARD_SOURCES: usize = 20;
+
+// Frames listed as wasting the most time on restarted analysis on index.html
+const TOP_RESTART_TIME_FRAMES: usize = 10;
+
+pub const DEFAULT_RECOMPILE_STORM_THRESHOLD: usize = 10;
+pub const DEFAULT_REGRESSION_THRESHOLD_PCT: f64 = 10.0;
+pub const DEFAULT_RENDER_BUDGET_S: f64 = 30.0;
+pub const DEFAULT_RECOMPILE_STORM_WINDOW_S: f64 = 60.0;
+pub const DEFAULT_METRICS_COLUMNS: &[&str] = &[
+    "entire_frame_compile_time_s",
+    "backend_compile_time_s",
+    "graph_op_count",
+    "guard_count",
+    "fail_type",
+];
+
+// Written in every compile id directory, next to its index.html
+pub const COMPILE_ID_SUMMARY_FILE: &str = "summary.json";
+
+pub const JSON_OUTPUT_PATH: &str = "parsed.json";
+
+pub const METRICS_CSV_PATH: &str = "compilation_metrics.csv";
+
+// A parsed log: the files of its report, and what they were made from, for programmatic use without
+// reading the files back.  Iterating over it gives the files.
+#[derive(Debug, Default)]
+pub struct ParseOutput {
+    // Empty when they were sent to ParseConfig::output_sink instead
+    pub files: OutputFiles,
+    pub stats: Stats,
+    pub metrics: CompilationMetricsIndex,
+    // In log order
+    pub failures: Vec<JsonFailure>,
+    // The files of every compile id
+    pub directory: FxIndexMap<Option<CompileId>, Vec<OutputFile>>,
+    // Frames that regressed from ParseConfig::baseline, of every rank when parsing all ranks
+    pub regressions: Vec<Regression>,
+    // When parsing all ranks, what each rank's report was made from.  Their files are in files,
+    // under rank_{rank}/
+    pub ranks: FxIndexMap<Option<u32>, ParseOutput>,
+}
+
+impl IntoIterator for ParseOutput {
+    type Item = (PathBuf, String);
+    type IntoIter = std::vec::IntoIter<(PathBuf, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+pub struct ParseResult {
+    pub output: ParseOutput,
+    // Only taken by parse_path_incremental
+    pub checkpoint: Option<ParseCheckpoint>,
+    // What each compile id of each rank compiled, for parse_partitions to compare the ranks
+    pub(crate) rank_compiles_index: RankCompilesIndex,
 }
 
 fn maybe_remove_convert_frame_suffixes(frames: &mut Vec<FrameSummary>) {
@@ -77,33 +372,63 @@ fn maybe_remove_convert_frame_suffixes(frames: &mut Vec<FrameSummary>) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_parser<'t>(
     lineno: usize,
-    parser: &Box<dyn StructuredLogParser + 't>,
+    parser: &(dyn StructuredLogParser + 't),
     e: &Envelope,
     payload: &str,
     output_count: &mut i32,
     output: &mut Vec<(PathBuf, String)>,
     compile_directory: &mut Vec<OutputFile>,
-    multi: &MultiProgress,
+    progress: &dyn ProgressSink,
     stats: &mut Stats,
+    output_budget: Option<u64>,
+    output_usage: &mut OutputUsage,
 ) {
-    if let Some(md) = parser.get_metadata(&e) {
-        let results = parser.parse(lineno, md, e.rank, &e.compile_id, &payload);
-        fn extract_suffix(filename: &String) -> String {
-            if filename.contains("cache_miss") {
-                "❌".to_string()
-            } else if filename.contains("cache_hit") {
-                "✅".to_string()
-            } else if filename.contains("cache_bypass") {
-                "❓".to_string()
-            } else {
-                "".to_string()
-            }
+    if let Some(md) = parser.get_metadata(e) {
+        let over_budget = |usage: &OutputUsage| output_budget.is_some_and(|b| usage.total > b);
+        if parser.low_value() && over_budget(output_usage) {
+            *output_usage
+                .skipped
+                .entry(parser.name().to_string())
+                .or_default() += 1;
+            return;
         }
+        let mut record_output = |size: usize| {
+            let was_over_budget = over_budget(output_usage);
+            output_usage.record(parser.name(), size);
+            if !was_over_budget && over_budget(output_usage) {
+                progress.warn(&format!(
+                    "Output budget of {} exceeded, skipping low value outputs from now on",
+                    format_bytes(output_budget.unwrap())
+                ));
+            }
+        };
+        let artifact_kind = parser.artifact_kind(&md);
+        let phase = match artifact_kind {
+            ArtifactKind::Cache => CompilePhase::Caching,
+            _ => parser.phase(&md),
+        };
+        let results = parser.parse(lineno, md, e.rank, &e.compile_id, payload);
         match results {
             Ok(results) => {
-                for parser_result in results {
+                for mut parser_result in results {
+                    let mut suffix = "".to_string();
+                    let mut source_filename = None;
+                    loop {
+                        match parser_result {
+                            ParserOutput::Badged(badge, inner) => {
+                                suffix = badge.symbol().to_string();
+                                parser_result = *inner;
+                            }
+                            ParserOutput::Sourced(source, inner) => {
+                                source_filename = Some(source);
+                                parser_result = *inner;
+                            }
+                            _ => break,
+                        }
+                    }
                     match parser_result {
                         ParserOutput::File(raw_filename, out) => {
                             let filename = if let Some(stem) = raw_filename.file_stem() {
@@ -119,48 +444,73 @@ fn run_parser<'t>(
                             } else {
                                 raw_filename
                             };
+                            let size = Some(out.len());
+                            let preview = inline_preview(&filename, &out);
+                            record_output(out.len());
                             output.push((filename.clone(), out));
                             let filename_str = format!("{}", filename.to_string_lossy());
-                            let suffix = extract_suffix(&filename_str);
                             compile_directory.push(OutputFile {
                                 url: filename_str.clone(),
                                 name: filename_str,
                                 number: *output_count,
-                                suffix: suffix,
+                                suffix,
+                                size,
+                                lineno,
+                                preview,
+                                source_filename,
+                                artifact_kind,
+                                phase,
                             });
                             *output_count += 1;
                         }
                         ParserOutput::GlobalFile(filename, out) => {
+                            let size = Some(out.len());
+                            let preview = inline_preview(&filename, &out);
+                            record_output(out.len());
                             output.push((filename.clone(), out));
                             let filename_str = format!("{}", filename.to_string_lossy());
-                            let suffix = extract_suffix(&filename_str);
                             compile_directory.push(OutputFile {
                                 url: filename_str.clone(),
                                 name: filename_str,
                                 number: *output_count,
-                                suffix: suffix,
+                                suffix,
+                                size,
+                                lineno,
+                                preview,
+                                source_filename,
+                                artifact_kind,
+                                phase,
                             });
                             *output_count += 1;
                         }
                         ParserOutput::Link(name, url) => {
                             compile_directory.push(OutputFile {
-                                url: url,
-                                name: name,
+                                url,
+                                name,
                                 number: *output_count,
-                                suffix: "".to_string(),
+                                suffix,
+                                size: None,
+                                lineno,
+                                preview: None,
+                                source_filename,
+                                artifact_kind: ArtifactKind::Link,
+                                phase,
                             });
                             *output_count += 1;
                         }
+                        ParserOutput::Badged(..) | ParserOutput::Sourced(..) => {
+                            unreachable!("badges and sources are unwrapped above")
+                        }
                     }
                 }
             }
             Err(err) => match parser.name() {
                 "dynamo_guards" => {
-                    multi.suspend(|| eprintln!("Failed to parse guards json: {}", err));
+                    progress.warn(&format!("Failed to parse guards json: {}", err));
                     stats.fail_dynamo_guards_json += 1;
                 }
                 name => {
-                    multi.suspend(|| eprintln!("Parser {name} failed: {err}"));
+                    progress.warn(&format!("Parser {name} failed: {err}"));
                     stats.fail_parser += 1;
                 }
             },
@@ -169,256 +519,1398 @@ fn run_parser<'t>(
 }
 
 pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOutput> {
-    let strict = config.strict;
+    parse_path_impl(path, config, None, false).map(|r| r.output)
+}
+
+// The parsed run as JSON, i.e., JSON_OUTPUT_PATH of parse_path with config.json
+pub fn parse_path_json(path: &PathBuf, config: ParseConfig) -> anyhow::Result<serde_json::Value> {
+    let config = ParseConfig {
+        json: true,
+        ..config
+    };
+    let (_, json) = parse_path(path, config)?
+        .into_iter()
+        .find(|(p, _)| p == Path::new(JSON_OUTPUT_PATH))
+        .ok_or_else(|| anyhow::anyhow!("No {JSON_OUTPUT_PATH} in the output"))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+// Like parse_path, but also returns the parse statistics, e.g., to decide on an exit code
+pub fn parse_path_with_stats(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseResult> {
+    parse_path_impl(path, config, None, false)
+}
+
+// Like parse_path, but resumes from a checkpoint taken by an earlier run on the same log (which may
+// have grown since), and returns a new checkpoint.  A partially written line at the end of the log
+// is left for the next run, since that entry may still be completed, so the checkpoint is taken
+// before it, and the line isn't in the report.  With ParseConfig::output_sink,
+// the checkpoint only keeps the outputs that weren't sent to it yet, so only the outputs written
+// since the checkpoint are sent: send them to the same place as the run that took it.
+pub fn parse_path_incremental(
+    path: &PathBuf,
+    config: ParseConfig,
+    checkpoint: Option<ParseCheckpoint>,
+) -> anyhow::Result<ParseResult> {
+    parse_path_impl(path, config, checkpoint, true)
+}
+
+// Renders the report again from a checkpoint, without reparsing the log, e.g., to pick up changes
+// to the templates or the options.  raw_log gives the log the checkpoint was taken from, which is
+// also kept in the report as raw.log.  Outputs that were sent to an output sink aren't in the
+// checkpoint, so they aren't rendered again
+pub fn render_checkpoint(
+    checkpoint: ParseCheckpoint,
+    config: ParseConfig,
+    raw_log: impl FnOnce() -> io::Result<String>,
+) -> anyhow::Result<ParseOutput> {
+    parse_impl(
+        io::empty(),
+        None,
+        config,
+        Some(checkpoint),
+        None,
+        false,
+        raw_log,
+    )
+    .map(|r| r.output)
+}
+
+// Like parse_path, but reads the log from any source, e.g., a network stream, an archive member, or
+// an in-memory buffer.  The log is kept in memory as it is read, to write raw.log
+pub fn parse_reader(reader: impl BufRead, config: ParseConfig) -> anyhow::Result<ParseOutput> {
+    parse_reader_with_stats(reader, config).map(|r| r.output)
+}
+
+// Like parse_reader, but also returns the parse statistics, e.g., to decide on an exit code
+pub fn parse_reader_with_stats(
+    reader: impl BufRead,
+    config: ParseConfig,
+) -> anyhow::Result<ParseResult> {
+    if config.two_pass {
+        bail!("Two pass parsing needs a log that can be read twice, use parse_path");
+    }
+    if config.all_ranks {
+        let spilled = spill_log(reader)?;
+        let mut partitions = Partitions::default();
+        partition_by_rank(spilled.path(), &mut partitions)?;
+        return parse_partitions(partitions, config);
+    }
+    let raw = Rc::new(RefCell::new(Vec::new()));
+    let reader = io::BufReader::new(TeeReader {
+        inner: reader,
+        copy: raw.clone(),
+    });
+    let raw_log = || Ok(String::from_utf8_lossy(&raw.borrow()).into_owned());
+    parse_impl(reader, None, config, None, None, false, raw_log)
+}
+
+// The entries of a log one at a time, without generating a report, e.g., to ship them elsewhere.
+// str entries only serve to resolve the file names of later stack frames, so they aren't yielded,
+// and neither are lines that aren't entries
+pub fn parse_entries(path: &Path) -> anyhow::Result<impl Iterator<Item = ParsedEnvelope>> {
+    Ok(parse_entries_from_reader(open_log(path)?))
+}
+
+pub fn parse_entries_from_reader(reader: impl BufRead) -> impl Iterator<Item = ParsedEnvelope> {
+    Entries {
+        lines: LogLines {
+            lines: reader
+                .lines()
+                .map_while(Result::ok)
+                .enumerate()
+                .filter(|(_, l)| !l.is_empty())
+                .map(|(i, l)| (i + 1, l)),
+            given_back: VecDeque::new(),
+        },
+        intern_table: FxHashMap::default(),
+    }
+}
+
+struct Entries<I> {
+    lines: LogLines<I>,
+    intern_table: FxHashMap<u32, String>,
+}
+
+impl<I: Iterator<Item = (usize, String)>> Iterator for Entries<I> {
+    type Item = ParsedEnvelope;
+
+    fn next(&mut self) -> Option<ParsedEnvelope> {
+        while let Some((lineno, line)) = self.lines.next() {
+            let Some(prefix) = GlogPrefix::parse(&line) else {
+                continue;
+            };
+            let Ok(mut envelope) = serde_json::from_str::<Envelope>(&line[prefix.payload_start..])
+            else {
+                continue;
+            };
+            if let Some((s, i)) = envelope.str.take() {
+                self.intern_table.insert(i, s);
+                continue;
+            }
+            let (payload, check) = match &envelope.has_payload {
+                Some(expect) => {
+                    let (payload, check) = read_payload(&mut self.lines, expect, Some(0));
+                    (Some(payload), check)
+                }
+                None => (None, PayloadCheck::Unchecked),
+            };
+            envelope.unintern_stacks(&self.intern_table);
+            return Some(ParsedEnvelope {
+                lineno,
+                timestamp_us: prefix.micros(),
+                envelope,
+                payload,
+                payload_md5_mismatch: check == PayloadCheck::Mismatch,
+            });
+        }
+        None
+    }
+}
+
+// Keeps a copy of everything read
+struct TeeReader<R> {
+    inner: R,
+    copy: Rc<RefCell<Vec<u8>>>,
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.borrow_mut().extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+// Tracks how many bytes of the log have been read, for checkpointing
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: BufRead> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count.set(self.count.get() + amt as u64);
+        self.inner.consume(amt);
+    }
+}
+
+fn parse_path_impl(
+    path: &PathBuf,
+    config: ParseConfig,
+    checkpoint: Option<ParseCheckpoint>,
+    want_checkpoint: bool,
+) -> anyhow::Result<ParseResult> {
+    if path.is_dir() {
+        if want_checkpoint {
+            bail!("Incremental parsing doesn't support parsing a directory of logs");
+        }
+        return parse_log_dir(path, config);
+    }
     if !path.is_file() {
         bail!("{} is not a file", path.display())
     }
+    if config.all_ranks {
+        if want_checkpoint {
+            bail!("Incremental parsing doesn't support parsing all ranks");
+        }
+        return parse_all_ranks(path, config);
+    }
+    if is_compressed_log(path) {
+        if want_checkpoint {
+            bail!("Incremental parsing doesn't support compressed logs");
+        }
+        let lookahead = if config.two_pass {
+            Some(lookahead_pass(open_log(path)?, &config)?)
+        } else {
+            None
+        };
+        let raw_log = || io::read_to_string(open_log(path)?);
+        return parse_impl(
+            open_log(path)?,
+            None,
+            config,
+            None,
+            lookahead,
+            false,
+            raw_log,
+        );
+    }
+    if let Some(checkpoint) = &checkpoint {
+        if !checkpoint.matches(path)? {
+            bail!(
+                "{} is not a continuation of the log the checkpoint was taken from",
+                path.display()
+            )
+        }
+    }
+    // Plain logs are read straight out of the page cache, instead of being copied into a buffer
+    // first.  SAFETY: the map is of the log as it is now, and only read.  Appending to the log while
+    // it is parsed, e.g., with --watch, is fine, but truncating it would make reading the pages
+    // past its new end fault
+    let log = unsafe { memmap2::Mmap::map(&File::open(path)?)? };
+    let offset = checkpoint
+        .as_ref()
+        .map_or(0, |checkpoint| checkpoint.offset);
+    // Up to the end of the last complete line, when resuming is to be possible
+    let end = if want_checkpoint {
+        log.iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |i| i + 1)
+            .max(offset as usize)
+    } else {
+        log.len()
+    };
+    let raw_log = || {
+        std::str::from_utf8(&log[..end])
+            .map(str::to_owned)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    };
+    let lookahead = if config.two_pass {
+        Some(lookahead_pass(&log[..end], &config)?)
+    } else {
+        None
+    };
+    let mut result = parse_impl(
+        log.get(offset as usize..end).unwrap_or_default(),
+        Some(end as u64),
+        config,
+        checkpoint,
+        lookahead,
+        want_checkpoint,
+        raw_log,
+    )?;
+    if let Some(checkpoint) = &mut result.checkpoint {
+        checkpoint.tail_md5 = ParseCheckpoint::tail(path, checkpoint.offset)?;
+    }
+    Ok(result)
+}
+
+fn is_compressed_log(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "gz" || ext == "zst")
+}
+
+// Opens a log, decoding it as it is read if it is gzip or zstd compressed
+fn open_log(path: &Path) -> io::Result<Box<dyn BufRead>> {
     let file = File::open(path)?;
-    let metadata = file.metadata()?;
-    let file_size = metadata.len();
+    Ok(match path.extension().and_then(OsStr::to_str) {
+        // Concatenated gzip streams, e.g., from appending to a compressed log, are read as one
+        Some("gz") => Box::new(io::BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+        Some("zst") => Box::new(io::BufReader::new(zstd::Decoder::new(file)?)),
+        _ => Box::new(io::BufReader::new(file)),
+    })
+}
+
+// The first pass of two pass parsing, which collects the str entries of the whole log and what
+// metrics pages show about their compile id, from the first rank as in parse_impl.  Lines are told
+// apart from payloads the same way as in parse_impl, so both passes see the same entries
+fn lookahead_pass(reader: impl BufRead, config: &ParseConfig) -> anyhow::Result<Lookahead> {
+    let mut lookahead = Lookahead::default();
+    let mut expected_rank = None;
+    let mut lines = LogLines {
+        lines: reader
+            .lines()
+            .enumerate()
+            .filter_map(|(i, l)| l.ok().filter(|l| !l.is_empty()).map(|l| (i + 1, l))),
+        given_back: VecDeque::new(),
+    };
+    while let Some((_, line)) = lines.next() {
+        let Some(prefix) = GlogPrefix::parse(&line) else {
+            continue;
+        };
+        let Ok(e) = serde_json::from_str::<Envelope>(&line[prefix.payload_start..]) else {
+            continue;
+        };
+        if let Some((s, i)) = e.str {
+            lookahead.intern_table.insert(i, s);
+            continue;
+        }
+        if let Some(expect) = &e.has_payload {
+            read_payload(&mut lines, expect, config.payload_check_min_bytes());
+        }
+        if *expected_rank.get_or_insert(e.rank) != e.rank {
+            continue;
+        }
+        if let Some(specialization) = e.symbolic_shape_specialization {
+            lookahead
+                .symbolic_shape_specialization_index
+                .borrow_mut()
+                .entry(e.compile_id.clone())
+                .or_default()
+                .push(specialization);
+        }
+        if let Some(guard_added_fast) = e.guard_added_fast {
+            lookahead
+                .guard_added_fast_index
+                .borrow_mut()
+                .entry(e.compile_id.clone())
+                .or_default()
+                .push(guard_added_fast);
+        }
+        if let Some(guard_added) = e.guard_added {
+            if guard_added.prefix.as_deref() == Some("runtime_assert") {
+                lookahead
+                    .runtime_assert_index
+                    .borrow_mut()
+                    .entry(e.compile_id.clone())
+                    .or_default()
+                    .push(guard_added);
+            }
+        }
+        if let Some(mut stack) = e.dynamo_start.and_then(|m| m.stack) {
+            maybe_remove_convert_frame_suffixes(&mut stack);
+            lookahead
+                .stack_index
+                .borrow_mut()
+                .insert(e.compile_id.clone(), stack);
+        }
+    }
+    Ok(lookahead)
+}
+
+// The log's entries (with their payloads) split by rank, in the order the ranks first appear.
+// Lines that aren't entries stay with the entry before them, so they are still counted as failures.
+// Where a rank's part of a log is: whole logs, e.g., named after the rank, or byte ranges of a plain
+// log, so that parsing all ranks doesn't need the log in memory
+enum LogSegment {
+    Log(PathBuf),
+    Ranges(PathBuf, Vec<(u64, u64)>),
+}
 
-    // TODO: abstract out this spinner to not be part of the library
-    // Instead, add a callback trait for CLIs to implement
-    let multi = MultiProgress::new();
-    let pb = multi.add(ProgressBar::new(file_size));
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} [{bytes_per_sec}] ({eta})")?
-        .progress_chars("#>-"));
-    let spinner = multi.add(ProgressBar::new_spinner());
+type Partitions = FxIndexMap<Option<u32>, Vec<LogSegment>>;
 
-    let reader = io::BufReader::new(file);
+// Adds the lines of a plain log to the partitions of their entries' ranks.  Payload lines go with
+// their entry, and lines without a rank of their own with the entry before them
+fn partition_by_rank(path: &Path, partitions: &mut Partitions) -> anyhow::Result<()> {
+    #[derive(serde::Deserialize)]
+    struct RankOnly {
+        rank: Option<u32>,
+    }
+    let mut ranges: FxIndexMap<Option<u32>, Vec<(u64, u64)>> = FxIndexMap::default();
+    let mut reader = io::BufReader::new(File::open(path)?);
+    let mut line = Vec::new();
+    let mut rank = None;
+    let mut offset = 0;
+    loop {
+        line.clear();
+        let len = reader.read_until(b'\n', &mut line)? as u64;
+        if len == 0 {
+            break;
+        }
+        if !line.starts_with(b"\t") {
+            if let Some(r) = std::str::from_utf8(&line).ok().and_then(|line| {
+                let prefix = GlogPrefix::parse(line)?;
+                serde_json::from_str::<RankOnly>(&line[prefix.payload_start..]).ok()
+            }) {
+                rank = r.rank;
+            }
+        }
+        let rank_ranges = ranges.entry(rank).or_default();
+        match rank_ranges.last_mut() {
+            Some((_, end)) if *end == offset => *end += len,
+            _ => rank_ranges.push((offset, offset + len)),
+        }
+        offset += len;
+    }
+    for (rank, ranges) in ranges {
+        partitions
+            .entry(rank)
+            .or_default()
+            .push(LogSegment::Ranges(path.to_path_buf(), ranges));
+    }
+    Ok(())
+}
 
-    let re_glog = Regex::new(concat!(
-        r"(?<level>[VIWEC])(?<month>\d{2})(?<day>\d{2}) ",
-        r"(?<hour>\d{2}):(?<minute>\d{2}):(?<second>\d{2}).(?<millisecond>\d{6}) ",
-        r"(?<thread>\d+)",
-        r"(?<pathname>[^:]+):(?<line>\d+)\] ",
-        r"(?<payload>.)"
-    ))?;
+// A copy of a log that can't be partitioned where it is, e.g., a compressed one
+fn spill_log(mut reader: impl Read) -> io::Result<tempfile::NamedTempFile> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    io::copy(&mut reader, &mut file)?;
+    Ok(file)
+}
 
-    let mut stack_trie = StackTrieNode::default();
-    let mut unknown_stack_trie = StackTrieNode::default();
+// Reads a rank's segments one after the other, as one log
+struct SegmentsReader<'a> {
+    segments: std::slice::Iter<'a, LogSegment>,
+    // The rest of the current segment: either a whole log, or ranges of file
+    log: Option<Box<dyn BufRead>>,
+    file: Option<File>,
+    ranges: std::slice::Iter<'a, (u64, u64)>,
+    // Of the current range
+    remaining: u64,
+    // Segments may not end with a newline, which would join their last line with the next one's
+    at_line_start: bool,
+}
 
-    let mut stats = Stats::default();
-    let _mod_count: FxHashMap<String, i32> = FxHashMap::default();
+impl<'a> SegmentsReader<'a> {
+    fn new(segments: &'a [LogSegment]) -> Self {
+        SegmentsReader {
+            segments: segments.iter(),
+            log: None,
+            file: None,
+            ranges: [].iter(),
+            remaining: 0,
+            at_line_start: true,
+        }
+    }
+}
 
-    let mut bytes_read: u64 = 0;
+impl Read for SegmentsReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = match (&mut self.log, &mut self.file) {
+                (Some(log), _) => log.read(buf)?,
+                (None, Some(file)) if self.remaining > 0 => {
+                    let max = buf
+                        .len()
+                        .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+                    let n = file.read(&mut buf[..max])?;
+                    // The log got shorter since it was partitioned
+                    self.remaining = if n == 0 { 0 } else { self.remaining - n as u64 };
+                    n
+                }
+                _ => 0,
+            };
+            if n > 0 {
+                self.at_line_start = buf[n - 1] == b'\n';
+                return Ok(n);
+            }
+            if !self.at_line_start {
+                self.at_line_start = true;
+                buf[0] = b'\n';
+                return Ok(1);
+            }
+            self.log = None;
+            if let (Some(file), Some(&(start, end))) = (&mut self.file, self.ranges.next()) {
+                file.seek(SeekFrom::Start(start))?;
+                self.remaining = end - start;
+                continue;
+            }
+            self.file = None;
+            match self.segments.next() {
+                None => return Ok(0),
+                Some(LogSegment::Log(path)) => self.log = Some(open_log(path)?),
+                Some(LogSegment::Ranges(path, ranges)) => {
+                    self.file = Some(File::open(path)?);
+                    self.ranges = ranges.iter();
+                }
+            }
+        }
+    }
+}
 
-    // Some stuff for profiling
-    let mut fastest_time = std::time::Duration::MAX;
-    let mut slowest_time = std::time::Duration::ZERO;
+// The size of a rank's segments, unless they include compressed logs
+fn segments_size(segments: &[LogSegment]) -> io::Result<Option<u64>> {
+    let mut size = 0;
+    for segment in segments {
+        size += match segment {
+            LogSegment::Log(path) if is_compressed_log(path) => return Ok(None),
+            LogSegment::Log(path) => fs::metadata(path)?.len(),
+            LogSegment::Ranges(_, ranges) => ranges.iter().map(|(start, end)| end - start).sum(),
+        };
+    }
+    Ok(Some(size))
+}
 
-    let mut expected_rank: Option<Option<u32>> = None;
+fn parse_all_ranks(path: &Path, config: ParseConfig) -> anyhow::Result<ParseResult> {
+    // Compressed logs are partitioned from an uncompressed copy
+    let spilled = if is_compressed_log(path) {
+        Some(spill_log(open_log(path)?)?)
+    } else {
+        None
+    };
+    let mut partitions = Partitions::default();
+    partition_by_rank(spilled.as_ref().map_or(path, |f| f.path()), &mut partitions)?;
+    parse_partitions(partitions, config)
+}
 
-    // Each entry is a compile id => (link, rendered name, output number)
-    // For files, link and rendered name are the same
-    // For links, you can specify a custom name for the link
-    let mut directory: FxIndexMap<Option<CompileId>, Vec<OutputFile>> = FxIndexMap::default();
+// Merges the logs in a directory, e.g., TORCH_TRACE with one log per rank, into a report with a
+// report of its own for every rank
+fn parse_log_dir(path: &Path, config: ParseConfig) -> anyhow::Result<ParseResult> {
+    let mut files = fs::read_dir(path)
+        .with_context(|| format!("Couldn't access directory {}", path.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.retain(|f| {
+        f.is_file()
+            && f.file_name().and_then(OsStr::to_str).is_some_and(|name| {
+                [".log", ".log.gz", ".log.zst"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+            })
+    });
+    if files.is_empty() {
+        bail!("No .log files found in directory {}", path.display());
+    }
+    files.sort();
+    let mut partitions = Partitions::default();
+    // Kept until the partitions are parsed
+    let mut spilled = Vec::new();
+    for file in files {
+        let rank = file
+            .file_name()
+            .and_then(|name| RANK_LOG_FILE_RE.captures(name.to_str()?))
+            .and_then(|caps| caps[1].parse::<u32>().ok());
+        config
+            .progress()
+            .warn(&format!("Reading {}", file.display()));
+        match rank {
+            Some(rank) => partitions
+                .entry(Some(rank))
+                .or_default()
+                .push(LogSegment::Log(file)),
+            // Not named after its rank, so go by the ranks of its entries
+            None if is_compressed_log(&file) => {
+                let log = spill_log(open_log(&file)?)?;
+                partition_by_rank(log.path(), &mut partitions)?;
+                spilled.push(log);
+            }
+            None => partition_by_rank(&file, &mut partitions)?,
+        }
+    }
+    parse_partitions(partitions, config)
+}
 
-    let mut metrics_index: CompilationMetricsIndex = FxIndexMap::default();
-    let stack_index: RefCell<StackIndex> = RefCell::new(FxHashMap::default());
+// Notes what the entry tells about what its rank compiled
+fn record_rank_compile(index: &mut RankCompilesIndex, rank: Option<u32>, e: &Envelope) {
+    if e.compile_id.is_none()
+        || (e.dynamo_start.is_none()
+            && e.dynamo_output_graph.is_none()
+            && e.compilation_metrics.is_none())
+    {
+        return;
+    }
+    let compile = index.entry((rank, e.compile_id.clone())).or_default();
+    if e.dynamo_output_graph.is_some() {
+        compile.graph_hash = e.has_payload.clone();
+    }
+    if let Some(m) = &e.compilation_metrics {
+        compile.guard_count = m.guard_count;
+    }
+}
 
-    let symbolic_shape_specialization_index: RefCell<SymbolicShapeSpecializationIndex> =
-        RefCell::new(FxHashMap::default());
-    let guard_added_fast_index: RefCell<GuardAddedFastIndex> = RefCell::new(FxHashMap::default());
+fn format_rank(rank: Option<u32>) -> String {
+    rank.map_or("(unknown)".to_string(), |r| r.to_string())
+}
 
-    // Store results in an output Vec<PathBuf, String>
-    let mut output: Vec<(PathBuf, String)> = Vec::new();
+// Values grouped by the ranks that have them, e.g., "5 (ranks 0, 1); 7 (ranks 2)", or nothing if
+// every rank has the same value
+fn group_by_rank<T: std::fmt::Display + Eq + std::hash::Hash>(
+    values: impl Iterator<Item = (Option<u32>, T)>,
+) -> String {
+    let mut groups: FxIndexMap<T, Vec<String>> = FxIndexMap::default();
+    for (rank, value) in values {
+        groups.entry(value).or_default().push(format_rank(rank));
+    }
+    if groups.len() < 2 {
+        return String::new();
+    }
+    groups
+        .into_iter()
+        .map(|(value, ranks)| format!("{value} (ranks {})", ranks.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// Compares what the ranks compiled, if there is more than one
+fn compile_divergence(index: &RankCompilesIndex) -> Option<CompileDivergenceContext> {
+    let ranks: BTreeSet<Option<u32>> = index.keys().map(|(rank, _)| *rank).collect();
+    if ranks.len() < 2 {
+        return None;
+    }
+    let rank_rows: Vec<RankCompilesRow> = ranks
+        .iter()
+        .map(|&rank| RankCompilesRow {
+            rank: format_rank(rank),
+            num_compiles: index.keys().filter(|(r, _)| *r == rank).count(),
+        })
+        .collect();
+    let num_compiles_differ = rank_rows
+        .iter()
+        .any(|r| r.num_compiles != rank_rows[0].num_compiles);
+    let mut by_compile_id: FxIndexMap<&CompileId, Vec<(Option<u32>, &RankCompile)>> =
+        FxIndexMap::default();
+    for ((rank, cid), compile) in index {
+        if let Some(cid) = cid {
+            by_compile_id.entry(cid).or_default().push((*rank, compile));
+        }
+    }
+    let rows = by_compile_id
+        .into_iter()
+        .filter_map(|(cid, compiles)| {
+            let missing_ranks = ranks
+                .iter()
+                .filter(|&&r| !compiles.iter().any(|(rank, _)| *rank == r))
+                .map(|&r| format_rank(r))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let graph_hashes = group_by_rank(compiles.iter().filter_map(|(rank, c)| {
+                // A prefix is enough to tell them apart
+                c.graph_hash
+                    .as_ref()
+                    .map(|h| (*rank, h.chars().take(8).collect::<String>()))
+            }));
+            let guard_counts = group_by_rank(
+                compiles
+                    .iter()
+                    .filter_map(|(rank, c)| c.guard_count.map(|n| (*rank, n))),
+            );
+            (!missing_ranks.is_empty() || !graph_hashes.is_empty() || !guard_counts.is_empty())
+                .then(|| CompileDivergenceRow {
+                    compile_id: cid.to_string(),
+                    missing_ranks,
+                    graph_hashes,
+                    guard_counts,
+                })
+        })
+        .collect();
+    Some(CompileDivergenceContext {
+        css: TEMPLATE_FAILURES_CSS,
+        ranks: rank_rows,
+        num_compiles_differ,
+        rows,
+        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+    })
+}
+
+// Parses every rank's part of the log into rank_{rank}/, with an index.html linking them
+fn parse_partitions(
+    mut partitions: Partitions,
+    config: ParseConfig,
+) -> anyhow::Result<ParseResult> {
+    if !config.custom_parsers.is_empty() {
+        bail!("Custom parsers can't be used when parsing all ranks");
+    }
+    // No rank last
+    partitions.sort_by_key(|rank, _| (rank.is_none(), *rank));
+    let mut output = ParseOutput::default();
+    let mut ranks = Vec::new();
+    let mut rank_compiles_index = RankCompilesIndex::default();
+    for (rank, segments) in partitions {
+        let (dir, name) = rank_dir_and_name(rank);
+        config.progress().warn(&format!("Parsing {dir}"));
+        let read = || io::BufReader::new(SegmentsReader::new(&segments));
+        let lookahead = if config.two_pass {
+            Some(lookahead_pass(read(), &config)?)
+        } else {
+            None
+        };
+        let result = parse_impl(
+            read(),
+            segments_size(&segments)?,
+            config.for_rank(),
+            None,
+            lookahead,
+            false,
+            || io::read_to_string(read()),
+        )?;
+        // Entries of logs named after their rank may not have one
+        rank_compiles_index.extend(
+            result
+                .rank_compiles_index
+                .into_iter()
+                .map(|((_, compile_id), compile)| ((rank, compile_id), compile)),
+        );
+        let mut result = result.output;
+        ranks.push(RankContext {
+            name,
+            url: format!("{dir}/index.html"),
+            num_entries: result.stats.ok,
+            num_failed_entries: result.stats.fail_glog
+                + result.stats.fail_json
+                + result.stats.fail_payload_md5
+                + result.stats.fail_dynamo_guards_json
+                + result.stats.fail_parser,
+        });
+        output.stats += &result.stats;
+        output
+            .regressions
+            .extend(result.regressions.iter().cloned());
+        for (p, contents) in std::mem::take(&mut result.files) {
+            let out = (Path::new(&dir).join(p), contents);
+            match &config.output_sink {
+                Some(sink) => sink.send(out)?,
+                None => output.files.push(out),
+            }
+        }
+        output.ranks.insert(rank, result);
+    }
+    let mut tt = TinyTemplate::new();
+    tt.add_formatter("format_unescaped", tinytemplate::format_unescaped);
+    tt.add_template("ranks_index.html", TEMPLATE_RANKS_INDEX)?;
+    tt.add_template("compile_divergence.html", TEMPLATE_COMPILE_DIVERGENCE)?;
+    let compile_divergence = compile_divergence(&rank_compiles_index);
+    let mut pages = vec![(
+        PathBuf::from("index.html"),
+        tt.render(
+            "ranks_index.html",
+            &RanksIndexContext {
+                css: CSS,
+                num_ranks: ranks.len(),
+                ranks,
+                has_compile_divergence: compile_divergence.is_some(),
+                num_compile_divergences: compile_divergence
+                    .as_ref()
+                    .map_or(0, |divergence| divergence.num_divergences()),
+                compile_ids: merged_compile_ids(&output.ranks),
+                failures: merged_failures(&output.ranks),
+            },
+        )?,
+    )];
+    if let Some(divergence) = &compile_divergence {
+        pages.push((
+            PathBuf::from("compile_divergence.html"),
+            tt.render("compile_divergence.html", divergence)?,
+        ));
+    }
+    for page in pages {
+        match &config.output_sink {
+            Some(sink) => sink.send(page)?,
+            None => output.files.push(page),
+        }
+    }
+    Ok(ParseResult {
+        output,
+        checkpoint: None,
+        rank_compiles_index,
+    })
+}
+
+fn rank_dir_and_name(rank: Option<u32>) -> (String, String) {
+    match rank {
+        Some(r) => (format!("rank_{r}"), r.to_string()),
+        None => ("no_rank".to_string(), "(no rank)".to_string()),
+    }
+}
 
+// Every compile id of every rank, in the order the ranks first had them, with the status of each
+// rank's compilation
+fn merged_compile_ids(ranks: &FxIndexMap<Option<u32>, ParseOutput>) -> Vec<MergedCompileIdContext> {
+    let mut compile_ids: FxIndexMap<Option<CompileId>, Vec<MergedRankCompileContext>> =
+        FxIndexMap::default();
+    for (rank, result) in ranks {
+        let (dir, name) = rank_dir_and_name(*rank);
+        for (compile_id, files) in &result.directory {
+            let mut key = compile_id.clone();
+            if let Some(c) = key.as_mut() {
+                if c.frame_compile_id.is_some() && c.attempt.is_none() {
+                    // data migration for old logs that don't have attempt
+                    c.attempt = Some(0);
+                }
+            }
+            let (status_class, status) = compilation_status(result.metrics.get(&key));
+            compile_ids
+                .entry(compile_id.clone())
+                .or_default()
+                .push(MergedRankCompileContext {
+                    rank: name.clone(),
+                    url: match compile_id {
+                        Some(cid) => format!("{dir}/{}/index.html", cid.as_directory_name()),
+                        None => format!("{dir}/index.html"),
+                    },
+                    status_class,
+                    status,
+                    num_artifacts: files.len(),
+                });
+        }
+    }
+    compile_ids
+        .into_iter()
+        .map(|(compile_id, ranks)| MergedCompileIdContext {
+            compile_id: compile_id.map_or("(unknown)".to_string(), |c| c.to_string()),
+            ranks,
+        })
+        .collect()
+}
+
+// The failures and restarts of every rank, rank by rank
+fn merged_failures(ranks: &FxIndexMap<Option<u32>, ParseOutput>) -> Vec<MergedFailureContext> {
+    ranks
+        .iter()
+        .flat_map(|(rank, result)| {
+            let (dir, name) = rank_dir_and_name(*rank);
+            result.failures.iter().map(move |f| MergedFailureContext {
+                rank: name.clone(),
+                url: format!("{dir}/failures_and_restarts.html"),
+                compile_id: f.compile_id.clone(),
+                kind: f.kind,
+                reason: f.reason.clone(),
+            })
+        })
+        .collect()
+}
+
+// Payloads interrupted by more than this many unrelated lines at once are not stitched together
+const MAX_INTERLEAVED_LINES: usize = 8;
+
+// Numbered log lines, where lines taken while looking ahead can be given back to be read again
+struct LogLines<I> {
+    lines: I,
+    given_back: VecDeque<(usize, String)>,
+}
+
+impl<I: Iterator<Item = (usize, String)>> Iterator for LogLines<I> {
+    type Item = (usize, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.given_back.pop_front().or_else(|| self.lines.next())
+    }
+}
+
+impl<I: Iterator<Item = (usize, String)>> LogLines<I> {
+    fn next_if(&mut self, f: impl FnOnce(&(usize, String)) -> bool) -> Option<(usize, String)> {
+        let next = self.next()?;
+        if f(&next) {
+            Some(next)
+        } else {
+            self.given_back.push_front(next);
+            None
+        }
+    }
+
+    // These are read again, in order, before any other line
+    fn give_back(&mut self, lines: Vec<(usize, String)>) {
+        for line in lines.into_iter().rev() {
+            self.given_back.push_front(line);
+        }
+    }
+}
+
+// Some log collectors interleave unrelated lines into payload blocks.  Look past them for the rest
+// of a payload whose md5 didn't match, and return the stitched payload once it matches.  The
+// unrelated lines are given back to be parsed as usual, as is everything if nothing matches.
+fn stitch_payload<I: Iterator<Item = (usize, String)>>(
+    lines: &mut LogLines<I>,
+    payload: &str,
+    has_lines: bool,
+    matches: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let mut stitched = payload.to_string();
+    let mut first = !has_lines;
+    let mut taken = Vec::new();
+    let mut interleaved = Vec::new();
+    loop {
+        let mut num_interleaved = 0;
+        while num_interleaved < MAX_INTERLEAVED_LINES {
+            let Some(line) = lines.next_if(|(_, l)| !l.starts_with('\t')) else {
+                break;
+            };
+            taken.push(line.clone());
+            interleaved.push(line);
+            num_interleaved += 1;
+        }
+        let mut continued = false;
+        while let Some(line) = lines.next_if(|(_, l)| l.starts_with('\t')) {
+            if !first {
+                stitched.push('\n');
+            }
+            first = false;
+            stitched.push_str(&line.1[1..]);
+            taken.push(line);
+            continued = true;
+        }
+        if !continued {
+            break;
+        }
+        if matches(&stitched) {
+            lines.give_back(interleaved);
+            return Some(stitched);
+        }
+    }
+    lines.give_back(taken);
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PayloadCheck {
+    Unchecked,
+    Ok,
+    // Only matched its md5 once unrelated lines interleaved into it were left out
+    Stitched,
+    Mismatch,
+}
+
+// Reads the payload lines of an entry and checks them against the entry's md5, unless the payload
+// is shorter than verify_min_bytes (or that is None)
+fn read_payload<I: Iterator<Item = (usize, String)>>(
+    lines: &mut LogLines<I>,
+    expect: &str,
+    verify_min_bytes: Option<usize>,
+) -> (String, PayloadCheck) {
+    let mut payload = String::new();
+    let mut first = true;
+    while let Some((_payload_lineno, payload_line)) = lines.next_if(|(_, l)| l.starts_with('\t')) {
+        // Careful! Distinguish between missing EOL and not
+        if !first {
+            payload.push('\n');
+        }
+        first = false;
+        payload.push_str(&payload_line[1..]);
+    }
+    if verify_min_bytes.is_none_or(|min| payload.len() < min) {
+        return (payload, PayloadCheck::Unchecked);
+    }
+    let mut expect_buf = [0u8; 16];
+    let expected = base16ct::lower::decode(expect, &mut expect_buf).is_ok();
+    let matches = |p: &str| expected && expect_buf == Md5::digest(p.as_bytes())[..];
+    if matches(&payload) {
+        return (payload, PayloadCheck::Ok);
+    }
+    match stitch_payload(lines, &payload, !first, matches) {
+        Some(stitched) => (stitched, PayloadCheck::Stitched),
+        None => (payload, PayloadCheck::Mismatch),
+    }
+}
+
+fn send_all_outputs(output: &mut OutputFiles, sink: Option<&Sender<(PathBuf, String)>>) {
+    if let Some(sink) = sink {
+        for o in output.drain(..) {
+            // The receiver hanging up is for the caller to report, e.g., when joining a writer
+            // thread
+            let _ = sink.send(o);
+        }
+    }
+}
+
+// The options a report was generated with, for its metadata.  Every option is named, so that a new
+// one has to be added here (or skipped explicitly) for the code to build
+fn report_config(
+    config: &ParseConfig,
+    recompile_storm_threshold: usize,
+    recompile_storm_window_s: f64,
+    regression_threshold_pct: f64,
+    metrics_columns: &[String],
+) -> ReportConfig {
+    let ParseConfig {
+        strict,
+        strict_compile_id,
+        custom_parsers,
+        custom_header_html,
+        verbose,
+        plain_text,
+        render_budget_s,
+        export,
+        max_inline_compile_ids,
+        output_budget,
+        source_url_template,
+        known_issues,
+        recompile_storm_threshold: _,
+        recompile_storm_window_s: _,
+        baseline,
+        regression_threshold_pct: _,
+        output_sink: _,
+        hide_abandoned_attempts,
+        metrics_columns: _,
+        no_verify_payload,
+        verify_payload_min_bytes,
+        json,
+        max_compiles,
+        all_ranks,
+        two_pass,
+        hide_frames,
+        max_stack_depth,
+        stack_trie_times,
+        link_rewrites,
+        progress: _,
+    } = config.clone();
+    ReportConfig {
+        strict,
+        strict_compile_id,
+        custom_parsers: custom_parsers.iter().map(|p| p.name()).collect(),
+        custom_header_html,
+        verbose,
+        plain_text,
+        render_budget_s: render_budget_s.unwrap_or(DEFAULT_RENDER_BUDGET_S),
+        export,
+        max_inline_compile_ids,
+        output_budget,
+        source_url_template,
+        known_issues,
+        recompile_storm_threshold,
+        recompile_storm_window_s,
+        baseline: baseline.is_some(),
+        regression_threshold_pct,
+        hide_abandoned_attempts,
+        metrics_columns: metrics_columns.to_vec(),
+        no_verify_payload,
+        verify_payload_min_bytes,
+        json,
+        max_compiles,
+        all_ranks,
+        two_pass,
+        hide_frames,
+        max_stack_depth,
+        stack_trie_times,
+        link_rewrites,
+    }
+}
+
+// The templates of a report, or of an export report
+fn templates(export: bool) -> anyhow::Result<TinyTemplate<'static>> {
     let mut tt: TinyTemplate = TinyTemplate::new();
     tt.add_formatter("format_unescaped", tinytemplate::format_unescaped);
-    if config.export {
+    tt.add_formatter("format_duration", duration_formatter);
+    tt.add_formatter("format_bytes", bytes_formatter);
+    if export {
         tt.add_template("index.html", TEMPLATE_EXPORT_INDEX)?;
         tt.add_template(
             "symbolic_guard_information.html",
             TEMPLATE_SYMBOLIC_GUARD_INFO,
         )?;
-    } else {
-        tt.add_template("index.html", TEMPLATE_INDEX)?;
-        tt.add_template("failures_and_restarts.html", TEMPLATE_FAILURES_AND_RESTARTS)?;
-        tt.add_template("dynamo_guards.html", TEMPLATE_DYNAMO_GUARDS)?;
-        tt.add_template("compilation_metrics.html", TEMPLATE_COMPILATION_METRICS)?;
-        tt.add_template(
-            "bwd_compilation_metrics.html",
-            TEMPLATE_BWD_COMPILATION_METRICS,
-        )?;
-        tt.add_template(
-            "aot_autograd_backward_compilation_metrics.html",
-            TEMPLATE_AOT_AUTOGRAD_BACKWARD_COMPILATION_METRICS,
-        )?;
+        return Ok(tt);
     }
+    tt.add_template("index.html", TEMPLATE_INDEX)?;
+    tt.add_template("failures_and_restarts.html", TEMPLATE_FAILURES_AND_RESTARTS)?;
+    tt.add_template("print.html", TEMPLATE_PRINT)?;
+    tt.add_template("raw.html", TEMPLATE_RAW_CONTEXT)?;
+    tt.add_template(
+        "symbolic_shape_specializations.html",
+        TEMPLATE_SYMBOLIC_SHAPE_SPECIALIZATIONS,
+    )?;
+    tt.add_template("dynamo_guards.html", TEMPLATE_DYNAMO_GUARDS)?;
+    tt.add_template("recompile_thrashing.html", TEMPLATE_RECOMPILE_THRASHING)?;
+    tt.add_template("modules.html", TEMPLATE_COMPILES_BY_MODULE)?;
+    tt.add_template("non_compliant_ops.html", TEMPLATE_NON_COMPLIANT_OPS)?;
+    tt.add_template(
+        "specialization_recompiles.html",
+        TEMPLATE_SPECIALIZATION_RECOMPILES,
+    )?;
+    tt.add_template("cross_rank_metrics.html", TEMPLATE_CROSS_RANK_METRICS)?;
+    tt.add_template("compile_divergence.html", TEMPLATE_COMPILE_DIVERGENCE)?;
+    tt.add_template("compile_id_index.html", TEMPLATE_COMPILE_ID_INDEX)?;
+    tt.add_template("compile_directory.html", TEMPLATE_COMPILE_DIRECTORY)?;
+    tt.add_template("directory_page.html", TEMPLATE_DIRECTORY_PAGE)?;
+    tt.add_template("frame_history.html", TEMPLATE_FRAME_HISTORY)?;
+    tt.add_template("tensor_catalog.html", TEMPLATE_TENSOR_CATALOG)?;
+    tt.add_template(
+        "dynamo_output_graph_sizes.html",
+        TEMPLATE_DYNAMO_OUTPUT_GRAPH_SIZES,
+    )?;
+    tt.add_template("inductor_output_code.html", TEMPLATE_INDUCTOR_OUTPUT_CODE)?;
+    tt.add_template("compilation_metrics.html", TEMPLATE_COMPILATION_METRICS)?;
+    tt.add_template(
+        "bwd_compilation_metrics.html",
+        TEMPLATE_BWD_COMPILATION_METRICS,
+    )?;
+    tt.add_template(
+        "aot_autograd_backward_compilation_metrics.html",
+        TEMPLATE_AOT_AUTOGRAD_BACKWARD_COMPILATION_METRICS,
+    )?;
+    Ok(tt)
+}
 
-    let mut unknown_fields: FxHashSet<String> = FxHashSet::default();
-
-    let mut output_count = 0;
-
-    let mut breaks = RestartsAndFailuresContext {
-        css: TEMPLATE_FAILURES_CSS,
-        failures: Vec::new(),
-        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
-    };
-
-    let mut export_failures: Vec<ExportFailure> = Vec::new();
+// Everything parse_impl sets up once per log, for parse_entry to parse each entry into a
+// ParseCheckpoint, and for render to make the report from it
+struct ParseContext<'a> {
+    config: &'a ParseConfig,
+    tt: &'a TinyTemplate<'a>,
+    parsers: Vec<&'a dyn StructuredLogParser>,
+    progress: Rc<dyn ProgressSink>,
+    known_issues: KnownIssues,
+    link_rewriter: LinkRewriter,
+    frame_filter: &'a FrameFilter,
+    shared: &'a SharedIndices,
+    lookahead: Option<&'a Lookahead>,
+    // Recompile reasons already in recompile_reasons_index, including those of a checkpoint
+    interned_reasons: Interner<String>,
+    metrics_columns: Vec<String>,
+    recompile_storm_threshold: usize,
+    recompile_storm_window_s: f64,
+    regression_threshold_pct: f64,
+    report_config: ReportConfig,
+}
 
-    // NB: Sometimes, the log output we get from Logarithm stutters with a blank line.
-    // Filter them out, they're never valid (a blank line in payload will still be \t)
-    let mut iter = reader
-        .lines()
-        .enumerate()
-        .filter_map(|(i, l)| match l {
-            // 1-indexed line numbers please
-            Ok(l) if !l.is_empty() => Some((i + 1, l)),
-            _ => None,
-        })
-        .peekable();
+impl ParseContext<'_> {
+    // Metrics pages show what the lookahead pass found in the whole log, when there was one, so
+    // that what was logged after the compilation metrics shows too
+    fn metrics_parser<'p>(
+        &'p self,
+        output_files: &'p Vec<OutputFile>,
+        compile_id_dir: &'p PathBuf,
+    ) -> crate::parsers::CompilationMetricsParser<'p> {
+        let shared = self.shared;
+        crate::parsers::CompilationMetricsParser {
+            tt: self.tt,
+            stack_index: self
+                .lookahead
+                .map_or(&shared.stack_index, |l| &l.stack_index),
+            symbolic_shape_specialization_index: self
+                .lookahead
+                .map_or(&shared.symbolic_shape_specialization_index, |l| {
+                    &l.symbolic_shape_specialization_index
+                }),
+            guard_added_fast_index: self.lookahead.map_or(&shared.guard_added_fast_index, |l| {
+                &l.guard_added_fast_index
+            }),
+            runtime_assert_index: self
+                .lookahead
+                .map_or(&shared.runtime_assert_index, |l| &l.runtime_assert_index),
+            dynamo_guards_index: &shared.dynamo_guards_index,
+            input_source_index: &shared.input_source_index,
+            output_files,
+            compile_id_dir,
+            source_url_template: self.config.source_url_template.as_deref(),
+            frame_filter: self.frame_filter,
+        }
+    }
 
-    let mut all_parsers = default_parsers(&tt, &config);
-    all_parsers.extend(config.custom_parsers);
-    let mut chromium_events: Vec<serde_json::Value> = Vec::new();
+    fn report_metadata(&self, raw_log: &str, stats: &Stats) -> anyhow::Result<(PathBuf, String)> {
+        let metadata = ReportMetadata {
+            tlparse_version: env!("CARGO_PKG_VERSION"),
+            parsed_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            input_md5: format!("{:x}", Md5::digest(raw_log.as_bytes())),
+            input_bytes: raw_log.len(),
+            config: self.report_config.clone(),
+            stats: stats.clone(),
+        };
+        Ok((
+            PathBuf::from(REPORT_METADATA_PATH),
+            serde_json::to_string_pretty(&metadata)?,
+        ))
+    }
 
-    while let Some((lineno, line)) = iter.next() {
-        bytes_read += line.len() as u64;
-        pb.set_position(bytes_read);
-        spinner.set_message(format!("{:?}", stats));
-        //spinner.set_message(format!("{:?} {:?}", slowest_time, fastest_time));
-        let start = Instant::now();
-
-        let Some(caps) = re_glog.captures(&line) else {
-            multi.suspend(|| eprintln!("Failed to parse glog prefix on line {}", lineno));
-            stats.fail_glog += 1;
-            continue;
+    // Parses the entry on line lineno (and its payload, from the lines after it) into state
+    fn parse_entry<I: Iterator<Item = (usize, String)>>(
+        &mut self,
+        state: &mut ParseCheckpoint,
+        lineno: usize,
+        line: &str,
+        lines: &mut LogLines<I>,
+    ) -> anyhow::Result<()> {
+        let Some(prefix) = GlogPrefix::parse(line) else {
+            self.progress
+                .warn(&format!("Failed to parse glog prefix on line {}", lineno));
+            state.stats.fail_glog += 1;
+            return Ok(());
         };
 
-        let end = start.elapsed();
-        if end < fastest_time {
-            fastest_time = end;
-        }
-        if end > slowest_time {
-            slowest_time = end;
-        }
-        let payload = &line[caps.name("payload").unwrap().start()..];
+        let payload = &line[prefix.payload_start..];
+        let glog_ts = prefix.micros();
 
-        let e = match serde_json::from_str::<Envelope>(payload) {
+        let mut e = match serde_json::from_str::<Envelope>(payload) {
             Ok(r) => r,
             Err(err) => {
-                multi.suspend(|| {
-                    eprintln!("Failed to parse metadata JSON: {}\n{:?}", payload, err);
-                });
-                stats.fail_json += 1;
-                continue;
+                self.progress.warn(&format!(
+                    "Failed to parse metadata JSON: {}\n{:?}",
+                    payload, err
+                ));
+                state.stats.fail_json += 1;
+                return Ok(());
             }
         };
 
-        stats.unknown += e._other.len() as u64;
+        if let Some(link) = &mut e.link {
+            link.url = self.link_rewriter.rewrite(&link.url);
+        }
+
+        state.stats.unknown += e._other.len() as u64;
 
         for k in e._other.keys() {
-            unknown_fields.insert(k.clone());
-            if config.verbose {
-                multi.suspend(|| eprintln!("Unknown field {}", k))
+            state.unknown_fields.insert(k.clone());
+            if self.config.verbose {
+                self.progress.warn(&format!("Unknown field {}", k))
             }
         }
 
         if let Some((s, i)) = e.str {
-            let mut intern_table = INTERN_TABLE.lock().unwrap();
-            intern_table.insert(i, s);
-            continue;
+            if let Some(install) = torch_install(&s) {
+                if !state.torch_installs.contains_key(install) {
+                    state.torch_installs.insert(install.to_string(), lineno);
+                }
+            }
+            INTERN_TABLE.with_borrow_mut(|intern_table| intern_table.insert(i, s));
+            return Ok(());
         };
 
         let mut payload = String::new();
         if let Some(ref expect) = e.has_payload {
-            let mut first = true;
-            while let Some((_payload_lineno, payload_line)) =
-                iter.next_if(|(_, l)| l.starts_with('\t'))
-            {
-                // Careful! Distinguish between missing EOL and not
-                if !first {
-                    payload.push('\n');
-                }
-                first = false;
-                payload.push_str(&payload_line[1..]);
-            }
-            let mut hasher = Md5::new();
-            hasher.update(&payload);
-            let hash = hasher.finalize();
-            let mut expect_buf = [0u8; 16];
-            if base16ct::lower::decode(expect, &mut expect_buf).is_ok() {
-                if expect_buf != hash[..] {
-                    // TODO: error log
-                    stats.fail_payload_md5 += 1;
-                }
-            } else {
-                stats.fail_payload_md5 += 1;
+            let check;
+            (payload, check) = read_payload(lines, expect, self.config.payload_check_min_bytes());
+            match check {
+                PayloadCheck::Stitched => state.stats.stitched_payload += 1,
+                // TODO: error log
+                PayloadCheck::Mismatch => state.stats.fail_payload_md5 += 1,
+                PayloadCheck::Unchecked | PayloadCheck::Ok => {}
             }
         }
 
-        match expected_rank {
+        // Stack frames refer to their file names by intern table ids
+        for text in [line, payload.as_str()] {
+            if !text.contains("\"filename\": ") {
+                continue;
+            }
+            INTERN_TABLE.with_borrow(|intern_table| {
+                for caps in INTERNED_FILENAME_RE.captures_iter(text) {
+                    let Ok(id) = caps[1].parse::<u32>() else {
+                        continue;
+                    };
+                    if !intern_table.contains_key(&id) {
+                        state
+                            .interned_str_refs
+                            .entry(id)
+                            .or_insert(InternedStrRefs {
+                                first_lineno: lineno,
+                                count: 0,
+                            })
+                            .count += 1;
+                    }
+                }
+            });
+        }
+
+        // Collected before the rank filter below, so ranks can be compared against each other
+        record_rank_compile(&mut state.rank_compiles_index, e.rank, &e);
+        if let Some(ref m) = e.compilation_metrics {
+            state
+                .cross_rank_metrics_index
+                .entry(e.compile_id.clone())
+                .or_default()
+                .push((e.rank, m.clone()));
+        }
+
+        match state.expected_rank {
             Some(rank) => {
                 if rank != e.rank {
-                    stats.other_rank += 1;
-                    continue;
+                    state.stats.other_rank += 1;
+                    return Ok(());
                 }
             }
             None => {
-                multi.suspend(|| {
-                    eprintln!("Detected rank: {:?}", e.rank);
-                });
-                expected_rank = Some(e.rank);
+                self.progress.warn(&format!("Detected rank: {:?}", e.rank));
+                state.expected_rank = Some(e.rank);
             }
         };
 
-        stats.ok += 1;
+        state.stats.ok += 1;
 
         // lol this clone, probably shouldn't use entry
         // TODO: output should be able to generate this without explicitly creating
-        let compile_directory = directory.entry(e.compile_id.clone()).or_default();
+        *state
+            .logged_from_index
+            .entry(e.compile_id.clone())
+            .or_default()
+            .entry(format!("{}:{}", prefix.pathname.trim(), prefix.line))
+            .or_default() += 1;
+
+        let compile_directory = state.directory.entry(e.compile_id.clone()).or_default();
+        let num_outputs_before = compile_directory.len();
+        let num_output_files_before = state.output.len();
+
+        // Entries without a compile id are always rendered
+        let render = match self.config.max_compiles {
+            Some(max) if e.compile_id.is_some() => {
+                state.rendered_compile_ids.contains(&e.compile_id)
+                    || (state.rendered_compile_ids.len() < max
+                        && state.rendered_compile_ids.insert(e.compile_id.clone()))
+            }
+            _ => true,
+        };
 
-        for parser in &all_parsers {
+        for parser in self.parsers.iter().filter(|_| render) {
             run_parser(
                 lineno,
-                parser,
+                *parser,
                 &e,
                 &payload,
-                &mut output_count,
-                &mut output,
+                &mut state.output_count,
+                &mut state.output,
                 compile_directory,
-                &multi,
-                &mut stats,
+                &*self.progress,
+                &mut state.stats,
+                self.config.output_budget,
+                &mut state.output_usage,
             )
         }
 
+        // Guards pages link to the compilation metrics page, which doesn't have a name yet, so
+        // they are held back until it does
+        if let Some(guards_file) = compile_directory[num_outputs_before..]
+            .iter()
+            .find(|f| f.artifact_kind == ArtifactKind::Guards && f.url.ends_with(".html"))
+        {
+            let guards_path = Path::new(&guards_file.url);
+            if let Some(i) = state.output[num_output_files_before..]
+                .iter()
+                .position(|(p, _)| p == guards_path)
+            {
+                let page = state.output.remove(num_output_files_before + i);
+                state
+                    .pending_guards_pages
+                    .insert(e.compile_id.clone(), page);
+            }
+        }
+
+        if let Some(ts) = glog_ts {
+            let times = state
+                .glog_times_index
+                .entry(e.compile_id.clone())
+                .or_insert_with(|| GlogTimes::new(ts));
+            times.add(ts);
+            times.artifacts.extend(
+                compile_directory[num_outputs_before..]
+                    .iter()
+                    .map(|f| (f.name.rsplit('/').next().unwrap_or(&f.name).to_string(), ts)),
+            );
+        }
+
         if let Some(ref m) = e.compilation_metrics {
+            if let (Some(ts), Some(start), Some(t)) =
+                (glog_ts, m.start_time, m.entire_frame_compile_time_s)
+            {
+                state.glog_clock_samples.push((ts, start + t));
+            }
             let copied_directory = compile_directory.clone();
             let compile_id_dir: PathBuf = e
                 .compile_id
                 .as_ref()
                 .map_or(format!("unknown_{lineno}"), |cid| cid.as_directory_name())
                 .into();
-            let parser: Box<dyn StructuredLogParser> =
-                Box::new(crate::parsers::CompilationMetricsParser {
-                    tt: &tt,
-                    stack_index: &stack_index,
-                    symbolic_shape_specialization_index: &symbolic_shape_specialization_index,
-                    guard_added_fast_index: &guard_added_fast_index,
-                    output_files: &copied_directory,
-                    compile_id_dir: &compile_id_dir,
-                });
-            run_parser(
-                lineno,
-                &parser,
-                &e,
-                &payload,
-                &mut output_count,
-                &mut output,
-                compile_directory,
-                &multi,
-                &mut stats,
-            );
+            let parser = self.metrics_parser(&copied_directory, &compile_id_dir);
+            // Without a metrics page, failures link to the compile id's landing page
+            let mut metrics_filename = "index.html".to_string();
+            if render {
+                run_parser(
+                    lineno,
+                    &parser,
+                    &e,
+                    &payload,
+                    &mut state.output_count,
+                    &mut state.output,
+                    compile_directory,
+                    &*self.progress,
+                    &mut state.stats,
+                    self.config.output_budget,
+                    &mut state.output_usage,
+                );
 
-            // compilation metrics is always the last output, since it just ran
-            let metrics_filename = format!(
-                "compilation_metrics_{}.html",
-                (output_count - 1).to_string(),
-            );
+                // compilation metrics is always the last output, since it just ran
+                metrics_filename = format!("compilation_metrics_{}.html", state.output_count - 1);
+                // Now that the metrics page has a name, point the guards page's specialization
+                // links at it
+                if let Some((path, contents)) =
+                    state.pending_guards_pages.swap_remove(&e.compile_id)
+                {
+                    let contents = contents.replace(
+                        &format!("href=\"{COMPILATION_METRICS_PLACEHOLDER_URL}#"),
+                        &format!("href=\"{metrics_filename}#"),
+                    );
+                    state.output.push((path, contents));
+                }
+            }
             let id = e.compile_id.clone().map_or("(unknown) ".to_string(), |c| {
                 format!(
                     "<a href='{}/{}'>{cid}</a> ",
@@ -427,11 +1919,30 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
                     cid = c,
                 )
             });
+            // The unstructured log around the entry often has more context, e.g., warnings or C++
+            // asserts
+            let raw_context = format!(
+                "<td><a href='raw.html#L{}'>around line {lineno}</a></td>",
+                lineno.saturating_sub(RAW_CONTEXT_LINES).max(1)
+            );
+            if m.restart_reasons.as_ref().is_some_and(|rr| !rr.is_empty()) || m.fail_type.is_some()
+            {
+                state.raw_context_lines.push(lineno);
+            }
+            let anchor = compile_id_anchor(e.compile_id.as_ref());
+            let legacy_anchor = legacy_compile_id_anchor(e.compile_id.as_ref());
             if let Some(rr) = m.restart_reasons.as_ref() {
-                for restart in rr {
-                    breaks.failures.push((
-                        id.clone(),
-                        format!("{}", FailureReason::Restart(restart.clone())),
+                for (k, restart) in rr.iter().enumerate() {
+                    state.failures.push((
+                        format!(
+                            "<a id='restart-{anchor}-{k}'></a><a id='restart-{legacy_anchor}-{k}'></a>{id}",
+                            k = k + 1
+                        ),
+                        format!(
+                            "{}{raw_context}{}",
+                            FailureReason::Restart(restart.clone()),
+                            self.known_issues.html_cell(restart)
+                        ),
                     ));
                 }
             }
@@ -449,11 +1960,17 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
                     f.clone(),
                     reason.clone(),
                     user_frame_filename.clone(),
-                    user_frame_lineno.clone(),
+                    user_frame_lineno,
+                ));
+                state.failures.push((
+                    format!(
+                        "<a id='failure-{anchor}'></a><a id='failure-{legacy_anchor}'></a>{id}"
+                    ),
+                    format!(
+                        "{failure_reason}{raw_context}{}",
+                        self.known_issues.html_cell(&format!("{f}: {reason}"))
+                    ),
                 ));
-                breaks
-                    .failures
-                    .push((id.clone(), format!("{failure_reason}")));
             }
             let mut cid = e.compile_id.clone();
             if let Some(c) = cid.as_mut() {
@@ -462,44 +1979,143 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
                     c.attempt = Some(0);
                 }
             }
-            metrics_index.entry(cid).or_default().push(m.clone());
+            state.metrics_index.entry(cid).or_default().push(m.clone());
         }
 
         if let Some(stack) = e.stack {
-            unknown_stack_trie.insert(stack.clone(), None);
+            state.unknown_stack_trie.insert(stack.clone(), None);
         }
 
-        if let Some(_) = e.chromium_event {
-            chromium_events.push(serde_json::from_str(&payload)?);
+        if e.chromium_event.is_some() {
+            match serde_json::from_str(&payload) {
+                Ok(event) => state.chromium_events.push(event),
+                Err(err) => state
+                    .chromium_event_problems
+                    .push(format!("Dropped malformed event on line {lineno}: {err}")),
+            }
         }
 
         if let Some(specialization) = e.symbolic_shape_specialization {
-            symbolic_shape_specialization_index
+            let key = (
+                specialization.sources.clone().unwrap_or_default(),
+                specialization.value.clone().unwrap_or_default(),
+            );
+            let summary = state
+                .specialization_summary_index
+                .entry(key)
+                .or_insert_with_key(|(sources, value)| SymbolicShapeSpecializationSummary {
+                    sources: sources.clone(),
+                    value: value.clone(),
+                    ..Default::default()
+                });
+            summary.count += 1;
+            if let Some(symbol) = &specialization.symbol {
+                if !summary.symbols.contains(symbol) {
+                    summary.symbols.push(symbol.clone());
+                }
+            }
+            let cid = CompileIdLink::new(e.compile_id.as_ref());
+            if !summary.compile_ids.contains(&cid) {
+                summary.compile_ids.push(cid);
+            }
+            self.shared
+                .symbolic_shape_specialization_index
                 .borrow_mut()
                 .entry(e.compile_id.clone())
                 .or_default()
                 .push(specialization);
         }
+        if let Some(artifact) = &e.artifact {
+            if artifact.name == "recompile_reasons" {
+                if let Ok(reasons) = serde_json::from_str::<Vec<String>>(&payload) {
+                    state
+                        .recompile_reasons_index
+                        .entry(e.compile_id.clone())
+                        .or_default()
+                        .extend(reasons.into_iter().map(|r| self.interned_reasons.intern(r)));
+                }
+            }
+        }
+        if let Some(link) = &e.link {
+            state
+                .link_index
+                .entry(e.compile_id.clone())
+                .or_default()
+                .push(link.clone());
+        }
+        if e.dynamo_output_graph.is_some() {
+            if let Some(counts) = graph_node_counts(&payload) {
+                state
+                    .graph_node_counts_index
+                    .insert(e.compile_id.clone(), counts);
+            }
+        }
+        if let Some(storage) = e.describe_storage {
+            state
+                .storage_descs
+                .insert((storage.describer_id, storage.id), storage);
+        }
+        if let Some(tensor) = e.describe_tensor {
+            state
+                .tensor_descs
+                .insert((tensor.describer_id, tensor.id), tensor);
+        }
+        if let Some(source) = e.describe_source {
+            if let Some(tensor) = state.tensor_descs.get(&(source.describer_id, source.id)) {
+                self.shared
+                    .input_source_index
+                    .borrow_mut()
+                    .entry(e.compile_id.clone())
+                    .or_default()
+                    .push(InputSource::new(
+                        source.source,
+                        tensor,
+                        tensor
+                            .storage
+                            .and_then(|id| state.storage_descs.get(&(tensor.describer_id, id))),
+                    ));
+            }
+        }
+
         if let Some(guard_added_fast) = e.guard_added_fast {
-            guard_added_fast_index
+            self.shared
+                .guard_added_fast_index
                 .borrow_mut()
                 .entry(e.compile_id.clone())
                 .or_default()
                 .push(guard_added_fast)
         }
 
+        if let Some(guard_added) = e.guard_added {
+            // Ordinary guards are already on the dynamo_guards page
+            if guard_added.prefix.as_deref() == Some("runtime_assert") {
+                self.shared
+                    .runtime_assert_index
+                    .borrow_mut()
+                    .entry(e.compile_id.clone())
+                    .or_default()
+                    .push(guard_added)
+            }
+        }
+
         if let Some(m) = e.dynamo_start {
+            // Logs from repeated runs reuse compile ids; inputs are described after this
+            self.shared
+                .input_source_index
+                .borrow_mut()
+                .remove(&e.compile_id);
             if let Some(mut stack) = m.stack {
                 maybe_remove_convert_frame_suffixes(&mut stack);
-                stack_index
+                self.shared
+                    .stack_index
                     .borrow_mut()
                     .insert(e.compile_id.clone(), stack.clone());
-                stack_trie.insert(stack, e.compile_id.clone());
+                state.stack_trie.insert(stack, e.compile_id.clone());
             };
         };
 
         if let Some(guard) = e.propagate_real_tensors {
-            if config.export {
+            if self.config.export {
                 let failure_type = "Data Dependent Error";
 
                 let reason = format!(
@@ -511,10 +2127,8 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
                     guard.result.unwrap()
                 );
 
-                let filename = format!(
-                    "symbolic_guard_information_{}.html",
-                    (output_count - 1).to_string(),
-                );
+                let filename =
+                    format!("symbolic_guard_information_{}.html", state.output_count - 1);
                 let compile_id_dir: PathBuf = e
                     .compile_id
                     .as_ref()
@@ -526,16 +2140,16 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
                     filename,
                 );
 
-                export_failures.push(ExportFailure {
+                state.export_failures.push(ExportFailure {
                     failure_type: failure_type.to_string(),
-                    reason: reason,
-                    additional_info: additional_info,
+                    reason,
+                    additional_info,
                 });
             }
         }
 
         if let Some(fake_kernel) = e.missing_fake_kernel {
-            if config.export {
+            if self.config.export {
                 let failure_type = "Missing Fake Kernel";
 
                 let reason = format!(
@@ -545,16 +2159,16 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
 
                 let additional_info = "Please refer to <a href='https://docs.google.com/document/d/1_W62p8WJOQQUzPsJYa7s701JXt0qf2OfLub2sbkHOaU/edit#heading=h.ahugy69p2jmz'>this doc</a> for more detailed instructions on how to write a fake kernel.";
 
-                export_failures.push(ExportFailure {
+                state.export_failures.push(ExportFailure {
                     failure_type: failure_type.to_string(),
-                    reason: reason,
+                    reason,
                     additional_info: additional_info.to_string(),
                 });
             }
         }
 
         if let Some(fake_kernel) = e.mismatched_fake_kernel {
-            if config.export {
+            if self.config.export {
                 let failure_type = "Mismatched Fake Kernel";
 
                 let reason = format!(
@@ -567,19 +2181,962 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
 
                 let additional_info = "Please refer to <a href='https://docs.google.com/document/d/1_W62p8WJOQQUzPsJYa7s701JXt0qf2OfLub2sbkHOaU/edit#heading=h.ahugy69p2jmz'>this doc</a> for more detailed instructions on how to write a fake kernel.";
 
-                export_failures.push(ExportFailure {
+                state.export_failures.push(ExportFailure {
                     failure_type: failure_type.to_string(),
-                    reason: reason,
+                    reason,
                     additional_info: additional_info.to_string(),
                 });
             }
         }
+        Ok(())
+    }
+
+    // The report of what was parsed into state.  raw_log is the whole log
+    fn render(
+        &self,
+        mut state: ParseCheckpoint,
+        raw_log: impl FnOnce() -> io::Result<String>,
+    ) -> anyhow::Result<ParseOutput> {
+        // The guards pages of compile ids without compilation metrics (so far) keep their
+        // specializations, without linking them
+        for (_, (path, contents)) in std::mem::take(&mut state.pending_guards_pages) {
+            let contents = PENDING_SPECIALIZATION_LINK_RE.replace_all(&contents, "$1");
+            state.output.push((path, contents.into_owned()));
+        }
+        if self.config.export {
+            return self.render_export(state, raw_log);
+        }
+
+        let breaks = RestartsAndFailuresContext {
+            css: TEMPLATE_FAILURES_CSS,
+            failures: std::mem::take(&mut state.failures),
+            qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+        };
+        state.output.push((
+            PathBuf::from("failures_and_restarts.html"),
+            self.tt.render("failures_and_restarts.html", &breaks)?,
+        ));
+        self.progress.finish(&state.stats);
+
+        let num_specializations = state.specialization_summary_index.len();
+        let mut specializations: Vec<SymbolicShapeSpecializationSummary> = state
+            .specialization_summary_index
+            .drain(..)
+            .map(|(_, v)| v)
+            .collect();
+        // Most frequently specialized first; sort is stable so ties stay in log order
+        specializations.sort_by_key(|s| std::cmp::Reverse(s.count));
+        state.output.push((
+            PathBuf::from("symbolic_shape_specializations.html"),
+            self.tt.render(
+                "symbolic_shape_specializations.html",
+                &SymbolicShapeSpecializationsContext {
+                    css: TEMPLATE_FAILURES_CSS,
+                    specializations,
+                    qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                },
+            )?,
+        ));
+
+        let thrashing = recompile_thrashing(&state.recompile_reasons_index);
+        let num_recompile_thrashing = thrashing.len();
+        if !thrashing.is_empty() {
+            state.output.push((
+                PathBuf::from("recompile_thrashing.html"),
+                self.tt.render(
+                    "recompile_thrashing.html",
+                    &RecompileThrashingContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        thrashing,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let modules = compiles_by_module(&state.metrics_index, &self.shared.stack_index.borrow());
+        let num_modules = modules.len();
+        if !modules.is_empty() {
+            state.output.push((
+                PathBuf::from("modules.html"),
+                self.tt.render(
+                    "modules.html",
+                    &ModuleCompilesContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        modules,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let ops = non_compliant_ops(&state.metrics_index);
+        let num_non_compliant_ops = ops.len();
+        if !ops.is_empty() {
+            state.output.push((
+                PathBuf::from("non_compliant_ops.html"),
+                self.tt.render(
+                    "non_compliant_ops.html",
+                    &NonCompliantOpsContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        ops,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let chains = specialization_recompiles(
+            &self.shared.symbolic_shape_specialization_index.borrow(),
+            &state.recompile_reasons_index,
+        );
+        let num_specialization_recompiles = chains.iter().map(|c| c.links.len()).sum();
+        if !chains.is_empty() {
+            state.output.push((
+                PathBuf::from("specialization_recompiles.html"),
+                self.tt.render(
+                    "specialization_recompiles.html",
+                    &SpecializationRecompilesContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        chains,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let ranks: FxIndexSet<Option<u32>> = state
+            .cross_rank_metrics_index
+            .values()
+            .flatten()
+            .map(|(rank, _)| *rank)
+            .collect();
+        let has_cross_rank_metrics = ranks.len() > 1;
+        if has_cross_rank_metrics {
+            let fmt_rank = |r: &Option<u32>| r.map_or("(unknown)".to_string(), |r| r.to_string());
+            let rows = state
+                .cross_rank_metrics_index
+                .drain(..)
+                .map(|(cid, metrics)| {
+                    let mut times: Vec<f64> = metrics
+                        .iter()
+                        .filter_map(|(_, m)| m.entire_frame_compile_time_s)
+                        .collect();
+                    times.sort_by(f64::total_cmp);
+                    let median = match times.len() {
+                        0 => None,
+                        n if n % 2 == 0 => Some((times[n / 2 - 1] + times[n / 2]) / 2.0),
+                        n => Some(times[n / 2]),
+                    };
+                    let fmt_time =
+                        |t: Option<&f64>| t.map_or("".to_string(), |t| format_duration(*t));
+                    CrossRankMetricsRow {
+                        compile_id: cid.map_or("(unknown)".to_string(), |c| c.to_string()),
+                        num_ranks: metrics.len(),
+                        min_time: fmt_time(times.first()),
+                        median_time: fmt_time(median.as_ref()),
+                        max_time: fmt_time(times.last()),
+                        failed_ranks: metrics
+                            .iter()
+                            .filter(|(_, m)| m.fail_type.is_some())
+                            .map(|(r, _)| fmt_rank(r))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    }
+                })
+                .collect();
+            state.output.push((
+                PathBuf::from("cross_rank_metrics.html"),
+                self.tt.render(
+                    "cross_rank_metrics.html",
+                    &CrossRankMetricsContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        ranks: ranks.iter().map(fmt_rank).collect::<Vec<_>>().join(", "),
+                        rows,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let compile_divergence = compile_divergence(&state.rank_compiles_index);
+        let num_compile_divergences = compile_divergence
+            .as_ref()
+            .map_or(0, |divergence| divergence.num_divergences());
+        if let Some(divergence) = &compile_divergence {
+            state.output.push((
+                PathBuf::from("compile_divergence.html"),
+                self.tt.render("compile_divergence.html", divergence)?,
+            ));
+        }
+
+        let (mut chromium_events, repair_problems) = repair_chromium_events(state.chromium_events);
+        state.chromium_event_problems.extend(repair_problems);
+        chromium_events.extend(synthesize_chromium_events(
+            &state.glog_times_index,
+            &state.metrics_index,
+            &state.glog_clock_samples,
+        ));
+        for problem in &state.chromium_event_problems {
+            self.progress.warn(&format!("Chromium events: {problem}"));
+        }
+        state.output.push((
+            PathBuf::from("chromium_events.json"),
+            serde_json::to_string_pretty(&chromium_events).unwrap(),
+        ));
+
+        if self
+            .config
+            .output_budget
+            .is_some_and(|b| state.output_usage.total > b)
+        {
+            self.progress.warn(&format!(
+                "Output budget exceeded, {} of output by parser:\n{}",
+                format_bytes(state.output_usage.total),
+                state.output_usage.summary()
+            ));
+        }
+        if !state.unknown_fields.is_empty() {
+            self.progress.warn(&format!(
+                "Unknown fields: {:?} (consider updating tlparse to render these)",
+                state.unknown_fields
+            ));
+        }
+
+        let (missing_interned_strs, num_late_interned_strs) = {
+            let (late, missing): (Vec<_>, Vec<_>) = INTERN_TABLE.with_borrow(|intern_table| {
+                state
+                    .interned_str_refs
+                    .iter()
+                    .partition(|(id, _)| intern_table.contains_key(id))
+            });
+            (
+                missing
+                    .into_iter()
+                    .map(|(id, refs)| MissingInternedStr {
+                        id: *id,
+                        count: refs.count,
+                        first_lineno: refs.first_lineno,
+                        raw_url: format!(
+                            "raw.html#L{}",
+                            refs.first_lineno.saturating_sub(RAW_CONTEXT_LINES).max(1)
+                        ),
+                    })
+                    .collect::<Vec<_>>(),
+                late.len(),
+            )
+        };
+        if let Some(first) = missing_interned_strs.first() {
+            self.progress.warn(&format!(
+                "{} stack frame file names were never defined by str entries and show as (unknown), \
+                 e.g., id {} first referred to on line {}",
+                missing_interned_strs.len(),
+                first.id,
+                first.first_lineno
+            ));
+        }
+        if num_late_interned_strs > 0 {
+            self.progress.warn(&format!(
+                "{num_late_interned_strs} stack frame file names were used before they were defined \
+                 (consider --two-pass)"
+            ));
+        }
+        state.raw_context_lines.extend(
+            missing_interned_strs
+                .iter()
+                .take(TOP_MISSING_INTERNED_STRS)
+                .map(|m| m.first_lineno),
+        );
+        if state.torch_installs.len() > 1 {
+            self.progress.warn(&format!(
+                "Stack frames come from {} different torch installs, so this log mixes entries from \
+                 different torch builds (e.g., a resumed job), and comparisons across its compiles may \
+                 be wrong: {}",
+                state.torch_installs.len(),
+                state.torch_installs
+                    .iter()
+                    .map(|(path, lineno)| format!("{path} (from line {lineno})"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            state
+                .raw_context_lines
+                .extend(state.torch_installs.values());
+        }
+        let regressions = self
+            .config
+            .baseline
+            .as_ref()
+            .map_or(Vec::new(), |baseline| {
+                baseline.regressions(
+                    &Baseline::new(&state.metrics_index),
+                    self.regression_threshold_pct,
+                )
+            });
+        for regression in &regressions {
+            self.progress
+                .warn(&format!("Regressed from the baseline: {regression}"));
+        }
+
+        let has_unknown_compile_id = state.directory.contains_key(&None);
+
+        let mut compile_directories = Vec::new();
+        // Only collected for config.json
+        let mut json_compile_ids = Vec::new();
+        let directory_keys: Vec<Option<CompileId>> = state.directory.keys().cloned().collect();
+        // The attempts of each compilation of a frame (the compile id without its attempt), in log
+        // order, to find the other attempts of a compile id without going through every compile id
+        let mut frame_compile_attempts: FxHashMap<CompileId, Vec<Option<u32>>> =
+            FxHashMap::default();
+        for cid in directory_keys.iter().flatten() {
+            frame_compile_attempts
+                .entry(CompileId {
+                    attempt: None,
+                    ..cid.clone()
+                })
+                .or_default()
+                .push(cid.attempt);
+        }
+        let guards_urls: FxHashMap<Option<CompileId>, String> = state
+            .directory
+            .iter()
+            .filter_map(|(cid, files)| {
+                let f = files.iter().find(|f| {
+                    f.artifact_kind == ArtifactKind::Guards && f.url.ends_with(".html")
+                })?;
+                Some((cid.clone(), f.url.clone()))
+            })
+            .collect();
+        // Every compile id and attempt of each frame, in log order, for the frame history pages
+        let mut frame_histories: FxIndexMap<CompileId, (String, Vec<FrameCompileRow>)> =
+            FxIndexMap::default();
+        for (compile_id, files) in state.directory.clone() {
+            let input_bytes = self
+                .shared
+                .input_source_index
+                .borrow()
+                .get(&compile_id)
+                .map_or("".to_string(), |sources| {
+                    format_bytes(total_input_bytes(sources))
+                });
+            // Artifacts of an abandoned attempt are easily mistaken for those of the final one
+            let restarted_as = compile_id.as_ref().and_then(|cid| {
+                let frame_compile = CompileId {
+                    attempt: None,
+                    ..cid.clone()
+                };
+                let last = *frame_compile_attempts.get(&frame_compile)?.iter().max()?;
+                (last > cid.attempt).then_some(CompileId {
+                    attempt: last,
+                    ..frame_compile
+                })
+            });
+            let restarted_as = restarted_as.as_ref();
+            let (landing_url, backend) = if let Some(ref cid) = compile_id {
+                // Per compile id landing page, so a single compilation can be linked to directly
+                let dir = cid.as_directory_name();
+                let mut key = compile_id.clone();
+                if let Some(c) = key.as_mut() {
+                    if c.frame_compile_id.is_some() {
+                        // data migration for old logs that don't have attempt
+                        c.attempt = Some(0);
+                    }
+                }
+                let metrics = state.metrics_index.get(&key);
+                let (status_class, status) = compilation_status(metrics);
+                // An abandoned attempt didn't get as far as the backend
+                let backend = restarted_as
+                    .is_none()
+                    .then(|| compile_id_backend(metrics, &files))
+                    .flatten();
+                let prefix = format!("{dir}/");
+                // Latest attempt of the previous compilation of this frame
+                let prior = cid
+                    .frame_compile_id
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|n| {
+                        let prior = CompileId {
+                            frame_compile_id: Some(n),
+                            attempt: None,
+                            ..cid.clone()
+                        };
+                        let attempt = *frame_compile_attempts.get(&prior)?.last()?;
+                        Some(CompileId { attempt, ..prior })
+                    });
+                let prior = prior.as_ref();
+                let prior_guards_url = prior.and_then(|p| guards_urls.get(&Some(p.clone())));
+                let prior_guards = prior.and_then(|p| {
+                    self.shared
+                        .dynamo_guards_index
+                        .borrow()
+                        .get(&Some(p.clone()))
+                        .cloned()
+                });
+                let recompile_reasons: Vec<RecompileReasonContext> = state
+                    .recompile_reasons_index
+                    .get(&compile_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|reason| RecompileReasonContext {
+                        reason: reason.to_string(),
+                        change: recompile_reason_change(reason),
+                        guard_url: prior_guards_url.map_or(
+                            "".to_string(),
+                            |url| match prior_guards
+                                .as_ref()
+                                .and_then(|g| recompile_reason_guard_index(g, reason))
+                            {
+                                Some(i) => format!("../{url}#guard-{i}"),
+                                None => format!("../{url}"),
+                            },
+                        ),
+                    })
+                    .collect();
+                let frame = CompileId {
+                    frame_compile_id: None,
+                    attempt: None,
+                    ..cid.clone()
+                };
+                let last = metrics.and_then(|m| m.last());
+                let (guards_added, guards_removed) = match (
+                    &prior_guards,
+                    self.shared.dynamo_guards_index.borrow().get(&compile_id),
+                ) {
+                    (Some(prior), Some(guards)) => guards_diff(prior, guards),
+                    _ => Default::default(),
+                };
+                let history = frame_histories.entry(frame).or_default();
+                if let Some(co_name) = last.and_then(|m| m.co_name.clone()) {
+                    history.0 = co_name;
+                }
+                history.1.push(FrameCompileRow {
+                    compile_id: cid.to_string(),
+                    url: format!("{dir}/index.html"),
+                    status_class,
+                    status,
+                    compile_time_s: last.and_then(|m| m.entire_frame_compile_time_s),
+                    guard_count: last.and_then(|m| m.guard_count),
+                    shape_env_guard_count: last.and_then(|m| m.shape_env_guard_count),
+                    changes: recompile_reasons
+                        .iter()
+                        .map(|r| r.change.clone().unwrap_or(r.reason.clone()))
+                        .collect(),
+                    guards_url: guards_urls.get(&compile_id).cloned().unwrap_or_default(),
+                    prior_compile_id: prior.map_or("".to_string(), |p| p.to_string()),
+                    prior_guards_url: prior_guards_url.cloned().unwrap_or_default(),
+                    num_guards_added: guards_added.len(),
+                    num_guards_removed: guards_removed.len(),
+                    guards_added,
+                    guards_removed,
+                });
+                let context = CompileIdIndexContext {
+                    css: CSS,
+                    javascript: JAVASCRIPT,
+                    compile_id: cid.to_string(),
+                    anchor: cid.anchor(),
+                    status_class,
+                    status,
+                    backend: backend.clone(),
+                    m: metrics.and_then(|m| m.last()),
+                    input_bytes: input_bytes.clone(),
+                    output_files: artifact_anchors(&files)
+                        .into_iter()
+                        .zip(&files)
+                        .map(|(anchor, f)| ArtifactContext {
+                            anchor,
+                            file: OutputFile {
+                                url: f.url.strip_prefix(&prefix).unwrap_or(&f.url).to_string(),
+                                name: f.name.strip_prefix(&prefix).unwrap_or(&f.name).to_string(),
+                                ..f.clone()
+                            },
+                        })
+                        .collect(),
+                    stack_html: self
+                        .shared
+                        .stack_index
+                        .borrow()
+                        .get(&compile_id)
+                        .map_or("".to_string(), |stack| {
+                            format_stack(stack, self.frame_filter)
+                        }),
+                    prior_compile_id: prior.map_or("".to_string(), |p| p.to_string()),
+                    prior_url: prior.map_or("".to_string(), |p| {
+                        format!("../{}/index.html", p.as_directory_name())
+                    }),
+                    frame_url: format!("../{}", frame_page_url(cid)),
+                    recompile_reasons,
+                    logged_from: state
+                        .logged_from_index
+                        .get(&compile_id)
+                        .map(|l| l.iter().map(|(k, v)| (k.clone(), *v)).collect())
+                        .unwrap_or_default(),
+                    waterfall: state
+                        .glog_times_index
+                        .get(&compile_id)
+                        .map(waterfall)
+                        .unwrap_or_default(),
+                    links: state
+                        .link_index
+                        .get(&compile_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                };
+                let summary = CompileIdSummary::new(
+                    cid,
+                    status,
+                    last,
+                    backend.clone(),
+                    prior,
+                    context
+                        .recompile_reasons
+                        .iter()
+                        .map(|r| r.reason.clone())
+                        .collect(),
+                    context
+                        .output_files
+                        .iter()
+                        .map(|a| SummaryArtifact {
+                            url: a.file.url.clone(),
+                            kind: a.file.artifact_kind,
+                            phase: a.file.phase,
+                            size: a.file.size,
+                            lineno: a.file.lineno,
+                            source_filename: a.file.source_filename.clone(),
+                        })
+                        .collect(),
+                );
+                state.output.push((
+                    PathBuf::from(format!("{dir}/{COMPILE_ID_SUMMARY_FILE}")),
+                    serde_json::to_string_pretty(&summary)?,
+                ));
+                if self.config.json {
+                    json_compile_ids.push(JsonCompileId {
+                        dir: dir.clone(),
+                        summary,
+                        metrics: metrics.cloned().unwrap_or_default(),
+                        guards: self
+                            .shared
+                            .dynamo_guards_index
+                            .borrow()
+                            .get(&compile_id)
+                            .cloned()
+                            .unwrap_or_default(),
+                    });
+                }
+                let url = format!("{dir}/index.html");
+                state.output.push((
+                    PathBuf::from(&url),
+                    self.tt.render("compile_id_index.html", &context)?,
+                ));
+                (url, backend)
+            } else {
+                ("".to_string(), None)
+            };
+            compile_directories.push(CompileDirectoryContext {
+                anchor: compile_id_anchor(compile_id.as_ref()),
+                legacy_anchor: legacy_compile_id_anchor(compile_id.as_ref()),
+                collapse: restarted_as.is_some()
+                    && self.config.hide_abandoned_attempts
+                    && !files.is_empty(),
+                restarted_as: restarted_as.map(|c| CompileIdLink::new(Some(c))),
+                backend,
+                frame_url: compile_id.as_ref().map_or("".to_string(), frame_page_url),
+                compiled_autograd: compile_id
+                    .as_ref()
+                    .is_some_and(CompileId::is_compiled_autograd),
+                compile_id: compile_id.map_or("(unknown)".to_string(), |e| e.to_string()),
+                phases: group_by_phase(files),
+                input_bytes,
+                landing_url,
+            });
+        }
+
+        let backends: FxIndexSet<String> = compile_directories
+            .iter()
+            .filter_map(|c| c.backend.clone())
+            .collect();
+        let backends: Vec<String> = if backends.len() > 1 {
+            backends.into_iter().collect()
+        } else {
+            Vec::new()
+        };
+
+        for (frame, (co_name, rows)) in frame_histories {
+            state.output.push((
+                PathBuf::from(frame_page_url(&frame)),
+                self.tt.render(
+                    "frame_history.html",
+                    &FrameHistoryContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        frame: frame.to_string(),
+                        co_name,
+                        rows,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let (shapes, varying_sources) =
+            tensor_catalog(&self.shared.input_source_index.borrow(), &directory_keys);
+        let num_tensor_shapes = shapes.len();
+        if !shapes.is_empty() {
+            state.output.push((
+                PathBuf::from("tensor_catalog.html"),
+                self.tt.render(
+                    "tensor_catalog.html",
+                    &TensorCatalogContext {
+                        css: TEMPLATE_FAILURES_CSS,
+                        shapes,
+                        varying_sources,
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+
+        let mut directory_pages: Vec<DirectoryPageContext> = Vec::new();
+        if self
+            .config
+            .max_inline_compile_ids
+            .is_some_and(|max| compile_directories.len() > max)
+        {
+            // Group compile ids by frame, preserving the order frames were first seen
+            let mut frames: FxIndexMap<Option<CompileId>, Vec<CompileDirectoryContext>> =
+                FxIndexMap::default();
+            for (compile_id, compile_directory) in
+                directory_keys.iter().zip(compile_directories.drain(..))
+            {
+                let frame = compile_id.as_ref().map(|c| CompileId {
+                    frame_compile_id: None,
+                    attempt: None,
+                    ..c.clone()
+                });
+                frames.entry(frame).or_default().push(compile_directory);
+            }
+            for (frame, compile_directories) in frames {
+                let (url, label) = frame.map_or(
+                    (
+                        "build_products/unknown.html".to_string(),
+                        "(unknown)".to_string(),
+                    ),
+                    |f| {
+                        (
+                            format!("build_products/{}.html", f.as_directory_name()),
+                            format!("frame {f}"),
+                        )
+                    },
+                );
+                state.output.push((
+                    PathBuf::from(&url),
+                    self.tt.render(
+                        "directory_page.html",
+                        &DirectoryPageRenderContext {
+                            css: CSS,
+                            label: &label,
+                            directory: &compile_directories,
+                            qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                        },
+                    )?,
+                ));
+                directory_pages.push(DirectoryPageContext {
+                    url,
+                    label,
+                    compile_ids: compile_directories
+                        .into_iter()
+                        .map(|c| CompileIdLink {
+                            compile_id: c.compile_id,
+                            anchor: c.anchor,
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        // Everything on one page, with the stack trie expanded, for printing
+        // Restarts are merged into attempt 0, like in metrics_index
+        let print_compile_ids: FxIndexSet<CompileId> = directory_keys
+            .iter()
+            .flatten()
+            .map(|cid| CompileId {
+                attempt: cid.frame_compile_id.map(|_| 0),
+                ..cid.clone()
+            })
+            .collect();
+        let metrics: Vec<PrintMetricsRow> = print_compile_ids
+            .iter()
+            .map(|cid| {
+                let m = state.metrics_index.get(&Some(cid.clone()));
+                let (status_class, status) = compilation_status(m);
+                let last = m.and_then(|m| m.last());
+                PrintMetricsRow {
+                    compile_id: cid.to_string(),
+                    anchor: cid.anchor(),
+                    status_class,
+                    status,
+                    entire_frame_compile_time_s: last.and_then(|m| m.entire_frame_compile_time_s),
+                    backend_compile_time_s: last.and_then(|m| m.backend_compile_time_s),
+                    graph_op_count: last.and_then(|m| m.graph_op_count),
+                    guard_count: last.and_then(|m| m.guard_count),
+                    fail_type: last.and_then(|m| m.fail_type.clone()),
+                }
+            })
+            .collect();
+        let metrics_table: Vec<MetricsTableRow> = print_compile_ids
+            .iter()
+            .map(|cid| {
+                let m = state.metrics_index.get(&Some(cid.clone()));
+                let (status_class, status) = compilation_status(m);
+                MetricsTableRow {
+                    compile_id: cid.to_string(),
+                    status_class,
+                    status,
+                    cells: metrics_table_cells(m.and_then(|m| m.last()), &self.metrics_columns),
+                }
+            })
+            .collect();
+        state.output.push((
+            PathBuf::from(METRICS_CSV_PATH),
+            metrics_table_csv(&self.metrics_columns, &metrics_table),
+        ));
+        let (restart_time_s, restart_time_frames) =
+            restart_time_waste(&state.metrics_index, TOP_RESTART_TIME_FRAMES);
+        let print_context = PrintContext {
+            css: CSS,
+            failures_css: TEMPLATE_FAILURES_CSS,
+            custom_header_html: self.config.custom_header_html.clone(),
+            num_compilations: metrics.len(),
+            num_failed: metrics
+                .iter()
+                .filter(|m| m.status_class == "status-error")
+                .count(),
+            num_restarted: metrics
+                .iter()
+                .filter(|m| m.status_class == "status-break")
+                .count(),
+            total_compile_time_s: state
+                .metrics_index
+                .values()
+                .flatten()
+                .filter_map(|m| m.entire_frame_compile_time_s)
+                .sum(),
+            num_breaks: breaks.failures.len(),
+            num_specializations,
+            num_recompile_thrashing,
+            restart_time_s,
+            stack_trie_html: state
+                .stack_trie
+                .fmt(
+                    Some(&state.metrics_index),
+                    self.frame_filter.max_depth,
+                    self.config.stack_trie_times,
+                )
+                .unwrap()
+                .replace("<details>", "<details open>"),
+            failures: breaks.failures.clone(),
+            metrics,
+        };
+        state.output.push((
+            PathBuf::from("print.html"),
+            self.tt.render("print.html", &print_context)?,
+        ));
+
+        // The stack trie for flame graph tools
+        let has_stack_trie_exports = !state.stack_trie.is_empty();
+        if has_stack_trie_exports {
+            let trie = state.stack_trie.hide_frames(self.frame_filter);
+            state.output.push((
+                PathBuf::from("stack_trie.folded"),
+                folded_stacks(&trie, &state.metrics_index, false),
+            ));
+            state.output.push((
+                PathBuf::from("stack_trie_time_us.folded"),
+                folded_stacks(&trie, &state.metrics_index, true),
+            ));
+            state.output.push((
+                PathBuf::from("stack_trie.speedscope.json"),
+                serde_json::to_string(&speedscope_profile(&trie, &state.metrics_index))?,
+            ));
+        }
+
+        let index_context = IndexContext {
+            css: CSS,
+            javascript: JAVASCRIPT,
+            custom_header_html: self.config.custom_header_html.clone(),
+            directory: compile_directories,
+            directory_pages,
+            stack_trie_html: state
+                .stack_trie
+                .hide_frames(self.frame_filter)
+                .fmt(
+                    Some(&state.metrics_index),
+                    self.frame_filter.max_depth,
+                    self.config.stack_trie_times,
+                )
+                .unwrap(),
+            unknown_stack_trie_html: state
+                .unknown_stack_trie
+                .hide_frames(self.frame_filter)
+                .fmt(
+                    Some(&state.metrics_index),
+                    self.frame_filter.max_depth,
+                    false,
+                )
+                .unwrap(),
+            has_unknown_stack_trie: !state.unknown_stack_trie.is_empty(),
+            has_stack_trie_exports,
+            num_breaks: breaks.failures.len(),
+            num_specializations,
+            num_recompile_thrashing,
+            num_modules,
+            num_non_compliant_ops,
+            num_specialization_recompiles,
+            compile_time_histogram: compile_time_histogram(
+                state
+                    .metrics_index
+                    .values()
+                    .flatten()
+                    .filter_map(|m| m.entire_frame_compile_time_s),
+            ),
+            guard_construction: guard_construction_summary(&state.metrics_index),
+            restart_time_s,
+            restart_time_frames,
+            metrics_columns: self.metrics_columns.clone(),
+            metrics_table,
+            graph_count_mismatches: graph_count_mismatches(
+                &state.metrics_index,
+                &state.graph_node_counts_index,
+            ),
+            num_tensor_shapes,
+            num_unrendered_compiles: self.config.max_compiles.map_or(0, |_| {
+                directory_keys
+                    .iter()
+                    .filter(|cid| cid.is_some() && !state.rendered_compile_ids.contains(cid))
+                    .count()
+            }),
+            max_compiles: self.config.max_compiles.unwrap_or(0),
+            num_missing_interned_strs: missing_interned_strs.len(),
+            missing_interned_strs: missing_interned_strs
+                .into_iter()
+                .take(TOP_MISSING_INTERNED_STRS)
+                .collect(),
+            num_late_interned_strs,
+            torch_installs: if state.torch_installs.len() > 1 {
+                state
+                    .torch_installs
+                    .into_iter()
+                    .map(|(path, first_lineno)| TorchInstall {
+                        path,
+                        first_lineno,
+                        raw_url: format!(
+                            "raw.html#L{}",
+                            first_lineno.saturating_sub(RAW_CONTEXT_LINES).max(1)
+                        ),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            guard_sources: guard_source_contributions(
+                &self.shared.dynamo_guards_index.borrow(),
+                &directory_keys,
+                TOP_GUARD_SOURCES,
+            ),
+            external_links: external_links(&state.link_index),
+            backends,
+            recompile_storms: recompile_storms(
+                &state.glog_times_index,
+                self.recompile_storm_threshold,
+                self.recompile_storm_window_s,
+            ),
+            recompile_storm_threshold: self.recompile_storm_threshold,
+            recompile_storm_window: format_duration(self.recompile_storm_window_s),
+            regressions: regressions.clone(),
+            regression_threshold_pct: self.regression_threshold_pct.to_string(),
+            has_cross_rank_metrics,
+            has_compile_divergence: compile_divergence.is_some(),
+            num_compile_divergences,
+            has_chromium_events: !chromium_events.is_empty(),
+            chromium_event_problems: state.chromium_event_problems,
+            qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+        };
+        state.output.push((
+            PathBuf::from("index.html"),
+            self.tt.render("index.html", &index_context)?,
+        ));
+
+        let raw_log = raw_log()?;
+        if !state.raw_context_lines.is_empty() {
+            state.output.push((
+                PathBuf::from("raw.html"),
+                self.tt.render(
+                    "raw.html",
+                    &RawContextContext {
+                        css: CSS,
+                        ranges: raw_context_ranges(&raw_log, &state.raw_context_lines),
+                        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                    },
+                )?,
+            ));
+        }
+        if self.config.json {
+            let report = JsonReport {
+                tlparse_version: env!("CARGO_PKG_VERSION"),
+                stats: &state.stats,
+                compile_ids: json_compile_ids,
+                failures: json_failures(&state.metrics_index),
+            };
+            state.output.push((
+                PathBuf::from(JSON_OUTPUT_PATH),
+                serde_json::to_string_pretty(&report)?,
+            ));
+        }
+        state
+            .output
+            .push(self.report_metadata(&raw_log, &state.stats)?);
+        state.output.push((PathBuf::from("raw.log"), raw_log));
+
+        // other_rank is included here because you should only have logs from one rank when
+        // configured properly
+        if self.config.strict
+            && (state.stats.fail_glog
+                + state.stats.fail_json
+                + state.stats.fail_payload_md5
+                + state.stats.other_rank
+                + state.stats.fail_dynamo_guards_json
+                + state.stats.fail_parser
+                > 0)
+        {
+            // Report something went wrong
+            return Err(anyhow!("Something went wrong"));
+        }
+
+        if self.config.strict_compile_id && has_unknown_compile_id {
+            return Err(anyhow!("Some log entries did not have compile id"));
+        }
+        send_all_outputs(&mut state.output, self.config.output_sink.as_ref());
+
+        Ok(ParseOutput {
+            files: state.output,
+            stats: state.stats,
+            failures: json_failures(&state.metrics_index),
+            metrics: state.metrics_index,
+            directory: state.directory,
+            regressions,
+            ranks: FxIndexMap::default(),
+        })
     }
 
-    if config.export {
-        let num_failures = export_failures.len();
+    fn render_export(
+        &self,
+        mut state: ParseCheckpoint,
+        raw_log: impl FnOnce() -> io::Result<String>,
+    ) -> anyhow::Result<ParseOutput> {
+        let num_failures = state.export_failures.len();
 
-        let exported_program_url = directory
+        let exported_program_url = state
+            .directory
             .values()
             .flatten()
             .find(|output_file| output_file.url.contains("exported_program"))
@@ -588,88 +3145,476 @@ pub fn parse_path(path: &PathBuf, config: ParseConfig) -> anyhow::Result<ParseOu
         let index_context = ExportIndexContext {
             css: EXPORT_CSS,
             javascript: JAVASCRIPT,
-            custom_header_html: config.custom_header_html,
-            directory: directory
-                .drain(..)
-                .map(|(x, y)| (x.map_or("(unknown)".to_string(), |e| e.to_string()), y))
+            custom_header_html: self.config.custom_header_html.clone(),
+            directory: state
+                .directory
+                .iter()
+                .map(|(x, y)| {
+                    (
+                        x.as_ref()
+                            .map_or("(unknown)".to_string(), |e| e.to_string()),
+                        y.clone(),
+                    )
+                })
                 .collect(),
-            failures: export_failures,
-            num_failures: num_failures,
+            failures: state.export_failures,
+            num_failures,
             success: num_failures == 0,
             exported_program_url: exported_program_url.unwrap_or("".to_string()),
             qps: TEMPLATE_QUERY_PARAM_SCRIPT,
         };
 
-        output.push((
+        state.output.push((
             PathBuf::from("index.html"),
-            tt.render("index.html", &index_context)?,
+            self.tt.render("index.html", &index_context)?,
         ));
+        state
+            .output
+            .push(self.report_metadata(&raw_log()?, &state.stats)?);
+        send_all_outputs(&mut state.output, self.config.output_sink.as_ref());
+
+        Ok(ParseOutput {
+            files: state.output,
+            stats: state.stats,
+            failures: json_failures(&state.metrics_index),
+            metrics: state.metrics_index,
+            directory: state.directory,
+            regressions: Vec::new(),
+            ranks: FxIndexMap::default(),
+        })
+    }
+}
+
+// Parses a log that reader has been positioned in at the checkpoint's offset, if any.  size is
+// only for the progress bar
+fn parse_impl(
+    reader: impl BufRead,
+    size: Option<u64>,
+    mut config: ParseConfig,
+    checkpoint: Option<ParseCheckpoint>,
+    mut lookahead: Option<Lookahead>,
+    want_checkpoint: bool,
+    raw_log: impl FnOnce() -> io::Result<String>,
+) -> anyhow::Result<ParseResult> {
+    let (mut state, shared) = checkpoint.unwrap_or_default().restore(lookahead.as_mut());
+    let known_issues = KnownIssues::new(&config.known_issues)?;
+    let link_rewriter = LinkRewriter::new(&config.link_rewrites)?;
+    let frame_filter = FrameFilter::new(&config.hide_frames, config.max_stack_depth)?;
+    let recompile_storm_threshold = config
+        .recompile_storm_threshold
+        .unwrap_or(DEFAULT_RECOMPILE_STORM_THRESHOLD);
+    let regression_threshold_pct = config
+        .regression_threshold_pct
+        .unwrap_or(DEFAULT_REGRESSION_THRESHOLD_PCT);
+    let recompile_storm_window_s = config
+        .recompile_storm_window_s
+        .unwrap_or(DEFAULT_RECOMPILE_STORM_WINDOW_S);
+    let metrics_columns = config.metrics_columns.clone().unwrap_or_else(|| {
+        DEFAULT_METRICS_COLUMNS
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    });
+    let known_columns = metrics_column_names();
+    if let Some(c) = metrics_columns.iter().find(|c| !known_columns.contains(c)) {
+        bail!(
+            "Unknown metrics column {c}, expected one of {}",
+            known_columns.join(", ")
+        );
+    }
+    let report_config = report_config(
+        &config,
+        recompile_storm_threshold,
+        recompile_storm_window_s,
+        regression_threshold_pct,
+        &metrics_columns,
+    );
+    config.custom_header_html = link_rewriter.rewrite_html(&config.custom_header_html);
+    let tt = templates(config.export)?;
+    let default_parsers = default_parsers(
+        &tt,
+        &config,
+        &shared.symbolic_shape_specialization_index,
+        &shared.dynamo_guards_index,
+        &shared.input_source_index,
+        &shared.graph_inputs_index,
+        &frame_filter,
+    );
+    let mut cx = ParseContext {
+        config: &config,
+        tt: &tt,
+        parsers: default_parsers
+            .iter()
+            .map(|p| p.as_ref())
+            .chain(config.custom_parsers.iter().map(|p| p.as_ref() as _))
+            .collect(),
+        progress: config.progress(),
+        known_issues,
+        link_rewriter,
+        frame_filter: &frame_filter,
+        shared: &shared,
+        lookahead: lookahead.as_ref(),
+        interned_reasons: state
+            .recompile_reasons_index
+            .values()
+            .flatten()
+            .cloned()
+            .collect(),
+        metrics_columns,
+        recompile_storm_threshold,
+        recompile_storm_window_s,
+        regression_threshold_pct,
+        report_config,
+    };
+
+    let bytes_consumed = Rc::new(Cell::new(state.offset));
+    let lines_read = Cell::new(state.lines);
+    let lines_before = state.lines;
+    let mut bytes_read = state.offset;
+    cx.progress.start(size);
+    let reader = CountingReader {
+        inner: reader,
+        count: bytes_consumed.clone(),
+    };
+    // NB: Sometimes, the log output we get from Logarithm stutters with a blank line.
+    // Filter them out, they're never valid (a blank line in payload will still be \t)
+    let mut lines = LogLines {
+        lines: reader
+            .lines()
+            .enumerate()
+            .inspect(|_| lines_read.set(lines_read.get() + 1))
+            .filter_map(|(i, l)| match l {
+                // 1-indexed line numbers please
+                Ok(l) if !l.is_empty() => Some((lines_before + i + 1, l)),
+                _ => None,
+            }),
+        given_back: VecDeque::new(),
+    };
+    while let Some((lineno, line)) = lines.next() {
+        send_all_outputs(&mut state.output, config.output_sink.as_ref());
+        bytes_read += line.len() as u64;
+        cx.progress.bytes_read(bytes_read);
+        cx.progress.stats(&state.stats);
+        cx.parse_entry(&mut state, lineno, &line, &mut lines)?;
+    }
+    drop(lines);
+
+    let checkpoint =
+        want_checkpoint.then(|| state.save(&shared, bytes_consumed.get(), lines_read.get()));
+    Ok(ParseResult {
+        rank_compiles_index: state.rank_compiles_index.clone(),
+        output: cx.render(state, raw_log)?,
+        checkpoint,
+    })
+}
 
-        return Ok(output);
+// Compares two report directories written by tlparse, e.g., for the same program before and after a
+// change, without needing the logs they came from
+pub fn diff_output_dirs(old: &Path, new: &Path) -> anyhow::Result<OutputDiff> {
+    let old_outputs = outputs_by_diff_key(old)?;
+    let new_outputs = outputs_by_diff_key(new)?;
+    let mut diff = OutputDiff::default();
+    let keys: BTreeSet<_> = old_outputs.keys().chain(new_outputs.keys()).collect();
+    for key in keys {
+        let olds = old_outputs.get(key).map_or(&[][..], |v| &v[..]);
+        let news = new_outputs.get(key).map_or(&[][..], |v| &v[..]);
+        for i in 0..olds.len().max(news.len()) {
+            match (olds.get(i), news.get(i)) {
+                (Some(o), Some(n)) => {
+                    if fs::read(old.join(o))? == fs::read(new.join(n))? {
+                        diff.unchanged += 1;
+                    } else {
+                        diff.changed.push((o.clone(), n.clone()));
+                    }
+                }
+                (Some(o), None) => diff.removed.push(o.clone()),
+                (None, Some(n)) => diff.added.push(n.clone()),
+                (None, None) => unreachable!(),
+            }
+        }
     }
+    Ok(diff)
+}
+
+// Compares the runs in two logs, e.g., before and after a change when bisecting: the compile ids
+// added and removed, the compile time of each frame, the guard counts, and new failures
+pub fn diff_logs(old: &PathBuf, new: &PathBuf) -> anyhow::Result<LogDiff> {
+    let old: DiffInput = serde_json::from_value(parse_path_json(old, ParseConfig::default())?)?;
+    let new: DiffInput = serde_json::from_value(parse_path_json(new, ParseConfig::default())?)?;
+    Ok(LogDiff::new(&old, &new))
+}
 
-    output.push((
-        PathBuf::from("failures_and_restarts.html"),
-        tt.render("failures_and_restarts.html", &breaks)?,
-    ));
-    pb.finish_with_message("done");
-    spinner.finish();
+// Relative paths of the outputs under dir, keyed by output_diff_key and in output number order.
+// Hidden files, like the incremental parsing checkpoint, and the report metadata are skipped
+fn outputs_by_diff_key(dir: &Path) -> anyhow::Result<BTreeMap<PathBuf, Vec<PathBuf>>> {
+    let mut outputs: BTreeMap<PathBuf, Vec<(u64, PathBuf)>> = BTreeMap::new();
+    for path in list_outputs(dir)? {
+        if path == Path::new(REPORT_METADATA_PATH) {
+            continue;
+        }
+        let (key, number) = output_diff_key(&path);
+        outputs.entry(key).or_default().push((number, path));
+    }
+    Ok(outputs
+        .into_iter()
+        .map(|(key, mut paths)| {
+            paths.sort();
+            (key, paths.into_iter().map(|(_, path)| path).collect())
+        })
+        .collect())
+}
 
-    output.push((
-        PathBuf::from("chromium_events.json"),
-        serde_json::to_string_pretty(&chromium_events).unwrap(),
-    ));
+// Relative paths of the files under dir, sorted, skipping hidden files
+fn list_outputs(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display())
+    }
+    let mut outputs = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else {
+                outputs.push(path);
+            }
+        }
+    }
+    outputs.sort();
+    Ok(outputs)
+}
 
-    eprintln!("{:?}", stats);
-    if unknown_fields.len() > 0 {
-        eprintln!(
-            "Unknown fields: {:?} (consider updating tlparse to render these)",
-            unknown_fields
+// Set to rewrite snapshots from the current output, instead of checking against them
+pub const UPDATE_SNAPSHOTS_ENV: &str = "TLPARSE_UPDATE_SNAPSHOTS";
+
+// Golden test helper: parses a log fixture and checks that the report (except raw.log, which is
+// just the fixture, and the report metadata) matches the files in snapshot_dir exactly.  Run with TLPARSE_UPDATE_SNAPSHOTS=1
+// to write the snapshot, and review the changes with git diff.
+pub fn check_snapshot(
+    log: &PathBuf,
+    config: ParseConfig,
+    snapshot_dir: &Path,
+) -> anyhow::Result<()> {
+    let output: BTreeMap<PathBuf, String> = parse_path(log, config)?
+        .into_iter()
+        .filter(|(path, _)| path != Path::new("raw.log") && path != Path::new(REPORT_METADATA_PATH))
+        .collect();
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        if snapshot_dir.exists() {
+            fs::remove_dir_all(snapshot_dir)?;
+        }
+        for (path, contents) in &output {
+            let snapshot_file = snapshot_dir.join(path);
+            fs::create_dir_all(snapshot_file.parent().unwrap())?;
+            fs::write(snapshot_file, contents)?;
+        }
+        return Ok(());
+    }
+    let snapshot = list_outputs(snapshot_dir)?;
+    let mut mismatches = Vec::new();
+    for path in &snapshot {
+        if !output.contains_key(path) {
+            mismatches.push(format!("{} is no longer written", path.display()));
+        }
+    }
+    for (path, contents) in &output {
+        if !snapshot.contains(path) {
+            mismatches.push(format!("{} is not in the snapshot", path.display()));
+            continue;
+        }
+        let expected = fs::read_to_string(snapshot_dir.join(path))?;
+        // Point at the first differing line, the rest can be found with git diff after updating
+        let differing = expected
+            .lines()
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .zip(contents.lines().map(Some).chain(std::iter::repeat(None)))
+            .take_while(|(e, a)| e.is_some() || a.is_some())
+            .enumerate()
+            .find(|(_, (e, a))| e != a);
+        if let Some((i, (e, a))) = differing {
+            mismatches.push(format!(
+                "{} differs at line {}:\n  expected: {}\n    actual: {}",
+                path.display(),
+                i + 1,
+                e.unwrap_or("<end of file>"),
+                a.unwrap_or("<end of file>")
+            ));
+        } else if expected != *contents {
+            mismatches.push(format!("{} differs in line endings", path.display()));
+        }
+    }
+    if !mismatches.is_empty() {
+        bail!(
+            "Report for {} does not match the snapshot in {} (set {}=1 to update it):\n{}",
+            log.display(),
+            snapshot_dir.display(),
+            UPDATE_SNAPSHOTS_ENV,
+            mismatches.join("\n")
         );
     }
+    Ok(())
+}
 
-    let has_unknown_compile_id = directory.contains_key(&None);
+// JSON Schemas, by name, for the structured log entries tlparse reads and the JSON files it writes
+pub fn json_schemas() -> Vec<(&'static str, serde_json::Value)> {
+    let schema = |root: schemars::schema::RootSchema| serde_json::to_value(root).unwrap();
+    vec![
+        // The JSON metadata at the start of every log line
+        ("envelope", schema(schemars::schema_for!(Envelope))),
+        // dynamo_guards_N.json
+        (
+            "dynamo_guards",
+            schema(schemars::schema_for!(Vec<DynamoGuard>)),
+        ),
+    ]
+}
 
-    let index_context = IndexContext {
-        css: CSS,
-        javascript: JAVASCRIPT,
-        custom_header_html: config.custom_header_html,
-        directory: directory
-            .drain(..)
-            .map(|(x, y)| (x.map_or("(unknown)".to_string(), |e| e.to_string()), y))
-            .collect(),
-        stack_trie_html: stack_trie.fmt(Some(&metrics_index)).unwrap(),
-        unknown_stack_trie_html: unknown_stack_trie.fmt(Some(&metrics_index)).unwrap(),
-        has_unknown_stack_trie: !unknown_stack_trie.is_empty(),
-        num_breaks: breaks.failures.len(),
-        has_chromium_events: !chromium_events.is_empty(),
-        qps: TEMPLATE_QUERY_PARAM_SCRIPT,
-    };
-    output.push((
-        PathBuf::from("index.html"),
-        tt.render("index.html", &index_context)?,
-    ));
-
-    output.push((PathBuf::from("raw.log"), fs::read_to_string(path)?));
-
-    // other_rank is included here because you should only have logs from one rank when
-    // configured properly
-    if strict
-        && (stats.fail_glog
-            + stats.fail_json
-            + stats.fail_payload_md5
-            + stats.other_rank
-            + stats.fail_dynamo_guards_json
-            + stats.fail_parser
-            > 0)
-    {
-        // Report something went wrong
-        return Err(anyhow!("Something went wrong"));
+// Bundle the outputs of parse_path into a single standalone HTML page that navigates between
+// them client side.  raw.log is left out, since it is as big as the input.
+pub fn render_single_file_report(output: &[(PathBuf, String)]) -> anyhow::Result<String> {
+    let files: FxIndexMap<String, &str> = output
+        .iter()
+        .filter(|(path, _)| path != &PathBuf::from("raw.log"))
+        .map(|(path, content)| (path.to_string_lossy().into_owned(), content.as_str()))
+        .collect();
+    let mut tt = TinyTemplate::new();
+    tt.add_formatter("format_unescaped", tinytemplate::format_unescaped);
+    tt.add_template("report.html", TEMPLATE_SINGLE_FILE_REPORT)?;
+    Ok(tt.render(
+        "report.html",
+        &SingleFileReportContext {
+            // Keep the embedded pages from closing the surrounding <script> tag
+            files: serde_json::to_string(&files)?.replace("</", "<\\/"),
+            javascript: SINGLE_FILE_JAVASCRIPT,
+        },
+    )?)
+}
+
+// Content-Type to serve a report file with, by its extension
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("json") => "application/json",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("gz") => "application/gzip",
+        Some("txt" | "log" | "folded" | "py") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+// The report file a request path is for, e.g., /-_0_0_0/?x=1 is -_0_0_0/index.html.  None if it
+// would be outside the report
+fn request_file(target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or("");
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        match (b, tail) {
+            (b'%', [h, l, tail @ ..]) => {
+                let hex = [*h, *l];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                rest = tail;
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
     }
+    let path = String::from_utf8(bytes).ok()?;
+    let mut file = PathBuf::new();
+    for part in path.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        if part == ".." || part.contains('\\') {
+            return None;
+        }
+        file.push(part);
+    }
+    if path.ends_with('/') || file.as_os_str().is_empty() {
+        file.push("index.html");
+    }
+    Some(file)
+}
 
-    if config.strict_compile_id && has_unknown_compile_id {
-        return Err(anyhow!("Some log entries did not have compile id"));
+fn handle_request(
+    stream: std::net::TcpStream,
+    lookup: &(dyn Fn(&Path) -> Option<Vec<u8>> + Sync),
+) -> io::Result<()> {
+    let mut reader = io::BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers don't matter
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
     }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let mut location = None;
+    let (status, content_type, body) = match method {
+        "GET" | "HEAD" => match request_file(target).map(|f| (lookup(&f), f)) {
+            Some((Some(body), file)) => ("200 OK", content_type(&file), body),
+            // As a static file server would, send /rank_0 on to /rank_0/, so that the relative
+            // links of its index.html resolve inside it
+            Some((None, file))
+                if !target.split(['?', '#']).next().unwrap_or("").ends_with('/')
+                    && lookup(&file.join("index.html")).is_some() =>
+            {
+                let (path, query) =
+                    target.split_at(target.find(['?', '#']).unwrap_or(target.len()));
+                location = Some(format!("{path}/{query}"));
+                ("301 Moved Permanently", "text/plain", b"Moved\n".to_vec())
+            }
+            _ => ("404 Not Found", "text/plain", b"Not found\n".to_vec()),
+        },
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed\n".to_vec(),
+        ),
+    };
+    let mut stream = stream;
+    write!(stream, "HTTP/1.1 {status}\r\n")?;
+    if let Some(location) = location {
+        write!(stream, "Location: {location}\r\n")?;
+    }
+    write!(
+        stream,
+        "Content-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
 
-    Ok(output)
+// Serves a report over HTTP, e.g., so that it can be viewed from a laptop through port forwarding
+// instead of copying it off a remote host.  lookup gives the contents of a file of the report by
+// its path, relative to the report's root.  Runs until the process is stopped
+pub fn serve_report(
+    listener: std::net::TcpListener,
+    lookup: impl Fn(&Path) -> Option<Vec<u8>> + Sync,
+) -> anyhow::Result<()> {
+    let lookup = &lookup;
+    std::thread::scope(|scope| {
+        // Browsers open connections before they have a request to send, so each is served on its
+        // own thread
+        for stream in listener.incoming() {
+            scope.spawn(move || {
+                // A client going away mid request is no reason to stop serving the others
+                let _ = stream.and_then(|stream| handle_request(stream, lookup));
+            });
+        }
+    });
+    Ok(())
 }
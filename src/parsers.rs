@@ -1,21 +1,61 @@
-use crate::templates::TEMPLATE_QUERY_PARAM_SCRIPT;
-use crate::{types::*, ParseConfig};
+use crate::templates::{COMPILATION_METRICS_PLACEHOLDER_URL, TEMPLATE_QUERY_PARAM_SCRIPT};
+use crate::{types::*, ParseConfig, ProgressSink, DEFAULT_RENDER_BUDGET_S};
+use fxhash::FxHashMap;
 use html_escape::encode_text;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::cell::RefCell;
 use std::ffi::{OsStr, OsString};
-use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use tinytemplate::TinyTemplate;
 
 use serde_json::Value;
 
+use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
+use syntect::html::{
+    append_highlighted_html_for_styled_line, start_highlighted_html_snippet, IncludeBackground,
+};
 use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
 
 pub enum ParserOutput {
-    File(PathBuf, String),       // File to be saved on disk
-    GlobalFile(PathBuf, String), // Like file, but don't give a unique suffix
+    File(PathBuf, String),              // File to be saved on disk
+    GlobalFile(PathBuf, String),        // Like file, but don't give a unique suffix
     Link(String, String), // External href to (name, url) (linked in compile_directory, not returned)
+    Badged(Badge, Box<ParserOutput>), // Any of the above, marked with a badge in listings
+    Sourced(String, Box<ParserOutput>), // Any of the above, with the file it was originally written to
+}
+
+impl ParserOutput {
+    pub fn with_badge(self, badge: Badge) -> ParserOutput {
+        ParserOutput::Badged(badge, Box::new(self))
+    }
+    pub fn with_source(self, source: String) -> ParserOutput {
+        ParserOutput::Sourced(source, Box::new(self))
+    }
+}
+
+// Semantic markers parsers can attach to their outputs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Badge {
+    CacheHit,
+    CacheMiss,
+    CacheBypass,
+    Failure,
+}
+
+impl Badge {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Badge::CacheHit => "✅",
+            Badge::CacheMiss => "❌",
+            Badge::CacheBypass => "❓",
+            Badge::Failure => "⚠️",
+        }
+    }
 }
 
 // Each parser returns a list of files to save and links to render in compile directory
@@ -45,6 +85,34 @@ pub trait StructuredLogParser {
 
     // Name of the parser, for error logging
     fn name(&self) -> &'static str;
+
+    // Whether this parser's outputs can be skipped once the output budget is used up
+    fn low_value(&self) -> bool {
+        false
+    }
+
+    // What the files this parser returns for the metadata are; links are always ArtifactKind::Link
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Other
+    }
+
+    // Part of the compiler the files this parser returns for the metadata come from; cache
+    // artifacts are always CompilePhase::Caching
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Other
+    }
+}
+
+// Marks the outputs of a metrics parser when the compilation failed
+fn badge_failure(results: ParserResults, failed: bool) -> ParserResults {
+    if failed {
+        results
+            .into_iter()
+            .map(|r| r.with_badge(Badge::Failure))
+            .collect()
+    } else {
+        results
+    }
 }
 
 // Takes a filename and a payload and writes that payload into a the file
@@ -58,8 +126,7 @@ fn simple_file_output(
         .as_ref()
         .map_or(format!("unknown_{lineno}"), |cid| cid.as_directory_name())
         .into();
-    let subdir = PathBuf::from(compile_id_dir);
-    let f = subdir.join(filename);
+    let f = compile_id_dir.join(filename);
     Ok(Vec::from([ParserOutput::File(f, String::from(payload))]))
 }
 
@@ -85,8 +152,24 @@ impl StructuredLogParser for SentinelFileParser {
     fn name(&self) -> &'static str {
         self.filename
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        match self.filename {
+            "dynamo_cpp_guards_str" => ArtifactKind::Guards,
+            _ => ArtifactKind::Graph,
+        }
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        match self.filename {
+            "optimize_ddp_split_graph" | "compiled_autograd_graph" | "dynamo_cpp_guards_str" => {
+                CompilePhase::Dynamo
+            }
+            "aot_backward_graph" | "aot_joint_graph" => CompilePhase::AOTAutograd,
+            "inductor_post_grad_graph" | "inductor_pre_grad_graph" => CompilePhase::Inductor,
+            _ => CompilePhase::Other,
+        }
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        (self.get_sentinel)(e).map(|m| Metadata::Empty(m))
+        (self.get_sentinel)(e).map(Metadata::Empty)
     }
     fn parse<'e>(
         &self,
@@ -113,8 +196,11 @@ impl StructuredLogParser for GraphDumpParser {
     fn name(&self) -> &'static str {
         "graph_dump" // ToDO: more specific?
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Graph
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        e.graph_dump.as_ref().map(|m| Metadata::GraphDump(m))
+        e.graph_dump.as_ref().map(Metadata::GraphDump)
     }
     fn parse<'e>(
         &self,
@@ -137,38 +223,295 @@ impl StructuredLogParser for GraphDumpParser {
     }
 }
 
-// Same as SentinelFileParser, but can log the size of the graph
-pub struct DynamoOutputGraphParser;
-impl StructuredLogParser for DynamoOutputGraphParser {
+// Placeholder names of the top level forward() of a printed FX graph module, in order
+fn graph_placeholders(payload: &str) -> Vec<String> {
+    static ARG_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"[(,]\s*(\w+)(?::\s*"[^"]*")?"#).unwrap());
+    payload
+        .lines()
+        .find_map(|l| l.trim_start().strip_prefix("def forward("))
+        .map_or(Vec::new(), |args| {
+            ARG_RE
+                .captures_iter(&format!("({args}"))
+                .map(|c| c[1].to_string())
+                .filter(|name| name != "self")
+                .collect()
+        })
+}
+
+// Count the nodes of the top level forward in a graph dump: placeholders, assignments (other than
+// print_readable's renaming of placeholders) and the output.  None if there is no forward.
+pub fn graph_node_counts(payload: &str) -> Option<GraphNodeCounts> {
+    static ASSIGN_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"^\w+(?::\s*"[^"]*")?\s*=\s*([^;]*)"#).unwrap());
+    static GET_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^self(\.\w+)+$").unwrap());
+    let indent = |l: &str| l.len() - l.trim_start().len();
+    let mut lines = payload.lines();
+    let forward = lines.find(|l| l.trim_start().starts_with("def forward("))?;
+    let body_indent = indent(forward) + 4;
+    let placeholders = graph_placeholders(payload);
+    let (mut ops, mut others) = (0, 0);
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent(line) < body_indent {
+            break;
+        }
+        let stmt = line.trim();
+        if indent(line) > body_indent || stmt.starts_with('#') {
+            continue;
+        }
+        if stmt.starts_with("return") {
+            others += 1;
+        } else if let Some(caps) = ASSIGN_RE.captures(stmt) {
+            let rhs = caps[1].trim();
+            if GET_ATTR_RE.is_match(rhs) {
+                others += 1;
+            } else if !placeholders.iter().any(|p| p == rhs) {
+                ops += 1;
+            }
+        }
+    }
+    Some(GraphNodeCounts {
+        ops,
+        nodes: placeholders.len() as u64 + ops + others,
+    })
+}
+
+// Prefix a graph dump with a comment mapping each placeholder to the input it came from
+fn annotate_graph_inputs<'a>(
+    payload: &str,
+    inputs: impl Iterator<Item = (&'a String, &'a InputSource)>,
+) -> String {
+    let inputs: Vec<_> = inputs.collect();
+    let header: String = inputs
+        .iter()
+        .map(|(placeholder, input)| {
+            let aliases: Vec<&str> = inputs
+                .iter()
+                .filter(|(p, i)| {
+                    p != placeholder && i.storage.is_some() && i.storage == input.storage
+                })
+                .map(|(_, i)| i.source.as_str())
+                .collect();
+            let aliases = if aliases.is_empty() {
+                "".to_string()
+            } else {
+                format!(" (shares storage with {})", aliases.join(", "))
+            };
+            format!(
+                "#   {placeholder}: {} {}{}{aliases}\n",
+                input.source, input.dtype, input.size
+            )
+        })
+        .collect();
+    if header.is_empty() {
+        payload.to_string()
+    } else {
+        format!("# Graph inputs:\n{header}\n{payload}")
+    }
+}
+
+// Same as SentinelFileParser, but annotates placeholders with their input sources
+pub struct DynamoOutputGraphParser<'t> {
+    tt: &'t TinyTemplate<'t>,
+    input_source_index: &'t RefCell<InputSourceIndex>,
+    graph_inputs_index: &'t RefCell<GraphInputsIndex>,
+    // Graph inputs already in graph_inputs_index, so recompiles share the inputs they didn't change
+    interned: RefCell<Interner<GraphInputs>>,
+}
+impl<'t> DynamoOutputGraphParser<'t> {
+    fn new(
+        tt: &'t TinyTemplate<'t>,
+        input_source_index: &'t RefCell<InputSourceIndex>,
+        graph_inputs_index: &'t RefCell<GraphInputsIndex>,
+    ) -> Self {
+        // Resumed from a checkpoint, the index already has graph inputs
+        let interned = graph_inputs_index.borrow().values().cloned().collect();
+        Self {
+            tt,
+            input_source_index,
+            graph_inputs_index,
+            interned: RefCell::new(interned),
+        }
+    }
+}
+impl StructuredLogParser for DynamoOutputGraphParser<'_> {
     fn name(&self) -> &'static str {
         "dynamo_output_graph"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Graph
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Dynamo
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.dynamo_output_graph
             .as_ref()
-            .map(|m| Metadata::DynamoOutputGraph(m))
+            .map(Metadata::DynamoOutputGraph)
     }
     fn parse<'e>(
         &self,
         lineno: usize,
-        _metadata: Metadata<'e>, // TODO: log size of graph
+        metadata: Metadata<'e>,
         _rank: Option<u32>,
         compile_id: &Option<CompileId>,
         payload: &str,
     ) -> anyhow::Result<ParserResults> {
-        simple_file_output("dynamo_output_graph.txt", lineno, compile_id, payload)
+        let Metadata::DynamoOutputGraph(metadata) = metadata else {
+            return Err(anyhow::anyhow!("Expected DynamoOutputGraph metadata"));
+        };
+        let input_sources = self.input_source_index.borrow();
+        let sources = input_sources.get(compile_id).map_or(&[][..], Vec::as_slice);
+        let graph_inputs: GraphInputs = graph_placeholders(payload)
+            .into_iter()
+            .map(|p| {
+                let input = sources.iter().find(|s| s.placeholder == p).cloned();
+                (p, input)
+            })
+            .collect();
+        let output = annotate_graph_inputs(
+            payload,
+            graph_inputs
+                .iter()
+                .filter_map(|(p, input)| input.as_ref().map(|i| (p, i))),
+        );
+        let graph_inputs = self.interned.borrow_mut().intern(graph_inputs);
+        self.graph_inputs_index
+            .borrow_mut()
+            .insert(compile_id.clone(), graph_inputs);
+        let mut results =
+            simple_file_output("dynamo_output_graph.txt", lineno, compile_id, &output)?;
+        if let Some(sizes) = &metadata.sizes {
+            let filename = "dynamo_output_graph_sizes.html";
+            let context = DynamoOutputGraphSizesContext {
+                css: crate::CSS,
+                compile_id: compile_id
+                    .as_ref()
+                    .map_or("(unknown)".to_string(), |c| c.to_string()),
+                sizes: sizes
+                    .iter()
+                    .map(|(name, size)| GraphSizeContext {
+                        name: name.clone(),
+                        size: format!(
+                            "[{}]",
+                            size.iter()
+                                .map(|s| s.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        symbolic: size.iter().any(|s| matches!(s, SymInt::Symbol(_))),
+                    })
+                    .collect(),
+                qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+            };
+            results.extend(simple_file_output(
+                filename,
+                lineno,
+                compile_id,
+                &self.tt.render(filename, &context)?,
+            )?);
+        }
+        Ok(results)
+    }
+}
+
+/**
+ * Parser for AOTAutograd graphs whose placeholders line up one-to-one with the Dynamo
+ * graph's, so they can be annotated with the same input sources
+ */
+pub struct AOTGraphParser<'t> {
+    filename: &'static str,
+    get_sentinel: fn(&Envelope) -> Option<&EmptyMetadata>,
+    graph_inputs_index: &'t RefCell<GraphInputsIndex>,
+}
+impl StructuredLogParser for AOTGraphParser<'_> {
+    fn name(&self) -> &'static str {
+        self.filename
+    }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Graph
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::AOTAutograd
+    }
+    fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
+        (self.get_sentinel)(e).map(Metadata::Empty)
+    }
+    fn parse<'e>(
+        &self,
+        lineno: usize,
+        _metadata: Metadata<'e>,
+        _rank: Option<u32>,
+        compile_id: &Option<CompileId>,
+        payload: &str,
+    ) -> anyhow::Result<ParserResults> {
+        let placeholders = graph_placeholders(payload);
+        let graph_inputs_index = self.graph_inputs_index.borrow();
+        let output = match graph_inputs_index.get(compile_id) {
+            // Only trust the positional correspondence if the arity matches
+            Some(graph_inputs) if graph_inputs.len() == placeholders.len() => {
+                annotate_graph_inputs(
+                    payload,
+                    placeholders
+                        .iter()
+                        .zip(graph_inputs.iter())
+                        .filter_map(|(p, (_, input))| input.as_ref().map(|i| (p, i))),
+                )
+            }
+            _ => payload.to_string(),
+        };
+        simple_file_output(
+            &format!("{}.txt", self.filename),
+            lineno,
+            compile_id,
+            &output,
+        )
     }
 }
 
 pub struct DynamoGuardParser<'t> {
     tt: &'t TinyTemplate<'t>,
+    symbolic_shape_specialization_index: &'t RefCell<SymbolicShapeSpecializationIndex>,
+    dynamo_guards_index: &'t RefCell<DynamoGuardsIndex>,
+    // Guards already in dynamo_guards_index, so recompiles share the guards they didn't change
+    interned: RefCell<Interner<DynamoGuard>>,
+}
+impl<'t> DynamoGuardParser<'t> {
+    fn new(
+        tt: &'t TinyTemplate<'t>,
+        symbolic_shape_specialization_index: &'t RefCell<SymbolicShapeSpecializationIndex>,
+        dynamo_guards_index: &'t RefCell<DynamoGuardsIndex>,
+    ) -> Self {
+        // Resumed from a checkpoint, the index already has guards
+        let interned = dynamo_guards_index
+            .borrow()
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        Self {
+            tt,
+            symbolic_shape_specialization_index,
+            dynamo_guards_index,
+            interned: RefCell::new(interned),
+        }
+    }
 }
 impl StructuredLogParser for DynamoGuardParser<'_> {
     fn name(&self) -> &'static str {
         "dynamo_guards"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Guards
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Dynamo
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        e.dynamo_guards.as_ref().map(|m| Metadata::Empty(m))
+        e.dynamo_guards.as_ref().map(Metadata::Empty)
     }
     fn parse<'e>(
         &self,
@@ -180,36 +523,113 @@ impl StructuredLogParser for DynamoGuardParser<'_> {
     ) -> anyhow::Result<ParserResults> {
         let filename = format!("{}.html", self.name());
         let guards = serde_json::from_str::<Vec<DynamoGuard>>(payload)?;
+        // Specializations are logged while tracing, so they are all known by the time
+        // the guards are
+        let specialization_index = self.symbolic_shape_specialization_index.borrow();
+        let specializations = specialization_index
+            .get(compile_id)
+            .map_or(&[][..], Vec::as_slice);
+        let mut by_source: FxIndexMap<String, Vec<usize>> = FxIndexMap::default();
+        for (index, guard) in guards.iter().enumerate() {
+            let objects: FxIndexSet<String> = SOURCE_RE
+                .find_iter(&guard.code)
+                .map(|m| source_object(m.as_str()))
+                .collect();
+            for object in objects {
+                by_source.entry(object).or_default().push(index);
+            }
+        }
+        let mut by_source: Vec<GuardSourceGroup> = by_source
+            .into_iter()
+            .map(|(source, guards)| GuardSourceGroup {
+                source,
+                count: guards.len(),
+                guards,
+            })
+            .collect();
+        // Most guarded first; sort is stable so ties stay in the order they were first guarded
+        by_source.sort_by_key(|g| std::cmp::Reverse(g.count));
         let guards_context = DynamoGuardsContext {
-            guards,
+            by_source,
+            guards: guards
+                .iter()
+                .map(|guard| DynamoGuardContext {
+                    code: guard.code.clone(),
+                    code_html: format_guard_code(&guard.code),
+                    specializations: specializations
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, spec)| {
+                            spec.sources
+                                .iter()
+                                .flatten()
+                                .any(|source| mentions_source(&guard.code, source))
+                        })
+                        .map(|(index, spec)| GuardSpecializationLink {
+                            index,
+                            symbol: spec.symbol.clone().unwrap_or_default(),
+                            value: spec.value.clone().unwrap_or_default(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            metrics_url: COMPILATION_METRICS_PLACEHOLDER_URL,
             qps: TEMPLATE_QUERY_PARAM_SCRIPT,
         };
         let output = self.tt.render(&filename, &guards_context)?;
-        simple_file_output(&filename, lineno, compile_id, &output)
+        let mut results = simple_file_output(&filename, lineno, compile_id, &output)?;
+        // Raw guard list, for external tools which want guards without the HTML
+        results.extend(simple_file_output(
+            &format!("{}.json", self.name()),
+            lineno,
+            compile_id,
+            &serde_json::to_string_pretty(&guards)?,
+        )?);
+        let mut interned = self.interned.borrow_mut();
+        let guards = guards.into_iter().map(|g| interned.intern(g)).collect();
+        self.dynamo_guards_index
+            .borrow_mut()
+            .insert(compile_id.clone(), guards);
+        Ok(results)
     }
 }
 
-pub struct InductorOutputCodeParser {
+pub struct InductorOutputCodeParser<'t> {
+    tt: &'t TinyTemplate<'t>,
     // If true we output the code as plain text, otherwise we output it as rendered html
     plain_text: bool,
+    // How long highlighting one output may take before it is output as plain text instead
+    render_budget: Duration,
+    progress: Rc<dyn ProgressSink>,
 }
 
-impl InductorOutputCodeParser {
-    pub fn new(config: &ParseConfig) -> Self {
+impl<'t> InductorOutputCodeParser<'t> {
+    pub fn new(tt: &'t TinyTemplate<'t>, config: &ParseConfig) -> Self {
         InductorOutputCodeParser {
+            tt,
             plain_text: config.plain_text,
+            render_budget: Duration::from_secs_f64(
+                config.render_budget_s.unwrap_or(DEFAULT_RENDER_BUDGET_S),
+            ),
+            progress: config.progress(),
         }
     }
 }
 
-impl StructuredLogParser for InductorOutputCodeParser {
+impl StructuredLogParser for InductorOutputCodeParser<'_> {
     fn name(&self) -> &'static str {
         "inductor_output_code"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Code
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Inductor
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.inductor_output_code
             .as_ref()
-            .map(|m| Metadata::InductorOutputCode(m))
+            .map(Metadata::InductorOutputCode)
     }
 
     fn parse<'e>(
@@ -221,63 +641,157 @@ impl StructuredLogParser for InductorOutputCodeParser {
         payload: &str,
     ) -> anyhow::Result<ParserResults> {
         if let Metadata::InductorOutputCode(metadata) = metadata {
-            let filename = metadata
-                .filename
-                .as_ref()
-                .and_then(|p| Path::file_stem(p))
-                .map_or_else(
-                    || {
-                        if self.plain_text {
-                            PathBuf::from("inductor_output_code.txt")
-                        } else {
-                            PathBuf::from("inductor_output_code.html")
-                        }
-                    },
-                    |stem| {
-                        let mut r = OsString::from("inductor_output_code_");
-                        r.push(stem);
-                        if self.plain_text {
-                            r.push(OsStr::new(".txt"));
-                        } else {
-                            r.push(OsStr::new(".html"));
-                        }
-                        r.into()
-                    },
-                );
-            let output_content = if self.plain_text {
-                payload.to_string()
+            let code_html = if self.plain_text {
+                None
             } else {
-                match generate_html_output(payload) {
+                let code_html = match generate_html_output(payload, self.render_budget) {
                     Ok(html) => html,
                     Err(_e) => {
                         return Err(anyhow::anyhow!("Failed to parse inductor code to html"))
                     }
+                };
+                if code_html.is_none() {
+                    self.progress.warn(&format!(
+                        "Highlighting the inductor output code on line {lineno} took longer than \
+                         {}s, so it is written as plain text (see --render-budget-s)",
+                        self.render_budget.as_secs_f64()
+                    ));
+                }
+                code_html
+            };
+            // Named after the compile id directory it's in rather than inductor's tmp file, whose
+            // stem can be the same across compilations.  The tmp file is kept as its source
+            let (filename, output_content) = match code_html {
+                None => ("inductor_output_code.txt", payload.to_string()),
+                Some(code_html) => {
+                    let kernels = kernel_annotations(payload);
+                    let html = if kernels.is_empty() {
+                        code_html
+                    } else {
+                        self.tt.render(
+                            "inductor_output_code.html",
+                            &InductorOutputCodeContext {
+                                css: crate::CSS,
+                                kernels,
+                                buffer_reuse: payload
+                                    .lines()
+                                    .filter(|l| l.trim_end().ends_with("# reuse"))
+                                    .map(|l| l.trim().to_string())
+                                    .collect(),
+                                code_html,
+                                qps: TEMPLATE_QUERY_PARAM_SCRIPT,
+                            },
+                        )?
+                    };
+                    ("inductor_output_code.html", html)
                 }
             };
 
-            simple_file_output(
-                &filename.to_string_lossy(),
-                lineno,
-                compile_id,
-                &output_content,
-            )
+            let results = simple_file_output(filename, lineno, compile_id, &output_content)?;
+            Ok(match &metadata.filename {
+                Some(source) => results
+                    .into_iter()
+                    .map(|r| r.with_source(source.display().to_string()))
+                    .collect(),
+                None => results,
+            })
         } else {
             Err(anyhow::anyhow!("Expected InductorOutputCode metadata"))
         }
     }
 }
 
-fn generate_html_output(payload: &str) -> Result<String, anyhow::Error> {
-    let syntax_set = SyntaxSet::load_defaults_newlines();
-    let theme_set = ThemeSet::load_defaults();
+static KERNEL_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?<name>\w+) = async_compile\.(?<kind>\w+)\(").unwrap());
+static SOURCE_NODES_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^# Topologically Sorted Source Nodes: \[(?<nodes>.*)\], Original ATen: \[(?<aten>.*)\]$",
+    )
+    .unwrap()
+});
+static KERNEL_COMMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^# (?<key>\w[\w ]*): (?<value>.+)$").unwrap());
+
+// Kernels defined with async_compile at the top level of Inductor output code, annotated from the
+// block of comments directly above each definition.  Multi-line sections of the block, like the
+// graph fragment, are left in the code.
+pub fn kernel_annotations(code: &str) -> Vec<KernelAnnotation> {
+    let lines: Vec<&str> = code.lines().collect();
+    let split = |s: &str| -> Vec<String> {
+        s.split(", ")
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut kernels = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = KERNEL_DEFINITION_RE.captures(line) else {
+            continue;
+        };
+        let mut kernel = KernelAnnotation {
+            name: caps["name"].to_string(),
+            kind: caps["kind"].to_string(),
+            source_nodes: Vec::new(),
+            original_aten: Vec::new(),
+            comments: Vec::new(),
+            launches: 0,
+        };
+        let comments = lines[..i]
+            .iter()
+            .rev()
+            .take_while(|l| l.starts_with('#'))
+            .collect::<Vec<_>>();
+        for comment in comments.into_iter().rev() {
+            if let Some(caps) = SOURCE_NODES_RE.captures(comment) {
+                kernel.source_nodes = split(&caps["nodes"]);
+                kernel.original_aten = split(&caps["aten"]);
+            } else if let Some(caps) = KERNEL_COMMENT_RE.captures(comment) {
+                kernel
+                    .comments
+                    .push((caps["key"].to_string(), caps["value"].to_string()));
+            }
+        }
+        let call = format!("{}(", kernel.name);
+        let run = format!("{}.run(", kernel.name);
+        kernel.launches = lines
+            .iter()
+            .map(|l| l.trim_start())
+            // Skip the definition itself, and the kernel's own def in its source
+            .filter(|l| !l.starts_with("def ") && !l.contains("async_compile"))
+            .filter(|l| l.contains(&call) || l.contains(&run))
+            .count();
+        kernels.push(kernel);
+    }
+    kernels
+}
+
+// Loading these takes tens of milliseconds, far longer than highlighting most outputs, so they are
+// loaded once instead of per output
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+// As syntect::html::highlighted_html_for_string, but gives up with None once highlighting has
+// taken longer than budget, e.g., for an enormous graph
+fn generate_html_output(payload: &str, budget: Duration) -> Result<Option<String>, anyhow::Error> {
+    let syntax_set = &*SYNTAX_SET;
     let syntax = syntax_set.find_syntax_by_extension("py").unwrap();
-    let html = syntect::html::highlighted_html_for_string(
-        &payload,
-        &syntax_set,
-        &syntax,
-        &theme_set.themes["InspiredGitHub"],
-    );
-    Ok(html?)
+    let theme = &THEME_SET.themes["InspiredGitHub"];
+    let deadline = Instant::now() + budget;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let (mut html, bg) = start_highlighted_html_snippet(theme);
+    for line in LinesWithEndings::from(payload) {
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        let regions = highlighter.highlight_line(line, syntax_set)?;
+        append_highlighted_html_for_styled_line(
+            &regions[..],
+            IncludeBackground::IfDifferent(bg),
+            &mut html,
+        )?;
+    }
+    html.push_str("</pre>\n");
+    Ok(Some(html))
 }
 
 pub struct OptimizeDdpSplitChildParser;
@@ -285,10 +799,16 @@ impl StructuredLogParser for OptimizeDdpSplitChildParser {
     fn name(&self) -> &'static str {
         "optimize_ddp_split_child"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Graph
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Dynamo
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.optimize_ddp_split_child
             .as_ref()
-            .map(|m| Metadata::OptimizeDdpSplitChild(m))
+            .map(Metadata::OptimizeDdpSplitChild)
     }
 
     fn parse<'e>(
@@ -314,7 +834,7 @@ impl StructuredLogParser for LinkParser {
         "link_parser"
     }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        e.link.as_ref().map(|m| Metadata::Link(m))
+        e.link.as_ref().map(Metadata::Link)
     }
 
     fn parse<'e>(
@@ -336,10 +856,83 @@ impl StructuredLogParser for LinkParser {
     }
 }
 
-fn format_stack(stack: &StackSummary) -> String {
+// Guards shorter than this are left on a single line
+const GUARD_LINE_WIDTH: usize = 80;
+
+// Render guard code as HTML, breaking long guards before boolean operators (indented by
+// nesting depth) and wrapping source expressions, strings, numbers and keywords in spans
+// for highlighting
+fn format_guard_code(code: &str) -> String {
+    let sources: FxHashMap<usize, usize> = SOURCE_RE
+        .find_iter(code)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    let wrap = code.len() > GUARD_LINE_WIDTH;
+    let mut html = String::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap();
+        if let Some(&end) = sources.get(&i) {
+            html.push_str(&format!(
+                "<span class='guard-source'>{}</span>",
+                encode_text(&code[i..end])
+            ));
+            i = end;
+        } else if c == '\'' || c == '"' {
+            let mut end = i + 1;
+            let mut escaped = false;
+            for (j, d) in code[i + 1..].char_indices() {
+                end = i + 1 + j + d.len_utf8();
+                if d == c && !escaped {
+                    break;
+                }
+                escaped = d == '\\' && !escaped;
+            }
+            html.push_str(&format!(
+                "<span class='guard-string'>{}</span>",
+                encode_text(&code[i..end])
+            ));
+            i = end;
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|d: char| !(d.is_alphanumeric() || d == '_'))
+                .map_or(code.len(), |n| i + n);
+            let word = &code[i..end];
+            if matches!(word, "and" | "or") && wrap && html.ends_with(' ') {
+                html.pop();
+                html.push('\n');
+                html.push_str(&"    ".repeat(depth + 1));
+            }
+            if c.is_ascii_digit() {
+                html.push_str(&format!("<span class='guard-number'>{word}</span>"));
+            } else if matches!(
+                word,
+                "and" | "or" | "not" | "is" | "in" | "None" | "True" | "False"
+            ) {
+                html.push_str(&format!("<span class='guard-keyword'>{word}</span>"));
+            } else {
+                html.push_str(word);
+            }
+            i = end;
+        } else {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            html.push_str(&encode_text(&code[i..i + c.len_utf8()]));
+            i += c.len_utf8();
+        }
+    }
+    html
+}
+
+pub fn format_stack(stack: &StackSummary, frame_filter: &FrameFilter) -> String {
     let mut trie = StackTrieNode::default();
-    trie.insert_no_terminal(stack.to_vec());
-    trie.fmt(None).unwrap()
+    trie.insert_no_terminal(frame_filter.apply(stack));
+    trie.fmt(None, frame_filter.max_depth, false).unwrap()
 }
 
 pub struct CompilationMetricsParser<'t> {
@@ -347,17 +940,28 @@ pub struct CompilationMetricsParser<'t> {
     pub stack_index: &'t RefCell<StackIndex>,
     pub symbolic_shape_specialization_index: &'t RefCell<SymbolicShapeSpecializationIndex>,
     pub guard_added_fast_index: &'t RefCell<GuardAddedFastIndex>,
+    pub runtime_assert_index: &'t RefCell<RuntimeAssertIndex>,
+    pub dynamo_guards_index: &'t RefCell<DynamoGuardsIndex>,
+    pub input_source_index: &'t RefCell<InputSourceIndex>,
     pub output_files: &'t Vec<OutputFile>,
     pub compile_id_dir: &'t PathBuf,
+    pub source_url_template: Option<&'t str>,
+    pub frame_filter: &'t FrameFilter,
 }
 impl StructuredLogParser for CompilationMetricsParser<'_> {
     fn name(&self) -> &'static str {
         "compilation_metrics"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Metrics
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Dynamo
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.compilation_metrics
             .as_ref()
-            .map(|m| Metadata::CompilationMetrics(m))
+            .map(Metadata::CompilationMetrics)
     }
     fn parse<'e>(
         &self,
@@ -383,43 +987,83 @@ impl StructuredLogParser for CompilationMetricsParser<'_> {
                 .stack_index
                 .borrow()
                 .get(&cid)
-                .map_or("".to_string(), format_stack);
+                .map_or("".to_string(), |stack| {
+                    format_stack(stack, self.frame_filter)
+                });
             let mini_stack_html = if let (Some(name), Some(filename), Some(line)) =
                 (&m.co_name, &m.co_filename, m.co_firstlineno)
             {
-                format_stack(&Vec::from([FrameSummary {
+                FrameSummary {
                     uninterned_filename: Some(filename.clone()),
                     filename: u32::MAX,
-                    line: line,
+                    line,
                     name: name.clone(),
-                }]))
+                }
+                .fmt_with_source_url(self.source_url_template)
             } else {
                 "".to_string()
             };
+            let guards_index = self.dynamo_guards_index.borrow();
+            let guards = guards_index.get(compile_id).map_or(&[][..], Vec::as_slice);
+            // Not removed, since specialization_recompiles needs every compile id's specializations
             let specializations = self
                 .symbolic_shape_specialization_index
+                .borrow()
+                .get(&cid)
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|spec| {
+                    let sources = spec.sources.unwrap_or(Vec::new());
+                    SymbolicShapeSpecializationContext {
+                        symbol: spec.symbol.unwrap_or("".to_string()),
+                        value: spec.value.unwrap_or("".to_string()),
+                        guards: guards
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, g)| sources.iter().any(|s| mentions_source(&g.code, s)))
+                            .map(|(i, _)| i)
+                            .collect(),
+                        sources,
+                        user_stack_html: format_stack(
+                            &spec.user_stack.unwrap_or(Vec::new()),
+                            self.frame_filter,
+                        ),
+                        stack_html: format_stack(
+                            &spec.stack.unwrap_or(Vec::new()),
+                            self.frame_filter,
+                        ),
+                    }
+                })
+                .collect();
+            let guards_added_fast = self
+                .guard_added_fast_index
                 .borrow_mut()
                 .remove(&cid)
-                .unwrap_or(Vec::new())
+                .unwrap_or_default()
                 .drain(..)
-                .map(|spec| SymbolicShapeSpecializationContext {
-                    symbol: spec.symbol.unwrap_or("".to_string()),
-                    sources: spec.sources.unwrap_or(Vec::new()),
-                    value: spec.value.unwrap_or("".to_string()),
-                    user_stack_html: format_stack(&spec.user_stack.unwrap_or(Vec::new())),
-                    stack_html: format_stack(&spec.stack.unwrap_or(Vec::new())),
+                .map(|guard| GuardAddedFastContext {
+                    expr: guard.expr.unwrap_or("".to_string()),
+                    user_stack_html: format_stack(
+                        &guard.user_stack.unwrap_or(Vec::new()),
+                        self.frame_filter,
+                    ),
+                    stack_html: format_stack(&guard.stack.unwrap_or(Vec::new()), self.frame_filter),
                 })
                 .collect();
-            let guards_added_fast = self
-                .guard_added_fast_index
+            let runtime_asserts = self
+                .runtime_assert_index
                 .borrow_mut()
                 .remove(&cid)
-                .unwrap_or(Vec::new())
+                .unwrap_or_default()
                 .drain(..)
                 .map(|guard| GuardAddedFastContext {
                     expr: guard.expr.unwrap_or("".to_string()),
-                    user_stack_html: format_stack(&guard.user_stack.unwrap_or(Vec::new())),
-                    stack_html: format_stack(&guard.stack.unwrap_or(Vec::new())),
+                    user_stack_html: format_stack(
+                        &guard.user_stack.unwrap_or(Vec::new()),
+                        self.frame_filter,
+                    ),
+                    stack_html: format_stack(&guard.stack.unwrap_or(Vec::new()), self.frame_filter),
                 })
                 .collect();
             let remove_prefix = |x: &String| -> String {
@@ -435,24 +1079,58 @@ impl StructuredLogParser for CompilationMetricsParser<'_> {
                 .map(|o| OutputFile {
                     url: remove_prefix(&o.url),
                     name: remove_prefix(&o.name),
-                    number: o.number.clone(),
+                    number: o.number,
                     suffix: o.suffix.clone(),
+                    size: o.size,
+                    lineno: o.lineno,
+                    preview: o.preview.clone(),
+                    source_filename: o.source_filename.clone(),
+                    artifact_kind: o.artifact_kind,
+                    phase: o.phase,
                 })
                 .collect();
+            let guards_url = output_files
+                .iter()
+                .find(|o| o.artifact_kind == ArtifactKind::Guards && o.url.ends_with(".html"))
+                .map_or("".to_string(), |o| o.url.clone());
+            let input_source_index = self.input_source_index.borrow();
+            let input_sources = input_source_index
+                .get(compile_id)
+                .map_or(&[][..], Vec::as_slice);
+            let input_bytes = if input_sources.is_empty() {
+                "".to_string()
+            } else {
+                format_bytes(total_input_bytes(input_sources))
+            };
             let context = CompilationMetricsContext {
                 css: crate::CSS,
-                m: &m,
+                m,
                 compile_id: id,
-                stack_html: stack_html,
-                mini_stack_html: mini_stack_html,
+                stack_html,
+                mini_stack_html,
+                restart_reasons: m
+                    .restart_reasons
+                    .iter()
+                    .flatten()
+                    .map(|reason| RestartReasonContext {
+                        reason: reason.clone(),
+                        advice: graph_break_advice(reason),
+                    })
+                    .collect(),
+                javascript: crate::JAVASCRIPT,
                 symbolic_shape_specializations: specializations,
-                guards_added_fast: guards_added_fast,
+                guards_added_fast,
+                runtime_asserts,
+                guards_url,
+                input_bytes,
+                aliased_inputs: aliased_inputs(input_sources),
                 output_files: &output_files,
-                compile_id_dir: &self.compile_id_dir,
+                compile_id_dir: self.compile_id_dir,
                 qps: TEMPLATE_QUERY_PARAM_SCRIPT,
             };
             let output = self.tt.render(&filename, &context)?;
             simple_file_output(&filename, lineno, compile_id, &output)
+                .map(|results| badge_failure(results, m.fail_type.is_some()))
         } else {
             Err(anyhow::anyhow!("Expected CompilationMetrics metadata"))
         }
@@ -466,10 +1144,16 @@ impl StructuredLogParser for AOTAutogradBackwardCompilationMetricsParser<'_> {
     fn name(&self) -> &'static str {
         "aot_autograd_backward_compilation_metrics"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Metrics
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::AOTAutograd
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.aot_autograd_backward_compilation_metrics
             .as_ref()
-            .map(|m| Metadata::AOTAutogradBackwardCompilationMetrics(m))
+            .map(Metadata::AOTAutogradBackwardCompilationMetrics)
     }
     fn parse<'e>(
         &self,
@@ -486,12 +1170,13 @@ impl StructuredLogParser for AOTAutogradBackwardCompilationMetricsParser<'_> {
                 .map_or("(unknown) ".to_string(), |c| format!("{cid} ", cid = c));
             let context = AOTAutogradBackwardCompilationMetricsContext {
                 css: crate::CSS,
-                m: &m,
+                m,
                 compile_id: id,
                 qps: TEMPLATE_QUERY_PARAM_SCRIPT,
             };
             let output = self.tt.render(&filename, &context)?;
             simple_file_output(&filename, lineno, compile_id, &output)
+                .map(|results| badge_failure(results, m.fail_type.is_some()))
         } else {
             Err(anyhow::anyhow!(
                 "Expected AOTAutogradBackwardCompilationMetrics metadata"
@@ -507,10 +1192,16 @@ impl StructuredLogParser for BwdCompilationMetricsParser<'_> {
     fn name(&self) -> &'static str {
         "bwd_compilation_metrics"
     }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Metrics
+    }
+    fn phase(&self, _metadata: &Metadata<'_>) -> CompilePhase {
+        CompilePhase::Inductor
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.bwd_compilation_metrics
             .as_ref()
-            .map(|m| Metadata::BwdCompilationMetrics(m))
+            .map(Metadata::BwdCompilationMetrics)
     }
     fn parse<'e>(
         &self,
@@ -527,12 +1218,13 @@ impl StructuredLogParser for BwdCompilationMetricsParser<'_> {
                 .map_or("(unknown) ".to_string(), |c| format!("{cid} ", cid = c));
             let context = BwdCompilationMetricsContext {
                 css: crate::CSS,
-                m: &m,
+                m,
                 compile_id: id,
                 qps: TEMPLATE_QUERY_PARAM_SCRIPT,
             };
             let output = self.tt.render(&filename, &context)?;
             simple_file_output(&filename, lineno, compile_id, &output)
+                .map(|results| badge_failure(results, m.fail_type.is_some()))
         } else {
             Err(anyhow::anyhow!("Expected BwdCompilationMetrics metadata"))
         }
@@ -544,8 +1236,14 @@ impl StructuredLogParser for DumpFileParser {
     fn name(&self) -> &'static str {
         "dump_file"
     }
+    fn low_value(&self) -> bool {
+        true
+    }
+    fn artifact_kind(&self, _metadata: &Metadata<'_>) -> ArtifactKind {
+        ArtifactKind::Code
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        e.dump_file.as_ref().map(|m| Metadata::DumpFile(m))
+        e.dump_file.as_ref().map(Metadata::DumpFile)
     }
     fn parse<'e>(
         &self,
@@ -627,8 +1325,35 @@ impl StructuredLogParser for ArtifactParser {
     fn name(&self) -> &'static str {
         "artifact"
     }
+    fn low_value(&self) -> bool {
+        true
+    }
+    fn artifact_kind(&self, metadata: &Metadata<'_>) -> ArtifactKind {
+        match metadata {
+            // e.g. fx_graph_cache_hit, aotautograd_cache_hash
+            Metadata::Artifact(m) if m.name.contains("cache_") => ArtifactKind::Cache,
+            // e.g. fx_graph_runnable
+            Metadata::Artifact(m) if m.name.contains("graph") => ArtifactKind::Graph,
+            _ => ArtifactKind::Other,
+        }
+    }
+    fn phase(&self, metadata: &Metadata<'_>) -> CompilePhase {
+        let Metadata::Artifact(m) = metadata else {
+            return CompilePhase::Other;
+        };
+        match m.name.as_str() {
+            "recompile_reasons" => CompilePhase::Dynamo,
+            // e.g. dynamo_error, aot_forward_graph_fw_metadata, fx_graph_runnable
+            name if name.starts_with("dynamo") => CompilePhase::Dynamo,
+            name if name.starts_with("aot") => CompilePhase::AOTAutograd,
+            name if name.starts_with("inductor") || name.starts_with("fx_graph") => {
+                CompilePhase::Inductor
+            }
+            _ => CompilePhase::Other,
+        }
+    }
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
-        e.artifact.as_ref().map(|m| Metadata::Artifact(m))
+        e.artifact.as_ref().map(Metadata::Artifact)
     }
     fn parse<'e>(
         &self,
@@ -642,11 +1367,11 @@ impl StructuredLogParser for ArtifactParser {
             match metadata.encoding.as_str() {
                 "string" => {
                     let filename = format!("{}.txt", metadata.name);
-                    simple_file_output(&filename, lineno, compile_id, &payload)
+                    simple_file_output(&filename, lineno, compile_id, payload)
                 }
                 "json" => {
                     let filename = format!("{}.json", metadata.name);
-                    let value: Value = serde_json::from_str(&payload).unwrap();
+                    let value: Value = serde_json::from_str(payload).unwrap();
                     let pretty = serde_json::to_string_pretty(&value).unwrap();
                     simple_file_output(&filename, lineno, compile_id, &pretty)
                 }
@@ -655,6 +1380,19 @@ impl StructuredLogParser for ArtifactParser {
                     metadata.encoding
                 )),
             }
+            .map(|results| {
+                // e.g. fx_graph_cache_hit, aotautograd_cache_bypass
+                let badge = match metadata.name.rsplit_once("cache_") {
+                    Some((_, "hit")) => Some(Badge::CacheHit),
+                    Some((_, "miss")) => Some(Badge::CacheMiss),
+                    Some((_, "bypass")) => Some(Badge::CacheBypass),
+                    _ => None,
+                };
+                match badge {
+                    Some(badge) => results.into_iter().map(|r| r.with_badge(badge)).collect(),
+                    None => results,
+                }
+            })
         } else {
             Err(anyhow::anyhow!("Expected Artifact metadata"))
         }
@@ -663,6 +1401,7 @@ impl StructuredLogParser for ArtifactParser {
 
 pub struct PropagateRealTensorsParser<'t> {
     tt: &'t TinyTemplate<'t>,
+    frame_filter: &'t FrameFilter,
 }
 impl StructuredLogParser for PropagateRealTensorsParser<'_> {
     fn name(&self) -> &'static str {
@@ -671,7 +1410,7 @@ impl StructuredLogParser for PropagateRealTensorsParser<'_> {
     fn get_metadata<'e>(&self, e: &'e Envelope) -> Option<Metadata<'e>> {
         e.propagate_real_tensors
             .as_ref()
-            .map(|m| Metadata::SymbolicShapePropagateRealTensor(m))
+            .map(Metadata::SymbolicShapePropagateRealTensor)
     }
     fn parse<'e>(
         &self,
@@ -683,15 +1422,16 @@ impl StructuredLogParser for PropagateRealTensorsParser<'_> {
     ) -> anyhow::Result<ParserResults> {
         if let Metadata::SymbolicShapePropagateRealTensor(m) = metadata {
             let filename = "symbolic_guard_information.html";
-            let stack_html = format_stack(&m.stack.clone().unwrap_or(Vec::new()));
+            let stack_html =
+                format_stack(&m.stack.clone().unwrap_or(Vec::new()), self.frame_filter);
 
             let context = SymbolicGuardContext {
                 css: crate::CSS,
                 expr: m.expr.clone().unwrap(),
-                stack_html: stack_html,
+                stack_html,
             };
-            let output = self.tt.render(&filename, &context)?;
-            simple_file_output(&filename, lineno, compile_id, &output)
+            let output = self.tt.render(filename, &context)?;
+            simple_file_output(filename, lineno, compile_id, &output)
         } else {
             Err(anyhow::anyhow!(
                 "Expected SymbolicShapePropagateRealTensor metadata"
@@ -704,11 +1444,16 @@ impl StructuredLogParser for PropagateRealTensorsParser<'_> {
 pub fn default_parsers<'t>(
     tt: &'t TinyTemplate<'t>,
     parser_config: &ParseConfig,
+    symbolic_shape_specialization_index: &'t RefCell<SymbolicShapeSpecializationIndex>,
+    dynamo_guards_index: &'t RefCell<DynamoGuardsIndex>,
+    input_source_index: &'t RefCell<InputSourceIndex>,
+    graph_inputs_index: &'t RefCell<GraphInputsIndex>,
+    frame_filter: &'t FrameFilter,
 ) -> Vec<Box<dyn StructuredLogParser + 't>> {
     // We need to use Box wrappers here because vecs in Rust need to have known size
     if parser_config.export {
         return vec![
-            Box::new(PropagateRealTensorsParser { tt }),
+            Box::new(PropagateRealTensorsParser { tt, frame_filter }),
             Box::new(SentinelFileParser::new("exported_program", |e| {
                 e.exported_program.as_ref()
             })),
@@ -722,15 +1467,19 @@ pub fn default_parsers<'t>(
         Box::new(SentinelFileParser::new("compiled_autograd_graph", |e| {
             e.compiled_autograd_graph.as_ref()
         })),
-        Box::new(SentinelFileParser::new("aot_forward_graph", |e| {
-            e.aot_forward_graph.as_ref()
-        })),
+        Box::new(AOTGraphParser {
+            filename: "aot_forward_graph",
+            get_sentinel: |e| e.aot_forward_graph.as_ref(),
+            graph_inputs_index,
+        }),
         Box::new(SentinelFileParser::new("aot_backward_graph", |e| {
             e.aot_backward_graph.as_ref()
         })),
-        Box::new(SentinelFileParser::new("aot_inference_graph", |e| {
-            e.aot_inference_graph.as_ref()
-        })),
+        Box::new(AOTGraphParser {
+            filename: "aot_inference_graph",
+            get_sentinel: |e| e.aot_inference_graph.as_ref(),
+            graph_inputs_index,
+        }),
         Box::new(SentinelFileParser::new("aot_joint_graph", |e| {
             e.aot_joint_graph.as_ref()
         })),
@@ -744,9 +1493,17 @@ pub fn default_parsers<'t>(
             e.dynamo_cpp_guards_str.as_ref()
         })),
         Box::new(GraphDumpParser),
-        Box::new(DynamoOutputGraphParser),
-        Box::new(DynamoGuardParser { tt }),
-        Box::new(InductorOutputCodeParser::new(parser_config)),
+        Box::new(DynamoOutputGraphParser::new(
+            tt,
+            input_source_index,
+            graph_inputs_index,
+        )),
+        Box::new(DynamoGuardParser::new(
+            tt,
+            symbolic_shape_specialization_index,
+            dynamo_guards_index,
+        )),
+        Box::new(InductorOutputCodeParser::new(tt, parser_config)),
         Box::new(OptimizeDdpSplitChildParser),
         Box::new(AOTAutogradBackwardCompilationMetricsParser { tt }), // TODO: use own tt instances
         Box::new(BwdCompilationMetricsParser { tt }),                 // TODO: use own tt instances
@@ -757,3 +1514,126 @@ pub fn default_parsers<'t>(
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The formatted guard without its highlighting
+    fn format_plain(code: &str) -> String {
+        let html = format_guard_code(code);
+        Regex::new("<[^>]*>")
+            .unwrap()
+            .replace_all(&html, "")
+            .into_owned()
+    }
+
+    #[test]
+    fn test_format_guard_code_short() {
+        let html = format_guard_code("L['x'].size()[0] == 3");
+        assert_eq!(
+            html,
+            "<span class='guard-source'>L['x'].size()[0]</span> == \
+             <span class='guard-number'>3</span>"
+        );
+    }
+
+    #[test]
+    fn test_format_guard_code_nested_parentheses() {
+        // Breaks before and/or, indented by how deep in parentheses they are
+        let code = "___check_type_id(L['x'], 123) and (L['y'] is None or \
+                    (len(L['z']) == 2 and L['z'][0] == 'a (b) and c'))";
+        assert_eq!(
+            format_plain(code),
+            "___check_type_id(L['x'], 123)\n    \
+             and (L['y'] is None\n        \
+             or (len(L['z']) == 2\n            \
+             and L['z'][0] == 'a (b) and c'))"
+        );
+    }
+
+    #[test]
+    fn test_format_guard_code_long_line() {
+        let code = format!(
+            "L['x'].size()[0] == 3 and {}",
+            "L['y'] is not None and ".repeat(4)
+        );
+        let code = code.trim_end_matches(" and ");
+        let plain = format_plain(code);
+        assert_eq!(plain.lines().count(), 5);
+        assert!(plain.lines().skip(1).all(|l| l.starts_with("    and ")));
+        // Nothing but whitespace changes
+        assert_eq!(
+            plain.split_whitespace().collect::<Vec<_>>(),
+            code.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_format_guard_code_multi_line() {
+        // Guards that already span lines, and are short, are left as they are
+        let code = "L['x'].size()[0] == 3 and\nL['y'].size()[0] == 4";
+        assert_eq!(format_plain(code), code);
+        // A string spanning lines is still one string
+        assert!(format_guard_code("x == 'a\nb' and y")
+            .contains("<span class='guard-string'>'a\nb'</span>"));
+    }
+
+    #[test]
+    fn test_recompile_graph_inputs_are_interned() {
+        let tt = TinyTemplate::new();
+        let input_source_index = RefCell::new(InputSourceIndex::default());
+        let graph_inputs_index = RefCell::new(GraphInputsIndex::default());
+        let parser = DynamoOutputGraphParser::new(&tt, &input_source_index, &graph_inputs_index);
+        let input = InputSource {
+            source: "L['x']".to_string(),
+            placeholder: "L_x_".to_string(),
+            dtype: "torch.float32".to_string(),
+            size: "[3]".to_string(),
+            storage: None,
+            storage_bytes: None,
+        };
+        let payload = "class GraphModule(torch.nn.Module):\n    \
+                       def forward(self, L_x_: \"f32[3]\"):\n        return (L_x_,)\n";
+        let metadata = DynamoOutputGraphMetadata { sizes: None };
+        // The same graph, for a compile id and its recompile
+        let compile_ids: Vec<Option<CompileId>> = (0..2)
+            .map(|n| {
+                Some(CompileId {
+                    compiled_autograd_id: None,
+                    frame_id: Some(0),
+                    frame_compile_id: Some(n),
+                    attempt: Some(0),
+                })
+            })
+            .collect();
+        let outputs: Vec<String> = compile_ids
+            .iter()
+            .map(|cid| {
+                input_source_index
+                    .borrow_mut()
+                    .insert(cid.clone(), vec![input.clone()]);
+                let results = parser
+                    .parse(
+                        1,
+                        Metadata::DynamoOutputGraph(&metadata),
+                        None,
+                        cid,
+                        payload,
+                    )
+                    .unwrap();
+                match &results[..] {
+                    [ParserOutput::File(_, content)] => content.clone(),
+                    _ => panic!("Expected a single file"),
+                }
+            })
+            .collect();
+        assert_eq!(
+            outputs[0],
+            format!("# Graph inputs:\n#   L_x_: L['x'] torch.float32[3]\n\n{payload}")
+        );
+        assert_eq!(outputs[0], outputs[1]);
+        let index = graph_inputs_index.borrow();
+        assert!(Rc::ptr_eq(&index[&compile_ids[0]], &index[&compile_ids[1]]));
+    }
+}
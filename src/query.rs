@@ -0,0 +1,257 @@
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+use crate::types::ParsedEnvelope;
+
+// A jq-like pipeline of selectors over the entries of a log, e.g.,
+// `compilation_metrics | select(.fail_type) | .fail_reason`.  Each stage maps every value out of
+// the previous one to any number of values:
+//   name                the name field of the value, left out if missing or null, e.g., to only
+//                       keep the entries of one kind
+//   .a.b[0] .a[] .      a path: fields, array elements (negative from the end), every element
+//   select(PATH)        the value, if the path gives anything but null or false
+//   select(PATH OP LIT) the value, if the path compares to a JSON literal with ==, !=, <, <=, >
+//                       or >=
+#[derive(Debug)]
+pub struct Query {
+    stages: Vec<Stage>,
+}
+
+#[derive(Debug)]
+enum Stage {
+    Kind(String),
+    Path(Vec<Step>),
+    Select(Vec<Step>, Option<(Op, Value)>),
+}
+
+#[derive(Debug)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Each,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> anyhow::Result<Query> {
+        let stages = split_stages(query)?
+            .into_iter()
+            .map(parse_stage)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Query { stages })
+    }
+
+    pub fn run(&self, input: Value) -> Vec<Value> {
+        let mut values = vec![input];
+        for stage in &self.stages {
+            values = values
+                .into_iter()
+                .flat_map(|value| stage.apply(value))
+                .collect();
+        }
+        values
+    }
+}
+
+// An entry as a query sees it: its metadata, compile id, line number and payload, without the
+// fields it doesn't have
+pub fn entry_value(entry: &ParsedEnvelope) -> Value {
+    let mut value = serde_json::to_value(entry).unwrap_or(Value::Null);
+    if let Value::Object(fields) = &mut value {
+        fields.retain(|_, v| !v.is_null());
+    }
+    value
+}
+
+impl Stage {
+    fn apply(&self, value: Value) -> Vec<Value> {
+        match self {
+            Stage::Kind(name) => match value {
+                Value::Object(mut fields) => fields
+                    .remove(name)
+                    .filter(|v| !v.is_null())
+                    .into_iter()
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Stage::Path(steps) => eval_path(&value, steps),
+            Stage::Select(steps, comparison) => {
+                let keep = eval_path(&value, steps).iter().any(|v| match comparison {
+                    None => !matches!(v, Value::Null | Value::Bool(false)),
+                    Some((op, literal)) => compare(v, *op, literal),
+                });
+                if keep {
+                    vec![value]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+fn eval_path(value: &Value, steps: &[Step]) -> Vec<Value> {
+    let Some((step, rest)) = steps.split_first() else {
+        return vec![value.clone()];
+    };
+    match step {
+        Step::Field(name) => eval_path(value.get(name).unwrap_or(&Value::Null), rest),
+        Step::Index(i) => {
+            let element = value.as_array().and_then(|a| {
+                let i = if *i < 0 { a.len() as i64 + i } else { *i };
+                usize::try_from(i).ok().and_then(|i| a.get(i))
+            });
+            eval_path(element.unwrap_or(&Value::Null), rest)
+        }
+        Step::Each => match value {
+            Value::Array(a) => a.iter().flat_map(|v| eval_path(v, rest)).collect(),
+            Value::Object(o) => o.values().flat_map(|v| eval_path(v, rest)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Value) -> bool {
+    // 1 and 1.0 are equal
+    let ordering = match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .zip(b.as_f64())
+            .and_then(|(a, b)| a.partial_cmp(&b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => (value == literal).then_some(Ordering::Equal),
+    };
+    match op {
+        Op::Eq => ordering == Some(Ordering::Equal),
+        Op::Ne => ordering != Some(Ordering::Equal),
+        Op::Lt => ordering == Some(Ordering::Less),
+        Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Op::Gt => ordering == Some(Ordering::Greater),
+        Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+// Splits on the | that aren't in a string literal
+fn split_stages(query: &str) -> anyhow::Result<Vec<&str>> {
+    let mut stages = Vec::new();
+    let (mut start, mut in_string, mut escaped) = (0, false, false);
+    for (i, c) in query.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '|' if !in_string => {
+                stages.push(query[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if in_string {
+        bail!("Unterminated string in query {query:?}");
+    }
+    stages.push(query[start..].trim());
+    if let Some(empty) = stages.iter().position(|s| s.is_empty()) {
+        bail!("Stage {} of query {query:?} is empty", empty + 1);
+    }
+    Ok(stages)
+}
+
+fn parse_stage(stage: &str) -> anyhow::Result<Stage> {
+    if let Some(inner) = stage
+        .strip_prefix("select(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        let inner = inner.trim();
+        let (path, rest) = parse_path(inner)?;
+        let rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(Stage::Select(path, None));
+        }
+        let (op, literal) = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .into_iter()
+        .find_map(|(token, op)| rest.strip_prefix(token).map(|l| (op, l)))
+        .ok_or_else(|| anyhow!("Expected a comparison after the path in {stage:?}"))?;
+        let literal: Value = serde_json::from_str(literal.trim())
+            .map_err(|_| anyhow!("Expected a JSON literal to compare with in {stage:?}"))?;
+        return Ok(Stage::Select(path, Some((op, literal))));
+    }
+    if stage.starts_with('.') {
+        let (path, rest) = parse_path(stage)?;
+        if !rest.is_empty() {
+            bail!("Unexpected {rest:?} in {stage:?}");
+        }
+        return Ok(Stage::Path(path));
+    }
+    if is_identifier(stage) {
+        return Ok(Stage::Kind(stage.to_string()));
+    }
+    bail!("Expected a name, a path starting with ., or select(...), got {stage:?}")
+}
+
+// A path at the start of s, and what follows it
+fn parse_path(s: &str) -> anyhow::Result<(Vec<Step>, &str)> {
+    let Some(mut rest) = s.strip_prefix('.') else {
+        bail!("Expected a path starting with ., got {s:?}");
+    };
+    let mut steps = Vec::new();
+    // . on its own is the whole value
+    let field_len = |s: &str| {
+        s.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(s.len())
+    };
+    let len = field_len(rest);
+    if len > 0 {
+        steps.push(Step::Field(rest[..len].to_string()));
+        rest = &rest[len..];
+    }
+    loop {
+        if let Some(after) = rest.strip_prefix('.') {
+            let len = field_len(after);
+            if len == 0 {
+                bail!("Expected a field name after . in {s:?}");
+            }
+            steps.push(Step::Field(after[..len].to_string()));
+            rest = &after[len..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| anyhow!("Unclosed [ in {s:?}"))?;
+            let index = after[..end].trim();
+            steps.push(if index.is_empty() {
+                Step::Each
+            } else {
+                Step::Index(
+                    index
+                        .parse()
+                        .map_err(|_| anyhow!("Expected an array index, got {index:?} in {s:?}"))?,
+                )
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Ok((steps, rest));
+        }
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && s.chars().next().is_some_and(|c| !c.is_ascii_digit())
+}
@@ -13,6 +13,8 @@ table td { vertical-align: top; }
 .stack-trie .marker:not(.collapsed)::before {
   content: "- ";
 }
+.stack-trie .more-frames summary { color: #888; cursor: pointer; }
+.stack-trie .trie-time { color: #a33; font-family: monospace; }
 .stack-trie a { text-decoration: none; }
 .stack-trie a:hover { text-decoration: underline; }
 .status-missing { background-color: purple; color: white; }
@@ -20,6 +22,22 @@ table td { vertical-align: top; }
 .status-empty { background-color: white; color: black; }
 .status-ok { background-color: green; color: white; }
 .status-break { background-color: lime; color: black; }
+.output-meta { color: gray; font-size: smaller; }
+.graph-break-advice { margin: 4px 0; padding: 4px 8px; border-left: 3px solid steelblue; background-color: #eef4fa; }
+.abandoned-attempt { color: gray; border-left: 3px dashed gray; padding-left: 4px; margin-bottom: 8px; }
+.abandoned-attempt-label { font-style: italic; }
+.compiled-autograd { background-color: #6a4c93; color: white; font-size: 0.75em; padding: 0 0.3em; border-radius: 3px; }
+.backend { background-color: #eee; color: #333; font-size: 0.75em; padding: 0 0.3em; border-radius: 3px; }
+.link-category { background-color: #e3ecf5; color: #234; font-size: 0.85em; padding: 0 0.3em; border-radius: 3px; }
+.recompile-storm { margin: 8px 0; padding: 0 12px; border: 3px solid red; background-color: #fee; }
+.guard-construction-dominates { background-color: orange; color: black; }
+.histogram-bar { display: inline-block; height: 1em; background-color: steelblue; }
+.waterfall { width: 400px; }
+.inline-preview summary { color: gray; font-size: smaller; }
+.phase { list-style: none; }
+.phase-heading { color: gray; font-size: smaller; }
+.inline-preview pre { margin: 2px 0 4px 0; padding: 4px; background-color: #f6f6f6; overflow-x: auto; }
+.waterfall-bar { display: inline-block; height: 1em; min-width: 1px; background-color: steelblue; }
 summary::-webkit-details-marker { color: #00ACF3; font-size: 125%; margin-right: 2px; }
 summary:focus { outline-style: none; }
 article > details > summary { font-size: 28px; margin-top: 16px; }
@@ -39,6 +57,14 @@ pub static JAVASCRIPT: &str = r#"
       toggleItem.classList.toggle('collapsed');
     }
   }
+
+  // Only show the compile ids compiled by the selected backend
+  function filterBackend(select) {
+    document.querySelectorAll('li[id^="cid-"]').forEach((item) => {
+      const shown = !select.value || item.dataset.backend === select.value;
+      item.style.display = shown ? '' : 'none';
+    });
+  }
 "#;
 
 pub static EXPORT_CSS: &str = r#"
@@ -75,13 +101,47 @@ td:first-child {
 }
 "#;
 
+// The guards page is rendered before the compilation metrics page exists, so links to it
+// use this placeholder, which is rewritten once the metrics filename is known
+pub static COMPILATION_METRICS_PLACEHOLDER_URL: &str = "compilation_metrics.html";
+
 pub static TEMPLATE_DYNAMO_GUARDS: &str = r#"
 <html>
+<head>
+<style>
+.guard \{ margin: 0; white-space: pre-wrap; }
+.guard-source \{ background-color: #fff3bf; }
+.guard-string \{ color: #0a7d27; }
+.guard-number \{ color: #1750eb; }
+.guard-keyword \{ color: #a626a4; font-weight: bold; }
+</style>
+</head>
 <body>
+{{ if by_source }}
+<h2>Guards by Source</h2>
+<p>
+Top level objects the guards check, most guarded first.  A guard that mentions several objects is
+counted for each of them.
+</p>
+<table>
+<tr> <th>Source</th> <th>Guards</th> <th></th> </tr>
+{{ for group in by_source }}
+<tr>
+<td><code>{group.source}</code></td>
+<td>{group.count}</td>
+<td>{{ for g in group.guards }}<a href='#guard-{g}'>#{g}</a> {{ endfor }}</td>
+</tr>
+{{ endfor }}
+</table>
+{{ endif }}
 <h2>Guards</h2>
 <ul>
 {{ for guard in guards }}
-    <li><code>{guard.code}</code></li>
+    <li id="guard-{@index}"><pre class="guard">{guard.code_html | format_unescaped}</pre>
+    {{ for spec in guard.specializations }}
+    <a href="{metrics_url}#specialization-{spec.index}">[specialized {spec.symbol} = {spec.value}]</a>
+    {{ endfor }}
+    </li>
 {{ endfor }}
 </ul>
 {qps | format_unescaped}
@@ -103,6 +163,83 @@ pub static TEMPLATE_INDEX: &str = r#"
 <body>
 <div>
 {custom_header_html | format_unescaped}
+{{ if regressions }}
+<div class="regressions">
+<h2>Regressions</h2>
+<p>
+These frames compiled slower, or into more graphs, than in the baseline this run was checked against, by more
+than {regression_threshold_pct}%.
+</p>
+<table>
+<tr><th>Frame</th><th>Metric</th><th>Baseline</th><th>This run</th><th>Increase</th></tr>
+{{ for regression in regressions }}
+<tr><td>{regression.frame}{{ if regression.co_name }} <code>{regression.co_name}</code>{{ endif }}</td><td>{regression.metric}</td><td>{regression.baseline}</td><td>{regression.current}</td><td>+{regression.increase_pct}%</td></tr>
+{{ endfor }}
+</table>
+</div>
+{{ endif }}
+{{ if recompile_storms }}
+<div class="recompile-storm">
+<h2> Recompile Storm </h2>
+<p>
+These frames recompiled more than {recompile_storm_threshold} times within {recompile_storm_window}.  Every
+recompile stalls the program while it compiles, and frames that keep recompiling usually end up hitting the
+cache size limit and falling back to eager.  See the recompile reasons on each compile id's page for what changed.
+</p>
+<ul>
+{{ for storm in recompile_storms }}
+<li>Frame <strong>{storm.frame}</strong>: {storm.num_recompiles} recompiles between {storm.window_start} and {storm.window_end}</li>
+{{ endfor }}
+</ul>
+</div>
+{{ endif }}
+{{ if num_missing_interned_strs }}
+<div class="missing-interned-strs">
+<h2>Missing Interned Strings</h2>
+<p>
+{num_missing_interned_strs} file names referred to by stack frames were never defined by a <code>str</code>
+entry (e.g., the start of the log was lost), so those frames show <code>(unknown)</code>.
+</p>
+<table>
+<tr><th>Id</th><th>References</th><th>First referred to</th></tr>
+{{ for missing in missing_interned_strs }}
+<tr><td>{missing.id}</td><td>{missing.count}</td><td><a href="{missing.raw_url}">line {missing.first_lineno}</a></td></tr>
+{{ endfor }}
+</table>
+</div>
+{{ endif }}
+{{ if torch_installs }}
+<div class="torch-installs">
+<h2>Mixed Torch Builds</h2>
+<p>
+Stack frames in this log come from more than one torch install, so it mixes entries from different torch
+builds, e.g., from a job that was resumed after an upgrade.  Comparisons across compile ids (recompiles,
+compile times, graph counts) may be misleading when the compile ids ran on different builds.
+</p>
+<table>
+<tr><th>Torch installed in</th><th>First seen</th></tr>
+{{ for install in torch_installs }}
+<tr><td><code>{install.path}</code></td><td><a href="{install.raw_url}">line {install.first_lineno}</a></td></tr>
+{{ endfor }}
+</table>
+</div>
+{{ endif }}
+{{ if num_late_interned_strs }}
+<p class="late-interned-strs">
+{num_late_interned_strs} file names were referred to by stack frames before their <code>str</code> entry
+was logged; rerun with <code>--two-pass</code> to resolve them.
+</p>
+{{ endif }}
+{{ if num_unrendered_compiles }}
+<p class="unrendered-compiles">
+Only the artifacts of the first {max_compiles} compile ids were rendered (<code>--max-compiles</code>), so
+{num_unrendered_compiles} compile ids below have no artifacts.  The stack trie, failures, and the metrics
+based summaries still cover every compilation.
+</p>
+{{ endif }}
+<p>
+A <a href="print.html">printable summary</a> of this report is available, e.g., for attaching to a postmortem.
+</p>
 <h2>Stack trie</h2>
 <p>
 The <strong>stack trie</strong> is a way of getting a quick orientation on where all the
@@ -118,8 +255,19 @@ Links to particular compilation are color coded by status:
 <span class="status-break">[Success with restart (e.g., graph break)]</span>,
 <span class="status-empty">[Empty graph]</span>,
 <span class="status-error">[Error]</span>,
-<span class="status-missing">[Metrics were missing]</span>
+<span class="status-missing">[Metrics were missing]</span>.
+Compilations of backward graphs traced by compiled autograd are marked
+<span class="compiled-autograd">CA</span>.
+</p>
+{{ if has_stack_trie_exports }}
+<p>
+For flame graph tools, the stack trie is also available as folded stacks weighted by
+<a href="stack_trie.folded">number of compilations</a> or by
+<a href="stack_trie_time_us.folded">compile time in microseconds</a> (for <code>flamegraph.pl</code> or
+<code>inferno-flamegraph</code>), and as a <a href="stack_trie.speedscope.json">speedscope profile</a>
+with both weights.
 </p>
+{{ endif }}
 {stack_trie_html | format_unescaped}
 </div>
 <div>
@@ -130,6 +278,160 @@ Various issues may cause Dynamo to restart its analysis or give up on compilatio
 This run had <strong><a href="failures_and_restarts.html">{num_breaks} restart(s) and/or compilation failure(s)</a></strong>.
 </p>
 {{ endif }}
+{{ if restart_time_s }}
+<h2> Time Wasted on Restarted Analysis </h2>
+<p>
+When Dynamo restarts its analysis of a frame (e.g., on a graph break in an inlined call), everything it traced
+before the restart is thrown away.  This run spent <strong>{restart_time_s | format_duration}</strong> on analysis
+that was restarted.  The frames that wasted the most time:
+</p>
+<table>
+<tr><th>Frame</th><th>Function</th><th>Time Before Restart</th><th>Restarted Compilations</th></tr>
+{{ for r in restart_time_frames }}
+<tr><td>{r.frame}</td><td><code>{r.co_name}</code></td><td>{r.restart_time_s | format_duration}</td><td>{r.num_restarted}</td></tr>
+{{ endfor }}
+</table>
+{{ endif }}
+{{ if num_specializations }}
+<h2> Symbolic Shape Specializations </h2>
+<p>
+When a dynamic dimension is forced to take a particular value, it is specialized, and any change to it
+will cause a recompile.  This run specialized
+<strong><a href="symbolic_shape_specializations.html">{num_specializations} distinct source expression(s)</a></strong>;
+the summary is sorted by how often each specialization happened across compilations.
+</p>
+{{ endif }}
+{{ if num_tensor_shapes }}
+<h2> Tensor Catalog </h2>
+<p>
+The inputs of all compilations had <strong><a href="tensor_catalog.html">{num_tensor_shapes} distinct shape(s) and dtype(s)</a></strong>.
+The catalog counts how often each was seen, and lists the inputs whose shape changed between compilations, which
+is useful for deciding what to mark dynamic (or static) across the whole model.
+</p>
+{{ endif }}
+{{ if compile_time_histogram }}
+<h2> Compile Time Distribution </h2>
+<p>
+Number of compilations by entire frame compile time.
+</p>
+<table>
+{{ for bucket in compile_time_histogram }}
+<tr>
+<td>{bucket.label}</td>
+<td>{bucket.count}</td>
+<td style="width: 400px"><span class="histogram-bar" style="width: {bucket.width}%"></span></td>
+</tr>
+{{ endfor }}
+</table>
+{{ endif }}
+{{ if metrics_table }}
+<h2> Compilation Metrics </h2>
+<p>
+Selected fields of each compilation's metrics (choose them with <code>--metrics-columns</code>), also available as
+<a href="compilation_metrics.csv">CSV</a>.
+</p>
+<details>
+<summary>Metrics of each compilation</summary>
+<table>
+<tr><th>Compile Id</th><th>Status</th>{{ for c in metrics_columns }}<th>{c}</th>{{ endfor }}</tr>
+{{ for row in metrics_table }}
+<tr><td>{row.compile_id}</td><td><span class="{row.status_class}">{row.status}</span></td>{{ for cell in row.cells }}<td>{cell}</td>{{ endfor }}</tr>
+{{ endfor }}
+</table>
+</details>
+{{ endif }}
+{{ if guard_construction }}
+<h2> Guard Construction </h2>
+<p>
+Guards added vs. suppressed as duplicates of an existing guard, per frame.  Frames where building
+guards took at least half of the compile time are <span class="guard-construction-dominates">flagged</span>; these
+usually have a very large number of inputs or parameters being guarded on.
+</p>
+<table>
+<tr><th>Frame</th><th>Guards Added</th><th>Duplicates Suppressed</th><th>Guard Build Time</th><th>Compile Time</th><th>% of Compile Time</th></tr>
+{{ for row in guard_construction }}
+<tr{{ if row.dominates }} class="guard-construction-dominates"{{ endif }}>
+<td>{row.frame}</td>
+<td>{row.guards_added}</td>
+<td>{row.guards_suppressed}</td>
+<td>{row.build_guards_time_s | format_duration}</td>
+<td>{row.compile_time_s | format_duration}</td>
+<td>{row.percent_of_compile_time}</td>
+</tr>
+{{ endfor }}
+</table>
+{{ endif }}
+{{ if guard_sources }}
+<h2> Top Guard Sources </h2>
+<p>
+The source expressions mentioned by the most guards, across all compilations.  Marking a tensor static
+(or dynamic) or freezing a module attribute that shows up here is the most direct way to cut down on
+guards, and on the time spent checking them.
+</p>
+<table>
+<tr><th>Source</th><th>Guards</th><th>Compile Ids</th></tr>
+{{ for s in guard_sources }}
+<tr><td><code>{s.source}</code></td><td>{s.num_guards}</td><td>{s.num_compile_ids}</td></tr>
+{{ endfor }}
+</table>
+{{ endif }}
+{{ if external_links }}
+<h2> External Links </h2>
+<p>
+Links logged alongside the compilations, e.g., to profiler traces or dashboards.
+</p>
+<table>
+<tr><th>Category</th><th>Link</th><th>Compile Id</th></tr>
+{{ for l in external_links }}
+<tr><td>{{ if l.category }}<span class="link-category">{l.category}</span>{{ endif }}</td><td><a href="{l.url}">{l.name}</a>{{ if l.description }}: {l.description}{{ endif }}</td><td>{{ if l.compile_id_url }}<a href="{l.compile_id_url}">{l.compile_id}</a>{{ else }}{l.compile_id}{{ endif }}</td></tr>
+{{ endfor }}
+</table>
+{{ endif }}
+{{ if num_modules }}
+<h2> Compilations by Module </h2>
+<p>
+Compilations came from <strong><a href="modules.html">{num_modules} module(s)</a></strong>, judging by the
+<code>forward</code> each was compiled from or called by.
+</p>
+{{ endif }}
+{{ if num_non_compliant_ops }}
+<h2> Non-Compliant Custom Ops </h2>
+<p>
+<strong><a href="non_compliant_ops.html">{num_non_compliant_ops} custom op(s)</a></strong> used in this run are
+non-compliant, e.g., they have no fake implementation, which causes graph breaks or wrong results under
+compilation.
+</p>
+{{ endif }}
+{{ if num_recompile_thrashing }}
+<h2> Recompile Thrashing </h2>
+<p>
+<strong><a href="recompile_thrashing.html">{num_recompile_thrashing} guard(s)</a></strong> failed on more than one
+recompile of the same frame.  This usually means an input keeps alternating between values (e.g., shapes
+ping-ponging the cache), which is better fixed by marking it dynamic than by raising the cache size limit.
+</p>
+{{ endif }}
+{{ if num_specialization_recompiles }}
+<h2> Specializations That Caused Recompiles </h2>
+<p>
+<strong><a href="specialization_recompiles.html">{num_specialization_recompiles} recompile(s)</a></strong> were caused
+by the guard on a symbolic shape specialization failing, i.e., a dimension was specialized to a value in one compile
+id and then seen with a different value.  The page follows each frame's history from specialization to recompile.
+</p>
+{{ endif }}
+{{ if has_cross_rank_metrics }}
+<h2> Cross-Rank Comparison </h2>
+<p>
+This log contains compilations from more than one rank, but only the first rank seen is rendered in this report.
+The <strong><a href="cross_rank_metrics.html">cross-rank comparison</a></strong> summarizes compile times and failures for each
+compile id across all ranks.
+</p>
+{{ endif }}
+{{ if has_compile_divergence }}
+<p>
+The <strong><a href="compile_divergence.html">compile divergence report</a></strong> found {num_compile_divergences}
+differences between what the ranks compiled.
+</p>
+{{ endif }}
 <h2>IR dumps</h2>
 <p>
 The <strong>IR dumps</strong> collected dumped intermediate products from various points of the PT2
@@ -174,25 +476,62 @@ phase generates:
 <li>Inductor will perform code generation, producing the final <code>inductor_output_code</code> which will be executed at runtime.  This output is a valid Python program and can be directly run.</li>
 </ol>
 
+{{ if graph_count_mismatches }}
+<h2> Graph Size Mismatches </h2>
+<p>
+The graph size in these compilations' metrics doesn't match the <code>dynamo_output_graph</code> that was logged for them.
+This usually means the log was truncated, or was written by a version of torch that tlparse doesn't understand the
+graph dumps of, so other parts of this report may be incomplete as well.
+</p>
+<table>
+<tr><th>Compile Id</th><th>Ops (metrics)</th><th>Ops (graph)</th><th>Nodes (metrics)</th><th>Nodes (graph)</th></tr>
+{{ for g in graph_count_mismatches }}
+<tr><td><a href="{g.url}">{g.compile_id}</a></td><td>{g.metrics_op_count}</td><td>{g.op_count}</td><td>{g.metrics_node_count}</td><td>{g.node_count}</td></tr>
+{{ endfor }}
+</table>
+{{ endif }}
 {{ if has_chromium_events }}
 <h2> Chromium Events </h2>
 PT2 generates <a href='chromium_events.json'>Chromium Trace Events</a> in JSON on specific events during compilation.
+tlparse adds a span for each compilation and an instant for each build product, based on the log timestamps, in a separate process track.
 You can download and view them in a tool like <a href='https://ui.perfetto.dev/'>Perfetto</a>.
+{{ if chromium_event_problems }}
+<p>
+Some events were malformed or unbalanced, and were repaired so the trace still loads:
+</p>
+<ul>
+{{ for problem in chromium_event_problems }}
+<li>{problem}</li>
+{{ endfor }}
+</ul>
+{{ endif }}
 {{ endif  }}
+{{ if directory_pages }}
+<p>
+This run has too many compilations to list every build product here; they are split into one page per frame:
+</p>
+<ul>
+{{ for page in directory_pages }}
+    <li><a href="{page.url}">{page.label}</a>:
+    {{ for cid in page.compile_ids }}<a id="{cid.anchor}" href="{page.url}#{cid.anchor}">{cid.compile_id}</a> {{ endfor }}
+    </li>
+{{ endfor }}
+</ul>
+{{ else }}
 <p>
 Build products below:
 </p>
+{{ if backends }}
+<p>
+Backend: <select onchange="filterBackend(this)"><option value="">all</option>{{ for b in backends }}<option>{b}</option>{{ endfor }}</select>
+</p>
+{{ endif }}
 <ul>
 {{ for compile_directory in directory }}
-    <li><a id="{compile_directory.0}">{compile_directory.0}</a>
-    <ul>
-        {{ for path_idx in compile_directory.1 }}
-            <li><a href="{path_idx.url}">{path_idx.name}</a> {path_idx.suffix} ({path_idx.number})</li>
-        {{ endfor }}
-    </ul>
-    </li>
+{{ call compile_directory.html with compile_directory }}
 {{ endfor }}
 </ul>
+{{ endif }}
 </div>
 
 {{ if has_unknown_stack_trie }}
@@ -236,6 +575,100 @@ a {
 a:hover {
     text-decoration: underline;
 }
+.graph-break-advice {
+    margin: 4px 0;
+    padding: 4px 8px;
+    border-left: 3px solid steelblue;
+    background-color: #eef4fa;
+}
+"#;
+
+pub static TEMPLATE_PRINT: &str = r#"
+<html>
+<head>
+  <meta charset="UTF-8">
+  <title>Compilation Summary</title>
+</head>
+<style>
+{css | format_unescaped}
+{failures_css | format_unescaped}
+.stack-trie .marker \{ display: none; }
+@media print \{
+  h2 \{ page-break-after: avoid; }
+  .page \{ page-break-before: always; }
+  tr \{ page-break-inside: avoid; }
+}
+</style>
+<body>
+{custom_header_html | format_unescaped}
+<h1>Compilation Summary</h1>
+<ul>
+<li>{num_compilations} compilation(s), {num_failed} failed, {num_restarted} restarted</li>
+<li>Total entire frame compile time: {total_compile_time_s | format_duration}</li>
+<li>{num_breaks} restart(s) and/or compilation failure(s)</li>
+<li>Time wasted on restarted analysis: {restart_time_s | format_duration}</li>
+<li>{num_specializations} distinct symbolic shape specialization(s)</li>
+<li>{num_recompile_thrashing} guard(s) failed on more than one recompile of the same frame</li>
+</ul>
+<h2>Stack trie</h2>
+{stack_trie_html | format_unescaped}
+{{ if failures }}
+<div class="page">
+<h2>Failures and Restarts</h2>
+<table>
+<tr> <th> Compile Id </th> <th> Failure Type </th> <th> Failure Description </th> <th> Failure Source (compilation failures only) </th> <th> Raw Log </th> <th> Known Issue </th> </tr>
+{{ for failure in failures }}
+<tr> <td> {failure.0 | format_unescaped} </td>{failure.1 | format_unescaped}</tr>
+{{ endfor }}
+</table>
+</div>
+{{ endif }}
+{{ if metrics }}
+<div class="page">
+<h2>Compilation Metrics</h2>
+<table>
+<tr> <th> Compile Id </th> <th> Status </th> <th> Entire Frame </th> <th> Backend </th> <th> Graph Ops </th> <th> Guards </th> <th> Failure </th> </tr>
+{{ for row in metrics }}
+<tr id="{row.anchor}">
+<td>{row.compile_id}</td>
+<td><span class="{row.status_class}">{row.status}</span></td>
+<td>{row.entire_frame_compile_time_s | format_duration}</td>
+<td>{row.backend_compile_time_s | format_duration}</td>
+<td>{row.graph_op_count}</td>
+<td>{row.guard_count}</td>
+<td>{row.fail_type}</td>
+</tr>
+{{ endfor }}
+</table>
+</div>
+{{ endif }}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_RAW_CONTEXT: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css | format_unescaped}
+    .raw-failing \{ background-color: yellow; }
+    </style>
+    <title>Raw Log Context</title>
+</head>
+<body>
+    <h1>Raw Log Context</h1>
+    <p>
+    The lines of the log around each failure and restart, which are highlighted.  The whole log is in <a href="raw.log">raw.log</a>.
+    </p>
+    {{ for range in ranges }}
+    <h2>Lines {range.start}-{range.end}</h2>
+    <pre>{{ for line in range.lines }}<span id="L{line.number}"{{ if line.failing }} class="raw-failing"{{ endif }}>{line.number}: {line.text}</span>
+{{ endfor }}</pre>
+    {{ endfor }}
+    {qps | format_unescaped}
+</body>
+</html>
 "#;
 
 pub static TEMPLATE_FAILURES_AND_RESTARTS: &str = r#"
@@ -248,7 +681,7 @@ pub static TEMPLATE_FAILURES_AND_RESTARTS: &str = r#"
 <body>
     <h1>Failures and Restarts</h1>
     <table>
-    <tr> <th> Compile Id </th> <th> Failure Type </th> <th> Failure Description </th> <th> Failure Source (compilation failures only) </th> </tr>
+    <tr> <th> Compile Id </th> <th> Failure Type </th> <th> Failure Description </th> <th> Failure Source (compilation failures only) </th> <th> Raw Log </th> <th> Known Issue </th> </tr>
     {{ for failure in failures }}
     <tr> <td> {failure.0 | format_unescaped} </td>{failure.1 | format_unescaped}</tr>
     {{ endfor }}
@@ -257,91 +690,830 @@ pub static TEMPLATE_FAILURES_AND_RESTARTS: &str = r#"
 </html>
 "#;
 
-pub static TEMPLATE_COMPILATION_METRICS: &str = r#"
+pub static TEMPLATE_SYMBOLIC_SHAPE_SPECIALIZATIONS: &str = r#"
 <html>
 <head>
     <style>
     {css}
     </style>
-    <title>Compilation Metrics</title>
-    <base href="..">
+    <title>Symbolic Shape Specializations</title>
 </head>
 <body>
-    <h1>Compilation Info for {compile_id}</h1>
-    <p>{mini_stack_html | format_unescaped}</p>
-    <h2>Output files:</h2>
-    <ul>
-        {{ for path_idx in output_files }}
-            <li><a href="{compile_id_dir}/{path_idx.url}">{path_idx.name}</a> ({path_idx.number})</li>
-        {{ endfor }}
-    </ul>
-    <h2>Stack</h2>
-    {stack_html | format_unescaped}
-    <h2>Compile Time(seconds)</h2>
-    <p>Entire Frame <abbr title="Total time spent in convert_frame function">[?]</abbr>: {m.entire_frame_compile_time_s}</div>
-    <p>Backend <abbr title="Time spent running the backend compiler">[?]</abbr>: {m.backend_compile_time_s}</div>
-    {{ if m.inductor_compile_time_s }}
-    <p>Inductor <abbr title="Total time spent running inductor">[?]</abbr>: {m.inductor_compile_time_s}</div>
-    {{ endif }}
-    {{ if m.code_gen_time_s }}
-    <p>Code Gen Time: {m.code_gen_time_s}</p>
-    {{ endif}}
-    <div>Dynamo Time Before Restart <abbr title="Total time spent restarting dynamo analysis">[?]</abbr>: {m.dynamo_time_before_restart_s}</div>
-    <h2>Restarts and Failures</h2>
-    {{ if m.fail_type }}
-    <p>Failure Exception: <pre>{m.fail_type}</pre></p>
-    <p>Failure Reason: <pre>{m.fail_reason}</pre></p>
-    {{ if m.fail_user_frame_filename }}
-    <p>In file <pre>{m.fail_user_frame_filename}</pre>, line {m.fail_user_frame_lineno}</p>
-    {{ endif}}
-    {{ else }}
-    <p> No failures! </p>
-    {{ endif }}
-    {{ if m.restart_reasons }}
-    <p>Restart Reasons:<p>
-    {{ for restart_reason in m.restart_reasons }}
-     <li> <code> {restart_reason} </code> </li>
-    {{ endfor }}
-    {{ else }}
-    <p> No restarts! </p>
-    {{ endif }}
-    <h2>Cache Metrics</h2>
-    <p>Cache Size: {m.cache_size}</p>
-    <p>Accumulated Cache Size: {m.accumulated_cache_size}</p>
-    <h2>Graph Metrics</h2>
-    <p>Guard Count: {m.guard_count}</p>
-    <p>Shape Env Guards: {m.shape_env_guard_count}</p>
-    <p>Graph Ops: {m.graph_op_count}</p>
-    <p>Graph Nodes: {m.graph_node_count}</p>
-    <p>Graph Inputs: {m.graph_input_count}</p>
-    <h2> Custom Ops </h2>
-    {{ if m.compliant_custom_ops }}
-    <p> Compliant Custom Ops:</p>
-    {{ for op in m.compliant_custom_ops }}
-    <li> <code> {op} </code> </li>
-    {{ endfor }}
-    {{ endif }}
-    {{ if m.non_compliant_ops }}
-    <p> Non-Compliant Custom Ops:</p>
-    {{ for op in m.non_compliant_ops }}
-    <li> <code> {op} </code> </li>
-    {{ endfor }}
-    {{ endif }}
-    <h2>Symbolic shape specializations</h2>
+    <h1>Symbolic Shape Specializations</h1>
+    <p>
+    Identical specializations (same source expression, same value) are merged across compile ids.
+    </p>
     <table>
+    <tr> <th> Count </th> <th> Source(s) </th> <th> Value </th> <th> Symbol(s) </th> <th> Compile Ids </th> </tr>
+    {{ for spec in specializations }}
     <tr>
-        <th>Sym</th> <th>Source(s)</th> <th>Value</th> <th>User stack</th> <th>Framework stack</th>
-    </tr>
-    {{ for spec in symbolic_shape_specializations }}
-    <tr>
-        <td>{spec.symbol}</td>
-        <td>{{ for source in spec.sources }}{source}<br>{{ endfor }}</td>
+        <td>{spec.count}</td>
+        <td>{{ for source in spec.sources }}<code>{source}</code><br>{{ endfor }}</td>
         <td>{spec.value}</td>
-        <td>{spec.user_stack_html | format_unescaped}</td>
-        <td>{spec.stack_html | format_unescaped}</td>
+        <td>{{ for symbol in spec.symbols }}{symbol} {{ endfor }}</td>
+        <td>{{ for cid in spec.compile_ids }}<a href="index.html#{cid.anchor}">{cid.compile_id}</a> {{ endfor }}</td>
     </tr>
     {{ endfor }}
     </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static SINGLE_FILE_JAVASCRIPT: &str = r#"
+const files = JSON.parse(document.getElementById('files').textContent);
+const view = document.getElementById('view');
+
+function escapeHtml(s) {
+  return s.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;');
+}
+
+// Resolve a relative href against the page at path; both are relative to the report root
+function resolve(path, href) {
+  const parts = path.split('/');
+  parts.pop();
+  for (const p of href.split('/')) {
+    if (p === '..') {
+      parts.pop();
+    } else if (p !== '.' && p !== '') {
+      parts.push(p);
+    }
+  }
+  return parts.join('/');
+}
+
+function scrollTo(doc, hash) {
+  const target = hash && doc.getElementById(decodeURIComponent(hash));
+  if (target) {
+    target.scrollIntoView();
+  }
+}
+
+function show(path, hash) {
+  let content = files[path];
+  if (content === undefined) {
+    content = '<p><code>' + escapeHtml(path) + '</code> is not included in this report.</p>';
+  } else if (!path.endsWith('.html')) {
+    content = '<pre>' + escapeHtml(content) + '</pre>';
+  }
+  for (const a of document.querySelectorAll('nav a')) {
+    a.classList.toggle('current', a.dataset.path === path);
+  }
+  view.onload = () => {
+    const doc = view.contentDocument;
+    // Some pages set <base href=".."> so that their links are relative to the report root
+    const base = doc.querySelector('base');
+    const root = base ? resolve(path, base.getAttribute('href') + '/index.html') : path;
+    doc.addEventListener('click', (ev) => {
+      const a = ev.target.closest('a');
+      const href = a && a.getAttribute('href');
+      if (!href) {
+        return;
+      }
+      if (/^[a-z]+:/i.test(href)) {
+        a.target = '_blank';
+        return;
+      }
+      ev.preventDefault();
+      const [p, h] = href.split('#');
+      if (p === '') {
+        scrollTo(doc, h);
+      } else {
+        show(resolve(root, p), h);
+      }
+    });
+    scrollTo(doc, hash);
+  };
+  view.srcdoc = content;
+}
+
+const nav = document.getElementById('nav');
+for (const path of Object.keys(files).sort()) {
+  const a = document.createElement('a');
+  a.textContent = path;
+  a.href = '#';
+  a.dataset.path = path;
+  a.onclick = (ev) => {
+    ev.preventDefault();
+    show(path);
+  };
+  nav.appendChild(a);
+}
+show('index.html');
+"#;
+
+pub static TEMPLATE_SINGLE_FILE_REPORT: &str = r#"<html>
+<head>
+    <meta charset="UTF-8">
+    <title>tlparse report</title>
+    <style>
+    body \{ margin: 0; display: flex; height: 100vh; }
+    nav \{ width: 20%; overflow: auto; border-right: 1px solid #ccc; padding: 4px; font-family: monospace; font-size: smaller; }
+    nav a \{ display: block; white-space: nowrap; }
+    nav a.current \{ font-weight: bold; }
+    iframe \{ flex: 1; height: 100%; border: 0; }
+    </style>
+</head>
+<body>
+<nav id="nav"></nav>
+<iframe id="view"></iframe>
+<script type="application/json" id="files">{files | format_unescaped}</script>
+<script>
+{javascript | format_unescaped}
+</script>
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_COMPILE_DIRECTORY: &str = r#"
+    <li id="{anchor}"{{ if restarted_as }} class="abandoned-attempt"{{ endif }}{{ if backend }} data-backend="{backend}"{{ endif }}><a id="compile-{legacy_anchor}"></a><a id="{compile_id}">{compile_id}</a>
+    {{ if compiled_autograd }}<span class="compiled-autograd" title="Compiled autograd (backward)">CA</span>{{ endif }}
+    {{ if backend }}<span class="backend" title="Backend">{backend}</span>{{ endif }}
+    {{ if landing_url }}<a href="{landing_url}">[summary]</a>{{ endif }}
+    {{ if frame_url }}<a href="{frame_url}">[frame history]</a>{{ endif }}
+    {{ if input_bytes }}<span class="input-bytes">(total input bytes: {input_bytes})</span>{{ endif }}
+    {{ if restarted_as }}<span class="abandoned-attempt-label">abandoned attempt, restarted as <a href='#{restarted_as.anchor}'>{restarted_as.compile_id}</a></span>{{ endif }}
+    {{ if collapse }}<details><summary>Artifacts of the abandoned attempt</summary>{{ endif }}
+    <ul>
+    {{ for phase in phases }}
+        <li class="phase"><span class="phase-heading">{phase.heading}</span>
+        <ul>
+        {{ for path_idx in phase.files }}
+            <li data-kind="{path_idx.artifact_kind}"><a href="{path_idx.url}"{{ if path_idx.source_filename }} title="{path_idx.source_filename}"{{ endif }}>{path_idx.name}</a> {path_idx.suffix} ({path_idx.number}) <span class="output-meta">{{ if path_idx.size }}{path_idx.size | format_bytes}, {{ endif }}line {path_idx.lineno}</span>
+            {{ if path_idx.preview }}<details class="inline-preview"><summary>preview</summary><pre>{path_idx.preview}</pre></details>{{ endif }}</li>
+        {{ endfor }}
+        </ul>
+        </li>
+    {{ endfor }}
+    </ul>
+    {{ if collapse }}</details>{{ endif }}
+    </li>
+"#;
+
+pub static TEMPLATE_DIRECTORY_PAGE: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css | format_unescaped}
+    </style>
+    <title>Build products for {label}</title>
+    <base href="..">
+</head>
+<body>
+    <h1>Build products for {label}</h1>
+    <p><a href="index.html">Back to index</a></p>
+    <ul>
+    {{ for compile_directory in directory }}
+    {{ call compile_directory.html with compile_directory }}
+    {{ endfor }}
+    </ul>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_RANKS_INDEX: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css | format_unescaped}
+    </style>
+    <title>Ranks</title>
+</head>
+<body>
+    <h1>Ranks</h1>
+    <p>
+    This log has entries from {num_ranks} ranks, merged into this report: every compile id and failure of every rank
+    is listed below, linked to the pages of the rank's own report.  Entries logged before the rank was known, e.g.,
+    before the process group was initialized, are under <em>(no rank)</em>.
+    </p>
+    <table>
+    <tr> <th>Rank</th> <th>Entries</th> <th>Failed Entries</th> </tr>
+    {{ for rank in ranks }}
+    <tr> <td><a href="{rank.url}">{rank.name}</a></td> <td>{rank.num_entries}</td> <td>{rank.num_failed_entries}</td> </tr>
+    {{ endfor }}
+    </table>
+    {{ if has_compile_divergence }}
+    <p>
+    The <strong><a href="compile_divergence.html">compile divergence report</a></strong> found {num_compile_divergences}
+    differences between what the ranks compiled.
+    </p>
+    {{ endif }}
+    <h2>Compile Ids</h2>
+    <p>
+    The ranks that have each compile id, colored by how its compilation went.
+    </p>
+    <table>
+    <tr> <th>Compile Id</th> <th>Ranks</th> </tr>
+    {{ for c in compile_ids }}
+    <tr> <td>{c.compile_id}</td> <td>{{ for r in c.ranks }}<a href="{r.url}" class="{r.status_class}" title="{r.status}, {r.num_artifacts} artifacts">{r.rank}</a> {{ endfor }}</td> </tr>
+    {{ endfor }}
+    </table>
+    {{ if failures }}
+    <h2>Failures and Restarts</h2>
+    <table>
+    <tr> <th>Rank</th> <th>Compile Id</th> <th>Type</th> <th>Reason</th> </tr>
+    {{ for f in failures }}
+    <tr> <td><a href="{f.url}">{f.rank}</a></td> <td>{f.compile_id}</td> <td>{f.kind}</td> <td>{f.reason}</td> </tr>
+    {{ endfor }}
+    </table>
+    {{ endif }}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_TENSOR_CATALOG: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css}
+    </style>
+    <title>Tensor Catalog</title>
+</head>
+<body>
+    <h1>Tensor Catalog</h1>
+    <p>
+    Every distinct shape and dtype of the tensor inputs described across all compilations, most common first.
+    Sizes with symbols (e.g., <code>s0</code>) were already dynamic.  (<a href="index.html">back to index</a>)
+    </p>
+    <table>
+    <tr> <th>Dtype</th> <th>Size</th> <th>Inputs</th> <th>Compile Ids</th> <th>Sources</th> </tr>
+    {{ for s in shapes }}
+    <tr>
+        <td>{s.dtype}</td>
+        <td><code>{s.size}</code></td>
+        <td>{s.count}</td>
+        <td>{s.num_compile_ids}</td>
+        <td>{{ for source in s.sources }}<code>{source}</code> {{ endfor }}{{ if s.num_more_sources }}and {s.num_more_sources} more{{ endif }}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ if varying_sources }}
+    <h2>Inputs With Varying Shapes</h2>
+    <p>
+    These inputs had a different shape or dtype in different compilations.  Each change caused a recompile, unless
+    the dimension was already dynamic; consider <code>torch._dynamo.mark_dynamic</code> on the dimensions that vary.
+    </p>
+    <table>
+    <tr> <th>Frame</th> <th>Source</th> <th>Shapes</th> <th>Compile Ids</th> </tr>
+    {{ for v in varying_sources }}
+    <tr>
+        <td><a href="{v.frame_url}">{v.frame}</a></td>
+        <td><code>{v.source}</code></td>
+        <td>{{ for shape in v.shapes }}<div><code>{shape}</code></div>{{ endfor }}</td>
+        <td>{v.num_compile_ids}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ endif }}
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_FRAME_HISTORY: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css}
+    </style>
+    <title>Frame {frame}</title>
+</head>
+<body>
+    <h1>Frame {frame}{{ if co_name }}: <code>{co_name}</code>{{ endif }}</h1>
+    <p>
+    Every compilation of this frame, in the order they happened, with what changed since the previous one.
+    Guard changes compare against the latest attempt of the previous compilation.  (<a href="index.html">back to index</a>)
+    </p>
+    <table>
+    <tr> <th>Compile Id</th> <th>Status</th> <th>Compile Time</th> <th>Guards</th> <th>Shape Env Guards</th> <th>Changes</th> </tr>
+    {{ for row in rows }}
+    <tr>
+        <td><a href="{row.url}">{row.compile_id}</a></td>
+        <td><span class="{row.status_class}">{row.status}</span></td>
+        <td>{{ if row.compile_time_s }}{row.compile_time_s | format_duration}{{ endif }}</td>
+        <td>{{ if row.guards_url }}<a href="{row.guards_url}">{row.guard_count}</a>{{ else }}{row.guard_count}{{ endif }}</td>
+        <td>{row.shape_env_guard_count}</td>
+        <td>
+        {{ for change in row.changes }}<div>{change}</div>{{ endfor }}
+        {{ if row.prior_compile_id }}
+        <div>
+        Guards since {{ if row.prior_guards_url }}<a href="{row.prior_guards_url}">{row.prior_compile_id}</a>{{ else }}{row.prior_compile_id}{{ endif }}:
+        +{row.num_guards_added}, -{row.num_guards_removed}
+        </div>
+        {{ if row.guards_added }}<details><summary>Added guards</summary>{{ for g in row.guards_added }}<div><code>{g}</code></div>{{ endfor }}</details>{{ endif }}
+        {{ if row.guards_removed }}<details><summary>Removed guards</summary>{{ for g in row.guards_removed }}<div><code>{g}</code></div>{{ endfor }}</details>{{ endif }}
+        {{ endif }}
+        </td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_COMPILE_ID_INDEX: &str = r#"
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+    {css | format_unescaped}
+    </style>
+    <script>
+    {javascript | format_unescaped}
+    </script>
+    <title>{compile_id}</title>
+</head>
+<body>
+    <h1>Compilation {compile_id}</h1>
+    <p>Status: <span class="{status_class}">{status}</span> (<a href="../index.html#{anchor}">back to index</a>, <a href="{frame_url}">frame history</a>)</p>
+    {{ if backend }}<p>Backend: <code>{backend}</code></p>{{ endif }}
+    {{ if m }}
+    <h2>Highlights</h2>
+    <ul>
+    <li>Function: <code>{m.co_name}</code> at {m.co_filename}:{m.co_firstlineno}</li>
+    {{ if m.entire_frame_compile_time_s }}<li>Entire frame compile time: {m.entire_frame_compile_time_s | format_duration}</li>{{ endif }}
+    {{ if m.backend_compile_time_s }}<li>Backend compile time: {m.backend_compile_time_s | format_duration}</li>{{ endif }}
+    {{ if m.guard_count }}<li>Guards: {m.guard_count}, of which shape env guards: {m.shape_env_guard_count}</li>{{ endif }}
+    {{ if m.graph_op_count }}<li>Graph ops: {m.graph_op_count}, graph inputs: {m.graph_input_count}</li>{{ endif }}
+    {{ if input_bytes }}<li>Total input bytes: {input_bytes}</li>{{ endif }}
+    {{ if m.fail_type }}<li>Failure: <code>{m.fail_type}</code>: {m.fail_reason}</li>{{ endif }}
+    {{ if m.restart_reasons }}<li>Restart reasons: {{ for r in m.restart_reasons }}<code>{r}</code> {{ endfor }}</li>{{ endif }}
+    </ul>
+    {{ endif }}
+    {{ if recompile_reasons }}
+    <h2>Recompile Reasons</h2>
+    <p>Recompiled because guards from {{ if prior_url }}<a href="{prior_url}">{prior_compile_id}</a>{{ else }}an earlier compilation of this frame{{ endif }} failed:</p>
+    <ul>
+    {{ for r in recompile_reasons }}
+        <li>{{ if r.change }}{r.change}{{ else }}<code>{r.reason}</code>{{ endif }}{{ if r.guard_url }} (<a href="{r.guard_url}">guard</a>){{ endif }}</li>
+    {{ endfor }}
+    </ul>
+    {{ endif }}
+    {{ if links }}
+    <h2>External Links</h2>
+    <ul>
+    {{ for l in links }}
+        <li>{{ if l.category }}<span class="link-category">{l.category}</span> {{ endif }}<a href="{l.url}">{l.name}</a>{{ if l.description }}: {l.description}{{ endif }}</li>
+    {{ endfor }}
+    </ul>
+    {{ endif }}
+    <h2>Artifacts</h2>
+    <ul>
+    {{ for path_idx in output_files }}
+        <li id="{path_idx.anchor}" data-kind="{path_idx.artifact_kind}"><a href="{path_idx.url}"{{ if path_idx.source_filename }} title="{path_idx.source_filename}"{{ endif }}>{path_idx.name}</a> {path_idx.suffix} ({path_idx.number}) <span class="output-meta">{{ if path_idx.size }}{path_idx.size | format_bytes}, {{ endif }}line {path_idx.lineno}</span></li>
+    {{ endfor }}
+    </ul>
+    {{ if waterfall }}
+    <h2>Timeline</h2>
+    <p>When each output was logged, from the first entry logged for this compilation.  The bars show the time spent since the previous output.</p>
+    <table>
+    <tr><th>Output</th><th>Since start</th><th>Since previous</th><th></th></tr>
+    {{ for row in waterfall }}
+    <tr>
+    <td>{row.name}</td>
+    <td>{row.since_start}</td>
+    <td>{row.since_previous}</td>
+    <td class="waterfall"><span class="waterfall-bar" style="margin-left: {row.left}%; width: {row.width}%"></span></td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ endif }}
+    {{ if stack_html }}
+    <h2>Stack</h2>
+    {stack_html | format_unescaped}
+    {{ endif }}
+    {{ if logged_from }}
+    <h2>Where Logged</h2>
+    <p>Source locations in torch that logged entries for this compilation, and how many entries each logged.</p>
+    <table>
+    {{ for l in logged_from }}
+    <tr><td><code>{l.0}</code></td><td>{l.1}</td></tr>
+    {{ endfor }}
+    </table>
+    {{ endif }}
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_RECOMPILE_THRASHING: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Recompile Thrashing</title>
+</head>
+<body>
+    <h1>Recompile Thrashing</h1>
+    <p>
+    Each of these guards failed on several recompiles of the same frame.  Unlike an ordinary recompile,
+    which happens once and then settles (e.g., via automatic dynamic shapes), a guard that keeps failing
+    means the frame is being recompiled over and over for the same reason.  If the guard is on a size,
+    consider <code>torch._dynamo.mark_dynamic</code> on the sources involved.
+    </p>
+    <table>
+    <tr> <th>Frame</th> <th>Guard</th> <th>Sources</th> <th>Recompiles</th> <th>Reasons</th> </tr>
+    {{ for t in thrashing }}
+    <tr>
+        <td>{t.frame}</td>
+        <td><code>{t.guard}</code></td>
+        <td>{{ for source in t.sources }}<code>{source}</code> {{ endfor }}</td>
+        <td>{{ for cid in t.compile_ids }}<a href="index.html#{cid.anchor}">{cid.compile_id}</a> {{ endfor }}</td>
+        <td>{{ for reason in t.reasons }}<div><code>{reason}</code></div>{{ endfor }}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_COMPILES_BY_MODULE: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Compilations by Module</title>
+</head>
+<body>
+    <h1>Compilations by Module</h1>
+    <p>
+    Compilations grouped by the <code>nn.Module</code> they belong to, most compile time first.  The log
+    doesn't record module classes, so a module is named by where its <code>forward</code> is defined;
+    a compilation of some other function is attributed to the innermost <code>forward</code> on the stack
+    that called it.  Every instance of a module class shares a row, so a row with many compilations
+    usually means its instances aren't hitting each other's cache.
+    </p>
+    <table>
+    <tr> <th>Module</th> <th>Functions</th> <th>Compilations</th> <th>Failed</th> <th>Compile Time</th> <th>Compile Ids</th> </tr>
+    {{ for m in modules }}
+    <tr>
+        <td><code>{m.module}</code></td>
+        <td>{{ for name in m.functions }}<code>{name}</code> {{ endfor }}</td>
+        <td>{m.num_compiles}</td>
+        <td>{m.num_failed}</td>
+        <td>{m.compile_time_s | format_duration}</td>
+        <td>{{ for cid in m.compile_ids }}<a href="index.html#{cid.anchor}">{cid.compile_id}</a> {{ endfor }}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_NON_COMPLIANT_OPS: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Non-Compliant Custom Ops</title>
+</head>
+<body>
+    <h1>Non-Compliant Custom Ops</h1>
+    <p>
+    Custom ops that dynamo found to be non-compliant in some compilation of this run, e.g., because
+    they have no fake implementation or don't declare which inputs they mutate, in the most graphs
+    first.  See the <a href="https://pytorch.org/tutorials/advanced/custom_ops_landing_page.html">custom
+    ops landing page</a> for how to fix them.
+    </p>
+    <table>
+    <tr> <th>Op</th> <th>Graphs</th> <th>Frames</th> <th>Compile Ids</th> </tr>
+    {{ for o in ops }}
+    <tr>
+        <td><code>{o.op}</code></td>
+        <td>{o.num_graphs}</td>
+        <td>{{ for frame in o.frames }}<a href="{frame.url}">{frame.label}</a><br>{{ endfor }}</td>
+        <td>{{ for cid in o.compile_ids }}<a href="index.html#{cid.anchor}">{cid.compile_id}</a> {{ endfor }}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_SPECIALIZATION_RECOMPILES: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Specializations That Caused Recompiles</title>
+</head>
+<body>
+    <h1>Specializations That Caused Recompiles</h1>
+    <p>
+    Each step below is a symbolic shape specialization in one compile id whose guard failed on a later call,
+    causing the frame's next compile id.  A frame that specializes the same dimension again after every
+    recompile will keep recompiling; consider <code>torch._dynamo.mark_dynamic</code> on the source, or find
+    what forces the specialization (see the specialization's stack on the compile id's metrics page).
+    </p>
+    {{ for chain in chains }}
+    <h2>Frame {chain.frame}</h2>
+    <ol>
+    {{ for link in chain.links }}
+    <li>
+        specialized <code>{link.symbol} = {link.value}</code> (<code>{link.source}</code>)
+        in <a href="{link.specialized_url}">{link.specialized_in}</a>
+        &rarr; guard failed{{ if link.actual }} with <code>{link.actual}</code>{{ endif }}
+        &rarr; recompiled as <a href="{link.recompiled_url}">{link.recompiled_as}</a>
+        <div><code>{link.reason}</code></div>
+    </li>
+    {{ endfor }}
+    </ol>
+    {{ endfor }}
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_CROSS_RANK_METRICS: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Cross-Rank Comparison</title>
+</head>
+<body>
+    <h1>Cross-Rank Comparison</h1>
+    <p>
+    Compile times (entire frame) for each compile id across ranks {ranks}.  A large spread
+    between ranks, or a compile id that failed or is missing on only some ranks, often explains
+    collective timeouts in distributed runs.
+    </p>
+    <table>
+    <tr> <th>Compile Id</th> <th>Ranks</th> <th>Min</th> <th>Median</th> <th>Max</th> <th>Failed Ranks</th> </tr>
+    {{ for row in rows }}
+    <tr>
+        <td>{row.compile_id}</td>
+        <td>{row.num_ranks}</td>
+        <td>{row.min_time}</td>
+        <td>{row.median_time}</td>
+        <td>{row.max_time}</td>
+        <td>{row.failed_ranks}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_COMPILE_DIVERGENCE: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Compile Divergence</title>
+</head>
+<body>
+    <h1>Compile Divergence</h1>
+    <p>
+    Where the ranks didn't compile the same thing.  Ranks that compile different numbers of frames, different
+    graphs, or graphs with different guards will likely issue different collectives, which often explains hangs
+    (e.g., NCCL timeouts) in distributed runs.
+    </p>
+    <table>
+    <tr> <th>Rank</th> <th>Compile Ids</th> </tr>
+    {{ for rank in ranks }}
+    <tr> <td>{rank.rank}</td> <td>{rank.num_compiles}</td> </tr>
+    {{ endfor }}
+    </table>
+    {{ if num_compiles_differ }}
+    <p class="divergent">The ranks compiled different numbers of compile ids.</p>
+    {{ endif }}
+    {{ if rows }}
+    <table>
+    <tr> <th>Compile Id</th> <th>Missing On Ranks</th> <th>Graph Hashes</th> <th>Guard Counts</th> </tr>
+    {{ for row in rows }}
+    <tr>
+        <td>{row.compile_id}</td>
+        <td>{row.missing_ranks}</td>
+        <td>{row.graph_hashes}</td>
+        <td>{row.guard_counts}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ else }}
+    <p>Every compile id has the same graph and guard count on every rank that compiled it.</p>
+    {{ endif }}
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_DYNAMO_OUTPUT_GRAPH_SIZES: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Dynamo Output Graph Sizes</title>
+</head>
+<body>
+    <h1>Dynamo output graph sizes for {compile_id}</h1>
+    <p>
+    Sizes recorded by Dynamo for the inputs, intermediates and outputs of the graph.  A size is
+    symbolic if any of its dimensions is a symbol rather than a concrete integer.
+    </p>
+    <table>
+    <tr> <th>Name</th> <th>Size</th> <th>Kind</th> </tr>
+    {{ for s in sizes }}
+    <tr>
+        <td><code>{s.name}</code></td>
+        <td><code>{s.size}</code></td>
+        <td>{{ if s.symbolic }}symbolic{{ else }}concrete{{ endif }}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_INDUCTOR_OUTPUT_CODE: &str = r#"
+<html>
+<head>
+    <style>
+    {css | format_unescaped}
+    </style>
+    <title>Inductor Output Code</title>
+</head>
+<body>
+    <h2>Kernels</h2>
+    <p>
+    Kernels defined by this code, with the comments Inductor wrote above each of them.  Launches
+    counts the places each kernel is called from.
+    </p>
+    <table>
+    <tr> <th>Kernel</th> <th>Kind</th> <th>Source Nodes</th> <th>Original ATen</th> <th>Other Annotations</th> <th>Launches</th> </tr>
+    {{ for k in kernels }}
+    <tr>
+        <td><code>{k.name}</code></td>
+        <td>{k.kind}</td>
+        <td>{{ for n in k.source_nodes }}<code>{n}</code> {{ endfor }}</td>
+        <td>{{ for op in k.original_aten }}<code>{op}</code> {{ endfor }}</td>
+        <td>{{ for c in k.comments }}{c.0}: <code>{c.1}</code><br>{{ endfor }}</td>
+        <td>{k.launches}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ if buffer_reuse }}
+    <h2>Buffer Reuse</h2>
+    <ul>
+    {{ for line in buffer_reuse }}
+    <li><code>{line}</code></li>
+    {{ endfor }}
+    </ul>
+    {{ endif }}
+    <h2>Code</h2>
+    {code_html | format_unescaped}
+    {qps | format_unescaped}
+</body>
+</html>
+"#;
+
+pub static TEMPLATE_COMPILATION_METRICS: &str = r#"
+<html>
+<head>
+    <style>
+    {css}
+    </style>
+    <title>Compilation Metrics</title>
+    <base href="..">
+    <script>
+    {javascript | format_unescaped}
+    </script>
+</head>
+<body>
+    <h1>Compilation Info for {compile_id}</h1>
+    <p>{mini_stack_html | format_unescaped}</p>
+    <h2>Output files:</h2>
+    <ul>
+        {{ for path_idx in output_files }}
+            <li><a href="{compile_id_dir}/{path_idx.url}"{{ if path_idx.source_filename }} title="{path_idx.source_filename}"{{ endif }}>{path_idx.name}</a> ({path_idx.number}) <span class="output-meta">{{ if path_idx.size }}{path_idx.size | format_bytes}, {{ endif }}line {path_idx.lineno}</span></li>
+        {{ endfor }}
+    </ul>
+    <h2>Stack</h2>
+    {stack_html | format_unescaped}
+    <h2>Compile Time</h2>
+    <p>Entire Frame <abbr title="Total time spent in convert_frame function">[?]</abbr>: {m.entire_frame_compile_time_s | format_duration}</div>
+    <p>Backend <abbr title="Time spent running the backend compiler">[?]</abbr>: {m.backend_compile_time_s | format_duration}</div>
+    {{ if m.inductor_compile_time_s }}
+    <p>Inductor <abbr title="Total time spent running inductor">[?]</abbr>: {m.inductor_compile_time_s | format_duration}</div>
+    {{ endif }}
+    {{ if m.code_gen_time_s }}
+    <p>Code Gen Time: {m.code_gen_time_s | format_duration}</p>
+    {{ endif}}
+    <div>Dynamo Time Before Restart <abbr title="Total time spent restarting dynamo analysis">[?]</abbr>: {m.dynamo_time_before_restart_s | format_duration}</div>
+    <h2>Restarts and Failures</h2>
+    {{ if m.fail_type }}
+    <p>Failure Exception: <pre>{m.fail_type}</pre></p>
+    <p>Failure Reason: <pre>{m.fail_reason}</pre></p>
+    {{ if m.fail_user_frame_filename }}
+    <p>In file <pre>{m.fail_user_frame_filename}</pre>, line {m.fail_user_frame_lineno}</p>
+    {{ endif}}
+    {{ else }}
+    <p> No failures! </p>
+    {{ endif }}
+    {{ if restart_reasons }}
+    <p>Restart Reasons:<p>
+    {{ for restart_reason in restart_reasons }}
+     <li> <code> {restart_reason.reason} </code>
+     {{ if restart_reason.advice }}<div class="graph-break-advice">{restart_reason.advice.explanation} <strong>Fix:</strong> {restart_reason.advice.remediation}</div>{{ endif }}
+     </li>
+    {{ endfor }}
+    {{ else }}
+    <p> No restarts! </p>
+    {{ endif }}
+    <h2>Cache Metrics</h2>
+    <p>Cache Size: {m.cache_size}</p>
+    <p>Accumulated Cache Size: {m.accumulated_cache_size}</p>
+    <h2>Graph Metrics</h2>
+    <p>Guard Count: {m.guard_count}</p>
+    <p>Shape Env Guards: {m.shape_env_guard_count}</p>
+    {{ if m.duplicate_guards_suppressed }}<p>Duplicate Guards Suppressed: {m.duplicate_guards_suppressed}</p>{{ endif }}
+    {{ if m.build_guards_time_s }}<p>Guard Build Time: {m.build_guards_time_s | format_duration}</p>{{ endif }}
+    <p>Graph Ops: {m.graph_op_count}</p>
+    <p>Graph Nodes: {m.graph_node_count}</p>
+    <p>Graph Inputs: {m.graph_input_count}</p>
+    {{ if input_bytes }}
+    <p>Total Input Bytes <abbr title="Sum of the storages backing the inputs; storages shared between inputs are counted once">[?]</abbr>: {input_bytes}</p>
+    {{ endif }}
+    {{ if aliased_inputs }}
+    <h2>Aliased Inputs</h2>
+    <p>
+    These inputs share a storage.  Inputs that alias each other change how AOTAutograd
+    traces the graph (e.g., mutations to one input must be reflected in the others), and
+    guards are installed to check that they continue to alias on later calls.
+    </p>
+    <ul>
+    {{ for group in aliased_inputs }}
+        <li>{{ for source in group }}{{ if not @first }}, {{ endif }}<code>{source}</code>{{ endfor }}</li>
+    {{ endfor }}
+    </ul>
+    {{ endif }}
+    <h2> Custom Ops </h2>
+    {{ if m.compliant_custom_ops }}
+    <p> Compliant Custom Ops:</p>
+    {{ for op in m.compliant_custom_ops }}
+    <li> <code> {op} </code> </li>
+    {{ endfor }}
+    {{ endif }}
+    {{ if m.non_compliant_ops }}
+    <p> Non-Compliant Custom Ops:</p>
+    {{ for op in m.non_compliant_ops }}
+    <li> <code> {op} </code> </li>
+    {{ endfor }}
+    {{ endif }}
+    <h2>Symbolic shape specializations</h2>
+    <table>
+    <tr>
+        <th>Sym</th> <th>Source(s)</th> <th>Value</th> <th>Guards</th> <th>User stack</th> <th>Framework stack</th>
+    </tr>
+    {{ for spec in symbolic_shape_specializations }}
+    <tr id="specialization-{@index}">
+        <td>{spec.symbol}</td>
+        <td>{{ for source in spec.sources }}{source}<br>{{ endfor }}</td>
+        <td>{spec.value}</td>
+        <td>{{ for g in spec.guards }}<a href="{compile_id_dir}/{guards_url}#guard-{g}">#{g}</a> {{ endfor }}</td>
+        <td>{spec.user_stack_html | format_unescaped}</td>
+        <td>{spec.stack_html | format_unescaped}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ if runtime_asserts }}
+    <h2>Runtime asserts</h2>
+    <p>
+    Facts about unbacked symbols (e.g., from <code>.item()</code> or <code>nonzero</code>) that could not be
+    checked at compile time, and are asserted when the compiled code runs instead, e.g., from
+    <code>torch._check</code>.  They often explain why such programs recompile or fail.
+    </p>
+    <table>
+    <tr>
+        <th>Expr</th> <th>User stack</th> <th>Framework stack</th>
+    </tr>
+    {{ for g in runtime_asserts }}
+    <tr>
+        <td>{g.expr}</td>
+        <td>{g.user_stack_html | format_unescaped}</td>
+        <td>{g.stack_html | format_unescaped}</td>
+    </tr>
+    {{ endfor }}
+    </table>
+    {{ endif }}
     <h2>Guards added fast</h2>
     <table>
     <tr>
@@ -392,12 +1564,12 @@ pub static TEMPLATE_BWD_COMPILATION_METRICS: &str = r#"
 </head>
 <body>
     <h1>Backward Compilation Info for {compile_id}</h1>
-    <h2>Compile Time(seconds)</h2>
+    <h2>Compile Time</h2>
     {{ if m.inductor_compile_time_s }}
-    <p>Inductor <abbr title="Total time spent running inductor">[?]</abbr>: {m.inductor_compile_time_s}</div>
+    <p>Inductor <abbr title="Total time spent running inductor">[?]</abbr>: {m.inductor_compile_time_s | format_duration}</div>
     {{ endif }}
     {{ if m.code_gen_time_s }}
-    <p>Code Gen Time: {m.code_gen_time_s}</p>
+    <p>Code Gen Time: {m.code_gen_time_s | format_duration}</p>
     {{ endif}}
     <h2>Failures</h2>
     {{ if m.fail_type }}
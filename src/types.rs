@@ -1,26 +1,241 @@
 use core::hash::BuildHasherDefault;
-use fxhash::{FxHashMap, FxHasher};
+use fxhash::{FxHashMap, FxHashSet, FxHasher};
 use html_escape::encode_text;
-use indexmap::IndexMap;
-use regex::Regex;
+use indexmap::{IndexMap, IndexSet};
+use regex::{Regex, RegexSet};
+use schemars::JsonSchema;
 use serde_json::Value;
 
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fmt::{self, Display, Write};
-use std::path::PathBuf;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
 
 // Main function returns a list of files to save
-pub type ParseOutput = Vec<(PathBuf, String)>;
+// Files of a report, by their path relative to the output directory
+pub type OutputFiles = Vec<(PathBuf, String)>;
 pub type CompilationMetricsIndex = FxIndexMap<Option<CompileId>, Vec<CompilationMetricsMetadata>>;
 pub type StackIndex = FxHashMap<Option<CompileId>, StackSummary>; // NB: attempt is always 0 here
 pub type SymbolicShapeSpecializationIndex =
     FxHashMap<Option<CompileId>, Vec<SymbolicShapeSpecializationMetadata>>;
 pub type GuardAddedFastIndex = FxHashMap<Option<CompileId>, Vec<GuardAddedFastMetadata>>;
+// Deferred runtime asserts (e.g., from torch._check), which are guard_added entries with the
+// runtime_assert prefix
+pub type RuntimeAssertIndex = FxHashMap<Option<CompileId>, Vec<GuardAddedMetadata>>;
+pub type DynamoGuardsIndex = FxHashMap<Option<CompileId>, Vec<Rc<DynamoGuard>>>;
+pub type InputSourceIndex = FxHashMap<Option<CompileId>, Vec<InputSource>>;
+// Dynamo graph placeholders in order, with the input they came from (None for e.g. SymInts).
+// Interned, since recompiles mostly have the same inputs
+pub type GraphInputs = Vec<(String, Option<InputSource>)>;
+pub type GraphInputsIndex = FxHashMap<Option<CompileId>, Rc<GraphInputs>>;
+// Keyed on (sources, value); the symbol name is not part of the key, since every
+// compilation allocates its own symbols.
+pub type SymbolicShapeSpecializationSummaryIndex =
+    FxIndexMap<(Vec<String>, String), SymbolicShapeSpecializationSummary>;
+// Reasons from the recompile_reasons artifact, for each compile id that was a recompile.
+// Interned, since a frame that keeps recompiling gives the same reasons over and over
+pub type RecompileReasonsIndex = FxIndexMap<Option<CompileId>, Vec<Rc<String>>>;
+// External links logged for each compile id, in log order
+pub type LinkIndex = FxIndexMap<Option<CompileId>, Vec<LinkMetadata>>;
+// Nodes counted in each compile id's dynamo_output_graph, to check the metrics against
+pub type GraphNodeCountsIndex = FxHashMap<Option<CompileId>, GraphNodeCounts>;
+// For each compile id, the source locations (pathname:line in the glog prefix) that logged
+// entries for it, with how many entries each logged
+pub type LoggedFromIndex = FxIndexMap<Option<CompileId>, FxIndexMap<String, usize>>;
+// Glog timestamps of the entries for each compile id, to synthesize a chromium trace from
+pub type GlogTimesIndex = FxIndexMap<Option<CompileId>, GlogTimes>;
+// Compilation metrics from every rank in the log, not just the one being rendered
+pub type CrossRankMetricsIndex =
+    FxIndexMap<Option<CompileId>, Vec<(Option<u32>, CompilationMetricsMetadata)>>;
+// What each rank compiled, to find where ranks diverged
+pub type RankCompilesIndex = FxIndexMap<(Option<u32>, Option<CompileId>), RankCompile>;
 
 pub type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+pub type FxIndexSet<K> = IndexSet<K, BuildHasherDefault<FxHasher>>;
+
+// JSON object keys must be strings, so maps keyed on e.g. compile ids are serialized as lists of
+// pairs instead
+mod map_as_pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<'a, M, K, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Serialize + 'a,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(K, V)>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+// Everything parse_path accumulates while reading a log, so that a later run on the same log
+// (after more has been appended to it) can pick up where this one stopped.  It is also the state of
+// the parse in progress, apart from the indices in SharedIndices
+#[derive(Default, Clone, Deserialize, Serialize)]
+pub struct ParseCheckpoint {
+    pub(crate) offset: u64, // bytes of the log consumed
+    pub(crate) lines: usize,
+    pub(crate) tail_md5: String, // of the (up to) TAIL_BYTES bytes before offset
+    pub(crate) intern_table: Vec<(u32, String)>,
+    pub(crate) expected_rank: Option<Option<u32>>,
+    pub(crate) stats: Stats,
+    pub(crate) unknown_fields: FxHashSet<String>,
+    pub(crate) output_count: i32,
+    // Outputs that weren't sent to ParseConfig::output_sink yet (all of them without one)
+    pub(crate) output: OutputFiles,
+    // Guards pages waiting for the name of their compile id's compilation metrics page, which
+    // their specialization links point at
+    #[serde(with = "map_as_pairs")]
+    pub(crate) pending_guards_pages: FxIndexMap<Option<CompileId>, (PathBuf, String)>,
+    // The files of each compile id.  For links, the name can differ from the url
+    #[serde(with = "map_as_pairs")]
+    pub(crate) directory: FxIndexMap<Option<CompileId>, Vec<OutputFile>>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) metrics_index: CompilationMetricsIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) stack_index: StackIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) symbolic_shape_specialization_index: SymbolicShapeSpecializationIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) guard_added_fast_index: GuardAddedFastIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) runtime_assert_index: RuntimeAssertIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) dynamo_guards_index: DynamoGuardsIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) input_source_index: InputSourceIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) graph_inputs_index: GraphInputsIndex,
+    // Keyed on (describer_id, id), to resolve describe_source
+    #[serde(with = "map_as_pairs")]
+    pub(crate) tensor_descs: FxHashMap<(u64, u64), TensorDesc>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) storage_descs: FxHashMap<(u64, u64), StorageDesc>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) specialization_summary_index: SymbolicShapeSpecializationSummaryIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) recompile_reasons_index: RecompileReasonsIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) graph_node_counts_index: GraphNodeCountsIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) link_index: LinkIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) cross_rank_metrics_index: CrossRankMetricsIndex,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) rank_compiles_index: RankCompilesIndex,
+    pub(crate) stack_trie: StackTrieNode,
+    pub(crate) unknown_stack_trie: StackTrieNode,
+    pub(crate) failures: Vec<(String, String)>,
+    pub(crate) export_failures: Vec<ExportFailure>,
+    pub(crate) chromium_events: Vec<Value>,
+    pub(crate) chromium_event_problems: Vec<String>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) glog_times_index: GlogTimesIndex,
+    pub(crate) glog_clock_samples: Vec<(i64, f64)>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) logged_from_index: LoggedFromIndex,
+    pub(crate) output_usage: OutputUsage,
+    pub(crate) raw_context_lines: Vec<usize>,
+    pub(crate) rendered_compile_ids: FxHashSet<Option<CompileId>>,
+    #[serde(with = "map_as_pairs")]
+    pub(crate) interned_str_refs: FxIndexMap<u32, InternedStrRefs>,
+    // The line each torch install was first seen on, by torch_install
+    pub(crate) torch_installs: FxIndexMap<String, usize>,
+}
+
+impl ParseCheckpoint {
+    const TAIL_BYTES: u64 = 64 * 1024;
+
+    // md5 of the bytes just before offset
+    pub(crate) fn tail(path: &PathBuf, offset: u64) -> std::io::Result<String> {
+        use md5::{Digest, Md5};
+        use std::io::{Read, Seek, SeekFrom};
+        let start = offset.saturating_sub(Self::TAIL_BYTES);
+        let mut file = std::fs::File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut buf = Vec::new();
+        file.take(offset - start).read_to_end(&mut buf)?;
+        Ok(format!("{:x}", Md5::digest(&buf)))
+    }
+
+    // The state to parse on from, and the indices it shares with parsers, taken out of it.  The
+    // intern table is only that of this log (and of its lookahead pass), so that the strings of
+    // another, e.g., the previous rank, don't stand in for ones it never defined
+    pub(crate) fn restore(mut self, lookahead: Option<&mut Lookahead>) -> (Self, SharedIndices) {
+        INTERN_TABLE.with_borrow_mut(|table| {
+            *table = std::mem::take(&mut self.intern_table).into_iter().collect();
+            if let Some(lookahead) = lookahead {
+                table.extend(std::mem::take(&mut lookahead.intern_table));
+            }
+        });
+        let shared = SharedIndices {
+            stack_index: RefCell::new(std::mem::take(&mut self.stack_index)),
+            symbolic_shape_specialization_index: RefCell::new(std::mem::take(
+                &mut self.symbolic_shape_specialization_index,
+            )),
+            guard_added_fast_index: RefCell::new(std::mem::take(&mut self.guard_added_fast_index)),
+            runtime_assert_index: RefCell::new(std::mem::take(&mut self.runtime_assert_index)),
+            dynamo_guards_index: RefCell::new(std::mem::take(&mut self.dynamo_guards_index)),
+            input_source_index: RefCell::new(std::mem::take(&mut self.input_source_index)),
+            graph_inputs_index: RefCell::new(std::mem::take(&mut self.graph_inputs_index)),
+        };
+        (self, shared)
+    }
+
+    // The checkpoint of a state restored by restore, after lines lines (offset bytes) of the log.
+    // The caller fills in tail_md5
+    pub(crate) fn save(&self, shared: &SharedIndices, offset: u64, lines: usize) -> Self {
+        ParseCheckpoint {
+            offset,
+            lines,
+            tail_md5: String::new(),
+            intern_table: INTERN_TABLE
+                .with_borrow(|t| t.iter().map(|(k, v)| (*k, v.clone())).collect()),
+            stack_index: shared.stack_index.borrow().clone(),
+            symbolic_shape_specialization_index: shared
+                .symbolic_shape_specialization_index
+                .borrow()
+                .clone(),
+            guard_added_fast_index: shared.guard_added_fast_index.borrow().clone(),
+            runtime_assert_index: shared.runtime_assert_index.borrow().clone(),
+            dynamo_guards_index: shared.dynamo_guards_index.borrow().clone(),
+            input_source_index: shared.input_source_index.borrow().clone(),
+            graph_inputs_index: shared.graph_inputs_index.borrow().clone(),
+            ..self.clone()
+        }
+    }
+
+    // How much of the log the checkpoint was taken after, in bytes, which always ends a line
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    // Whether path still starts with the log this checkpoint was taken from
+    pub fn matches(&self, path: &PathBuf) -> std::io::Result<bool> {
+        if std::fs::metadata(path)?.len() < self.offset {
+            return Ok(false);
+        }
+        Ok(Self::tail(path, self.offset)? == self.tail_md5)
+    }
+}
 
 pub fn extract_eval_with_key_id(filename: &str) -> Option<u64> {
     let re = Regex::new(r"<eval_with_key>\.([0-9]+)").unwrap();
@@ -29,13 +244,88 @@ pub fn extract_eval_with_key_id(filename: &str) -> Option<u64> {
         .and_then(|m| m.as_str().parse::<u64>().ok())
 }
 
-pub static INTERN_TABLE: Lazy<Mutex<FxHashMap<u32, String>>> =
-    Lazy::new(|| Mutex::new(FxHashMap::default()));
+thread_local! {
+    // The str entries of the log being parsed on this thread.  parse_impl starts every log, and
+    // every rank of one, with only the strings that come with it, e.g., in its checkpoint
+    pub static INTERN_TABLE: RefCell<FxHashMap<u32, String>> = RefCell::new(FxHashMap::default());
+}
+
+// Rank of a per-rank log file, e.g., dedicated_log_torch_trace_rank_0_8xk1ohzq.log in a
+// TORCH_TRACE directory
+pub static RANK_LOG_FILE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"rank_([0-9]+)").unwrap());
+
+// What the first pass of two pass parsing collected from the whole log, for metrics pages to show
+// even when it was logged after the compilation metrics
+#[derive(Default)]
+pub(crate) struct Lookahead {
+    pub(crate) stack_index: RefCell<StackIndex>,
+    pub(crate) symbolic_shape_specialization_index: RefCell<SymbolicShapeSpecializationIndex>,
+    pub(crate) guard_added_fast_index: RefCell<GuardAddedFastIndex>,
+    pub(crate) runtime_assert_index: RefCell<RuntimeAssertIndex>,
+    // Merged into INTERN_TABLE by parse_impl
+    pub(crate) intern_table: FxHashMap<u32, String>,
+}
 
+// The indices of a ParseCheckpoint that parsers add to, or look up, while the log is parsed
 #[derive(Default)]
+pub(crate) struct SharedIndices {
+    pub(crate) stack_index: RefCell<StackIndex>,
+    pub(crate) symbolic_shape_specialization_index: RefCell<SymbolicShapeSpecializationIndex>,
+    pub(crate) guard_added_fast_index: RefCell<GuardAddedFastIndex>,
+    pub(crate) runtime_assert_index: RefCell<RuntimeAssertIndex>,
+    pub(crate) dynamo_guards_index: RefCell<DynamoGuardsIndex>,
+    pub(crate) input_source_index: RefCell<InputSourceIndex>,
+    pub(crate) graph_inputs_index: RefCell<GraphInputsIndex>,
+}
+
+// Interned file names of stack frames, which refer to INTERN_TABLE
+pub static INTERNED_FILENAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""filename": ([0-9]+)"#).unwrap());
+
+// Specialization links of a guards page that still point at COMPILATION_METRICS_PLACEHOLDER_URL
+pub static PENDING_SPECIALIZATION_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"<a href="{}#specialization-[0-9]+">([^<]*)</a>"#,
+        regex::escape(crate::templates::COMPILATION_METRICS_PLACEHOLDER_URL)
+    ))
+    .unwrap()
+});
+
+// References to an intern table id made before any str entry defined it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InternedStrRefs {
+    pub first_lineno: usize,
+    pub count: u64,
+}
+
+// Intern table ids that were never defined, so the frames referring to them show (unknown)
+#[derive(Debug, Serialize)]
+pub struct MissingInternedStr {
+    pub id: u32,
+    pub count: u64,
+    pub first_lineno: usize,
+    pub raw_url: String,
+}
+
+// The directory torch is installed in, for a file name inside torch's own modules, e.g.,
+// /usr/lib/python3/site-packages for /usr/lib/python3/site-packages/torch/_dynamo/eval_frame.py.
+// A log with frames from more than one install mixes entries from different torch builds
+pub fn torch_install(filename: &str) -> Option<&str> {
+    filename.find("/torch/_").map(|i| &filename[..i])
+}
+
+#[derive(Debug, Serialize)]
+pub struct TorchInstall {
+    pub path: String,
+    pub first_lineno: usize,
+    pub raw_url: String,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct StackTrieNode {
     terminal: Vec<Option<CompileId>>,
     // Ordered map so that when we print we roughly print in chronological order
+    #[serde(with = "map_as_pairs")]
     children: FxIndexMap<FrameSummary, StackTrieNode>,
 }
 
@@ -56,59 +346,191 @@ impl StackTrieNode {
     }
 
     pub fn is_empty(&self) -> bool {
-        return self.children.is_empty() && self.terminal.is_empty();
+        self.children.is_empty() && self.terminal.is_empty()
+    }
+
+    // The stack of every compile id in the trie, outermost frame first
+    pub fn stacks(&self) -> Vec<(Vec<&FrameSummary>, &Option<CompileId>)> {
+        let mut stacks = Vec::new();
+        self.stacks_into(&mut Vec::new(), &mut stacks);
+        stacks
+    }
+
+    fn stacks_into<'a>(
+        &'a self,
+        prefix: &mut Vec<&'a FrameSummary>,
+        stacks: &mut Vec<(Vec<&'a FrameSummary>, &'a Option<CompileId>)>,
+    ) {
+        for t in &self.terminal {
+            stacks.push((prefix.clone(), t));
+        }
+        for (frame, node) in &self.children {
+            prefix.push(frame);
+            node.stacks_into(prefix, stacks);
+            prefix.pop();
+        }
+    }
+
+    // A copy without the frames the filter hides, whose compile ids and children move up to
+    // their parent
+    pub fn hide_frames(&self, filter: &FrameFilter) -> StackTrieNode {
+        let mut trie = StackTrieNode {
+            terminal: self.terminal.clone(),
+            children: FxIndexMap::default(),
+        };
+        self.hide_frames_into(&mut trie, filter);
+        trie
+    }
+
+    fn hide_frames_into(&self, into: &mut StackTrieNode, filter: &FrameFilter) {
+        for (frame, node) in &self.children {
+            let into = if filter.hides(frame) {
+                &mut *into
+            } else {
+                into.children.entry(frame.clone()).or_default()
+            };
+            into.terminal.extend(node.terminal.iter().cloned());
+            node.hide_frames_into(into, filter);
+        }
     }
 
+    // Runs of more than max_depth frames without branches only show their innermost max_depth
+    // frames, with the rest behind an expander.  With times (which needs the metrics index), every
+    // frame is labeled with the compile time of the compilations under it and its share of the
+    // total, and siblings are listed heaviest first
     pub fn fmt(
         &self,
         metrics_index: Option<&CompilationMetricsIndex>,
+        max_depth: Option<usize>,
+        times: bool,
     ) -> Result<String, fmt::Error> {
+        let times = metrics_index.filter(|_| times).map(|metrics_index| {
+            let by_compile_id = stack_compile_times(metrics_index);
+            let total = self.compile_time(&by_compile_id);
+            TrieTimes {
+                by_compile_id,
+                total,
+            }
+        });
         let mut f = String::new();
         write!(f, "<details>")?;
         write!(f, "<summary>Stack</summary>")?;
         write!(f, "<div class='stack-trie'>")?;
         write!(f, "<ul>")?;
-        self.fmt_inner(&mut f, metrics_index)?;
+        self.fmt_inner(&mut f, metrics_index, max_depth, times.as_ref())?;
         write!(f, "</ul>")?;
         write!(f, "</div>")?;
         write!(f, "</details>")?;
         Ok(f)
     }
 
-    pub fn fmt_inner(
+    // Of the compilations whose stacks end at or under this node
+    fn compile_time(&self, by_compile_id: &FxHashMap<Option<CompileId>, f64>) -> f64 {
+        self.terminal
+            .iter()
+            .filter_map(|t| by_compile_id.get(t))
+            .sum::<f64>()
+            + self
+                .children
+                .values()
+                .map(|node| node.compile_time(by_compile_id))
+                .sum::<f64>()
+    }
+
+    // Links to the compile ids whose stacks end at this node, after the compile time under it
+    fn fmt_terminal(
         &self,
-        f: &mut String,
         mb_metrics_index: Option<&CompilationMetricsIndex>,
-    ) -> fmt::Result {
-        for (frame, node) in self.children.iter() {
-            let mut star = String::new();
-            for t in &node.terminal {
-                if let Some(c) = t {
-                    let ok_class = mb_metrics_index.map_or("status-missing", |metrics_index| {
-                        metrics_index.get(t).map_or("status-missing", |m| {
-                            if m.iter().any(|n| n.fail_type.is_some()) {
-                                "status-error"
-                            } else if m.iter().any(|n| n.graph_op_count.unwrap_or(0) == 0) {
-                                "status-empty"
-                            } else if m.iter().any(|n| {
-                                !n.restart_reasons.as_ref().map_or(false, |o| o.is_empty())
-                            }) {
-                                "status-break"
-                            } else {
-                                "status-ok"
-                            }
-                        })
-                    });
+        times: Option<&TrieTimes>,
+    ) -> Result<String, fmt::Error> {
+        let mut star = String::new();
+        if let Some(times) = times {
+            let time = self.compile_time(&times.by_compile_id);
+            let share = if times.total > 0.0 {
+                time / times.total * 100.0
+            } else {
+                0.0
+            };
+            write!(
+                star,
+                "<span class='trie-time'>{} ({share:.0}%)</span> ",
+                format_duration(time)
+            )?;
+        }
+        for t in &self.terminal {
+            if let Some(c) = t {
+                let ok_class = mb_metrics_index.map_or("status-missing", |metrics_index| {
+                    compilation_status(metrics_index.get(t)).0
+                });
+                write!(
+                    star,
+                    "<a href='#{anchor}' class='{ok_class}'>{cid}</a> ",
+                    anchor = c.anchor(),
+                    cid = c,
+                    ok_class = ok_class
+                )?;
+                if c.is_compiled_autograd() {
                     write!(
                         star,
-                        "<a href='#{cid}' class='{ok_class}'>{cid}</a> ",
-                        cid = c,
-                        ok_class = ok_class
+                        "<span class='compiled-autograd' title='Compiled autograd (backward)'>CA</span> "
                     )?;
-                } else {
-                    write!(star, "(unknown) ")?;
                 }
+            } else {
+                write!(star, "(unknown) ")?;
+            }
+        }
+        Ok(star)
+    }
+
+    fn fmt_inner(
+        &self,
+        f: &mut String,
+        mb_metrics_index: Option<&CompilationMetricsIndex>,
+        max_depth: Option<usize>,
+        times: Option<&TrieTimes>,
+    ) -> fmt::Result {
+        if let (Some(max_depth), 1) = (max_depth, self.children.len()) {
+            // Follow the frames down to the next branch or compile id
+            let mut chain = Vec::new();
+            let mut cur = self;
+            while let (1, Some((frame, node))) = (cur.children.len(), cur.children.first()) {
+                chain.push((frame, node));
+                cur = node;
+                if !node.terminal.is_empty() {
+                    break;
+                }
+            }
+            let num_hidden = chain.len().saturating_sub(max_depth);
+            if num_hidden > 0 {
+                write!(
+                    f,
+                    "<li><details class='more-frames'><summary>\u{2026} {num_hidden} more frames</summary><ul>"
+                )?;
+                for (frame, _) in &chain[..num_hidden] {
+                    writeln!(f, "<li>{frame}</li>")?;
+                }
+                write!(f, "</ul></details></li>")?;
+                for (frame, node) in &chain[num_hidden..] {
+                    writeln!(
+                        f,
+                        "<li>{}{frame}</li>",
+                        node.fmt_terminal(mb_metrics_index, times)?
+                    )?;
+                }
+                return cur.fmt_inner(f, mb_metrics_index, Some(max_depth), times);
             }
+        }
+        let mut children: Vec<_> = self.children.iter().collect();
+        if let Some(times) = times {
+            let mut weighted: Vec<_> = children
+                .into_iter()
+                .map(|child| (child.1.compile_time(&times.by_compile_id), child))
+                .collect();
+            weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+            children = weighted.into_iter().map(|(_, child)| child).collect();
+        }
+        for (frame, node) in children {
+            let star = node.fmt_terminal(mb_metrics_index, times)?;
 
             if self.children.len() > 1 {
                 // If the node has multiple children, increase the indent and print a hyphen
@@ -118,19 +540,25 @@ impl StackTrieNode {
                     star = star
                 )?;
                 writeln!(f, "{}<ul>", frame)?;
-                node.fmt_inner(f, mb_metrics_index)?;
+                node.fmt_inner(f, mb_metrics_index, max_depth, times)?;
                 write!(f, "</ul></li>")?;
             } else {
                 // If the node has only one child, don't increase the indent and don't print a hyphen
                 writeln!(f, "<li>{star}{}</li>", frame, star = star)?;
-                node.fmt_inner(f, mb_metrics_index)?;
+                node.fmt_inner(f, mb_metrics_index, max_depth, times)?;
             }
         }
         Ok(())
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Deserialize, Serialize, Debug, Clone)]
+// Compile times for weighting the stack trie
+struct TrieTimes {
+    by_compile_id: FxHashMap<Option<CompileId>, f64>,
+    total: f64,
+}
+
+#[derive(Eq, PartialEq, Hash, Deserialize, Serialize, Debug, Clone, JsonSchema)]
 pub struct CompileId {
     pub compiled_autograd_id: Option<u32>,
     pub frame_id: Option<u32>,
@@ -138,6 +566,13 @@ pub struct CompileId {
     pub attempt: Option<u32>,
 }
 
+impl CompileId {
+    // Compiles a backward graph traced by compiled autograd, rather than a forward frame
+    pub fn is_compiled_autograd(&self) -> bool {
+        self.compiled_autograd_id.is_some()
+    }
+}
+
 impl fmt::Display for CompileId {
     // NOTE: If you want to elide an id e.g. attempt, compiled_autograd_id, you need to ensure
     // the representation remains unique. One way is to use a unique prefix.
@@ -174,21 +609,83 @@ impl CompileId {
 
         format!("{compiled_autograd_id_str}_{frame_id_str}_{frame_compile_id_str}_{attempt_str}")
     }
+
+    // An HTML id for the compile id, e.g. cid-2-0-0 for [2/0] and cid-ca1-2-0-0 for [!1/2/0],
+    // with x for a missing id.  Unlike Display, it tells a missing attempt from attempt 0, and it
+    // needs no escaping in a URL fragment
+    pub fn anchor(&self) -> String {
+        let id = |v: Option<u32>| v.map_or("x".to_string(), |v| v.to_string());
+        let compiled_autograd = self
+            .compiled_autograd_id
+            .map_or("".to_string(), |v| format!("ca{v}-"));
+        format!(
+            "cid-{compiled_autograd}{}-{}-{}",
+            id(self.frame_id),
+            id(self.frame_compile_id),
+            id(self.attempt)
+        )
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct Stats {
     pub ok: u64,
     pub other_rank: u64,
     pub fail_glog: u64,
     pub fail_json: u64,
     pub fail_payload_md5: u64,
+    // Payloads interrupted by unrelated lines, which were stitched back together
+    #[serde(default)]
+    pub stitched_payload: u64,
     pub fail_dynamo_guards_json: u64,
     pub fail_parser: u64,
     pub unknown: u64,
 }
 
-#[derive(Debug, Hash, Eq, PartialEq, Deserialize, Serialize, Clone)]
+impl std::ops::AddAssign<&Stats> for Stats {
+    fn add_assign(&mut self, other: &Stats) {
+        self.ok += other.ok;
+        self.other_rank += other.other_rank;
+        self.fail_glog += other.fail_glog;
+        self.fail_json += other.fail_json;
+        self.fail_payload_md5 += other.fail_payload_md5;
+        self.stitched_payload += other.stitched_payload;
+        self.fail_dynamo_guards_json += other.fail_dynamo_guards_json;
+        self.fail_parser += other.fail_parser;
+        self.unknown += other.unknown;
+    }
+}
+
+// Bytes of output written per parser, and outputs skipped once the output budget was used up
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct OutputUsage {
+    pub total: u64,
+    pub bytes: FxIndexMap<String, u64>,
+    pub skipped: FxIndexMap<String, u64>,
+}
+
+impl OutputUsage {
+    pub fn record(&mut self, parser: &str, size: usize) {
+        self.total += size as u64;
+        *self.bytes.entry(parser.to_string()).or_default() += size as u64;
+    }
+
+    pub fn summary(&self) -> String {
+        let mut bytes: Vec<_> = self.bytes.iter().collect();
+        bytes.sort_by(|a, b| b.1.cmp(a.1));
+        let mut s = bytes
+            .iter()
+            .map(|(parser, n)| format!("  {parser}: {}", format_bytes(**n)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        for (parser, n) in &self.skipped {
+            s.push_str(&format!("\n  {parser}: skipped {n} outputs"));
+        }
+        s
+    }
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Deserialize, Clone, JsonSchema)]
 pub struct FrameSummary {
     pub filename: u32,
     pub line: i32,
@@ -196,6 +693,102 @@ pub struct FrameSummary {
     pub uninterned_filename: Option<String>,
 }
 
+// Frames left out of rendered stacks, by file name, e.g., to hide framework internals, and how
+// many frames in a row are shown before the rest are collapsed
+#[derive(Default)]
+pub struct FrameFilter {
+    patterns: Option<RegexSet>,
+    pub max_depth: Option<usize>,
+}
+
+impl FrameFilter {
+    pub fn new(patterns: &[String], max_depth: Option<usize>) -> anyhow::Result<Self> {
+        let patterns = if patterns.is_empty() {
+            None
+        } else {
+            Some(
+                RegexSet::new(patterns)
+                    .map_err(|e| anyhow::anyhow!("Invalid frame filter pattern: {e}"))?,
+            )
+        };
+        Ok(FrameFilter {
+            patterns,
+            max_depth,
+        })
+    }
+
+    pub fn hides(&self, frame: &FrameSummary) -> bool {
+        let Some(patterns) = &self.patterns else {
+            return false;
+        };
+        match &frame.uninterned_filename {
+            Some(filename) => patterns.is_match(filename),
+            None => INTERN_TABLE.with_borrow(|intern_table| {
+                intern_table
+                    .get(&frame.filename)
+                    .is_some_and(|filename| patterns.is_match(filename))
+            }),
+        }
+    }
+
+    pub fn apply(&self, stack: &StackSummary) -> StackSummary {
+        stack.iter().filter(|f| !self.hides(f)).cloned().collect()
+    }
+}
+
+// Besides the fields as logged, JSON has the file name looked up in the intern table, both in full
+// and simplified, so that consumers don't need the str entries.  Deserializing ignores them
+impl Serialize for FrameSummary {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct ResolvedFrameSummary<'a> {
+            filename: u32,
+            line: i32,
+            name: &'a str,
+            uninterned_filename: &'a Option<String>,
+            // None if the file name was never interned
+            raw_filename: Option<String>,
+            simplified_filename: Option<String>,
+        }
+        let raw_filename = self
+            .uninterned_filename
+            .clone()
+            .or_else(|| INTERN_TABLE.with_borrow(|t| t.get(&self.filename).cloned()));
+        ResolvedFrameSummary {
+            filename: self.filename,
+            line: self.line,
+            name: &self.name,
+            uninterned_filename: &self.uninterned_filename,
+            simplified_filename: raw_filename
+                .as_deref()
+                .map(|f| simplify_filename(f).to_string()),
+            raw_filename,
+        }
+        .serialize(serializer)
+    }
+}
+
+// (CSS class, description) summarizing how a compilation went
+pub fn compilation_status(
+    metrics: Option<&Vec<CompilationMetricsMetadata>>,
+) -> (&'static str, &'static str) {
+    let Some(m) = metrics else {
+        return ("status-missing", "no compilation metrics");
+    };
+    if m.iter().any(|n| n.fail_type.is_some()) {
+        ("status-error", "failed")
+    } else if m.iter().any(|n| n.graph_op_count.unwrap_or(0) == 0) {
+        ("status-empty", "empty graph")
+    } else if m
+        .iter()
+        .any(|n| !n.restart_reasons.as_ref().is_some_and(|o| o.is_empty()))
+    {
+        ("status-break", "restarted")
+    } else {
+        ("status-ok", "ok")
+    }
+}
+
 pub fn simplify_filename<'a>(filename: &'a str) -> &'a str {
     let parts: Vec<&'a str> = filename.split("#link-tree/").collect();
     if parts.len() > 1 {
@@ -207,58 +800,241 @@ pub fn simplify_filename<'a>(filename: &'a str) -> &'a str {
             return &filename[capture.end()..];
         }
     }
-    return filename;
+    filename
+}
+
+// Matches Dynamo source expressions in guard code, e.g. L['x'].size()[0] or
+// G['torch'].nn.functional
+pub static SOURCE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r#"\b[LG]\[(?:'[^']*'|"[^"]*"|-?\d+)\]"#,
+        r#"(?:\.[A-Za-z_]\w*|\[(?:'[^']*'|"[^"]*"|-?\d+)\]|\(\))*"#
+    ))
+    .unwrap()
+});
+
+// Whether guard code refers to a source expression like L['x'].size()[0].  A plain substring
+// match is not enough, since L['self'].w would otherwise match L['self'].weight
+static SOURCE_ROOT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^[LG]\[(?:'[^']*'|"[^"]*"|-?\d+)\]"#).unwrap());
+static SOURCE_SEGMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^(?:\.[A-Za-z_]\w*|\[(?:'[^']*'|"[^"]*"|-?\d+)\]|\(\))"#).unwrap());
+
+// The top level object a source (as matched by SOURCE_RE) is part of: the local or global plus its
+// first attribute or item, e.g., L['self'].module for L['self'].module.weight, so that guards can
+// be attributed to a model attribute.  Method calls like .size() aren't part of the object, and
+// nn.Module dicts are looked through, e.g., L['self']._modules['layer1'].
+pub fn source_object(source: &str) -> String {
+    let Some((root, segments)) = split_source(source) else {
+        return source.to_string();
+    };
+    let mut end = 0;
+    if let Some(first) = segments.first() {
+        if *first != "()" && segments.get(1) != Some(&"()") {
+            end = 1;
+            if matches!(*first, "._modules" | "._parameters" | "._buffers")
+                && segments.get(1).is_some_and(|s| s.starts_with('['))
+            {
+                end = 2;
+            }
+        }
+    }
+    format!("{root}{}", segments[..end].concat())
+}
+
+// The expression a source is a property of, i.e., without any method calls and what follows them,
+// e.g., L['x'] for L['x'].size()[0].  This is what would be marked static or frozen to get rid of
+// the guard.
+pub fn source_expression(source: &str) -> String {
+    let Some((root, segments)) = split_source(source) else {
+        return source.to_string();
+    };
+    let end = segments
+        .iter()
+        .enumerate()
+        .position(|(i, s)| *s == "()" || segments.get(i + 1) == Some(&"()"))
+        .unwrap_or(segments.len());
+    format!("{root}{}", segments[..end].concat())
+}
+
+// The local or global a source starts from, and the attributes, items and calls that follow it
+fn split_source(source: &str) -> Option<(&str, Vec<&str>)> {
+    let root = SOURCE_ROOT_RE.find(source)?;
+    let mut segments = Vec::new();
+    let mut rest = &source[root.end()..];
+    while let Some(m) = SOURCE_SEGMENT_RE.find(rest) {
+        segments.push(m.as_str());
+        rest = &rest[m.end()..];
+    }
+    Some((root.as_str(), segments))
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuardSourceContribution {
+    pub source: String,
+    pub num_guards: usize,
+    pub num_compile_ids: usize,
+}
+
+// How many guards mention each source expression across the whole run, most first, so that it's
+// clear what to mark static or freeze to cut down on guards.  compile_ids gives the order to go
+// through the index in, so that ties are listed in the order they were first guarded on
+pub fn guard_source_contributions(
+    dynamo_guards_index: &DynamoGuardsIndex,
+    compile_ids: &[Option<CompileId>],
+    limit: usize,
+) -> Vec<GuardSourceContribution> {
+    let mut sources: FxIndexMap<String, (usize, FxHashSet<&Option<CompileId>>)> =
+        FxIndexMap::default();
+    for cid in compile_ids {
+        for guard in dynamo_guards_index.get(cid).into_iter().flatten() {
+            let expressions: FxIndexSet<String> = SOURCE_RE
+                .find_iter(&guard.code)
+                .map(|m| source_expression(m.as_str()))
+                .collect();
+            for expression in expressions {
+                let (num_guards, compile_ids) = sources.entry(expression).or_default();
+                *num_guards += 1;
+                compile_ids.insert(cid);
+            }
+        }
+    }
+    let mut contributions: Vec<GuardSourceContribution> = sources
+        .into_iter()
+        .map(
+            |(source, (num_guards, compile_ids))| GuardSourceContribution {
+                source,
+                num_guards,
+                num_compile_ids: compile_ids.len(),
+            },
+        )
+        .collect();
+    contributions.sort_by_key(|c| std::cmp::Reverse(c.num_guards));
+    contributions.truncate(limit);
+    contributions
+}
+
+pub fn mentions_source(code: &str, source: &str) -> bool {
+    code.match_indices(source).any(|(i, _)| {
+        code[i + source.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+    })
+}
+
+// Shares equal values kept in the in-memory indices, e.g., the guards or graph inputs of a frame,
+// which mostly stay the same from one recompile to the next
+pub struct Interner<T>(FxHashSet<Rc<T>>);
+
+impl<T: Hash + Eq> Default for Interner<T> {
+    fn default() -> Self {
+        Self(FxHashSet::default())
+    }
+}
+
+impl<T: Hash + Eq> Interner<T> {
+    pub fn intern(&mut self, value: T) -> Rc<T> {
+        if let Some(interned) = self.0.get(&value) {
+            return interned.clone();
+        }
+        let interned = Rc::new(value);
+        self.0.insert(interned.clone());
+        interned
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<Rc<T>> for Interner<T> {
+    fn from_iter<I: IntoIterator<Item = Rc<T>>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
 }
 
 pub fn unintern_str(interned_str: u32) -> String {
-    let intern_table = INTERN_TABLE.lock().unwrap();
-    let filename = intern_table
-        .get(&interned_str)
-        .map_or("(unknown)", |s| s.as_str());
-    return filename.to_string();
+    INTERN_TABLE.with_borrow(|intern_table| {
+        intern_table
+            .get(&interned_str)
+            .map_or("(unknown)", |s| s.as_str())
+            .to_string()
+    })
+}
+
+impl FrameSummary {
+    // Like Display, but when there is no dump_file for the frame, links to it using a template
+    // with {filename} and {line} placeholders, e.g., pointing at a source browser
+    pub fn fmt_with_source_url(&self, source_url_template: Option<&str>) -> String {
+        let filename = self
+            .uninterned_filename
+            .clone()
+            .unwrap_or_else(|| unintern_str(self.filename));
+        match source_url_template {
+            Some(template) if extract_eval_with_key_id(&filename).is_none() => {
+                let url = template
+                    .replace("{filename}", simplify_filename(&filename))
+                    .replace("{line}", &self.line.to_string());
+                format!(
+                    "<a href='{url}'>{filename}:{line}</a> in {name}",
+                    url = html_escape::encode_single_quoted_attribute(&url),
+                    filename = encode_text(simplify_filename(&filename)),
+                    line = self.line,
+                    name = encode_text(&self.name)
+                )
+            }
+            _ => self.to_string(),
+        }
+    }
 }
 
 impl fmt::Display for FrameSummary {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let intern_table = INTERN_TABLE.lock().unwrap();
-        let filename = if let Some(f) = &self.uninterned_filename {
-            f.as_str()
-        } else {
-            intern_table
-                .get(&self.filename)
-                .map_or("(unknown)", |s| s.as_str())
-        };
-        if let Some(fx_id) = extract_eval_with_key_id(filename) {
-            write!(
-                f,
-                "<a href='dump_file/eval_with_key_{fx_id}.html#L{line}'>{filename}:{line}</a> in {name}",
-                fx_id = fx_id,
-                filename = encode_text(simplify_filename(filename)),
-                line = self.line,
-                name = encode_text(&self.name)
-            )?;
-        } else {
-            write!(
-                f,
-                "{}:{} in {}",
-                encode_text(simplify_filename(filename)),
-                self.line,
-                encode_text(&self.name)
-            )?;
-        }
-        Ok(())
+        INTERN_TABLE.with_borrow(|intern_table| {
+            let filename = if let Some(f) = &self.uninterned_filename {
+                f.as_str()
+            } else {
+                intern_table
+                    .get(&self.filename)
+                    .map_or("(unknown)", |s| s.as_str())
+            };
+            if let Some(fx_id) = extract_eval_with_key_id(filename) {
+                write!(
+                    f,
+                    "<a href='dump_file/eval_with_key_{fx_id}.html#L{line}'>{filename}:{line}</a> in {name}",
+                    fx_id = fx_id,
+                    filename = encode_text(simplify_filename(filename)),
+                    line = self.line,
+                    name = encode_text(&self.name)
+                )
+            } else {
+                write!(
+                    f,
+                    "{}:{} in {}",
+                    encode_text(simplify_filename(filename)),
+                    self.line,
+                    encode_text(&self.name)
+                )
+            }
+        })
     }
 }
 
 pub type StackSummary = Vec<FrameSummary>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum SymInt {
     Int(i64),
     Symbol(String),
 }
 
+impl fmt::Display for SymInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymInt::Int(i) => write!(f, "{i}"),
+            SymInt::Symbol(s) => write!(f, "{s}"),
+        }
+    }
+}
+
 impl Default for SymInt {
     fn default() -> Self {
         SymInt::Int(0)
@@ -269,47 +1045,51 @@ fn default_layout() -> String {
     "torch.strided".to_string()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct OptimizeDdpSplitChildMetadata {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct EmptyMetadata {}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct GraphDumpMetadata {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct DynamoOutputGraphMetadata {
-    _sizes: Option<FxHashMap<String, Vec<SymInt>>>,
+    // Ordered map, so sizes are listed in graph order
+    pub sizes: Option<FxIndexMap<String, Vec<SymInt>>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct DynamoStartMetadata {
     pub stack: Option<StackSummary>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InductorOutputCodeMetadata {
     pub filename: Option<PathBuf>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct LinkMetadata {
     pub name: String,
     pub url: String,
+    // What kind of page it links to, e.g., "profiler trace" or "internal dashboard"
+    pub category: Option<String>,
+    pub description: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ArtifactMetadata {
     pub name: String,
     pub encoding: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct CompilationMetricsMetadata {
     // Other information like frame_key are already in envelope
     pub co_name: Option<String>,
@@ -335,9 +1115,14 @@ pub struct CompilationMetricsMetadata {
     pub compliant_custom_ops: Option<Vec<String>>,
     pub restart_reasons: Option<Vec<String>>,
     pub dynamo_time_before_restart_s: Option<f64>,
+    // Only logged by torch versions that deduplicate guards
+    pub duplicate_guards_suppressed: Option<u64>,
+    pub build_guards_time_s: Option<f64>,
+    // Name of the backend passed to torch.compile, when logged
+    pub backend_name: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct BwdCompilationMetricsMetadata {
     pub inductor_compile_time_s: Option<f64>,
     pub code_gen_time_s: Option<f64>,
@@ -345,7 +1130,7 @@ pub struct BwdCompilationMetricsMetadata {
     pub fail_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct AOTAutogradBackwardCompilationMetricsMetadata {
     pub start_time: Option<f64>,
     pub elapsed_time: Option<f64>, // technically redundant with envelope
@@ -353,7 +1138,7 @@ pub struct AOTAutogradBackwardCompilationMetricsMetadata {
     pub fail_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct SymbolicShapeSpecializationMetadata {
     pub symbol: Option<String>,
     pub sources: Option<Vec<String>>,
@@ -363,14 +1148,14 @@ pub struct SymbolicShapeSpecializationMetadata {
     pub user_stack: Option<StackSummary>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct SymbolicShapePropagateRealTensorMetadata {
     pub expr: Option<String>,
     pub result: Option<String>,
     pub stack: Option<StackSummary>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct FakeKernelMetadata {
     pub op: Option<String>,
     pub reason: Option<String>,
@@ -392,12 +1177,95 @@ pub struct AOTAutogradBackwardCompilationMetricsContext<'e> {
     pub qps: &'static str,
 }
 
-#[derive(Clone, Debug, Serialize)]
+// What an output is, as reported by the parser that made it, for grouping outputs in listings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Graph,
+    Guards,
+    Metrics,
+    Code,
+    Cache,
+    Link,
+    #[default]
+    Other,
+}
+
+// Part of the compiler an output comes from, in pipeline order, for grouping listings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompilePhase {
+    Dynamo,
+    AOTAutograd,
+    Inductor,
+    Caching,
+    #[default]
+    Other,
+}
+
+impl CompilePhase {
+    pub fn heading(&self) -> &'static str {
+        match self {
+            CompilePhase::Dynamo => "Dynamo",
+            CompilePhase::AOTAutograd => "AOTAutograd",
+            CompilePhase::Inductor => "Inductor",
+            CompilePhase::Caching => "Caching",
+            CompilePhase::Other => "Other",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct OutputFile {
     pub url: String,
     pub name: String,
     pub number: i32,
     pub suffix: String,
+    pub size: Option<usize>, // bytes written; None for links
+    pub lineno: usize,       // log line the output was produced from
+    // Contents of a small text output, shown inline in the build products listing
+    #[serde(default)]
+    pub preview: Option<String>,
+    // Where the output was originally written, e.g., inductor's tmp file for its output code; shown
+    // as a tooltip
+    #[serde(default)]
+    pub source_filename: Option<String>,
+    #[serde(default)]
+    pub artifact_kind: ArtifactKind,
+    #[serde(default)]
+    pub phase: CompilePhase,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhaseFilesContext {
+    pub heading: &'static str,
+    pub files: Vec<OutputFile>,
+}
+
+// Files grouped by phase in pipeline order, each group in the order the files were written
+pub fn group_by_phase(mut files: Vec<OutputFile>) -> Vec<PhaseFilesContext> {
+    files.sort_by_key(|f| f.phase);
+    let mut groups: Vec<PhaseFilesContext> = Vec::new();
+    for file in files {
+        match groups.last_mut() {
+            Some(group) if group.heading == file.phase.heading() => group.files.push(file),
+            _ => groups.push(PhaseFilesContext {
+                heading: file.phase.heading(),
+                files: vec![file],
+            }),
+        }
+    }
+    groups
+}
+
+// Outputs at most this big are previewed inline in the build products listing, so that e.g. short
+// graphs can be read without clicking through to them
+pub const INLINE_PREVIEW_MAX_BYTES: usize = 1024;
+
+pub fn inline_preview(filename: &Path, contents: &str) -> Option<String> {
+    let is_html = filename.extension().is_some_and(|e| e == "html");
+    (!is_html && !contents.trim().is_empty() && contents.len() <= INLINE_PREVIEW_MAX_BYTES)
+        .then(|| contents.to_string())
 }
 
 #[derive(Debug, Serialize)]
@@ -408,22 +1276,60 @@ pub struct CompilationMetricsContext<'e> {
     pub stack_html: String,
     pub symbolic_shape_specializations: Vec<SymbolicShapeSpecializationContext>,
     pub guards_added_fast: Vec<GuardAddedFastContext>,
+    pub runtime_asserts: Vec<GuardAddedFastContext>,
+    pub guards_url: String,
+    pub input_bytes: String,
+    pub aliased_inputs: Vec<Vec<String>>,
     pub output_files: &'e Vec<OutputFile>,
     pub compile_id_dir: &'e PathBuf,
     pub mini_stack_html: String,
+    pub restart_reasons: Vec<RestartReasonContext>,
+    pub javascript: &'static str,
     pub qps: &'static str,
 }
 
 #[derive(Debug, Serialize)]
-pub struct SymbolicGuardContext {
+pub struct GraphSizeContext {
+    pub name: String,
+    pub size: String,
+    pub symbolic: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DynamoOutputGraphSizesContext {
     pub css: &'static str,
-    pub expr: String,
-    pub stack_html: String,
+    pub compile_id: String,
+    pub sizes: Vec<GraphSizeContext>,
+    pub qps: &'static str,
 }
 
+// A kernel defined in Inductor output code, annotated with the comments Inductor wrote above it
 #[derive(Debug, Serialize)]
-pub struct GuardsAddedFastContext {
-    pub guards: Vec<GuardAddedFastContext>,
+pub struct KernelAnnotation {
+    pub name: String,
+    pub kind: String, // e.g., triton or cpp_pybinding
+    pub source_nodes: Vec<String>,
+    pub original_aten: Vec<String>,
+    // Other "# key: value" comments, e.g., the kernel path or an estimated runtime
+    pub comments: Vec<(String, String)>,
+    pub launches: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InductorOutputCodeContext {
+    pub css: &'static str,
+    pub kernels: Vec<KernelAnnotation>,
+    // Lines of the wrapper marked "# reuse", where a buffer is reused instead of allocated
+    pub buffer_reuse: Vec<String>,
+    pub code_html: String,
+    pub qps: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolicGuardContext {
+    pub css: &'static str,
+    pub expr: String,
+    pub stack_html: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -451,15 +1357,23 @@ impl Display for FailureReason {
                           "
                 )
             }
-            FailureReason::Restart(restart_reason) => write!(
-                f,
-                r#"<td> RestartAnalysis </td><td><pre>{restart_reason}</pre></td><td>Not availble for restarts(yet)!</td>"#
-            ),
-        }
-    }
+            FailureReason::Restart(restart_reason) => {
+                let advice = graph_break_advice(restart_reason).map_or(String::new(), |a| {
+                    format!(
+                        r#"<div class="graph-break-advice">{} <strong>Fix:</strong> {}</div>"#,
+                        a.explanation, a.remediation
+                    )
+                });
+                write!(
+                    f,
+                    r#"<td> RestartAnalysis </td><td><pre>{restart_reason}</pre>{advice}</td><td>Not availble for restarts(yet)!</td>"#
+                )
+            }
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ExportFailure {
     pub failure_type: String,
     pub reason: String,
@@ -505,19 +1419,28 @@ pub enum Metadata<'e> {
     SymbolicShapePropagateRealTensor(&'e SymbolicShapePropagateRealTensorMetadata),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct DumpFileMetadata {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct GuardAddedFastMetadata {
     pub expr: Option<String>,
     pub stack: Option<StackSummary>,
     pub user_stack: Option<StackSummary>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct GuardAddedMetadata {
+    pub expr: Option<String>,
+    // runtime_assert for deferred runtime asserts, eval for ordinary guards
+    pub prefix: Option<String>,
+    pub stack: Option<StackSummary>,
+    pub user_stack: Option<StackSummary>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Envelope {
     pub rank: Option<u32>,
     #[serde(flatten)]
@@ -558,22 +1481,79 @@ pub struct Envelope {
     pub dump_file: Option<DumpFileMetadata>,
     pub chromium_event: Option<EmptyMetadata>,
     pub guard_added_fast: Option<GuardAddedFastMetadata>,
+    pub guard_added: Option<GuardAddedMetadata>,
     pub exported_program: Option<EmptyMetadata>,
     #[serde(flatten)]
     pub _other: FxHashMap<String, Value>,
 }
 
+// An entry of a log as yielded by parse_entries, with the file names of its stack frames resolved
+// from the log's str entries
+#[derive(Debug, Serialize)]
+pub struct ParsedEnvelope {
+    // 1-indexed line number of the entry in the log
+    pub lineno: usize,
+    // Microseconds since the start of the year the entry was logged in, from its glog prefix
+    pub timestamp_us: Option<i64>,
+    #[serde(flatten)]
+    pub envelope: Envelope,
+    pub payload: Option<String>,
+    // The payload didn't match the md5 it was logged with, e.g., because it was cut short
+    pub payload_md5_mismatch: bool,
+}
+
+impl Envelope {
+    // What the entry logs, e.g., dynamo_output_graph, or the name of an artifact or graph dump
+    pub fn kind(&self) -> String {
+        if let Some(artifact) = &self.artifact {
+            return artifact.name.clone();
+        }
+        if let Some(graph_dump) = &self.graph_dump {
+            return graph_dump.name.clone();
+        }
+        // Fields every kind of entry may have
+        const COMMON: [&str; 7] = [
+            "rank",
+            "compiled_autograd_id",
+            "frame_id",
+            "frame_compile_id",
+            "attempt",
+            "has_payload",
+            "stack",
+        ];
+        let Ok(Value::Object(fields)) = serde_json::to_value(self) else {
+            return "(unknown)".to_string();
+        };
+        fields
+            .into_iter()
+            .find(|(k, v)| !v.is_null() && !COMMON.contains(&k.as_str()))
+            .map_or("(unknown)".to_string(), |(k, _)| k)
+    }
+
+    pub(crate) fn unintern_stacks(&mut self, intern_table: &FxHashMap<u32, String>) {
+        let stacks = [
+            self.stack.as_mut(),
+            self.dynamo_start.as_mut().and_then(|m| m.stack.as_mut()),
+        ];
+        for frame in stacks.into_iter().flatten().flatten() {
+            if frame.uninterned_filename.is_none() {
+                frame.uninterned_filename = intern_table.get(&frame.filename).cloned();
+            }
+        }
+    }
+}
+
 type MetaTensorId = u64;
 type MetaStorageId = u64;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct TensorDesc {
-    id: MetaTensorId,
-    describer_id: u64,
+    pub id: MetaTensorId,
+    pub describer_id: u64,
     ndim: u64,
-    dtype: String,
-    device: String,
-    size: Vec<SymInt>,
+    pub dtype: String,
+    pub device: String,
+    pub size: Vec<SymInt>,
     dynamo_dynamic_indices: Option<Vec<u64>>,
     // TODO: Make layout an enum
     #[serde(default = "default_layout")]
@@ -613,7 +1593,7 @@ pub struct TensorDesc {
     stride: Option<Vec<SymInt>>,
     #[serde(default)]
     storage_offset: SymInt,
-    storage: Option<MetaStorageId>,
+    pub storage: Option<MetaStorageId>,
     sparse_dim: Option<u64>,
     dense_dim: Option<u64>,
     is_coalesced: Option<bool>,
@@ -632,21 +1612,240 @@ pub struct TensorDesc {
     pub _other: FxHashMap<String, Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct StorageDesc {
-    id: MetaStorageId,
-    describer_id: u64,
-    size: u64,
+    pub id: MetaStorageId,
+    pub describer_id: u64,
+    pub size: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct SourceDesc {
-    describer_id: u64,
-    id: MetaTensorId,
-    source: String,
+    pub describer_id: u64,
+    pub id: MetaTensorId,
+    pub source: String,
+}
+
+// A graph input, assembled from the describe_tensor/describe_source entries
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct InputSource {
+    pub source: String,
+    pub placeholder: String,
+    pub dtype: String,
+    pub size: String,
+    pub storage: Option<MetaStorageId>,
+    pub storage_bytes: Option<u64>,
+}
+
+impl InputSource {
+    pub fn new(source: String, tensor: &TensorDesc, storage: Option<&StorageDesc>) -> Self {
+        // Dynamo names graph placeholders after the source, replacing punctuation
+        static PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^a-zA-Z0-9]+").unwrap());
+        InputSource {
+            placeholder: PLACEHOLDER_RE.replace_all(&source, "_").into_owned(),
+            source,
+            dtype: tensor.dtype.clone(),
+            size: format!(
+                "[{}]",
+                tensor
+                    .size
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            storage: tensor.storage,
+            storage_bytes: storage.map(|s| s.size),
+        }
+    }
+}
+
+// Example sources listed for each shape in the tensor catalog
+pub const TENSOR_CATALOG_MAX_SOURCES: usize = 5;
+
+#[derive(Debug, Serialize)]
+pub struct TensorShapeRow {
+    pub dtype: String,
+    pub size: String,
+    pub count: usize,
+    pub num_compile_ids: usize,
+    pub sources: Vec<String>,
+    pub num_more_sources: usize,
+}
+
+// A source that was seen with more than one shape or dtype, i.e. a candidate for marking dynamic
+#[derive(Debug, Serialize)]
+pub struct VaryingSourceRow {
+    pub frame: String,
+    pub frame_url: String,
+    pub source: String,
+    pub shapes: Vec<String>,
+    pub num_compile_ids: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TensorCatalogContext {
+    pub css: &'static str,
+    pub shapes: Vec<TensorShapeRow>,
+    pub varying_sources: Vec<VaryingSourceRow>,
+    pub qps: &'static str,
+}
+
+// Distinct (dtype, size) of the tensor inputs across all compilations, most common first, and the
+// sources whose shape changed between compilations.  compile_ids gives the order to go through
+// the index in, like for guard_source_contributions
+pub fn tensor_catalog(
+    input_source_index: &InputSourceIndex,
+    compile_ids: &[Option<CompileId>],
+) -> (Vec<TensorShapeRow>, Vec<VaryingSourceRow>) {
+    type Seen<'a> = (usize, FxHashSet<&'a Option<CompileId>>, FxIndexSet<&'a str>);
+    type Shapes<'a> = (FxIndexSet<String>, FxHashSet<&'a Option<CompileId>>);
+    let mut shapes: FxIndexMap<(&str, &str), Seen> = FxIndexMap::default();
+    // Sources are per frame, e.g. L['x'] is a different input in every function
+    let mut sources: FxIndexMap<(Option<CompileId>, &str), Shapes> = FxIndexMap::default();
+    for cid in compile_ids {
+        let frame = cid.as_ref().map(|c| CompileId {
+            frame_compile_id: None,
+            attempt: None,
+            ..c.clone()
+        });
+        for input in input_source_index.get(cid).into_iter().flatten() {
+            let (count, shape_compile_ids, shape_sources) = shapes
+                .entry((input.dtype.as_str(), input.size.as_str()))
+                .or_default();
+            *count += 1;
+            shape_compile_ids.insert(cid);
+            shape_sources.insert(&input.source);
+            let (source_shapes, source_compile_ids) = sources
+                .entry((frame.clone(), input.source.as_str()))
+                .or_default();
+            source_shapes.insert(format!("{}{}", input.dtype, input.size));
+            source_compile_ids.insert(cid);
+        }
+    }
+    let mut shapes: Vec<TensorShapeRow> = shapes
+        .into_iter()
+        .map(
+            |((dtype, size), (count, compile_ids, sources))| TensorShapeRow {
+                dtype: dtype.to_string(),
+                size: size.to_string(),
+                count,
+                num_compile_ids: compile_ids.len(),
+                num_more_sources: sources.len().saturating_sub(TENSOR_CATALOG_MAX_SOURCES),
+                sources: sources
+                    .into_iter()
+                    .take(TENSOR_CATALOG_MAX_SOURCES)
+                    .map(str::to_string)
+                    .collect(),
+            },
+        )
+        .collect();
+    shapes.sort_by_key(|s| std::cmp::Reverse(s.count));
+    let varying_sources = sources
+        .into_iter()
+        .filter(|(_, (shapes, _))| shapes.len() > 1)
+        .map(
+            |((frame, source), (shapes, compile_ids))| VaryingSourceRow {
+                frame: frame
+                    .as_ref()
+                    .map_or("(unknown)".to_string(), |f| f.to_string()),
+                frame_url: frame.as_ref().map_or("".to_string(), frame_page_url),
+                source: source.to_string(),
+                shapes: shapes.into_iter().collect(),
+                num_compile_ids: compile_ids.len(),
+            },
+        )
+        .collect();
+    (shapes, varying_sources)
+}
+
+// Sum of the storage sizes backing the inputs, counting storages shared by several inputs once
+pub fn total_input_bytes(sources: &[InputSource]) -> u64 {
+    let mut seen: FxHashSet<MetaStorageId> = FxHashSet::default();
+    sources
+        .iter()
+        .filter(|s| s.storage.is_none_or(|id| seen.insert(id)))
+        .filter_map(|s| s.storage_bytes)
+        .sum()
+}
+
+// Groups of input sources that are backed by the same storage, in log order
+pub fn aliased_inputs(sources: &[InputSource]) -> Vec<Vec<String>> {
+    let mut groups: FxIndexMap<MetaStorageId, Vec<String>> = FxIndexMap::default();
+    for s in sources {
+        if let Some(id) = s.storage {
+            groups.entry(id).or_default().push(s.source.clone());
+        }
+    }
+    groups.into_values().filter(|g| g.len() > 1).collect()
+}
+
+// e.g., 1m 23.4s, for times that range from milliseconds to hours
+pub fn format_duration(secs: f64) -> String {
+    if secs < 1e-3 {
+        format!("{:.0}µs", secs * 1e6)
+    } else if secs < 1.0 {
+        format!("{:.1}ms", secs * 1e3)
+    } else if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else if secs < 3600.0 {
+        format!("{}m {:.1}s", (secs / 60.0).floor(), secs % 60.0)
+    } else {
+        format!(
+            "{}h {}m {:.0}s",
+            (secs / 3600.0).floor(),
+            (secs % 3600.0 / 60.0).floor(),
+            secs % 60.0
+        )
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// e.g., 12.3 MiB, without the exact count format_bytes adds
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+// Template formatter for a number of seconds
+pub fn duration_formatter(value: &Value, out: &mut String) -> tinytemplate::error::Result<()> {
+    match value.as_f64() {
+        Some(secs) => out.push_str(&format_duration(secs)),
+        None => tinytemplate::format(value, out)?,
+    }
+    Ok(())
+}
+
+// Template formatter for a number of bytes, with the exact count on hover
+pub fn bytes_formatter(value: &Value, out: &mut String) -> tinytemplate::error::Result<()> {
+    match value.as_u64() {
+        Some(bytes) => write!(
+            out,
+            "<span title=\"{bytes} bytes\">{}</span>",
+            format_size(bytes)
+        )
+        .unwrap(),
+        None => tinytemplate::format(value, out)?,
+    }
+    Ok(())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+    format!("{} ({bytes} bytes)", format_size(bytes))
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct DynamoGuard {
     pub code: String,
     pub stack: Option<StackSummary>,
@@ -655,49 +1854,2689 @@ pub struct DynamoGuard {
 
 #[derive(Debug, Serialize)]
 pub struct DynamoGuardsContext {
-    pub guards: Vec<DynamoGuard>,
+    pub guards: Vec<DynamoGuardContext>,
+    pub by_source: Vec<GuardSourceGroup>,
+    pub metrics_url: &'static str,
+    pub qps: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DynamoGuardContext {
+    pub code: String,
+    pub code_html: String,
+    pub specializations: Vec<GuardSpecializationLink>,
+}
+
+// The guards (by index) that mention a top level source object, see source_object
+#[derive(Debug, Serialize)]
+pub struct GuardSourceGroup {
+    pub source: String,
+    pub count: usize,
+    pub guards: Vec<usize>,
+}
+
+// A symbolic shape specialization whose source a guard mentions; index is the position
+// of the specialization in the compilation metrics table
+#[derive(Debug, Serialize)]
+pub struct GuardSpecializationLink {
+    pub index: usize,
+    pub symbol: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompileDirectoryContext {
+    pub compile_id: String,
+    pub anchor: String,
+    pub legacy_anchor: String,
+    // For an attempt that was restarted, the attempt that finished the compilation
+    pub restarted_as: Option<CompileIdLink>,
+    pub backend: Option<String>,
+    // Collapse the artifacts of a restarted attempt behind a toggle
+    pub collapse: bool,
+    pub phases: Vec<PhaseFilesContext>,
+    pub input_bytes: String,
+    pub landing_url: String, // empty when there is no landing page, e.g. unknown compile id
+    pub frame_url: String,
+    pub compiled_autograd: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SingleFileReportContext {
+    pub files: String,
+    pub javascript: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirectoryPageContext {
+    pub url: String,
+    pub label: String,
+    pub compile_ids: Vec<CompileIdLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirectoryPageRenderContext<'e> {
+    pub css: &'static str,
+    pub label: &'e str,
+    pub directory: &'e [CompileDirectoryContext],
+    pub qps: &'static str,
+}
+
+// Facts about one compile id, written next to its landing page for dashboards and other tools
+// that would rather not scrape the HTML
+#[derive(Debug, Serialize)]
+pub struct CompileIdSummary {
+    pub compile_id: String,
+    pub status: &'static str,
+    pub co_name: Option<String>,
+    pub fail_type: Option<String>,
+    pub entire_frame_compile_time_s: Option<f64>,
+    pub backend_compile_time_s: Option<f64>,
+    pub inductor_compile_time_s: Option<f64>,
+    pub graph_op_count: Option<u64>,
+    pub graph_node_count: Option<u64>,
+    pub graph_input_count: Option<u64>,
+    pub guard_count: Option<u64>,
+    pub shape_env_guard_count: Option<u64>,
+    // See compile_id_backend
+    pub backend: Option<String>,
+    pub prior_compile_id: Option<String>,
+    pub recompile_reasons: Vec<String>,
+    pub artifacts: Vec<SummaryArtifact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SummaryArtifact {
+    pub url: String, // relative to the compile id directory
+    pub kind: ArtifactKind,
+    pub phase: CompilePhase,
+    pub size: Option<usize>,
+    pub lineno: usize,
+    pub source_filename: Option<String>,
+}
+
+impl CompileIdSummary {
+    pub fn new(
+        compile_id: &CompileId,
+        status: &'static str,
+        metrics: Option<&CompilationMetricsMetadata>,
+        backend: Option<String>,
+        prior: Option<&CompileId>,
+        recompile_reasons: Vec<String>,
+        artifacts: Vec<SummaryArtifact>,
+    ) -> Self {
+        Self {
+            compile_id: compile_id.to_string(),
+            status,
+            co_name: metrics.and_then(|m| m.co_name.clone()),
+            fail_type: metrics.and_then(|m| m.fail_type.clone()),
+            entire_frame_compile_time_s: metrics.and_then(|m| m.entire_frame_compile_time_s),
+            backend_compile_time_s: metrics.and_then(|m| m.backend_compile_time_s),
+            inductor_compile_time_s: metrics.and_then(|m| m.inductor_compile_time_s),
+            graph_op_count: metrics.and_then(|m| m.graph_op_count),
+            graph_node_count: metrics.and_then(|m| m.graph_node_count),
+            graph_input_count: metrics.and_then(|m| m.graph_input_count),
+            guard_count: metrics.and_then(|m| m.guard_count),
+            shape_env_guard_count: metrics.and_then(|m| m.shape_env_guard_count),
+            backend,
+            prior_compile_id: prior.map(|p| p.to_string()),
+            recompile_reasons,
+            artifacts,
+        }
+    }
+}
+
+// The backend that compiled a compile id: the one in its compilation metrics, if logged, or else
+// "inductor" when it has outputs from inductor (or its caches) and "other" (e.g., eager, or a
+// custom backend) when it compiled a graph without any.  None when it's unknown, e.g., for a
+// compilation that failed before the backend produced anything
+pub fn compile_id_backend(
+    metrics: Option<&Vec<CompilationMetricsMetadata>>,
+    files: &[OutputFile],
+) -> Option<String> {
+    if let Some(name) = metrics
+        .into_iter()
+        .flatten()
+        .rev()
+        .find_map(|m| m.backend_name.clone())
+    {
+        return Some(name);
+    }
+    if files
+        .iter()
+        .any(|f| matches!(f.phase, CompilePhase::Inductor | CompilePhase::Caching))
+    {
+        Some("inductor".to_string())
+    } else if metrics
+        .and_then(|m| m.last())
+        .is_some_and(|m| m.fail_type.is_none() && m.graph_op_count.unwrap_or(0) > 0)
+        && files
+            .iter()
+            .any(|f| f.phase == CompilePhase::Dynamo && f.artifact_kind == ArtifactKind::Graph)
+    {
+        Some("other".to_string())
+    } else {
+        None
+    }
+}
+
+// One compile id in the JSON output, with everything that was logged about it
+#[derive(Debug, Serialize)]
+pub struct JsonCompileId {
+    pub dir: String,
+    #[serde(flatten)]
+    pub summary: CompileIdSummary,
+    pub metrics: Vec<CompilationMetricsMetadata>,
+    pub guards: Vec<Rc<DynamoGuard>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonFailure {
+    pub compile_id: String,
+    pub kind: &'static str, // "failure" or "restart"
+    pub reason: String,
+    pub fail_type: Option<String>,
+    pub user_frame_filename: Option<String>,
+    pub user_frame_lineno: Option<u32>,
+}
+
+// The failures and restarts of failures_and_restarts.html, in log order
+pub fn json_failures(metrics_index: &CompilationMetricsIndex) -> Vec<JsonFailure> {
+    let mut failures = Vec::new();
+    for (cid, metrics) in metrics_index {
+        let compile_id = cid
+            .as_ref()
+            .map_or("(unknown)".to_string(), |c| c.to_string());
+        for m in metrics {
+            for restart in m.restart_reasons.iter().flatten() {
+                failures.push(JsonFailure {
+                    compile_id: compile_id.clone(),
+                    kind: "restart",
+                    reason: restart.clone(),
+                    fail_type: None,
+                    user_frame_filename: None,
+                    user_frame_lineno: None,
+                });
+            }
+            if let Some(fail_type) = &m.fail_type {
+                failures.push(JsonFailure {
+                    compile_id: compile_id.clone(),
+                    kind: "failure",
+                    reason: m.fail_reason.clone().unwrap_or_default(),
+                    fail_type: Some(fail_type.clone()),
+                    user_frame_filename: m.fail_user_frame_filename.clone(),
+                    user_frame_lineno: m.fail_user_frame_lineno,
+                });
+            }
+        }
+    }
+    failures
+}
+
+// The whole parsed run, for post-processing from scripts without scraping the HTML
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    pub tlparse_version: &'static str,
+    pub stats: &'a Stats,
+    pub compile_ids: Vec<JsonCompileId>,
+    pub failures: Vec<JsonFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompileIdIndexContext<'e> {
+    pub css: &'static str,
+    pub javascript: &'static str,
+    pub compile_id: String,
+    pub anchor: String,
+    pub status_class: &'static str,
+    pub status: &'static str,
+    pub backend: Option<String>,
+    pub m: Option<&'e CompilationMetricsMetadata>,
+    pub input_bytes: String,
+    pub output_files: Vec<ArtifactContext>,
+    pub stack_html: String,
+    // The compile id before this one in the same frame, whose guards failed to cause this recompile
+    pub prior_compile_id: String,
+    pub prior_url: String,
+    pub frame_url: String,
+    pub recompile_reasons: Vec<RecompileReasonContext>,
+    pub logged_from: Vec<(String, usize)>,
+    pub waterfall: Vec<WaterfallRow>,
+    pub links: Vec<LinkMetadata>,
     pub qps: &'static str,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ArtifactContext {
+    pub anchor: String,
+    #[serde(flatten)]
+    pub file: OutputFile,
+}
+
+// Anchors that external tools can link to, which only depend on the compile id and on what was
+// logged for it, so they stay the same across runs (unlike output numbers, which count every
+// output in the log):
+//   index.html#{cid}                                    a compile id in the build products listing
+//   {dir}/index.html#artifact-{name}                    an artifact of the compile id
+//   failures_and_restarts.html#failure-{cid}            the compile id's failure
+//   failures_and_restarts.html#restart-{cid}-{k}        its k-th restart, counting from 1
+// where {cid} is CompileId::anchor ("cid-unknown" without a compile id), {dir} is
+// CompileId::as_directory_name and {name} is the artifact's file name without its output number.
+// Artifacts logged more than once for a compile id get -2, -3, ... appended in log order.
+// The anchors these replaced, index.html#compile-{dir}, #failure-{dir} and #restart-{dir}-{k}
+// (with legacy_compile_id_anchor as {dir}), and index.html#{compile id as displayed}, are still
+// there too, so that links built from them keep working.
+pub fn compile_id_anchor(compile_id: Option<&CompileId>) -> String {
+    compile_id.map_or("cid-unknown".to_string(), CompileId::anchor)
+}
+
+pub fn legacy_compile_id_anchor(compile_id: Option<&CompileId>) -> String {
+    compile_id.map_or("unknown".to_string(), |c| c.as_directory_name())
+}
+
+// A compile id as shown on a page, along with the anchor of its entry in index.html
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CompileIdLink {
+    pub compile_id: String,
+    pub anchor: String,
+}
+
+impl CompileIdLink {
+    pub fn new(compile_id: Option<&CompileId>) -> Self {
+        CompileIdLink {
+            compile_id: compile_id.map_or("(unknown)".to_string(), |c| c.to_string()),
+            anchor: compile_id_anchor(compile_id),
+        }
+    }
+}
+
+pub fn artifact_anchors(files: &[OutputFile]) -> Vec<String> {
+    let mut seen: FxHashMap<String, usize> = FxHashMap::default();
+    files
+        .iter()
+        .map(|f| {
+            let name = f.name.rsplit('/').next().unwrap_or(&f.name);
+            let (name, _) = output_diff_key(Path::new(name));
+            let name: String = name
+                .to_string_lossy()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            let count = seen.entry(name.clone()).or_default();
+            *count += 1;
+            match *count {
+                1 => format!("artifact-{name}"),
+                n => format!("artifact-{name}-{n}"),
+            }
+        })
+        .collect()
+}
+
+// An output file of a compilation, placed on a timeline from the first entry logged for the
+// compilation to the last
+#[derive(Debug, Serialize)]
+pub struct WaterfallRow {
+    pub name: String,
+    pub since_start: String,
+    pub since_previous: String,
+    // Percentages of the timeline, for drawing the time since the previous output as a bar
+    pub left: String,
+    pub width: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecompileReasonContext {
+    pub reason: String,
+    pub change: Option<String>,
+    // The failed guard on the prior compile id's guards page, when it can be found
+    pub guard_url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct IndexContext {
     pub css: &'static str,
     pub javascript: &'static str,
-    pub directory: Vec<(String, Vec<OutputFile>)>,
+    pub directory: Vec<CompileDirectoryContext>,
+    // When non-empty, the directory is split into these pages instead of being inlined
+    pub directory_pages: Vec<DirectoryPageContext>,
     pub stack_trie_html: String,
     pub unknown_stack_trie_html: String,
     pub has_unknown_stack_trie: bool,
+    pub has_stack_trie_exports: bool,
     pub num_breaks: usize,
+    pub num_specializations: usize,
+    pub num_recompile_thrashing: usize,
+    pub num_modules: usize,
+    pub num_non_compliant_ops: usize,
+    pub num_specialization_recompiles: usize,
+    pub compile_time_histogram: Vec<CompileTimeBucket>,
+    pub guard_construction: Vec<GuardConstructionRow>,
+    pub restart_time_s: f64,
+    pub restart_time_frames: Vec<RestartTimeRow>,
+    pub metrics_columns: Vec<String>,
+    pub metrics_table: Vec<MetricsTableRow>,
+    pub graph_count_mismatches: Vec<GraphCountMismatch>,
+    pub num_tensor_shapes: usize,
+    // Compile ids whose artifacts were not rendered, because of max_compiles
+    pub num_unrendered_compiles: usize,
+    pub max_compiles: usize,
+    pub missing_interned_strs: Vec<MissingInternedStr>,
+    pub num_missing_interned_strs: usize,
+    // Defined by a str entry only after they were first used
+    pub num_late_interned_strs: usize,
+    // Only when there is more than one
+    pub torch_installs: Vec<TorchInstall>,
+    pub guard_sources: Vec<GuardSourceContribution>,
+    pub external_links: Vec<ExternalLinkRow>,
+    // Every backend that compiled something, to filter the build products by, when there is more
+    // than one
+    pub backends: Vec<String>,
+    pub recompile_storms: Vec<RecompileStorm>,
+    pub recompile_storm_threshold: usize,
+    pub recompile_storm_window: String,
+    pub regressions: Vec<Regression>,
+    pub regression_threshold_pct: String,
+    pub has_cross_rank_metrics: bool,
+    pub has_compile_divergence: bool,
+    pub num_compile_divergences: usize,
     pub custom_header_html: String,
     pub has_chromium_events: bool,
+    pub chromium_event_problems: Vec<String>,
     pub qps: &'static str,
 }
 
 #[derive(Debug, Serialize)]
-pub struct ExportIndexContext {
-    pub css: &'static str,
-    pub javascript: &'static str,
-    pub directory: Vec<(String, Vec<OutputFile>)>,
-    pub failures: Vec<ExportFailure>,
-    pub custom_header_html: String,
-    pub num_failures: usize,
-    pub success: bool,
-    pub exported_program_url: String,
-    pub qps: &'static str,
+pub struct ExternalLinkRow {
+    pub compile_id: String,
+    pub compile_id_url: String, // of its landing page; empty without a compile id
+    #[serde(flatten)]
+    pub link: LinkMetadata,
+}
+
+// Every external link in the log, grouped by category (uncategorized last), each group in log
+// order
+pub fn external_links(link_index: &LinkIndex) -> Vec<ExternalLinkRow> {
+    let mut rows: Vec<ExternalLinkRow> = link_index
+        .iter()
+        .flat_map(|(cid, links)| {
+            let compile_id = cid
+                .as_ref()
+                .map_or("(unknown)".to_string(), |c| c.to_string());
+            let compile_id_url = cid.as_ref().map_or("".to_string(), |c| {
+                format!("{}/index.html", c.as_directory_name())
+            });
+            links.iter().map(move |link| ExternalLinkRow {
+                compile_id: compile_id.clone(),
+                compile_id_url: compile_id_url.clone(),
+                link: link.clone(),
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        (a.link.category.is_none(), &a.link.category)
+            .cmp(&(b.link.category.is_none(), &b.link.category))
+    });
+    rows
 }
 
+// One compile id in the metrics table of print.html
 #[derive(Debug, Serialize)]
-pub struct SymbolicShapeSpecializationContext {
-    pub symbol: String,
-    pub sources: Vec<String>,
-    pub value: String,
-    pub user_stack_html: String,
-    pub stack_html: String,
+pub struct PrintMetricsRow {
+    pub compile_id: String,
+    pub anchor: String,
+    pub status_class: &'static str,
+    pub status: &'static str,
+    pub entire_frame_compile_time_s: Option<f64>,
+    pub backend_compile_time_s: Option<f64>,
+    pub graph_op_count: Option<u64>,
+    pub guard_count: Option<u64>,
+    pub fail_type: Option<String>,
 }
 
+// A row of the metrics table on index.html and of compilation_metrics.csv, with the configured
+// CompilationMetrics fields after the compile id and status
 #[derive(Debug, Serialize)]
-pub struct GuardAddedFastContext {
-    pub expr: String,
-    pub user_stack_html: String,
-    pub stack_html: String,
+pub struct MetricsTableRow {
+    pub compile_id: String,
+    pub status_class: &'static str,
+    pub status: &'static str,
+    pub cells: Vec<String>,
+}
+
+// The CompilationMetrics fields that can be chosen as metrics table columns
+pub fn metrics_column_names() -> Vec<String> {
+    schemars::schema_for!(CompilationMetricsMetadata)
+        .schema
+        .object
+        .map(|o| o.properties.into_keys().collect())
+        .unwrap_or_default()
+}
+
+pub fn metrics_table_cells(
+    m: Option<&CompilationMetricsMetadata>,
+    columns: &[String],
+) -> Vec<String> {
+    let value = m.map_or(Value::Null, |m| serde_json::to_value(m).unwrap_or_default());
+    columns
+        .iter()
+        .map(|c| match &value[c.as_str()] {
+            Value::Null => "".to_string(),
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn metrics_table_csv(columns: &[String], rows: &[MetricsTableRow]) -> String {
+    let mut csv = String::new();
+    let header = ["compile_id", "status"]
+        .into_iter()
+        .chain(columns.iter().map(String::as_str));
+    csv.push_str(&header.map(csv_field).collect::<Vec<_>>().join(","));
+    csv.push('\n');
+    for row in rows {
+        let fields = [row.compile_id.as_str(), row.status]
+            .into_iter()
+            .chain(row.cells.iter().map(String::as_str));
+        csv.push_str(&fields.map(csv_field).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// A known cause of compilation failures or restarts, recognized by a regex on the failure or
+// restart reason
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownIssue {
+    pub pattern: String,
+    pub explanation: String,
+    pub suggestion: Option<String>,
+    // e.g., a PyTorch issue or documentation page
+    pub link: Option<String>,
+}
+
+impl KnownIssue {
+    fn new(pattern: &str, explanation: &str, suggestion: Option<&str>, link: Option<&str>) -> Self {
+        KnownIssue {
+            pattern: pattern.to_string(),
+            explanation: explanation.to_string(),
+            suggestion: suggestion.map(str::to_string),
+            link: link.map(str::to_string),
+        }
+    }
+}
+
+pub fn default_known_issues() -> Vec<KnownIssue> {
+    vec![
+        KnownIssue::new(
+            r"dynamic shape operator",
+            "An operator whose output shape depends on the input data (e.g., nonzero) was traced.",
+            Some(
+                "Set torch._dynamo.config.capture_dynamic_output_shape_ops = True, or compute the \
+                  shape outside the compiled region.",
+            ),
+            None,
+        ),
+        KnownIssue::new(
+            r"GuardOnDataDependentSymNode|Could not guard on data-dependent expression",
+            "A check depends on a value computed from tensor data (e.g., .item() or nonzero), \
+             which isn't known while compiling.",
+            Some("Tell the compiler which way the check goes with torch._check."),
+            None,
+        ),
+        KnownIssue::new(
+            r"CUDA out of memory|OutOfMemoryError",
+            "The GPU ran out of memory while compiling or autotuning.",
+            Some("Reduce the batch size, or turn off max-autotune, which benchmarks many kernels."),
+            None,
+        ),
+        KnownIssue::new(
+            r"BackendCompilerFailed",
+            "The compiler backend raised an exception.",
+            Some(
+                "Compile with backend=\"aot_eager\" to tell whether the backend or AOTAutograd is \
+                  at fault, and check the backend's own logs.",
+            ),
+            None,
+        ),
+    ]
+}
+
+// What a graph break means, and how to avoid it
+#[derive(Debug, Serialize)]
+pub struct GraphBreakAdvice {
+    pub explanation: &'static str,
+    pub remediation: &'static str,
+}
+
+// Matched against restart reasons in order, so more specific patterns come first
+static GRAPH_BREAK_ADVICE: Lazy<Vec<(Regex, GraphBreakAdvice)>> = Lazy::new(|| {
+    let advice = |pattern: &str, explanation, remediation| {
+        (
+            Regex::new(pattern).unwrap(),
+            GraphBreakAdvice {
+                explanation,
+                remediation,
+            },
+        )
+    };
+    vec![
+        advice(
+            r"skip function graph_break",
+            "The code called torch._dynamo.graph_break() explicitly.",
+            "Remove the call if the graph break is not intended.",
+        ),
+        advice(
+            r"Tensor\.item|data dependent operator",
+            "A tensor was converted to a Python scalar (e.g., with .item()), which depends on its \
+             data.",
+            "Keep the value as a tensor, or set torch._dynamo.config.capture_scalar_outputs = True.",
+        ),
+        advice(
+            r"(?i)tolist|Tensor\.numpy",
+            "A tensor was converted to Python or NumPy data.",
+            "Keep the data as a tensor inside the compiled region.",
+        ),
+        advice(
+            r"generic_jump|data-dependent (jump|branching)|Dynamic control flow",
+            "An if or while depends on the value of a tensor.",
+            "Use torch.cond for data-dependent branches, or torch.where to select elementwise.",
+        ),
+        advice(
+            r"(?i)builtin print|\bprint\b",
+            "Printing happens as a side effect, which can't be part of a graph.",
+            "Remove the print, or move it out of the compiled region.",
+        ),
+        advice(
+            r"(?i)unsupported builtin|builtin: \w+ \[",
+            "A Python builtin was called in a way Dynamo can't trace.",
+            "Move the call out of the compiled region, or rewrite it with torch operations.",
+        ),
+        advice(
+            r"skip function|skipfiles|inline in skipfiles",
+            "Dynamo does not trace into the called function, e.g., because it is in a library it \
+             skips.",
+            "Move the call out of the compiled region, or wrap the function with \
+             torch._dynamo.allow_in_graph if it is safe to treat as a single op.",
+        ),
+    ]
+});
+
+pub fn graph_break_advice(reason: &str) -> Option<&'static GraphBreakAdvice> {
+    GRAPH_BREAK_ADVICE
+        .iter()
+        .find(|(re, _)| re.is_match(reason))
+        .map(|(_, advice)| advice)
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestartReasonContext {
+    pub reason: String,
+    pub advice: Option<&'static GraphBreakAdvice>,
+}
+
+// Rules from the config first, then the built in ones; the first matching rule wins
+pub struct KnownIssues {
+    rules: Vec<(Regex, KnownIssue)>,
+}
+
+impl KnownIssues {
+    pub fn new(custom: &[KnownIssue]) -> anyhow::Result<Self> {
+        let rules = custom
+            .iter()
+            .cloned()
+            .chain(default_known_issues())
+            .map(|issue| {
+                let re = Regex::new(&issue.pattern).map_err(|e| {
+                    anyhow::anyhow!("Invalid known issue pattern {}: {e}", issue.pattern)
+                })?;
+                Ok((re, issue))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(KnownIssues { rules })
+    }
+
+    pub fn find(&self, reason: &str) -> Option<&KnownIssue> {
+        self.rules
+            .iter()
+            .find(|(re, _)| re.is_match(reason))
+            .map(|(_, issue)| issue)
+    }
+
+    // A cell for the failures table
+    pub fn html_cell(&self, reason: &str) -> String {
+        let Some(issue) = self.find(reason) else {
+            return "<td></td>".to_string();
+        };
+        let mut cell = format!("<td>{}", encode_text(&issue.explanation));
+        if let Some(suggestion) = &issue.suggestion {
+            write!(
+                cell,
+                "<br><strong>Try:</strong> {}",
+                encode_text(suggestion)
+            )
+            .unwrap();
+        }
+        if let Some(link) = &issue.link {
+            write!(
+                cell,
+                " <a href=\"{}\">[more]</a>",
+                html_escape::encode_double_quoted_attribute(link)
+            )
+            .unwrap();
+        }
+        cell.push_str("</td>");
+        cell
+    }
+}
+
+// Rewrites link URLs matching pattern, e.g., internal hosts to a proxy that can be reached from
+// outside.  The replacement can refer to capture groups as $1 or ${name}
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkRewrite {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+static HTML_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(?<attr>href|src)\s*=\s*(?:"(?<dq>[^"]*)"|'(?<sq>[^']*)')"#).unwrap()
+});
+
+// Every rewrite is applied in turn
+pub struct LinkRewriter {
+    rules: Vec<(Regex, String)>,
+}
+
+impl LinkRewriter {
+    pub fn new(rewrites: &[LinkRewrite]) -> anyhow::Result<Self> {
+        let rules = rewrites
+            .iter()
+            .map(|r| {
+                let re = Regex::new(&r.pattern).map_err(|e| {
+                    anyhow::anyhow!("Invalid link rewrite pattern {}: {e}", r.pattern)
+                })?;
+                Ok((re, r.replacement.clone()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(LinkRewriter { rules })
+    }
+
+    pub fn rewrite(&self, url: &str) -> String {
+        self.rules
+            .iter()
+            .fold(url.to_string(), |url, (re, replacement)| {
+                re.replace_all(&url, replacement.as_str()).into_owned()
+            })
+    }
+
+    // The href and src attributes of some HTML
+    pub fn rewrite_html(&self, html: &str) -> String {
+        if self.rules.is_empty() {
+            return html.to_string();
+        }
+        HTML_LINK_RE
+            .replace_all(html, |caps: &regex::Captures| {
+                let attr = &caps["attr"];
+                match (caps.name("dq"), caps.name("sq")) {
+                    (Some(url), _) => format!("{attr}=\"{}\"", self.rewrite(url.as_str())),
+                    (_, Some(url)) => format!("{attr}='{}'", self.rewrite(url.as_str())),
+                    _ => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+// Lines of the raw log shown around each failing entry on raw.html
+pub const RAW_CONTEXT_LINES: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub struct RawContextRange {
+    pub start: usize,
+    pub end: usize,
+    pub lines: Vec<RawLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RawLine {
+    pub number: usize,
+    pub text: String,
+    // The entry of a failure or restart, rather than context
+    pub failing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RawContextContext {
+    pub css: &'static str,
+    pub ranges: Vec<RawContextRange>,
+    pub qps: &'static str,
+}
+
+// The lines of raw_log within RAW_CONTEXT_LINES of any of the given lines, as ranges with
+// overlapping ones merged
+pub fn raw_context_ranges(raw_log: &str, lines: &[usize]) -> Vec<RawContextRange> {
+    let mut bounds: Vec<(usize, usize)> = lines
+        .iter()
+        .map(|l| {
+            (
+                l.saturating_sub(RAW_CONTEXT_LINES).max(1),
+                l + RAW_CONTEXT_LINES,
+            )
+        })
+        .collect();
+    bounds.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in bounds {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let lines: Vec<RawLine> = raw_log
+                .lines()
+                .enumerate()
+                .skip(start - 1)
+                .take(end + 1 - start)
+                .map(|(i, l)| RawLine {
+                    number: i + 1,
+                    text: l.to_string(),
+                    failing: lines.contains(&(i + 1)),
+                })
+                .collect();
+            let end = lines.last()?.number;
+            Some(RawContextRange { start, end, lines })
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrintContext {
+    pub css: &'static str,
+    pub failures_css: &'static str,
+    pub custom_header_html: String,
+    pub num_compilations: usize,
+    pub num_failed: usize,
+    pub num_restarted: usize,
+    pub total_compile_time_s: f64,
+    pub num_breaks: usize,
+    pub num_specializations: usize,
+    pub num_recompile_thrashing: usize,
+    pub restart_time_s: f64,
+    pub stack_trie_html: String,
+    pub failures: Vec<(String, String)>,
+    pub metrics: Vec<PrintMetricsRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportIndexContext {
+    pub css: &'static str,
+    pub javascript: &'static str,
+    pub directory: Vec<(String, Vec<OutputFile>)>,
+    pub failures: Vec<ExportFailure>,
+    pub custom_header_html: String,
+    pub num_failures: usize,
+    pub success: bool,
+    pub exported_program_url: String,
+    pub qps: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolicShapeSpecializationContext {
+    pub symbol: String,
+    pub sources: Vec<String>,
+    pub value: String,
+    pub guards: Vec<usize>,
+    pub user_stack_html: String,
+    pub stack_html: String,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct SymbolicShapeSpecializationSummary {
+    pub sources: Vec<String>,
+    pub value: String,
+    pub symbols: Vec<String>,
+    pub count: usize,
+    pub compile_ids: Vec<CompileIdLink>,
+}
+
+// A guard that failed on more than one recompile of the same frame, e.g., because an input's
+// size keeps alternating and the frame's cache ping-pongs between the entries
+#[derive(Debug, Serialize)]
+pub struct RecompileThrashing {
+    pub frame: String,
+    pub guard: String,
+    pub sources: Vec<String>,
+    pub compile_ids: Vec<CompileIdLink>,
+    pub reasons: Vec<String>,
+}
+
+// The failed guard a recompile reason is about, without the values that were observed, so that
+// the same guard failing with different values is recognized
+fn recompile_reason_guard(reason: &str) -> &str {
+    reason
+        .split_once(". expected ")
+        .map_or(reason, |(guard, _)| guard)
+}
+
+static MISMATCH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"^tensor '(?P<source>.+)' (?P<what>[\w ]+?) mismatch(?: at index (?P<index>\d+))?",
+        r"\. expected (?P<old>.+), actual (?P<new>.+)$"
+    ))
+    .unwrap()
+});
+
+// What changed to trigger a recompile, e.g. "L['x'].size()[0] changed 8 → 16" for the reason
+// "tensor 'L['x']' size mismatch at index 0. expected 8, actual 16"
+pub fn recompile_reason_change(reason: &str) -> Option<String> {
+    let caps = MISMATCH_RE.captures(reason)?;
+    let (source, what) = (&caps["source"], &caps["what"]);
+    let (old, new) = (&caps["old"], &caps["new"]);
+    Some(match caps.name("index").map(|m| m.as_str()) {
+        Some(index) if what == "size" || what == "stride" => {
+            format!("{source}.{what}()[{index}] changed {old} → {new}")
+        }
+        Some(index) => format!("{source} {what} at index {index} changed {old} → {new}"),
+        None => format!("{source} {what} changed {old} → {new}"),
+    })
+}
+
+// The guard that refers to a source expression in the recompile reason; tensor mismatches come
+// from the tensor match guard, so that is preferred over e.g. hasattr guards on the same source
+pub fn recompile_reason_guard_index(guards: &[Rc<DynamoGuard>], reason: &str) -> Option<usize> {
+    let sources: Vec<&str> = SOURCE_RE.find_iter(reason).map(|m| m.as_str()).collect();
+    let mentions = |g: &Rc<DynamoGuard>| sources.iter().any(|s| mentions_source(&g.code, s));
+    reason
+        .starts_with("tensor '")
+        .then(|| {
+            guards
+                .iter()
+                .position(|g| g.code.starts_with("check_tensor(") && mentions(g))
+        })
+        .flatten()
+        .or_else(|| guards.iter().position(mentions))
+}
+
+// The compilations of one nn.Module's forward.  Module class names aren't logged, so a module is
+// known by where its forward is defined
+#[derive(Debug, Serialize)]
+pub struct ModuleCompiles {
+    pub module: String,
+    pub functions: Vec<String>,
+    pub num_compiles: usize,
+    pub num_failed: usize,
+    pub compile_time_s: f64,
+    pub compile_ids: Vec<CompileIdLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleCompilesContext {
+    pub css: &'static str,
+    pub modules: Vec<ModuleCompiles>,
+    pub qps: &'static str,
+}
+
+// A custom op that dynamo found to be non-compliant (e.g., without a fake implementation), with
+// every frame that used it
+#[derive(Debug, Serialize)]
+pub struct NonCompliantOp {
+    pub op: String,
+    pub frames: Vec<FrameLink>,
+    pub num_graphs: usize,
+    pub compile_ids: Vec<CompileIdLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameLink {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NonCompliantOpsContext {
+    pub css: &'static str,
+    pub ops: Vec<NonCompliantOp>,
+    pub qps: &'static str,
+}
+
+// The non-compliant ops of every compilation in the run, the ones in the most graphs first.  A
+// compile id counts once however many attempts it took
+pub fn non_compliant_ops(metrics_index: &CompilationMetricsIndex) -> Vec<NonCompliantOp> {
+    let mut ops: FxIndexMap<&str, NonCompliantOp> = FxIndexMap::default();
+    for (cid, metrics) in metrics_index {
+        let used: FxIndexSet<&str> = metrics
+            .iter()
+            .flat_map(|m| m.non_compliant_ops.iter().flatten())
+            .map(|op| op.as_str())
+            .collect();
+        let frame = cid.as_ref().map(|c| {
+            let name = metrics.iter().find_map(|m| {
+                let (name, filename) = (m.co_name.as_ref()?, m.co_filename.as_ref()?);
+                Some(format!(
+                    "{name} ({}:{})",
+                    simplify_filename(filename),
+                    m.co_firstlineno.unwrap_or_default()
+                ))
+            });
+            let frame_id = CompileId {
+                frame_compile_id: None,
+                attempt: None,
+                ..c.clone()
+            };
+            FrameLink {
+                label: name.unwrap_or_else(|| frame_id.to_string()),
+                url: frame_page_url(c),
+            }
+        });
+        for op in used {
+            let entry = ops.entry(op).or_insert_with(|| NonCompliantOp {
+                op: op.to_string(),
+                frames: Vec::new(),
+                num_graphs: 0,
+                compile_ids: Vec::new(),
+            });
+            entry.num_graphs += 1;
+            entry.compile_ids.push(CompileIdLink::new(cid.as_ref()));
+            if let Some(frame) = &frame {
+                if !entry.frames.contains(frame) {
+                    entry.frames.push(frame.clone());
+                }
+            }
+        }
+    }
+    let mut ops: Vec<NonCompliantOp> = ops.into_values().collect();
+    ops.sort_by(|a, b| {
+        b.num_graphs
+            .cmp(&a.num_graphs)
+            .then_with(|| a.op.cmp(&b.op))
+    });
+    ops
+}
+
+// Dynamo names the function that resumes a frame after a graph break after the original, e.g.,
+// torch_dynamo_resume_in_forward_at_120
+fn is_forward(name: &str) -> bool {
+    name == "forward" || name.starts_with("torch_dynamo_resume_in_forward_at_")
+}
+
+// Where the forward of the module a compilation belongs to is defined: the compiled frame itself
+// if it's a forward, or else the innermost forward on the stack that led to it.  A line in a
+// forward is attributed to the closest forward compiled from the same file that is defined before
+// it (if any)
+fn owning_module(
+    m: &CompilationMetricsMetadata,
+    stack: Option<&StackSummary>,
+    forwards: &FxHashMap<String, BTreeSet<i32>>,
+) -> Option<String> {
+    let (filename, line) = match (&m.co_name, &m.co_filename, m.co_firstlineno) {
+        (Some(name), Some(filename), Some(line)) if name == "forward" => {
+            return Some(format!("{}:{line}", simplify_filename(filename)));
+        }
+        (Some(name), Some(filename), Some(line)) if is_forward(name) => (filename.clone(), line),
+        _ => {
+            let frame = stack?.iter().rev().find(|f| is_forward(&f.name))?;
+            let filename = frame
+                .uninterned_filename
+                .clone()
+                .unwrap_or_else(|| unintern_str(frame.filename));
+            (filename, frame.line)
+        }
+    };
+    let filename = simplify_filename(&filename);
+    let def_line = forwards
+        .get(filename)
+        .and_then(|lines| lines.range(..=line).next_back())
+        .copied()
+        .unwrap_or(line);
+    Some(format!("{filename}:{def_line}"))
+}
+
+// Compilations grouped by the nn.Module they belong to, most compile time first
+pub fn compiles_by_module(
+    metrics_index: &CompilationMetricsIndex,
+    stack_index: &StackIndex,
+) -> Vec<ModuleCompiles> {
+    let mut forwards: FxHashMap<String, BTreeSet<i32>> = FxHashMap::default();
+    for m in metrics_index.values().flatten() {
+        if let (Some("forward"), Some(filename), Some(line)) =
+            (m.co_name.as_deref(), &m.co_filename, m.co_firstlineno)
+        {
+            forwards
+                .entry(simplify_filename(filename).to_string())
+                .or_default()
+                .insert(line);
+        }
+    }
+    let mut modules: FxIndexMap<String, ModuleCompiles> = FxIndexMap::default();
+    for (cid, metrics) in metrics_index {
+        let (Some(cid), Some(first)) = (cid, metrics.first()) else {
+            continue;
+        };
+        let mut stack_cid = cid.clone();
+        if stack_cid.frame_compile_id.is_some() {
+            stack_cid.attempt = Some(0);
+        }
+        let stack = stack_index.get(&Some(stack_cid));
+        let Some(module) = owning_module(first, stack, &forwards) else {
+            continue;
+        };
+        let compiles = modules
+            .entry(module.clone())
+            .or_insert_with(|| ModuleCompiles {
+                module,
+                functions: Vec::new(),
+                num_compiles: 0,
+                num_failed: 0,
+                compile_time_s: 0.0,
+                compile_ids: Vec::new(),
+            });
+        compiles.num_compiles += 1;
+        if metrics.iter().any(|m| m.fail_type.is_some()) {
+            compiles.num_failed += 1;
+        }
+        compiles.compile_time_s += metrics
+            .iter()
+            .filter_map(|m| m.entire_frame_compile_time_s)
+            .sum::<f64>();
+        if let Some(name) = &first.co_name {
+            if !compiles.functions.contains(name) {
+                compiles.functions.push(name.clone());
+            }
+        }
+        compiles.compile_ids.push(CompileIdLink::new(Some(cid)));
+    }
+    let mut modules: Vec<ModuleCompiles> = modules.into_values().collect();
+    modules.sort_by(|a, b| b.compile_time_s.total_cmp(&a.compile_time_s));
+    modules
+}
+
+pub fn recompile_thrashing(index: &RecompileReasonsIndex) -> Vec<RecompileThrashing> {
+    let mut guards: FxIndexMap<(Option<CompileId>, &str), RecompileThrashing> =
+        FxIndexMap::default();
+    for (compile_id, reasons) in index {
+        let frame = compile_id.as_ref().map(|c| CompileId {
+            frame_compile_id: None,
+            attempt: None,
+            ..c.clone()
+        });
+        let cid = CompileIdLink::new(compile_id.as_ref());
+        for reason in reasons {
+            let guard = recompile_reason_guard(reason);
+            let t = guards
+                .entry((frame.clone(), guard))
+                .or_insert_with(|| RecompileThrashing {
+                    frame: frame
+                        .as_ref()
+                        .map_or("(unknown)".to_string(), |f| f.to_string()),
+                    guard: guard.to_string(),
+                    sources: SOURCE_RE
+                        .find_iter(guard)
+                        .map(|m| m.as_str().to_string())
+                        .collect::<FxIndexSet<_>>()
+                        .into_iter()
+                        .collect(),
+                    compile_ids: Vec::new(),
+                    reasons: Vec::new(),
+                });
+            if !t.compile_ids.contains(&cid) {
+                t.compile_ids.push(cid.clone());
+            }
+            t.reasons.push(reason.to_string());
+        }
+    }
+    guards
+        .into_values()
+        .filter(|t| t.compile_ids.len() > 1)
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecompileThrashingContext {
+    pub css: &'static str,
+    pub thrashing: Vec<RecompileThrashing>,
+    pub qps: &'static str,
+}
+
+// A symbolic shape specialization in one compile id whose guard then failed, causing the next
+// compile id of the same frame
+#[derive(Debug, Serialize)]
+pub struct SpecializationRecompile {
+    pub specialized_in: String,
+    pub specialized_url: String,
+    pub symbol: String,
+    pub source: String,
+    pub value: String,
+    pub recompiled_as: String,
+    pub recompiled_url: String,
+    // The value seen when the guard failed, when the recompile reason says
+    pub actual: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpecializationRecompileChain {
+    pub frame: String,
+    pub links: Vec<SpecializationRecompile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpecializationRecompilesContext {
+    pub css: &'static str,
+    pub chains: Vec<SpecializationRecompileChain>,
+    pub qps: &'static str,
+}
+
+// The frame history page a compile id belongs to, e.g. frame_6.html for [6/2]
+pub fn frame_page_url(cid: &CompileId) -> String {
+    let frame_id = cid.frame_id.map_or("-".to_string(), |v| v.to_string());
+    match cid.compiled_autograd_id {
+        Some(ca) => format!("frame_{ca}_{frame_id}.html"),
+        None => format!("frame_{frame_id}.html"),
+    }
+}
+
+// Guards that are only in guards, and guards that are only in prior, in order
+pub fn guards_diff(
+    prior: &[Rc<DynamoGuard>],
+    guards: &[Rc<DynamoGuard>],
+) -> (Vec<String>, Vec<String>) {
+    let old: FxIndexSet<&str> = prior.iter().map(|g| g.code.as_str()).collect();
+    let new: FxIndexSet<&str> = guards.iter().map(|g| g.code.as_str()).collect();
+    (
+        new.difference(&old).map(|g| g.to_string()).collect(),
+        old.difference(&new).map(|g| g.to_string()).collect(),
+    )
+}
+
+// One compile id or attempt on a frame history page
+#[derive(Debug, Serialize)]
+pub struct FrameCompileRow {
+    pub compile_id: String,
+    pub url: String,
+    pub status_class: &'static str,
+    pub status: &'static str,
+    pub compile_time_s: Option<f64>,
+    pub guard_count: Option<u64>,
+    pub shape_env_guard_count: Option<u64>,
+    // What changed since the previous compilation, from the recompile reasons
+    pub changes: Vec<String>,
+    pub guards_url: String,
+    pub prior_compile_id: String,
+    pub prior_guards_url: String,
+    pub num_guards_added: usize,
+    pub num_guards_removed: usize,
+    pub guards_added: Vec<String>,
+    pub guards_removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameHistoryContext {
+    pub css: &'static str,
+    pub frame: String,
+    pub co_name: String,
+    pub rows: Vec<FrameCompileRow>,
+    pub qps: &'static str,
+}
+
+// Whether a recompile reason is the guard on a specialized source failing; returns the value that
+// was seen instead, if the reason includes it
+fn specialization_failed(reason: &str, source: &str, value: &str) -> Option<Option<String>> {
+    if let Some(caps) = MISMATCH_RE.captures(reason) {
+        let what = &caps["what"];
+        if let Some(index) = caps.name("index").map(|m| m.as_str()) {
+            let expr = format!("{}.{what}()[{index}]", &caps["source"]);
+            if (what == "size" || what == "stride") && expr == source && &caps["old"] == value {
+                return Some(Some(caps["new"].to_string()));
+            }
+        }
+        return None;
+    }
+    reason
+        .contains(&format!("{source} == {value}"))
+        .then_some(None)
+}
+
+// For every recompile, the specializations in the frame's previous compile id whose guards failed
+// to cause it, grouped by frame in log order
+pub fn specialization_recompiles(
+    specializations: &SymbolicShapeSpecializationIndex,
+    reasons: &RecompileReasonsIndex,
+) -> Vec<SpecializationRecompileChain> {
+    let mut chains: FxIndexMap<String, Vec<SpecializationRecompile>> = FxIndexMap::default();
+    for (compile_id, reasons) in reasons {
+        let Some(cid) = compile_id else { continue };
+        let Some(n) = cid.frame_compile_id.and_then(|n| n.checked_sub(1)) else {
+            continue;
+        };
+        // Latest attempt of the previous compilation that specialized anything
+        let Some((Some(prior), specs)) = specializations
+            .iter()
+            .filter(|(c, _)| {
+                c.as_ref().is_some_and(|c| {
+                    c.compiled_autograd_id == cid.compiled_autograd_id
+                        && c.frame_id == cid.frame_id
+                        && c.frame_compile_id == Some(n)
+                })
+            })
+            .max_by_key(|(c, _)| c.as_ref().and_then(|c| c.attempt))
+        else {
+            continue;
+        };
+        for reason in reasons {
+            for spec in specs {
+                let value = spec.value.clone().unwrap_or_default();
+                for source in spec.sources.iter().flatten() {
+                    let Some(actual) = specialization_failed(reason, source, &value) else {
+                        continue;
+                    };
+                    let frame = CompileId {
+                        frame_compile_id: None,
+                        attempt: None,
+                        ..cid.clone()
+                    };
+                    chains
+                        .entry(frame.to_string())
+                        .or_default()
+                        .push(SpecializationRecompile {
+                            specialized_in: prior.to_string(),
+                            specialized_url: format!("{}/index.html", prior.as_directory_name()),
+                            symbol: spec.symbol.clone().unwrap_or_default(),
+                            source: source.clone(),
+                            value: value.clone(),
+                            recompiled_as: cid.to_string(),
+                            recompiled_url: format!("{}/index.html", cid.as_directory_name()),
+                            actual,
+                            reason: reason.to_string(),
+                        });
+                }
+            }
+        }
+    }
+    chains
+        .into_iter()
+        .map(|(frame, links)| SpecializationRecompileChain { frame, links })
+        .collect()
+}
+
+// Drops chromium events that trace viewers can't use, and closes begin events that never ended
+// (e.g., because the process was killed) at the last timestamp in the log.  Returns the repaired
+// events and a description of every problem found.
+pub fn repair_chromium_events(events: Vec<Value>) -> (Vec<Value>, Vec<String>) {
+    let mut problems = Vec::new();
+    let mut repaired = Vec::new();
+    // Open begin events, per (pid, tid)
+    let mut open: FxIndexMap<(String, String), Vec<Value>> = FxIndexMap::default();
+    let mut last_ts: FxHashMap<(String, String), f64> = FxHashMap::default();
+    let mut max_ts = f64::NEG_INFINITY;
+    for event in events {
+        let name = event["name"].as_str().unwrap_or("(unnamed)").to_string();
+        let Some(ph) = event["ph"].as_str().map(str::to_string) else {
+            problems.push(format!("Dropped event {name} without a phase"));
+            continue;
+        };
+        let thread = (event["pid"].to_string(), event["tid"].to_string());
+        // Metadata events have no timestamp
+        if ph != "M" {
+            let Some(ts) = event["ts"].as_f64() else {
+                problems.push(format!("Dropped {ph} event {name} without a timestamp"));
+                continue;
+            };
+            // Only begin and end events have to be in order to nest; instant and complete events
+            // are routinely logged after the fact
+            if ph == "B" || ph == "E" {
+                let last = last_ts.entry(thread.clone()).or_insert(ts);
+                if ts < *last {
+                    problems.push(format!(
+                        "{ph} event {name} at {ts} is earlier than the previous event on its thread"
+                    ));
+                }
+                *last = last.max(ts);
+            }
+            max_ts = max_ts.max(ts);
+        }
+        match ph.as_str() {
+            "B" => open.entry(thread).or_default().push(event.clone()),
+            "E" => {
+                let stack = open.entry(thread).or_default();
+                // A named end event closes the innermost open begin event with that name
+                let matched = if event["name"].is_string() {
+                    stack.iter().rposition(|b| b["name"] == event["name"])
+                } else {
+                    stack.len().checked_sub(1)
+                };
+                let Some(matched) = matched else {
+                    problems.push(format!("Dropped end event {name} without a begin event"));
+                    continue;
+                };
+                // Trace viewers close the innermost begin event regardless of name, so begin
+                // events nested inside the matched one get ended here first
+                for begin in stack.split_off(matched + 1).into_iter().rev() {
+                    let inner = begin["name"].as_str().unwrap_or("(unnamed)");
+                    problems.push(format!(
+                        "Begin event {inner} never ended, added an end event before end event {name}"
+                    ));
+                    repaired.push(serde_json::json!({
+                        "name": begin["name"],
+                        "ts": event["ts"],
+                        "args": {},
+                        "ph": "E",
+                        "cat": begin["cat"],
+                        "tid": begin["tid"],
+                        "pid": begin["pid"],
+                    }));
+                }
+                stack.pop();
+            }
+            _ => {}
+        }
+        repaired.push(event);
+    }
+    for (_, stack) in open {
+        for begin in stack.into_iter().rev() {
+            let name = begin["name"].as_str().unwrap_or("(unnamed)");
+            problems.push(format!(
+                "Begin event {name} never ended, added an end event at the end of the log"
+            ));
+            repaired.push(serde_json::json!({
+                "name": begin["name"],
+                "ts": max_ts,
+                "args": {},
+                "ph": "E",
+                "cat": begin["cat"],
+                "tid": begin["tid"],
+                "pid": begin["pid"],
+            }));
+        }
+    }
+    (repaired, problems)
+}
+
+// Glog timestamps have no year or timezone, so they are kept as microseconds since the epoch as if
+// they were UTC times in 2000, and shifted when synthesizing the trace
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GlogTimes {
+    pub first: i64,
+    pub last: i64,
+    // Output files written for the entries of the compile id, and when they were logged
+    pub artifacts: Vec<(String, i64)>,
+}
+
+impl GlogTimes {
+    pub fn new(ts: i64) -> Self {
+        GlogTimes {
+            first: ts,
+            last: ts,
+            artifacts: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, ts: i64) {
+        self.first = self.first.min(ts);
+        self.last = self.last.max(ts);
+    }
+}
+
+pub fn waterfall(times: &GlogTimes) -> Vec<WaterfallRow> {
+    let total = (times.last - times.first).max(1) as f64;
+    let mut previous = times.first;
+    times
+        .artifacts
+        .iter()
+        .map(|(name, ts)| {
+            let row = WaterfallRow {
+                name: name.clone(),
+                since_start: format_duration((ts - times.first) as f64 / 1e6),
+                since_previous: format!("+{}", format_duration((ts - previous) as f64 / 1e6)),
+                left: format!("{:.1}", (previous - times.first) as f64 * 100.0 / total),
+                width: format!("{:.1}", (ts - previous) as f64 * 100.0 / total),
+            };
+            previous = *ts;
+            row
+        })
+        .collect()
+}
+
+// The glog prefix of a log line, e.g.,
+// "V1015 12:00:10.006000 4242 torch/_dynamo/convert_frame.py:1000] ", which is parsed by hand
+// since it is on every line, and a regex match was a good part of the time spent parsing
+pub struct GlogPrefix<'a> {
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub microsecond: u32,
+    pub pathname: &'a str,
+    pub line: &'a str,
+    // Byte offset in the line where the JSON after the prefix starts
+    pub payload_start: usize,
+}
+
+impl<'a> GlogPrefix<'a> {
+    pub fn parse(line: &'a str) -> Option<Self> {
+        // Usually at the start of the line, but the launcher may have added its own prefix, e.g.,
+        // [rank0]:
+        line.bytes()
+            .enumerate()
+            .filter(|(_, b)| b"VIWEC".contains(b))
+            .find_map(|(start, _)| Self::parse_at(line, start))
+    }
+
+    fn parse_at(line: &'a str, start: usize) -> Option<Self> {
+        let bytes = line.as_bytes();
+        let number = |from: usize, len: usize| -> Option<u32> {
+            let digits = bytes.get(from..from + len)?;
+            digits.iter().try_fold(0, |n, &b| {
+                b.is_ascii_digit().then(|| n * 10 + u32::from(b - b'0'))
+            })
+        };
+        let separator = |at: usize, expected: u8| bytes.get(at) == Some(&expected);
+        // Level, then MMDD hh:mm:ss, and any one character before the microseconds
+        if !(separator(start + 5, b' ')
+            && separator(start + 8, b':')
+            && separator(start + 11, b':'))
+        {
+            return None;
+        }
+        let month = number(start + 1, 2)?;
+        let day = number(start + 3, 2)?;
+        let hour = number(start + 6, 2)?;
+        let minute = number(start + 9, 2)?;
+        let second = number(start + 12, 2)?;
+        let micros_start = start + 14 + line.get(start + 14..)?.chars().next()?.len_utf8();
+        let microsecond = number(micros_start, 6)?;
+        if !separator(micros_start + 6, b' ') {
+            return None;
+        }
+        // Thread id, then the path up to the first colon, which is everything else before it
+        let thread_start = micros_start + 7;
+        let rest = &line[thread_start..];
+        let thread_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let colon = rest.find(':')?;
+        if thread_len == 0 || colon < 2 {
+            return None;
+        }
+        // The path gets the thread id's last digit if there is nothing else between it and the
+        // colon
+        let pathname = &rest[thread_len.min(colon - 1)..colon];
+        let after_colon = &rest[colon + 1..];
+        let line_len = after_colon.bytes().take_while(u8::is_ascii_digit).count();
+        if line_len == 0 || !after_colon[line_len..].starts_with("] ") {
+            return None;
+        }
+        let payload_start = thread_start + colon + 1 + line_len + 2;
+        if payload_start >= line.len() {
+            return None;
+        }
+        Some(GlogPrefix {
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            microsecond,
+            pathname,
+            line: &after_colon[..line_len],
+            payload_start,
+        })
+    }
+
+    pub fn micros(&self) -> Option<i64> {
+        glog_micros(
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.microsecond,
+        )
+    }
+}
+
+pub fn glog_micros(
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    micro: u32,
+) -> Option<i64> {
+    Some(
+        chrono::NaiveDate::from_ymd_opt(2000, month, day)?
+            .and_hms_micro_opt(hour, minute, second, micro)?
+            .and_utc()
+            .timestamp_micros(),
+    )
+}
+
+// Spans for each compile id (from its compilation metrics, or else from when its entries were
+// logged) and instants for each of its output files, so that logs without chromium events still
+// get a timeline.  clock_samples pair the glog time a compilation_metrics entry was logged at with
+// the time the compilation ended according to its metrics; they give the year and timezone that
+// glog timestamps are missing, up to the nearest 15 minutes.
+pub fn synthesize_chromium_events(
+    glog_times: &GlogTimesIndex,
+    metrics_index: &CompilationMetricsIndex,
+    clock_samples: &[(i64, f64)],
+) -> Vec<Value> {
+    const PID: u32 = 1;
+    const QUARTER_HOUR_US: i64 = 15 * 60 * 1_000_000;
+    let mut offsets: Vec<i64> = clock_samples
+        .iter()
+        .map(|(glog, end)| (end * 1e6) as i64 - glog)
+        .collect();
+    offsets.sort();
+    let offset = offsets.get(offsets.len() / 2).map_or(0, |o| {
+        (*o as f64 / QUARTER_HOUR_US as f64).round() as i64 * QUARTER_HOUR_US
+    });
+
+    let mut events = Vec::new();
+    for (compile_id, times) in glog_times {
+        let Some(cid) = compile_id else {
+            continue;
+        };
+        if events.is_empty() {
+            events.push(serde_json::json!({
+                "name": "process_name",
+                "ph": "M",
+                "pid": PID,
+                "args": {"name": "tlparse (synthesized from log timestamps)"},
+            }));
+        }
+        let tid = cid.frame_id.unwrap_or(0);
+        // Same data migration as metrics_index, for old logs that don't have attempt
+        let mut key = compile_id.clone();
+        if let Some(c) = key.as_mut() {
+            if c.frame_compile_id.is_some() {
+                c.attempt = Some(0);
+            }
+        }
+        let metrics = metrics_index.get(&key).and_then(|m| m.last());
+        let (ts, dur) = match metrics.and_then(|m| m.start_time.zip(m.entire_frame_compile_time_s))
+        {
+            Some((start, t)) => ((start * 1e6) as i64, (t * 1e6) as i64),
+            None => (times.first + offset, times.last - times.first),
+        };
+        events.push(serde_json::json!({
+            "name": format!("compile {cid}"),
+            "ts": ts,
+            "dur": dur.max(1),
+            "ph": "X",
+            "cat": "tlparse",
+            "pid": PID,
+            "tid": tid,
+        }));
+        for (name, ts) in &times.artifacts {
+            events.push(serde_json::json!({
+                "name": name,
+                "ts": ts + offset,
+                "ph": "i",
+                "s": "t",
+                "cat": "tlparse",
+                "pid": PID,
+                "tid": tid,
+            }));
+        }
+    }
+    events
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompileTimeBucket {
+    pub label: &'static str,
+    pub count: usize,
+    pub width: usize, // percent of the largest bucket
+}
+
+// Buckets of entire_frame_compile_time_s, so that a few very slow compilations stand out from
+// uniformly slow ones.  Buckets after the last nonempty one are left out.
+// The entire frame compile time of every frame compile, over all of its attempts, keyed by its
+// compile id in the stack trie (where the attempt is always 0)
+fn stack_compile_times(
+    metrics_index: &CompilationMetricsIndex,
+) -> FxHashMap<Option<CompileId>, f64> {
+    let mut times: FxHashMap<Option<CompileId>, f64> = FxHashMap::default();
+    for (cid, metrics) in metrics_index {
+        let mut cid = cid.clone();
+        if let Some(c) = cid.as_mut().filter(|c| c.attempt.is_some()) {
+            c.attempt = Some(0);
+        }
+        *times.entry(cid).or_default() += metrics
+            .iter()
+            .filter_map(|m| m.entire_frame_compile_time_s)
+            .sum::<f64>();
+    }
+    times
+}
+
+impl FrameSummary {
+    fn plain_filename(&self) -> String {
+        let filename = self
+            .uninterned_filename
+            .clone()
+            .unwrap_or_else(|| unintern_str(self.filename));
+        simplify_filename(&filename).to_string()
+    }
+}
+
+// The stack trie in the folded format of flamegraph.pl (one "outer;...;inner weight" line per
+// stack), weighted by the number of compilations or by the compile time in microseconds
+pub fn folded_stacks(
+    trie: &StackTrieNode,
+    metrics_index: &CompilationMetricsIndex,
+    by_time: bool,
+) -> String {
+    let times = stack_compile_times(metrics_index);
+    let mut weights: FxIndexMap<String, u64> = FxIndexMap::default();
+    for (stack, cid) in trie.stacks() {
+        let weight = if by_time {
+            (times.get(cid).copied().unwrap_or(0.0) * 1e6).round() as u64
+        } else {
+            1
+        };
+        let folded = stack
+            .iter()
+            .map(|frame| {
+                format!(
+                    "{}:{} in {}",
+                    frame.plain_filename(),
+                    frame.line,
+                    frame.name
+                )
+                .replace(';', ",")
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        *weights.entry(folded).or_default() += weight;
+    }
+    weights
+        .into_iter()
+        .filter(|(_, weight)| *weight > 0)
+        .map(|(folded, weight)| format!("{folded} {weight}\n"))
+        .collect()
+}
+
+// The stack trie as a speedscope (https://www.speedscope.app) profile, with one view weighted by
+// the number of compilations and another by compile time
+pub fn speedscope_profile(trie: &StackTrieNode, metrics_index: &CompilationMetricsIndex) -> Value {
+    let times = stack_compile_times(metrics_index);
+    let mut frames: FxIndexSet<&FrameSummary> = FxIndexSet::default();
+    let mut samples = Vec::new();
+    let mut counts = Vec::new();
+    let mut durations = Vec::new();
+    for (stack, cid) in trie.stacks() {
+        samples.push(
+            stack
+                .into_iter()
+                .map(|frame| frames.insert_full(frame).0)
+                .collect::<Vec<_>>(),
+        );
+        counts.push(1.0);
+        durations.push(times.get(cid).copied().unwrap_or(0.0));
+    }
+    let profile = |name: &str, unit: &str, weights: Vec<f64>| {
+        serde_json::json!({
+            "type": "sampled",
+            "name": name,
+            "unit": unit,
+            "startValue": 0,
+            "endValue": weights.iter().sum::<f64>(),
+            "samples": samples,
+            "weights": weights,
+        })
+    };
+    serde_json::json!({
+        "$schema": "https://www.speedscope.app/file-format-schema.json",
+        "name": "Compilation stacks",
+        "exporter": format!("tlparse {}", env!("CARGO_PKG_VERSION")),
+        "shared": {
+            "frames": frames
+                .iter()
+                .map(|frame| serde_json::json!({
+                    "name": frame.name,
+                    "file": frame.plain_filename(),
+                    "line": frame.line,
+                }))
+                .collect::<Vec<_>>(),
+        },
+        "profiles": [
+            profile("Compilations", "none", counts),
+            profile("Compile time", "seconds", durations),
+        ],
+    })
+}
+
+pub fn compile_time_histogram(times: impl Iterator<Item = f64>) -> Vec<CompileTimeBucket> {
+    const BUCKETS: [(f64, &str); 8] = [
+        (1.0, "< 1s"),
+        (5.0, "1s - 5s"),
+        (10.0, "5s - 10s"),
+        (30.0, "10s - 30s"),
+        (60.0, "30s - 1m"),
+        (300.0, "1m - 5m"),
+        (600.0, "5m - 10m"),
+        (f64::INFINITY, ">= 10m"),
+    ];
+    let mut counts = [0; BUCKETS.len()];
+    for t in times {
+        counts[BUCKETS.iter().position(|(upper, _)| t < *upper).unwrap()] += 1;
+    }
+    let Some(last) = counts.iter().rposition(|c| *c > 0) else {
+        return Vec::new();
+    };
+    let max = *counts.iter().max().unwrap();
+    BUCKETS[..=last]
+        .iter()
+        .zip(counts)
+        .map(|((_, label), count)| CompileTimeBucket {
+            label,
+            count,
+            width: count * 100 / max,
+        })
+        .collect()
+}
+
+// The top level index.html when parsing all ranks, merging what every rank compiled
+#[derive(Debug, Serialize)]
+pub struct RanksIndexContext {
+    pub css: &'static str,
+    pub num_ranks: usize,
+    pub ranks: Vec<RankContext>,
+    pub has_compile_divergence: bool,
+    pub num_compile_divergences: usize,
+    pub compile_ids: Vec<MergedCompileIdContext>,
+    pub failures: Vec<MergedFailureContext>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankContext {
+    pub name: String,
+    pub url: String,
+    pub num_entries: u64,
+    pub num_failed_entries: u64,
+}
+
+// A compile id of any rank, with how its compilation went on each rank that has it
+#[derive(Debug, Serialize)]
+pub struct MergedCompileIdContext {
+    pub compile_id: String,
+    pub ranks: Vec<MergedRankCompileContext>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergedRankCompileContext {
+    pub rank: String,
+    // The compile id's landing page in the rank's report
+    pub url: String,
+    pub status_class: &'static str,
+    pub status: &'static str,
+    pub num_artifacts: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergedFailureContext {
+    pub rank: String,
+    // The rank's failures_and_restarts.html
+    pub url: String,
+    pub compile_id: String,
+    pub kind: &'static str,
+    pub reason: String,
+}
+
+// tlparse_report.json, so that archived reports record what generated them and from what
+
+#[derive(Debug, Serialize)]
+pub struct ReportMetadata {
+    pub tlparse_version: &'static str,
+    pub parsed_at: String,
+    pub input_md5: String,
+    pub input_bytes: usize,
+    pub config: ReportConfig,
+    pub stats: Stats,
+}
+
+// The ParseConfig the report was generated with, with defaults filled in
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportConfig {
+    pub strict: bool,
+    pub strict_compile_id: bool,
+    pub custom_parsers: Vec<&'static str>,
+    pub custom_header_html: String,
+    pub verbose: bool,
+    pub plain_text: bool,
+    pub render_budget_s: f64,
+    pub export: bool,
+    pub max_inline_compile_ids: Option<usize>,
+    pub output_budget: Option<u64>,
+    pub source_url_template: Option<String>,
+    pub known_issues: Vec<KnownIssue>,
+    pub recompile_storm_threshold: usize,
+    pub recompile_storm_window_s: f64,
+    pub baseline: bool,
+    pub regression_threshold_pct: f64,
+    pub hide_abandoned_attempts: bool,
+    pub metrics_columns: Vec<String>,
+    pub no_verify_payload: bool,
+    pub verify_payload_min_bytes: Option<usize>,
+    pub json: bool,
+    pub max_compiles: Option<usize>,
+    pub all_ranks: bool,
+    pub two_pass: bool,
+    pub hide_frames: Vec<String>,
+    pub max_stack_depth: Option<usize>,
+    pub stack_trie_times: bool,
+    pub link_rewrites: Vec<LinkRewrite>,
+}
+
+// The compile time and graph count of every frame of a run, e.g., recorded by CI on a known good
+// run, for later runs to be checked against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub frames: Vec<BaselineFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineFrame {
+    // As its compile ids show it, e.g., [0/-]
+    pub frame: String,
+    pub co_name: Option<String>,
+    // Summed over every compilation of the frame
+    pub compile_time_s: f64,
+    // Compilations of the frame that produced a graph
+    pub graph_count: u64,
+}
+
+// A frame that compiled slower, or into more graphs, than in the baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct Regression {
+    pub frame: String,
+    pub co_name: Option<String>,
+    pub metric: &'static str,
+    pub baseline: String,
+    pub current: String,
+    pub increase_pct: String,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame {}", self.frame)?;
+        if let Some(co_name) = &self.co_name {
+            write!(f, " ({co_name})")?;
+        }
+        write!(
+            f,
+            ": {} {} -> {} (+{}%)",
+            self.metric, self.baseline, self.current, self.increase_pct
+        )
+    }
+}
+
+impl Baseline {
+    pub fn new(metrics_index: &CompilationMetricsIndex) -> Self {
+        let mut frames: FxIndexMap<CompileId, BaselineFrame> = FxIndexMap::default();
+        for (cid, metrics) in metrics_index {
+            let Some(cid) = cid else {
+                continue;
+            };
+            let frame = CompileId {
+                frame_compile_id: None,
+                attempt: None,
+                ..cid.clone()
+            };
+            let entry = frames.entry(frame.clone()).or_insert(BaselineFrame {
+                frame: frame.to_string(),
+                co_name: None,
+                compile_time_s: 0.0,
+                graph_count: 0,
+            });
+            for m in metrics {
+                if m.co_name.is_some() {
+                    entry.co_name = m.co_name.clone();
+                }
+                entry.compile_time_s += m.entire_frame_compile_time_s.unwrap_or(0.0);
+                if m.fail_type.is_none() && m.graph_op_count.is_some_and(|n| n > 0) {
+                    entry.graph_count += 1;
+                }
+            }
+        }
+        Baseline {
+            frames: frames.into_values().collect(),
+        }
+    }
+
+    // Frames of current that grew by more than threshold_pct percent over this baseline.  Frames
+    // that aren't in the baseline, or had nothing to grow from, are left out
+    pub fn regressions(&self, current: &Baseline, threshold_pct: f64) -> Vec<Regression> {
+        let baseline: FxHashMap<&str, &BaselineFrame> =
+            self.frames.iter().map(|f| (f.frame.as_str(), f)).collect();
+        let increase_pct = |old: f64, new: f64| (new - old) / old * 100.0;
+        let mut regressions = Vec::new();
+        for new in &current.frames {
+            let Some(old) = baseline.get(new.frame.as_str()) else {
+                continue;
+            };
+            let mut add = |metric, baseline, current, pct: f64| {
+                regressions.push(Regression {
+                    frame: new.frame.clone(),
+                    co_name: new.co_name.clone(),
+                    metric,
+                    baseline,
+                    current,
+                    increase_pct: format!("{pct:.0}"),
+                })
+            };
+            if old.compile_time_s > 0.0 {
+                let pct = increase_pct(old.compile_time_s, new.compile_time_s);
+                if pct > threshold_pct {
+                    add(
+                        "compile time",
+                        format!("{:.3}s", old.compile_time_s),
+                        format!("{:.3}s", new.compile_time_s),
+                        pct,
+                    );
+                }
+            }
+            if old.graph_count > 0 {
+                let pct = increase_pct(old.graph_count as f64, new.graph_count as f64);
+                if pct > threshold_pct {
+                    add(
+                        "graph count",
+                        old.graph_count.to_string(),
+                        new.graph_count.to_string(),
+                        pct,
+                    );
+                }
+            }
+        }
+        regressions
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecompileStorm {
+    pub frame: String,
+    pub num_recompiles: usize,
+    pub window_start: String,
+    pub window_end: String,
+}
+
+fn format_glog_micros(ts: i64) -> String {
+    chrono::DateTime::from_timestamp_micros(ts).map_or(ts.to_string(), |t| {
+        t.format("%m-%d %H:%M:%S%.3f").to_string()
+    })
+}
+
+// Frames that recompiled more than threshold times within window_s seconds, going by when the
+// entries of each recompile were first logged.  For each frame, the window with the most
+// recompiles is reported.
+pub fn recompile_storms(
+    glog_times: &GlogTimesIndex,
+    threshold: usize,
+    window_s: f64,
+) -> Vec<RecompileStorm> {
+    let window_us = (window_s * 1e6) as i64;
+    let mut frames: FxIndexMap<CompileId, Vec<i64>> = FxIndexMap::default();
+    for (cid, times) in glog_times {
+        let Some(cid) = cid else { continue };
+        // Restarts are retries of the same compilation, not recompiles
+        if cid.frame_compile_id.unwrap_or(0) == 0 || cid.attempt.unwrap_or(0) != 0 {
+            continue;
+        }
+        let frame = CompileId {
+            frame_compile_id: None,
+            attempt: None,
+            ..cid.clone()
+        };
+        frames.entry(frame).or_default().push(times.first);
+    }
+    frames
+        .into_iter()
+        .filter_map(|(frame, mut times)| {
+            times.sort();
+            let mut best = (0, 0);
+            let mut start = 0;
+            for end in 0..times.len() {
+                while times[end] - times[start] > window_us {
+                    start += 1;
+                }
+                if end - start > best.1 - best.0 {
+                    best = (start, end);
+                }
+            }
+            let num_recompiles = best.1 - best.0 + 1;
+            (num_recompiles > threshold).then(|| RecompileStorm {
+                frame: frame.to_string(),
+                num_recompiles,
+                window_start: format_glog_micros(times[best.0]),
+                window_end: format_glog_micros(times[best.1]),
+            })
+        })
+        .collect()
+}
+
+// Guard construction is considered to dominate a frame once it takes at least this fraction of
+// the frame's compile time
+pub const GUARD_CONSTRUCTION_DOMINATES: f64 = 0.5;
+
+#[derive(Debug, Serialize)]
+pub struct GuardConstructionRow {
+    pub frame: String,
+    pub guards_added: u64,
+    pub guards_suppressed: u64,
+    pub build_guards_time_s: f64,
+    pub compile_time_s: f64,
+    pub percent_of_compile_time: String,
+    pub dominates: bool,
+}
+
+// Per frame totals of guards added vs. suppressed as duplicates, and of the time spent
+// building guards.  Empty unless torch logged guard deduplication info for some compilation.
+pub fn guard_construction_summary(
+    metrics_index: &CompilationMetricsIndex,
+) -> Vec<GuardConstructionRow> {
+    if !metrics_index
+        .values()
+        .flatten()
+        .any(|m| m.duplicate_guards_suppressed.is_some() || m.build_guards_time_s.is_some())
+    {
+        return Vec::new();
+    }
+    let mut frames: FxIndexMap<String, GuardConstructionRow> = FxIndexMap::default();
+    for (cid, metrics) in metrics_index {
+        let frame = cid.as_ref().map_or("(unknown)".to_string(), |c| {
+            CompileId {
+                frame_compile_id: None,
+                attempt: None,
+                ..c.clone()
+            }
+            .to_string()
+        });
+        let row = frames
+            .entry(frame.clone())
+            .or_insert_with(|| GuardConstructionRow {
+                frame,
+                guards_added: 0,
+                guards_suppressed: 0,
+                build_guards_time_s: 0.0,
+                compile_time_s: 0.0,
+                percent_of_compile_time: String::new(),
+                dominates: false,
+            });
+        for m in metrics {
+            row.guards_added += m.guard_count.unwrap_or(0);
+            row.guards_suppressed += m.duplicate_guards_suppressed.unwrap_or(0);
+            row.build_guards_time_s += m.build_guards_time_s.unwrap_or(0.0);
+            row.compile_time_s += m.entire_frame_compile_time_s.unwrap_or(0.0);
+        }
+    }
+    frames
+        .into_values()
+        .map(|mut row| {
+            if row.compile_time_s > 0.0 {
+                let fraction = row.build_guards_time_s / row.compile_time_s;
+                row.percent_of_compile_time = format!("{:.0}%", fraction * 100.0);
+                row.dominates = fraction >= GUARD_CONSTRUCTION_DOMINATES;
+            }
+            row
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GraphNodeCounts {
+    // call_function, call_method and call_module nodes, which is what graph_op_count counts
+    pub ops: u64,
+    pub nodes: u64,
+}
+
+// A compile id whose metrics disagree with the dynamo_output_graph that was logged for it
+#[derive(Debug, Serialize)]
+pub struct GraphCountMismatch {
+    pub compile_id: String,
+    pub url: String,
+    pub metrics_op_count: Option<u64>,
+    pub metrics_node_count: Option<u64>,
+    pub op_count: u64,
+    pub node_count: u64,
+}
+
+pub fn graph_count_mismatches(
+    metrics_index: &CompilationMetricsIndex,
+    graph_node_counts_index: &GraphNodeCountsIndex,
+) -> Vec<GraphCountMismatch> {
+    metrics_index
+        .iter()
+        .filter_map(|(cid, metrics)| {
+            let m = metrics.last()?;
+            // The graph of a restarted compilation is from an attempt the metrics don't describe
+            if m.restart_reasons.as_ref().is_some_and(|r| !r.is_empty()) {
+                return None;
+            }
+            let counts = graph_node_counts_index.get(cid)?;
+            let differs = |metric: Option<u64>, count| metric.is_some_and(|m| m != count);
+            if !differs(m.graph_op_count, counts.ops) && !differs(m.graph_node_count, counts.nodes)
+            {
+                return None;
+            }
+            Some(GraphCountMismatch {
+                compile_id: cid
+                    .as_ref()
+                    .map_or("(unknown)".to_string(), |c| c.to_string()),
+                url: cid.as_ref().map_or("".to_string(), |c| {
+                    format!("{}/index.html", c.as_directory_name())
+                }),
+                metrics_op_count: m.graph_op_count,
+                metrics_node_count: m.graph_node_count,
+                op_count: counts.ops,
+                node_count: counts.nodes,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestartTimeRow {
+    pub frame: String,
+    pub co_name: String,
+    pub restart_time_s: f64,
+    pub num_restarted: usize,
+}
+
+// Total time Dynamo spent on analysis that was thrown away by a restart, and the frames that
+// wasted the most of it
+pub fn restart_time_waste(
+    metrics_index: &CompilationMetricsIndex,
+    limit: usize,
+) -> (f64, Vec<RestartTimeRow>) {
+    let mut frames: FxIndexMap<String, RestartTimeRow> = FxIndexMap::default();
+    for (cid, metrics) in metrics_index {
+        for m in metrics {
+            let t = m.dynamo_time_before_restart_s.unwrap_or(0.0);
+            if t <= 0.0 {
+                continue;
+            }
+            let frame = cid.as_ref().map_or("(unknown)".to_string(), |c| {
+                CompileId {
+                    frame_compile_id: None,
+                    attempt: None,
+                    ..c.clone()
+                }
+                .to_string()
+            });
+            let row = frames
+                .entry(frame.clone())
+                .or_insert_with(|| RestartTimeRow {
+                    frame,
+                    co_name: m.co_name.clone().unwrap_or_default(),
+                    restart_time_s: 0.0,
+                    num_restarted: 0,
+                });
+            row.restart_time_s += t;
+            row.num_restarted += 1;
+        }
+    }
+    let total = frames.values().map(|r| r.restart_time_s).sum();
+    let mut rows: Vec<RestartTimeRow> = frames.into_values().collect();
+    rows.sort_by(|a, b| b.restart_time_s.total_cmp(&a.restart_time_s));
+    rows.truncate(limit);
+    (total, rows)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrossRankMetricsRow {
+    pub compile_id: String,
+    pub num_ranks: usize,
+    pub min_time: String,
+    pub median_time: String,
+    pub max_time: String,
+    pub failed_ranks: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RankCompile {
+    // md5 of the Dynamo output graph, from its has_payload
+    pub graph_hash: Option<String>,
+    pub guard_count: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankCompilesRow {
+    pub rank: String,
+    pub num_compiles: usize,
+}
+
+// A compile id that isn't the same on every rank, with the values that differ grouped by the ranks
+// that have them
+#[derive(Debug, Serialize)]
+pub struct CompileDivergenceRow {
+    pub compile_id: String,
+    pub missing_ranks: String,
+    pub graph_hashes: String,
+    pub guard_counts: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompileDivergenceContext {
+    pub css: &'static str,
+    pub ranks: Vec<RankCompilesRow>,
+    pub num_compiles_differ: bool,
+    pub rows: Vec<CompileDivergenceRow>,
+    pub qps: &'static str,
+}
+
+impl CompileDivergenceContext {
+    // Number of divergences, counting different numbers of compiles across ranks as one
+    pub fn num_divergences(&self) -> usize {
+        self.rows.len() + usize::from(self.num_compiles_differ)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrossRankMetricsContext {
+    pub css: &'static str,
+    pub ranks: String,
+    pub rows: Vec<CrossRankMetricsRow>,
+    pub qps: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolicShapeSpecializationsContext {
+    pub css: &'static str,
+    pub specializations: Vec<SymbolicShapeSpecializationSummary>,
+    pub qps: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuardAddedFastContext {
+    pub expr: String,
+    pub user_stack_html: String,
+    pub stack_html: String,
+}
+
+// Differences between two report directories.  Outputs are matched up ignoring their output number
+// (e.g., the 6 in dynamo_output_graph_6.txt), which shifts whenever an earlier entry in the log is
+// added or removed
+#[derive(Debug, Default)]
+pub struct OutputDiff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    // (old, new) paths of outputs whose contents differ
+    pub changed: Vec<(PathBuf, PathBuf)>,
+    pub unchanged: usize,
+}
+
+static OUTPUT_NUMBER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?<stem>.*)_(?<number>\d+)(?<ext>\.[^_]*)$").unwrap());
+
+// Strips the output number from path, returning it separately for ordering outputs that only
+// differ by it
+pub fn output_diff_key(path: &Path) -> (PathBuf, u64) {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match OUTPUT_NUMBER_RE.captures(&name) {
+        Some(caps) => (
+            path.with_file_name(format!("{}{}", &caps["stem"], &caps["ext"])),
+            caps["number"].parse().unwrap_or(0),
+        ),
+        None => (path.to_path_buf(), 0),
+    }
+}
+
+impl OutputDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    // Differences grouped by compile id directory, followed by the totals
+    pub fn summary(&self) -> String {
+        let mut groups: IndexMap<String, Vec<String>> = IndexMap::new();
+        let mut add = |path: &Path, line: String| {
+            let group = match path.parent() {
+                Some(parent) if parent != Path::new("") => parent.display().to_string(),
+                _ => "(top level)".to_string(),
+            };
+            groups.entry(group).or_default().push(line);
+        };
+        let name = |path: &Path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        for (old, new) in &self.changed {
+            if old == new {
+                add(new, format!("changed {}", name(new)));
+            } else {
+                add(new, format!("changed {} -> {}", name(old), name(new)));
+            }
+        }
+        for path in &self.added {
+            add(path, format!("added {}", name(path)));
+        }
+        for path in &self.removed {
+            add(path, format!("removed {}", name(path)));
+        }
+        groups.sort_keys();
+        let mut summary = String::new();
+        for (group, lines) in groups {
+            summary.push_str(&group);
+            summary.push('\n');
+            for line in lines {
+                summary.push_str("  ");
+                summary.push_str(&line);
+                summary.push('\n');
+            }
+        }
+        summary.push_str(&format!(
+            "{} changed, {} added, {} removed, {} unchanged\n",
+            self.changed.len(),
+            self.added.len(),
+            self.removed.len(),
+            self.unchanged
+        ));
+        summary
+    }
+}
+
+// What tlparse diff compares of a run, read back from its JSON output
+#[derive(Debug, Deserialize)]
+pub struct DiffInput {
+    pub compile_ids: Vec<DiffCompileId>,
+    pub failures: Vec<DiffFailure>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffCompileId {
+    pub compile_id: String,
+    pub co_name: Option<String>,
+    pub entire_frame_compile_time_s: Option<f64>,
+    pub guard_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffFailure {
+    pub compile_id: String,
+    pub kind: String,
+    pub reason: String,
+}
+
+// The differences between two runs, e.g., before and after a change when bisecting
+#[derive(Debug, Default, Serialize)]
+pub struct LogDiff {
+    // Compile ids, with the name of their frame if known
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    // Frames whose total compile time changed, the largest change first
+    pub frame_times: Vec<FrameTimeDelta>,
+    pub guard_counts: Vec<GuardCountDelta>,
+    // (compile id, reason) of failures that only the new run has
+    pub new_failures: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameTimeDelta {
+    pub frame: String,
+    pub co_name: Option<String>,
+    pub old_s: Option<f64>,
+    pub new_s: Option<f64>,
+}
+
+impl FrameTimeDelta {
+    fn delta_s(&self) -> f64 {
+        self.new_s.unwrap_or(0.0) - self.old_s.unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuardCountDelta {
+    pub compile_id: String,
+    pub old: u64,
+    pub new: u64,
+}
+
+// The frame of a compile id as shown by CompileId, e.g., [0/-] for [0/1_1], so that it is the same
+// for every compilation of the frame
+fn diff_frame(compile_id: &str) -> Option<String> {
+    let (frame, _) = compile_id.strip_prefix('[')?.rsplit_once('/')?;
+    Some(format!("[{frame}/-]"))
+}
+
+impl LogDiff {
+    pub fn new(old: &DiffInput, new: &DiffInput) -> Self {
+        let by_id = |input: &'_ DiffInput| -> FxIndexMap<String, usize> {
+            input
+                .compile_ids
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (c.compile_id.clone(), i))
+                .collect()
+        };
+        let (old_ids, new_ids) = (by_id(old), by_id(new));
+        let label = |c: &DiffCompileId| match &c.co_name {
+            Some(co_name) => format!("{} ({co_name})", c.compile_id),
+            None => c.compile_id.clone(),
+        };
+        let mut diff = LogDiff::default();
+        for c in &new.compile_ids {
+            match old_ids.get(&c.compile_id).map(|&i| &old.compile_ids[i]) {
+                None => diff.added.push(label(c)),
+                Some(o) => {
+                    if let (Some(old_count), Some(new_count)) = (o.guard_count, c.guard_count) {
+                        if old_count != new_count {
+                            diff.guard_counts.push(GuardCountDelta {
+                                compile_id: c.compile_id.clone(),
+                                old: old_count,
+                                new: new_count,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        diff.removed = old
+            .compile_ids
+            .iter()
+            .filter(|c| !new_ids.contains_key(&c.compile_id))
+            .map(label)
+            .collect();
+
+        // Summed over the compilations of each frame, since recompiles may be split differently
+        let mut frames: FxIndexMap<String, FrameTimeDelta> = FxIndexMap::default();
+        for (input, is_new) in [(old, false), (new, true)] {
+            for c in &input.compile_ids {
+                let Some(frame) = diff_frame(&c.compile_id) else {
+                    continue;
+                };
+                let delta = frames.entry(frame.clone()).or_insert(FrameTimeDelta {
+                    frame,
+                    co_name: None,
+                    old_s: None,
+                    new_s: None,
+                });
+                if c.co_name.is_some() {
+                    delta.co_name = c.co_name.clone();
+                }
+                if let Some(time) = c.entire_frame_compile_time_s {
+                    let total = if is_new {
+                        &mut delta.new_s
+                    } else {
+                        &mut delta.old_s
+                    };
+                    *total = Some(total.unwrap_or(0.0) + time);
+                }
+            }
+        }
+        diff.frame_times = frames
+            .into_values()
+            // As precise as the times are shown
+            .filter(|f| (f.delta_s() * 1000.0).round() != 0.0)
+            .collect();
+        diff.frame_times
+            .sort_by(|a, b| b.delta_s().abs().total_cmp(&a.delta_s().abs()));
+
+        let old_failures: FxHashSet<(&str, &str)> = old
+            .failures
+            .iter()
+            .filter(|f| f.kind == "failure")
+            .map(|f| (f.compile_id.as_str(), f.reason.as_str()))
+            .collect();
+        diff.new_failures = new
+            .failures
+            .iter()
+            .filter(|f| {
+                f.kind == "failure"
+                    && !old_failures.contains(&(f.compile_id.as_str(), f.reason.as_str()))
+            })
+            .map(|f| (f.compile_id.clone(), f.reason.clone()))
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.frame_times.is_empty()
+            && self.guard_counts.is_empty()
+            && self.new_failures.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let seconds = |s: Option<f64>| s.map_or("-".to_string(), |s| format!("{s:.3}s"));
+        let mut summary = String::new();
+        for compile_id in &self.added {
+            summary.push_str(&format!("added {compile_id}\n"));
+        }
+        for compile_id in &self.removed {
+            summary.push_str(&format!("removed {compile_id}\n"));
+        }
+        for f in &self.frame_times {
+            let name = f
+                .co_name
+                .as_ref()
+                .map_or(String::new(), |n| format!(" ({n})"));
+            summary.push_str(&format!(
+                "frame {}{name}: compile time {} -> {} ({:+.3}s)\n",
+                f.frame,
+                seconds(f.old_s),
+                seconds(f.new_s),
+                f.delta_s()
+            ));
+        }
+        for g in &self.guard_counts {
+            summary.push_str(&format!(
+                "{}: guards {} -> {}\n",
+                g.compile_id, g.old, g.new
+            ));
+        }
+        for (compile_id, reason) in &self.new_failures {
+            // Reasons often span lines, e.g., with a traceback
+            let reason = reason.trim_end().replace('\n', "\n  ");
+            summary.push_str(&format!("new failure {compile_id}: {reason}\n"));
+        }
+        summary.push_str(&format!(
+            "{} compile ids added, {} removed, {} frames with a different compile time, {} guard count changes, {} new failures\n",
+            self.added.len(),
+            self.removed.len(),
+            self.frame_times.len(),
+            self.guard_counts.len(),
+            self.new_failures.len()
+        ));
+        summary
+    }
+}
+
+// What the report of a log would have, without rendering it: the entries of each compile id by
+// kind, with the size of their payloads, in log order
+#[derive(Debug, Default)]
+pub struct LogListing {
+    pub compile_ids: FxIndexMap<(Option<u32>, Option<CompileId>), FxIndexMap<String, ListedKind>>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ListedKind {
+    pub count: u64,
+    pub payload_bytes: u64,
+}
+
+impl LogListing {
+    pub fn new(entries: impl Iterator<Item = ParsedEnvelope>) -> Self {
+        let mut listing = LogListing::default();
+        for e in entries {
+            let kind = e.envelope.kind();
+            let listed = listing
+                .compile_ids
+                .entry((e.envelope.rank, e.envelope.compile_id))
+                .or_default()
+                .entry(kind)
+                .or_default();
+            listed.count += 1;
+            listed.payload_bytes += e.payload.map_or(0, |p| p.len() as u64);
+        }
+        listing
+    }
+
+    pub fn summary(&self) -> String {
+        let ranks: FxHashSet<Option<u32>> = self.compile_ids.keys().map(|(r, _)| *r).collect();
+        let width = self
+            .compile_ids
+            .values()
+            .flat_map(|kinds| kinds.keys())
+            .map(|k| k.len())
+            .max()
+            .unwrap_or(0);
+        let (mut num_entries, mut total_bytes) = (0, 0);
+        let mut summary = String::new();
+        for ((rank, compile_id), kinds) in &self.compile_ids {
+            let count: u64 = kinds.values().map(|k| k.count).sum();
+            let bytes: u64 = kinds.values().map(|k| k.payload_bytes).sum();
+            (num_entries, total_bytes) = (num_entries + count, total_bytes + bytes);
+            // Compile ids only tell ranks apart when there is more than one
+            if ranks.len() > 1 {
+                match rank {
+                    Some(rank) => summary.push_str(&format!("rank {rank} ")),
+                    None => summary.push_str("(no rank) "),
+                }
+            }
+            summary.push_str(&format!(
+                "{}: {count} entries, {}\n",
+                compile_id
+                    .as_ref()
+                    .map_or("(unknown)".to_string(), |c| c.to_string()),
+                format_size(bytes)
+            ));
+            for (kind, listed) in kinds {
+                summary.push_str(&format!(
+                    "  {kind:<width$} {:>6} {:>10}\n",
+                    listed.count,
+                    format_size(listed.payload_bytes)
+                ));
+            }
+        }
+        summary.push_str(&format!(
+            "{} compile ids, {num_entries} entries, {} of payloads\n",
+            self.compile_ids.len(),
+            format_size(total_bytes)
+        ));
+        summary
+    }
 }
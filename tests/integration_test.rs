@@ -1,11 +1,24 @@
 use std::collections::HashMap;
+use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
-use tlparse;
 
 fn prefix_exists(map: &HashMap<PathBuf, String>, prefix: &str) -> bool {
     map.keys()
-        .any(|key| key.to_str().map_or(false, |s| s.starts_with(prefix)))
+        .any(|key| key.to_str().is_some_and(|s| s.starts_with(prefix)))
+}
+
+// The tlparse binary, for testing what only the command line does
+fn tlparse_cli() -> std::process::Command {
+    std::process::Command::new(env!("CARGO_BIN_EXE_tlparse"))
+}
+
+// A fresh path under the temp directory, unique to this test process
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("tlparse_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    let _ = fs::remove_file(&path);
+    path
 }
 
 #[test]
@@ -99,6 +112,12 @@ fn test_parse_compilation_failures() {
             prefix
         );
     }
+    // The failed compilation's metrics are badged in the listing
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("compilation_metrics_3.html</a> ⚠️"));
+    // Listings record the size of each output and the log line it came from
+    assert!(index.contains("compilation_metrics_3.html</a> ⚠️ (3) <span class=\"output-meta\">"));
+    assert!(index.contains("<span title=\"3551 bytes\">3.5 KiB</span>, line 86</span>"));
 }
 
 #[test]
@@ -153,9 +172,9 @@ fn test_parse_chromium_event() {
 #[test]
 fn test_cache_hit_miss() {
     let expected_files = [
-        "-_1_0_0/fx_graph_cache_miss_33.json",
-        "-_1_0_0/fx_graph_cache_miss_9.json",
-        "-_1_0_0/fx_graph_cache_hit_20.json",
+        "-_1_0_0/fx_graph_cache_miss_39.json",
+        "-_1_0_0/fx_graph_cache_miss_11.json",
+        "-_1_0_0/fx_graph_cache_hit_24.json",
         "index.html",
     ];
     // Generated via TORCH_TRACE=~/trace_logs/test python test/inductor/test_codecache.py -k test_flex_attention_caching
@@ -175,6 +194,71 @@ fn test_cache_hit_miss() {
             prefix
         );
     }
+    // Cache artifacts are badged in the listing
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("fx_graph_cache_hit_24.json</a> ✅"));
+    assert!(index.contains("fx_graph_cache_miss_11.json</a> ❌"));
+}
+
+#[test]
+fn test_output_badges() {
+    let path = PathBuf::from("tests/inputs/cache_hit_miss.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let files: Vec<&tlparse::OutputFile> = output.directory.values().flatten().collect();
+    // Cache artifacts are badged by their outcome
+    for (outcome, badge) in [("hit", "✅"), ("miss", "❌"), ("bypass", "❓")] {
+        let badged: Vec<_> = files
+            .iter()
+            .filter(|f| f.name.contains(&format!("cache_{outcome}")))
+            .collect();
+        assert!(!badged.is_empty(), "no cache {outcome}");
+        assert!(badged.iter().all(|f| f.suffix == badge), "{outcome}");
+    }
+    assert!(files
+        .iter()
+        .filter(|f| f.name.contains("dynamo_output_graph"))
+        .all(|f| f.suffix.is_empty()));
+
+    // The metrics of failed compilations are badged as failures
+    let path = PathBuf::from("tests/inputs/comp_failure.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let metrics: Vec<_> = output
+        .directory
+        .values()
+        .flatten()
+        .filter(|f| f.name.contains("compilation_metrics"))
+        .collect();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].suffix, "⚠️");
+}
+
+#[test]
+fn test_output_size_and_lineno() {
+    let path = PathBuf::from("tests/inputs/simple.log");
+    let log = fs::read_to_string(&path).unwrap();
+    let log_lines: Vec<&str> = log.lines().collect();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let files: HashMap<PathBuf, String> = output.files.into_iter().collect();
+    let mut checked = 0;
+    for file in output.directory.values().flatten() {
+        let Some(size) = file.size else { continue };
+        // The size of what was written, and the log entry it came from
+        assert_eq!(size, files[&PathBuf::from(&file.url)].len(), "{}", file.url);
+        let line = log_lines[file.lineno - 1];
+        assert!(
+            line.contains("] {\""),
+            "line {} of {}",
+            file.lineno,
+            file.url
+        );
+        checked += 1;
+    }
+    assert!(checked > 10);
+    // Both are shown in the listing
+    let graph = output.directory.values().flatten().next().unwrap();
+    assert_eq!((graph.size, graph.lineno), (Some(1010), 114));
+    assert!(files[&PathBuf::from("index.html")]
+        .contains("<span title=\"1010 bytes\">1010 B</span>, line 114</span>"));
 }
 
 #[test]
@@ -206,3 +290,3387 @@ fn test_export_report() {
         );
     }
 }
+
+#[test]
+fn test_specialization_summary() {
+    let expected_files = ["symbolic_shape_specializations.html", "index.html"];
+    // dynamic_shapes.log is a reduced trace of a frame that recompiles under automatic dynamic
+    // shapes, specializing the same source expression across several compilations
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    // Check all files are present
+    for prefix in expected_files {
+        assert!(
+            prefix_exists(&map, prefix),
+            "{} not found in output",
+            prefix
+        );
+    }
+    // L['x'].size()[1] == 4 is specialized by four compilations, and should be merged into one row
+    let summary = &map[&PathBuf::from("symbolic_shape_specializations.html")];
+    assert_eq!(summary.matches("size()[1]").count(), 1);
+    // L['y'].size()[0] == 3 and L['x'].size()[0] == 8
+    assert_eq!(summary.matches("size()[0]").count(), 2);
+    assert!(summary.contains("index.html#cid-1-0-0"));
+}
+
+#[test]
+fn test_specialization_guard_links() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let find = |prefix: &str| {
+        map.iter()
+            .find(|(k, _)| {
+                k.to_str()
+                    .is_some_and(|s| s.starts_with(prefix) && s.ends_with(".html"))
+            })
+            .map(|(k, v)| (k.file_name().unwrap().to_str().unwrap().to_string(), v))
+            .unwrap()
+    };
+    let (guards_name, guards) = find("-_0_2_0/dynamo_guards");
+    let (metrics_name, metrics) = find("-_0_2_0/compilation_metrics");
+    // L['x'].size()[1] == 4 is the fifth guard, and comes from the first specialization
+    assert!(guards.contains(&format!("href=\"{metrics_name}#specialization-0\"")));
+    assert!(metrics.contains(&format!("{guards_name}#guard-4")));
+}
+
+#[test]
+fn test_specialization_guard_links_without_metrics() {
+    // Cut off just before the compilation metrics of [0/2]
+    let log = fs::read_to_string("tests/inputs/dynamic_shapes.log").unwrap();
+    let log: String = log.lines().take(83).map(|l| format!("{l}\n")).collect();
+    let output = tlparse::parse_reader(log.as_bytes(), tlparse::ParseConfig::default()).unwrap();
+    let (_, guards) = output
+        .files
+        .iter()
+        .find(|(k, _)| {
+            k.to_str()
+                .is_some_and(|s| s.starts_with("-_0_2_0/dynamo_guards") && s.ends_with(".html"))
+        })
+        .unwrap();
+    // The specializations are still shown, but don't link to a metrics page that doesn't exist
+    assert!(guards.contains("[specialized "));
+    assert!(!guards.contains("#specialization-"));
+}
+
+#[test]
+fn test_dynamo_guards_json() {
+    let path = PathBuf::from("tests/inputs/dynamic_shapes.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let files: HashMap<PathBuf, String> = output.files.into_iter().collect();
+    let mut checked = 0;
+    for entry in tlparse::parse_entries(&path).unwrap() {
+        if entry.envelope.dynamo_guards.is_none() {
+            continue;
+        }
+        let logged: Vec<serde_json::Value> =
+            serde_json::from_str(entry.payload.as_ref().unwrap()).unwrap();
+        let dir = entry
+            .envelope
+            .compile_id
+            .as_ref()
+            .unwrap()
+            .as_directory_name();
+        // The guards are written as JSON next to their HTML page
+        let (_, json) = files
+            .iter()
+            .find(|(k, _)| {
+                k.to_str().is_some_and(|s| {
+                    s.starts_with(&format!("{dir}/dynamo_guards_")) && s.ends_with(".json")
+                })
+            })
+            .unwrap();
+        let written: Vec<serde_json::Value> = serde_json::from_str(json).unwrap();
+        let codes = |guards: &[serde_json::Value]| -> Vec<String> {
+            guards
+                .iter()
+                .map(|g| g["code"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(codes(&written), codes(&logged));
+        checked += 1;
+    }
+    assert_eq!(checked, 5);
+}
+
+#[test]
+fn test_graph_input_sources() {
+    let path = PathBuf::from("tests/inputs/simple.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let files: HashMap<PathBuf, String> = output.files.into_iter().collect();
+    let find = |prefix: &str| {
+        files
+            .iter()
+            .find(|(k, _)| {
+                k.to_str()
+                    .is_some_and(|s| s.starts_with(prefix) && s.ends_with(".txt"))
+            })
+            .map(|(_, v)| v)
+            .unwrap()
+    };
+    // Each placeholder is annotated with the source it came from, its dtype and its shape
+    let dynamo_graph = find("-_0_0_0/dynamo_output_graph_");
+    assert!(dynamo_graph.starts_with(
+        "# Graph inputs:\n#   L_x_: L['x'] torch.float32[1, 320, 128, 128]\n\nclass GraphModule"
+    ));
+    // AOT graphs are annotated by position
+    let aot_graph = find("-_0_0_0/aot_inference_graph_");
+    assert!(aot_graph
+        .starts_with("# Graph inputs:\n#   arg0_1: L['x'] torch.float32[1, 320, 128, 128]\n"));
+}
+
+#[test]
+fn test_dynamo_output_graph_sizes() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let sizes = |prefix: &str| {
+        map.iter()
+            .find(|(k, _)| k.to_str().is_some_and(|s| s.starts_with(prefix)))
+            .map(|(_, v)| v)
+            .unwrap()
+    };
+    // [0/0] is compiled statically, while [0/1] marks L['x'].size()[0] as dynamic
+    assert!(!sizes("-_0_0_0/dynamo_output_graph_sizes").contains(">symbolic<"));
+    let dynamic = sizes("-_0_1_0/dynamo_output_graph_sizes");
+    assert!(dynamic.contains("[s0, 4]"));
+    assert!(dynamic.contains(">symbolic<"));
+}
+
+#[test]
+fn test_total_input_bytes() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let metrics = map
+        .iter()
+        .find(|(k, _)| {
+            k.to_str()
+                .is_some_and(|s| s.starts_with("-_1_0_0/compilation_metrics"))
+        })
+        .map(|(_, v)| v)
+        .unwrap();
+    // In [1/0], L['x_flat'] is a view of L['x'], so their 256 byte storage is only counted once
+    assert!(metrics.contains("Total Input Bytes"));
+    assert!(metrics.contains(": 256 bytes</p>"));
+    let index = &map[&PathBuf::from("index.html")];
+    // [0/2] takes L['x'] (128 bytes) and L['y'] (12 bytes)
+    assert!(index.contains("(total input bytes: 140 bytes)"));
+}
+
+#[test]
+fn test_aliased_inputs() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let find = |prefix: &str| {
+        map.iter()
+            .find(|(k, _)| {
+                k.to_str()
+                    .is_some_and(|s| s.starts_with(prefix) && !s.contains("_sizes"))
+            })
+            .map(|(_, v)| v)
+            .unwrap()
+    };
+    // L['x_flat'] is a view of L['x'] in [1/0]; no inputs alias in [0/2]
+    assert!(find("-_1_0_0/compilation_metrics").contains("Aliased Inputs"));
+    assert!(!find("-_0_2_0/compilation_metrics").contains("Aliased Inputs"));
+    assert!(find("-_1_0_0/dynamo_output_graph")
+        .contains("L_x_flat_: L['x_flat'] torch.float32[s2] (shares storage with L['x'])"));
+}
+
+#[test]
+fn test_cross_rank_metrics() {
+    // multi_rank.log has three ranks compiling the same two frames, where rank 2 is slow and
+    // fails on frame 1
+    let path = Path::new("tests/inputs/multi_rank.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: false,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let cross_rank = &map[&PathBuf::from("cross_rank_metrics.html")];
+    assert!(cross_rank.contains("across ranks 0, 1, 2"));
+    let row = |cid: &str| {
+        cross_rank
+            .split("<tr>")
+            .find(|r| r.contains(&format!("<td>{cid}</td>")))
+            .unwrap()
+            .split_whitespace()
+            .collect::<String>()
+    };
+    assert!(row("[0/0]").contains("<td>3</td><td>1.2s</td><td>1.5s</td><td>4.0s</td><td></td>"));
+    assert!(row("[1/0]").contains("<td>500.0ms</td><td>750.0ms</td><td>2.0s</td><td>2</td>"));
+    assert!(map[&PathBuf::from("index.html")].contains("cross_rank_metrics.html"));
+
+    // Single rank logs don't get the comparison
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    assert!(!output
+        .files
+        .iter()
+        .any(|(p, _)| p == &PathBuf::from("cross_rank_metrics.html")));
+}
+
+#[test]
+fn test_all_ranks() {
+    let path = Path::new("tests/inputs/multi_rank.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        all_ranks: true,
+        ..Default::default()
+    };
+    let result = tlparse::parse_path_with_stats(&path, config).unwrap();
+    // Nothing is dropped as being from another rank
+    assert_eq!(result.output.stats.other_rank, 0);
+    assert_eq!(result.output.stats.ok, 12);
+    // What each rank's report was made from; their files are in the combined output
+    assert_eq!(
+        result.output.ranks.keys().collect::<Vec<_>>(),
+        [&Some(0), &Some(1), &Some(2)]
+    );
+    assert!(result.output.ranks.values().all(|r| r.files.is_empty()));
+    assert_eq!(
+        result
+            .output
+            .ranks
+            .values()
+            .map(|r| r.stats.ok)
+            .sum::<u64>(),
+        12
+    );
+    let map: HashMap<PathBuf, String> = result.output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("This log has entries from 3 ranks"));
+    for rank in 0..3 {
+        assert!(index.contains(&format!("<a href=\"rank_{rank}/index.html\">{rank}</a>")));
+        // Each rank gets a full report of its own entries
+        let dir = PathBuf::from(format!("rank_{rank}"));
+        assert!(map.contains_key(&dir.join("index.html")));
+        assert!(map.contains_key(&dir.join("-_0_0_0/index.html")));
+        let raw_log = &map[&dir.join("raw.log")];
+        assert!(raw_log.contains(&format!("\"rank\": {rank}")));
+        assert!(raw_log
+            .lines()
+            .filter_map(|l| l.split_once("\"rank\": "))
+            .all(|(_, r)| r.starts_with(&rank.to_string())));
+    }
+    // Rank 2 fails on frame 1
+    assert!(map[&PathBuf::from("rank_2/failures_and_restarts.html")].contains("[1/0]"));
+    assert!(!map[&PathBuf::from("rank_0/failures_and_restarts.html")].contains("[1/0]"));
+    // The index merges the compile ids and failures of every rank
+    let row = index
+        .lines()
+        .find(|l| l.contains("<td>[1/0]</td> <td>"))
+        .unwrap();
+    assert!(row.contains(r#"<a href="rank_0/-_1_0_0/index.html" class="status-ok""#));
+    assert!(row.contains(r#"<a href="rank_2/-_1_0_0/index.html" class="status-error""#));
+    assert!(index.contains(
+        r#"<td><a href="rank_2/failures_and_restarts.html">2</a></td> <td>[1/0]</td> <td>failure</td> <td>NCCL timeout</td>"#
+    ));
+}
+
+#[test]
+fn test_compile_id_landing_pages() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let landing = &map[&PathBuf::from("-_0_1_0/index.html")];
+    assert!(landing.contains("<h1>Compilation [0/1]</h1>"));
+    assert!(landing.contains("class=\"status-ok\""));
+    assert!(landing.contains("Entire frame compile time: 1.2s"));
+    // Artifacts are linked relative to the compile id directory
+    assert!(landing.contains("<a href=\"recompile_reasons_"));
+    assert!(landing.contains("test_recompile"));
+    assert!(map[&PathBuf::from("index.html")].contains("href=\"-_0_1_0/index.html\""));
+}
+
+#[test]
+fn test_paginated_directory() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        max_inline_compile_ids: Some(2),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    // Four compile ids over two frames; the index keeps anchors but not the listing itself
+    assert!(!index.contains("dynamo_guards_"));
+    assert!(index.contains("<a id=\"cid-0-2-0\" href=\"build_products/-_0_-_-.html#cid-0-2-0\">"));
+    let frame0 = &map[&PathBuf::from("build_products/-_0_-_-.html")];
+    assert!(frame0.contains("<li id=\"cid-0-1-0\" data-backend=\"other\"><a id=\"compile--_0_1_0\"></a><a id=\"[0/1]\">[0/1]</a>"));
+    assert!(!frame0.contains("[1/0]"));
+    assert!(map.contains_key(&PathBuf::from("build_products/-_1_-_-.html")));
+}
+
+#[test]
+fn test_single_file_report() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let report = tlparse::render_single_file_report(&output.files).unwrap();
+    // Every page is embedded, and none of them can terminate the embedding script early
+    let start = report
+        .find("<script type=\"application/json\" id=\"files\">")
+        .unwrap();
+    let embedded = &report[start..];
+    let embedded = &embedded[embedded.find('>').unwrap() + 1..embedded.find("</script>").unwrap()];
+    let files: HashMap<String, String> = serde_json::from_str(embedded).unwrap();
+    assert!(files.contains_key("index.html"));
+    assert!(files.contains_key("-_0_0_0/compilation_metrics_3.html"));
+    assert!(!files.contains_key("raw.log"));
+    assert_eq!(
+        files["failures_and_restarts.html"],
+        output
+            .files
+            .iter()
+            .find(|(p, _)| p == &PathBuf::from("failures_and_restarts.html"))
+            .unwrap()
+            .1
+    );
+}
+
+#[test]
+fn test_incremental_parse() {
+    let full_path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let log = fs::read_to_string(&full_path).unwrap();
+    // Cut the log at an entry boundary partway through, as if it were still being written
+    let cut = log[log.len() / 2..].find("\nV").unwrap() + log.len() / 2 + 1;
+    let path = std::env::temp_dir().join(format!("tlparse_incremental_{}.log", std::process::id()));
+
+    fs::write(&path, &log[..cut]).unwrap();
+    let checkpoint = tlparse::parse_path_incremental(&path, tlparse::ParseConfig::default(), None)
+        .unwrap()
+        .checkpoint;
+    let checkpoint: tlparse::ParseCheckpoint =
+        serde_json::from_str(&serde_json::to_string(&checkpoint.unwrap()).unwrap()).unwrap();
+
+    // A partially written line is left for the next run, so the checkpoint is taken before it
+    fs::write(&path, &log[..cut + 10]).unwrap();
+    let partial = tlparse::parse_path_incremental(&path, tlparse::ParseConfig::default(), None)
+        .unwrap()
+        .checkpoint;
+    assert_eq!(
+        serde_json::to_value(partial.unwrap()).unwrap()["offset"],
+        cut
+    );
+
+    fs::write(&path, &log).unwrap();
+    let resumed =
+        tlparse::parse_path_incremental(&path, tlparse::ParseConfig::default(), Some(checkpoint))
+            .unwrap()
+            .output;
+    fs::remove_file(&path).unwrap();
+
+    let full = tlparse::parse_path(&full_path, tlparse::ParseConfig::default()).unwrap();
+    let resumed: HashMap<PathBuf, String> = resumed.into_iter().collect();
+    let full: HashMap<PathBuf, String> = full.into_iter().collect();
+    assert_eq!(resumed.len(), full.len());
+    for (name, contents) in full {
+        // Except for when each report was generated
+        if name == Path::new(tlparse::REPORT_METADATA_PATH) {
+            continue;
+        }
+        assert_eq!(resumed[&name], contents, "{} differs", name.display());
+    }
+}
+
+#[test]
+fn test_incremental_parse_with_sink() {
+    let full_path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let log = fs::read_to_string(&full_path).unwrap();
+    let cut = log[log.len() / 2..].find("\nV").unwrap() + log.len() / 2 + 1;
+    let root =
+        std::env::temp_dir().join(format!("tlparse_incremental_sink_{}", std::process::id()));
+    let path = root.join("log");
+    let (resumed_dir, full_dir) = (root.join("resumed"), root.join("full"));
+    fs::create_dir_all(&root).unwrap();
+
+    // Writes into dir as the outputs are sent
+    let parse = |dir: &Path, checkpoint| {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut writer = tlparse::DirectoryWriter::new(dir.to_path_buf(), false).unwrap();
+        let writer = std::thread::spawn(move || tlparse::write_outputs(&mut writer, receiver));
+        let config = tlparse::ParseConfig {
+            output_sink: Some(sender),
+            ..Default::default()
+        };
+        let result = tlparse::parse_path_incremental(&path, config, checkpoint).unwrap();
+        writer.join().unwrap().unwrap();
+        result
+    };
+
+    fs::write(&path, &log[..cut]).unwrap();
+    let first = parse(&resumed_dir, None);
+    let checkpoint = serde_json::to_string(&first.checkpoint.unwrap()).unwrap();
+    // The outputs that were written aren't kept in the checkpoint
+    let without_sink = tlparse::parse_path_incremental(&path, Default::default(), None).unwrap();
+    let kept = serde_json::to_string(&without_sink.checkpoint.unwrap()).unwrap();
+    assert!(checkpoint.len() * 2 < kept.len());
+
+    // Resuming only sends what's new, which completes the report written by the first run
+    fs::write(&path, &log).unwrap();
+    parse(
+        &resumed_dir,
+        Some(serde_json::from_str(&checkpoint).unwrap()),
+    );
+    let full = tlparse::parse_path(&full_path, tlparse::ParseConfig::default()).unwrap();
+    tlparse::write_outputs(
+        &mut tlparse::DirectoryWriter::new(full_dir.clone(), false).unwrap(),
+        full.files.clone(),
+    )
+    .unwrap();
+    let diff = tlparse::diff_output_dirs(&full_dir, &resumed_dir).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(diff.is_empty());
+    // The report metadata isn't compared
+    assert_eq!(diff.unchanged, full.files.len() - 1);
+}
+
+#[test]
+fn test_render_checkpoint() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let result = tlparse::parse_path_incremental(&path, Default::default(), None).unwrap();
+    let checkpoint = serde_json::to_string(&result.checkpoint.unwrap()).unwrap();
+    // The options apply to the pages rendered from the checkpoint
+    let config = tlparse::ParseConfig {
+        custom_header_html: "<b>rendered again</b>".to_string(),
+        ..Default::default()
+    };
+    let rendered =
+        tlparse::render_checkpoint(serde_json::from_str(&checkpoint).unwrap(), config, || {
+            fs::read_to_string(&path)
+        })
+        .unwrap();
+    let files: HashMap<_, _> = result.output.files.into_iter().collect();
+    assert_eq!(rendered.files.len(), files.len());
+    assert!(rendered.files.iter().any(|(p, contents)| {
+        p == Path::new("index.html") && contents.contains("<b>rendered again</b>")
+    }));
+    for (p, contents) in &rendered.files {
+        if p != Path::new(tlparse::REPORT_METADATA_PATH) {
+            let contents = contents.replace("<b>rendered again</b>", "");
+            assert_eq!(files.get(p), Some(&contents), "{}", p.display());
+        }
+    }
+}
+
+#[test]
+fn test_mixed_torch_builds() {
+    let index = |path: &PathBuf| {
+        let output = tlparse::parse_path(path, tlparse::ParseConfig::default()).unwrap();
+        let (_, index) = output
+            .into_iter()
+            .find(|(p, _)| p == Path::new("index.html"))
+            .unwrap();
+        index
+    };
+    let simple = Path::new("tests/inputs/simple.log").to_path_buf();
+    assert!(!index(&simple).contains("Mixed Torch Builds"));
+
+    // A job that was resumed on another torch install appends to the same log
+    let path = std::env::temp_dir().join(format!("tlparse_mixed_torch_{}.log", std::process::id()));
+    let log = fs::read_to_string(&simple).unwrap()
+        + &fs::read_to_string("tests/inputs/dynamic_shapes.log").unwrap();
+    fs::write(&path, log).unwrap();
+    let index = index(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(index.contains("Mixed Torch Builds"));
+    assert!(index.contains("<code>/data/users/xmfan/a/pytorch</code>"));
+    assert!(index.contains("<code>/data/users/dev/pytorch</code>"));
+}
+
+#[test]
+fn test_output_budget() {
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let has_output = |output: &Vec<(PathBuf, String)>, prefix: &str| {
+        output
+            .iter()
+            .any(|(p, _)| p.file_name().unwrap().to_string_lossy().starts_with(prefix))
+    };
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    assert!(has_output(&output.files, "fx_graph_runnable"));
+    // Once the budget is used up, low value artifacts are skipped, but everything else is kept
+    let config = tlparse::ParseConfig {
+        output_budget: Some(1),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    assert!(!has_output(&output.files, "fx_graph_runnable"));
+    assert!(has_output(&output.files, "inductor_output_code"));
+    assert!(has_output(&output.files, "compilation_metrics"));
+}
+
+#[test]
+fn test_recompile_thrashing() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")].contains("1 guard(s)</a></strong> failed"));
+    let thrashing = &map[&PathBuf::from("recompile_thrashing.html")];
+    // Frame 2 keeps failing the same size guard; frame 0's recompiles each fail a different one
+    assert!(thrashing.contains("<td>[2/-]</td>"));
+    assert!(!thrashing.contains("[0/-]"));
+    assert!(thrashing.contains("size mismatch at index 0</code>"));
+    assert!(thrashing.contains("href=\"index.html#cid-2-3-0\""));
+    assert!(thrashing.contains("expected 16, actual 8"));
+}
+
+#[test]
+fn test_recompile_reason_changes() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let landing = &map[&PathBuf::from("-_0_1_0/index.html")];
+    assert!(landing.contains("<h2>Recompile Reasons</h2>"));
+    assert!(landing.contains("<a href=\"../-_0_0_0/index.html\">[0/0]</a>"));
+    assert!(landing.contains("L[&#39;x&#39;].size()[0] changed 8 → 16"));
+    // Links to the tensor match guard of [0/0], which is the one that failed
+    let guards_name = map
+        .keys()
+        .map(|p| p.to_string_lossy().into_owned())
+        .find(|p| p.starts_with("-_0_0_0/dynamo_guards_") && p.ends_with(".html"))
+        .unwrap();
+    assert!(landing.contains(&format!("href=\"../{guards_name}#guard-1\"")));
+    assert!(map[&PathBuf::from(&guards_name)]
+        .contains("id=\"guard-1\"><pre class=\"guard\">check_tensor"));
+    assert!(!map[&PathBuf::from("-_0_0_0/index.html")].contains("Recompile Reasons"));
+}
+
+#[test]
+fn test_compile_time_histogram() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let histogram = &index[index.find("Compile Time Distribution").unwrap()..];
+    let histogram = &histogram[..histogram.find("</table>").unwrap()];
+    assert!(histogram.contains("<td>&lt; 1s</td>\n<td>15</td>"));
+    assert!(histogram.contains("<td>1s - 5s</td>\n<td>1</td>"));
+    // Nothing was slower than that
+    assert!(!histogram.contains("5s - 10s"));
+}
+
+#[test]
+fn test_json_schemas() {
+    let schemas: HashMap<&str, serde_json::Value> = tlparse::json_schemas().into_iter().collect();
+    let envelope = &schemas["envelope"];
+    // Compile id fields are flattened into the envelope
+    assert!(envelope["properties"]["frame_id"].is_object());
+    assert!(envelope["properties"]["compilation_metrics"].is_object());
+    assert!(
+        envelope["definitions"]["CompilationMetricsMetadata"]["properties"]
+            ["entire_frame_compile_time_s"]
+            .is_object()
+    );
+    assert_eq!(schemas["dynamo_guards"]["type"], "array");
+}
+
+#[test]
+fn test_repair_chromium_events() {
+    // chromium_events_malformed.log is chromium_events.log with the final "dynamo" end event
+    // removed, an end event without a begin event added, and a truncated event at the end
+    let path = Path::new("tests/inputs/chromium_events_malformed.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let events: Vec<serde_json::Value> =
+        serde_json::from_str(&map[&PathBuf::from("chromium_events.json")]).unwrap();
+    // Leave out the events synthesized from log timestamps
+    let events: Vec<&serde_json::Value> = events.iter().filter(|e| e["pid"] == 0).collect();
+    let phases: Vec<(&str, &str)> = events
+        .iter()
+        .map(|e| (e["ph"].as_str().unwrap(), e["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        phases,
+        [
+            ("B", "dynamo"),
+            ("B", "entire_frame_compile"),
+            ("B", "backend_compile"),
+            ("E", "backend_compile"),
+            ("E", "entire_frame_compile"),
+            ("E", "dynamo"),
+        ]
+    );
+    // The synthesized end is at the last timestamp in the log
+    assert_eq!(events[5]["ts"], events[4]["ts"]);
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("Dropped malformed event on line 156"));
+    assert!(index.contains("Dropped end event stray without a begin event"));
+    assert!(index.contains("Begin event dynamo never ended"));
+}
+
+#[test]
+fn test_chromium_event_nesting() {
+    // "outer" ends while "inner" is still open, and the instant event is logged after the fact
+    let events = [
+        ("outer", "B", 10),
+        ("inner", "B", 20),
+        ("outer", "E", 30),
+        ("cache_miss", "i", 15),
+        ("inner", "E", 40),
+    ];
+    let mut log = String::new();
+    for (i, (name, ph, ts)) in events.iter().enumerate() {
+        log += &format!(
+            "V1206 15:20:13.{i:06} 1543231 torch/_dynamo/utils.py:1288] {{\"chromium_event\": {{}}, \"frame_id\": 0, \"frame_compile_id\": 0, \"attempt\": 0, \"has_payload\": \"0\"}}\n"
+        );
+        log += &format!(
+            "\t{{\"name\": \"{name}\", \"ts\": {ts}, \"args\": {{}}, \"ph\": \"{ph}\", \"cat\": \"dynamo_timed\", \"tid\": 0, \"pid\": 0}}\n"
+        );
+    }
+    let config = tlparse::ParseConfig {
+        no_verify_payload: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_reader(log.as_bytes(), config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let events: Vec<serde_json::Value> =
+        serde_json::from_str(&map[&PathBuf::from("chromium_events.json")]).unwrap();
+    let phases: Vec<(&str, &str, i64)> = events
+        .iter()
+        .filter(|e| e["pid"] == 0)
+        .map(|e| {
+            (
+                e["ph"].as_str().unwrap(),
+                e["name"].as_str().unwrap(),
+                e["ts"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    // The end of "outer" closes "inner" first, instead of being taken as the end of "inner"
+    assert_eq!(
+        phases,
+        [
+            ("B", "outer", 10),
+            ("B", "inner", 20),
+            ("E", "inner", 30),
+            ("E", "outer", 30),
+            ("i", "cache_miss", 15),
+        ]
+    );
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(
+        index.contains("Begin event inner never ended, added an end event before end event outer")
+    );
+    assert!(index.contains("Dropped end event inner without a begin event"));
+    assert!(!index.contains("is earlier than the previous event"));
+
+    // Normal logs have no problems with their chromium events
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("is earlier than the previous event"));
+}
+
+#[test]
+fn test_synthesized_chromium_events() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    // This log has no chromium events, but still gets a timeline
+    assert!(map[&PathBuf::from("index.html")].contains("chromium_events.json"));
+    let events: Vec<serde_json::Value> =
+        serde_json::from_str(&map[&PathBuf::from("chromium_events.json")]).unwrap();
+    let span = events
+        .iter()
+        .find(|e| e["name"] == "compile [0/1]")
+        .unwrap();
+    // Taken from the compilation metrics
+    assert_eq!(span["ph"], "X");
+    assert_eq!(span["ts"], 1760529611000000i64);
+    assert_eq!(span["dur"], 1250000);
+    assert_eq!(span["tid"], 0);
+    let instant = events
+        .iter()
+        .find(|e| {
+            e["ph"] == "i"
+                && e["name"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with("dynamo_output_graph_sizes")
+        })
+        .unwrap();
+    assert_eq!(instant["pid"], span["pid"]);
+}
+
+#[test]
+fn test_mini_stack_source_link() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        source_url_template: Some("vscode://file{filename}:{line}".to_string()),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let metrics = map
+        .iter()
+        .find(|(p, _)| {
+            p.to_string_lossy()
+                .starts_with("-_0_0_0/compilation_metrics")
+        })
+        .unwrap()
+        .1;
+    assert!(metrics.contains(
+        "<a href='vscode://file/data/users/dev/pytorch/test/dynamo/test_dynamic_shapes.py:12'>"
+    ));
+    // The stack trie can be collapsed
+    assert!(metrics.contains("function toggleList"));
+}
+
+#[test]
+fn test_where_logged() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let landing = &map[&PathBuf::from("-_0_2_0/index.html")];
+    assert!(landing.contains("<h2>Where Logged</h2>"));
+    // Two tensors were described, and two specializations happened
+    assert!(landing
+        .contains("<tr><td><code>torch/_subclasses/meta_utils.py:454</code></td><td>2</td></tr>"));
+    assert!(landing.contains(
+        "<tr><td><code>torch/fx/experimental/symbolic_shapes.py:4800</code></td><td>2</td></tr>"
+    ));
+}
+
+#[test]
+fn test_duration_and_size_formatting() {
+    // A compilation that took 83.4s, and a 1.5 MiB artifact
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log")
+        .unwrap()
+        .replace(
+            "\"entire_frame_compile_time_s\": 0.012439489364624023",
+            "\"entire_frame_compile_time_s\": 83.4",
+        );
+    assert!(log.contains("83.4"));
+    let payload = "x".repeat(1024 * 1024 + 512 * 1024);
+    let log = format!(
+        "{log}V0403 07:28:48.070000 139877824898048 torch/_inductor/codecache.py:689] {{\"artifact\": {{\"name\": \"big\", \"encoding\": \"string\"}}, \"frame_id\": 0, \"frame_compile_id\": 0, \"attempt\": 0, \"has_payload\": \"0\"}}\n\t{payload}\n"
+    );
+    let config = tlparse::ParseConfig {
+        no_verify_payload: true,
+        ..Default::default()
+    };
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), config)
+        .unwrap()
+        .into_iter()
+        .collect();
+    let landing = &map[&PathBuf::from("-_0_0_0/index.html")];
+    assert!(landing.contains("Entire frame compile time: 1m 23.4s"));
+    assert!(
+        map[&PathBuf::from("index.html")].contains("<span title=\"1572864 bytes\">1.5 MiB</span>")
+    );
+}
+
+#[test]
+fn test_compile_id_waterfall() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let landing = &map[&PathBuf::from("-_0_1_0/index.html")];
+    assert!(landing.contains("<h2>Timeline</h2>"));
+    // Outputs are listed in the order they were logged, with the time since the previous one
+    let output_graph = landing.find("<td>dynamo_output_graph_6.txt</td>").unwrap();
+    let recompile_reasons = landing.find("<td>recompile_reasons_5.json</td>").unwrap();
+    assert!(recompile_reasons < output_graph);
+    assert!(landing[output_graph..].contains("<td>+6.0ms</td>"));
+}
+
+#[test]
+fn test_parse_reader() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let mut from_path: HashMap<PathBuf, String> = tlparse::parse_path(&path, config)
+        .unwrap()
+        .into_iter()
+        .collect();
+    // The same log from an in-memory buffer gives the same report
+    let log = fs::read(&path).unwrap();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let mut from_reader: HashMap<PathBuf, String> = tlparse::parse_reader(&log[..], config)
+        .unwrap()
+        .into_iter()
+        .collect();
+    // Except for when each report was generated
+    let metadata = PathBuf::from(tlparse::REPORT_METADATA_PATH);
+    from_path.remove(&metadata);
+    from_reader.remove(&metadata);
+    assert_eq!(from_path, from_reader);
+}
+
+#[test]
+fn test_diff_logs() {
+    let simple = Path::new("tests/inputs/simple.log").to_path_buf();
+    let diff = tlparse::diff_logs(&simple, &simple).unwrap();
+    assert!(diff.is_empty());
+
+    let comp_failure = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let diff = tlparse::diff_logs(&simple, &comp_failure).unwrap();
+    assert_eq!(diff.new_failures.len(), 1);
+    assert_eq!(diff.new_failures[0].0, "[0/0]");
+    assert!(diff.new_failures[0].1.contains("broken backend"));
+    // Only failures the old run didn't have are new
+    assert!(tlparse::diff_logs(&comp_failure, &comp_failure)
+        .unwrap()
+        .new_failures
+        .is_empty());
+
+    let dynamic_shapes = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let diff = tlparse::diff_logs(&simple, &dynamic_shapes).unwrap();
+    assert!(diff.added.contains(&"[0/1] (forward)".to_string()));
+    assert_eq!(diff.guard_counts.len(), 1);
+    assert_eq!(
+        (diff.guard_counts[0].old, diff.guard_counts[0].new),
+        (49, 4)
+    );
+    // The frame whose compile time grew the most comes first
+    assert_eq!(diff.frame_times[0].frame, "[0/-]");
+    assert_eq!(diff.frame_times[0].new_s, Some(2.74));
+    assert!(diff.summary().contains("[0/0]: guards 49 -> 4\n"));
+}
+
+#[test]
+fn test_baseline_regressions() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let baseline = tlparse::Baseline::new(&output.metrics);
+    assert_eq!(baseline.frames[0].frame, "[0/-]");
+    assert_eq!(baseline.frames[0].graph_count, 3);
+
+    let parse = |baseline: &tlparse::Baseline| {
+        let config = tlparse::ParseConfig {
+            baseline: Some(baseline.clone()),
+            regression_threshold_pct: Some(50.0),
+            ..Default::default()
+        };
+        tlparse::parse_path(&path, config).unwrap()
+    };
+    let output = parse(&baseline);
+    assert!(output.regressions.is_empty());
+
+    // Within the threshold, and a frame that had no graphs to grow from, aren't regressions
+    let mut old = baseline.clone();
+    old.frames[0].compile_time_s /= 1.4;
+    old.frames[0].graph_count = 1;
+    old.frames[1].graph_count = 0;
+    old.frames[2].compile_time_s /= 2.0;
+    let output = parse(&old);
+    let regressions: Vec<String> = output.regressions.iter().map(|r| r.to_string()).collect();
+    assert_eq!(
+        regressions,
+        vec![
+            "frame [0/-] (forward): graph count 1 -> 3 (+200%)",
+            "frame [2/-] (forward): compile time 0.600s -> 1.200s (+100%)",
+        ]
+    );
+    let index = output
+        .files
+        .iter()
+        .find(|(p, _)| p == Path::new("index.html"))
+        .unwrap()
+        .1
+        .clone();
+    assert!(index.contains("<h2>Regressions</h2>"));
+    assert!(index.contains("<td>0.600s</td><td>1.200s</td><td>+100%</td>"));
+}
+
+#[test]
+fn test_diff_output_dirs() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let root = std::env::temp_dir().join(format!("tlparse_diff_out_{}", std::process::id()));
+    let (old, new) = (root.join("old"), root.join("new"));
+    for dir in [&old, &new] {
+        for (filename, contents) in &output.files {
+            let out_file = dir.join(filename);
+            fs::create_dir_all(out_file.parent().unwrap()).unwrap();
+            fs::write(out_file, contents).unwrap();
+        }
+    }
+    let diff = tlparse::diff_output_dirs(&old, &new).unwrap();
+    assert!(diff.is_empty());
+    // The report metadata isn't compared
+    assert_eq!(diff.unchanged, output.files.len() - 1);
+
+    // A renumbered output is matched up with the old one, and only reported if it changed
+    fs::rename(
+        new.join("-_0_0_1/dynamo_output_graph_0.txt"),
+        new.join("-_0_0_1/dynamo_output_graph_1.txt"),
+    )
+    .unwrap();
+    fs::rename(
+        new.join("-_1_0_1/dynamo_output_graph_5.txt"),
+        new.join("-_1_0_1/dynamo_output_graph_6.txt"),
+    )
+    .unwrap();
+    fs::write(new.join("-_1_0_1/dynamo_output_graph_6.txt"), "changed").unwrap();
+    fs::remove_file(new.join("-_2_0_0/dynamo_guards_13.json")).unwrap();
+    let diff = tlparse::diff_output_dirs(&old, &new).unwrap();
+    assert_eq!(
+        diff.changed,
+        vec![(
+            PathBuf::from("-_1_0_1/dynamo_output_graph_5.txt"),
+            PathBuf::from("-_1_0_1/dynamo_output_graph_6.txt")
+        )]
+    );
+    assert_eq!(
+        diff.removed,
+        vec![PathBuf::from("-_2_0_0/dynamo_guards_13.json")]
+    );
+    assert!(diff.added.is_empty());
+    assert!(diff
+        .summary()
+        .contains("-_1_0_1\n  changed dynamo_output_graph_5.txt -> dynamo_output_graph_6.txt\n"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_snapshot_comp_failure() {
+    // After an intended change to the report, update the snapshot with
+    // TLPARSE_UPDATE_SNAPSHOTS=1 cargo test test_snapshot and review it with git diff
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    tlparse::check_snapshot(&path, config, Path::new("tests/snapshots/comp_failure")).unwrap();
+}
+
+#[test]
+fn test_print_summary() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")].contains("<a href=\"print.html\">"));
+    let print = &map[&PathBuf::from("print.html")];
+    // Restarts are counted with the compilation they restarted
+    assert!(print.contains("<li>3 compilation(s), 0 failed, 2 restarted</li>"));
+    // Nothing is collapsed
+    assert!(print.contains("<details open>"));
+    assert!(print.contains("<h2>Failures and Restarts</h2>"));
+    assert!(print.contains("<tr id=\"cid-0-0-0\">"));
+    assert!(!print.contains("<tr id=\"cid-0-0-1\">"));
+}
+
+#[test]
+fn test_failure_raw_context() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    // The failure links to the lines leading up to its entry
+    let failures = &map[&PathBuf::from("failures_and_restarts.html")];
+    assert!(failures.contains("<a href='raw.html#L135'>around line 145</a>"));
+    let raw = &map[&PathBuf::from("raw.html")];
+    assert!(raw.contains("<h2>Lines 135-155</h2>"));
+    assert!(raw.contains("<span id=\"L145\" class=\"raw-failing\">145: V1206 15:20:14.089000"));
+    assert!(raw.contains("RuntimeError: broken backend"));
+}
+
+#[test]
+fn test_known_issues() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let failures = &map[&PathBuf::from("failures_and_restarts.html")];
+    assert!(failures.contains("<td>The compiler backend raised an exception."));
+
+    // Rules from the config take precedence over the built in ones
+    let config = tlparse::ParseConfig {
+        strict: true,
+        known_issues: vec![tlparse::KnownIssue {
+            pattern: "broken backend".to_string(),
+            explanation: "Our test backend always fails.".to_string(),
+            suggestion: None,
+            link: Some("https://example.com/broken".to_string()),
+        }],
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let failures = &map[&PathBuf::from("failures_and_restarts.html")];
+    assert!(failures.contains(
+        "<td>Our test backend always fails. <a href=\"https://example.com/broken\">[more]</a></td>"
+    ));
+}
+
+#[test]
+fn test_graph_break_advice() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let advice = "<div class=\"graph-break-advice\">The code called torch._dynamo.graph_break() explicitly. <strong>Fix:</strong> Remove the call if the graph break is not intended.</div>";
+    // Inline with the restart reason, both in the failures table and on the metrics page
+    let failures = &map[&PathBuf::from("failures_and_restarts.html")];
+    assert!(failures.contains(&format!("decorators.py'</pre>{advice}</td>")));
+    let metrics = &map[&PathBuf::from("-_0_0_1/compilation_metrics_4.html")];
+    assert!(metrics.contains(advice));
+}
+
+#[test]
+fn test_runtime_asserts() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let (_, metrics) = map
+        .iter()
+        .find(|(k, _)| {
+            k.starts_with("-_3_0_0") && k.to_string_lossy().contains("compilation_metrics")
+        })
+        .unwrap();
+    assert!(metrics.contains("<h2>Runtime asserts</h2>"));
+    assert!(metrics.contains("<td>u0 &gt;= 0</td>"));
+    assert!(metrics.contains("<td>u0 &lt; 16</td>"));
+    assert!(metrics.contains("defer_runtime_assert"));
+    // Ordinary guards aren't runtime asserts
+    assert!(!metrics.contains("Eq(s0, 8)"));
+    // Compilations without runtime asserts don't get the section
+    let (_, metrics) = map
+        .iter()
+        .find(|(k, _)| {
+            k.starts_with("-_0_0_0") && k.to_string_lossy().contains("compilation_metrics")
+        })
+        .unwrap();
+    assert!(!metrics.contains("<h2>Runtime asserts</h2>"));
+}
+
+#[test]
+fn test_guard_construction_summary() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<h2> Guard Construction </h2>"));
+    // Building guards took 0.15s of the 0.2s compile of frame 3
+    assert!(index.contains(
+        "<tr class=\"guard-construction-dominates\">\n<td>[3/-]</td>\n<td>3</td>\n<td>12</td>\n<td>150.0ms</td>\n<td>200.0ms</td>\n<td>75%</td>"
+    ));
+    let (_, metrics) = map
+        .iter()
+        .find(|(k, _)| {
+            k.starts_with("-_3_0_0") && k.to_string_lossy().contains("compilation_metrics")
+        })
+        .unwrap();
+    assert!(metrics.contains("<p>Duplicate Guards Suppressed: 12</p>"));
+    assert!(metrics.contains("<p>Guard Build Time: 150.0ms</p>"));
+
+    // Logs without guard deduplication info don't get the section
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("Guard Construction"));
+}
+
+#[test]
+fn test_recompile_storm() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    // Frame 0 recompiles twice within a few milliseconds
+    let config = tlparse::ParseConfig {
+        strict: true,
+        recompile_storm_threshold: Some(1),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<h2> Recompile Storm </h2>"));
+    assert!(index.contains("recompiled more than 1 times within 1m 0.0s"));
+    assert!(index.contains("<li>Frame <strong>[0/-]</strong>: 2 recompiles between"));
+    // Other frames never recompiled
+    assert!(!index.contains("<strong>[1/-]</strong>"));
+
+    // Two recompiles are not a storm by default
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("Recompile Storm"));
+}
+
+#[test]
+fn test_stable_anchors() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")].contains("<li id=\"cid-0-0-0\">"));
+    assert!(map[&PathBuf::from("failures_and_restarts.html")]
+        .contains("<a id='failure-cid-0-0-0'></a>"));
+    // The stack trie and the compile id's page link to the same anchor
+    assert!(map[&PathBuf::from("index.html")].contains("<a href='#cid-0-0-0' class='status-"));
+    // No output numbers in artifact anchors
+    let compile_id_page = &map[&PathBuf::from("-_0_0_0/index.html")];
+    assert!(compile_id_page.contains("<a href=\"../index.html#cid-0-0-0\">back to index</a>"));
+    assert!(compile_id_page.contains("<li id=\"artifact-dynamo_error.txt\" data-kind=\"other\">"));
+    assert!(compile_id_page
+        .contains("<li id=\"artifact-compilation_metrics.html\" data-kind=\"metrics\">"));
+
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let restarts = &map[&PathBuf::from("failures_and_restarts.html")];
+    assert!(restarts.contains("<a id='restart-cid-0-0-1-1'></a><a id='restart--_0_0_1-1'></a>"));
+    // The anchors from before cid-*, kept as aliases
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<a id=\"compile--_0_0_1\"></a><a id=\"[0/0_1]\">[0/0_1]</a>"));
+
+    // Compiled autograd ids and missing attempts, as rendered
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log")
+        .unwrap()
+        .replace(
+            "\"frame_id\": 1,",
+            "\"compiled_autograd_id\": 0, \"frame_id\": 1,",
+        );
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(map[&PathBuf::from("index.html")].contains("<li id=\"cid-ca0-1-0-0\""));
+    assert!(map[&PathBuf::from("failures_and_restarts.html")]
+        .contains("<a id='restart-cid-ca0-1-0-1-1'></a><a id='restart-0_1_0_1-1'></a>"));
+    let log = fs::read_to_string("tests/inputs/comp_failure.log")
+        .unwrap()
+        .replace(", \"attempt\": 0", "");
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(map[&PathBuf::from("index.html")]
+        .contains("<li id=\"cid-0-0-x\"><a id=\"compile--_0_0_-\"></a>"));
+    assert!(map[&PathBuf::from("failures_and_restarts.html")]
+        .contains("<a id='failure-cid-0-0-x'></a><a id='failure--_0_0_-'></a>"));
+
+    // A missing attempt isn't attempt 0, and compiled autograd ids are kept apart
+    let cid = |compiled_autograd_id, attempt| tlparse::CompileId {
+        compiled_autograd_id,
+        frame_id: Some(2),
+        frame_compile_id: Some(0),
+        attempt,
+    };
+    assert_eq!(cid(None, Some(0)).anchor(), "cid-2-0-0");
+    assert_eq!(cid(None, None).anchor(), "cid-2-0-x");
+    assert_eq!(cid(Some(1), Some(0)).anchor(), "cid-ca1-2-0-0");
+}
+
+#[test]
+fn test_report_metadata() {
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        source_url_template: Some("https://example.com/{filename}#L{line}".to_string()),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let report: serde_json::Value =
+        serde_json::from_str(&map[&PathBuf::from("tlparse_report.json")]).unwrap();
+    assert_eq!(report["tlparse_version"], env!("CARGO_PKG_VERSION"));
+    assert!(report["parsed_at"].as_str().unwrap().ends_with('Z'));
+    assert_eq!(report["input_md5"], "c601683e3cc1336deefcb2b80c9a8db7");
+    assert_eq!(
+        report["input_bytes"],
+        fs::metadata(&path).unwrap().len() as usize
+    );
+    assert_eq!(report["config"]["strict"], true);
+    assert_eq!(
+        report["config"]["source_url_template"],
+        "https://example.com/{filename}#L{line}"
+    );
+    assert_eq!(report["config"]["recompile_storm_threshold"], 10);
+    assert_eq!(report["stats"]["ok"], 64);
+    assert_eq!(report["stats"]["fail_glog"], 0);
+}
+
+#[test]
+fn test_output_sink() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let mut returned: HashMap<PathBuf, String> = tlparse::parse_path(&path, config)
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        output_sink: Some(sender),
+        ..Default::default()
+    };
+    // Everything goes to the sink instead
+    assert!(tlparse::parse_path(&path, config).unwrap().files.is_empty());
+    let mut sent: HashMap<PathBuf, String> = receiver.into_iter().collect();
+
+    // Guards pages sent before their compilation metrics still link to them
+    let guards = &sent[&PathBuf::from("-_0_1_0/dynamo_guards_8.html")];
+    assert!(guards.contains("href=\"compilation_metrics_"));
+    assert!(!guards.contains("href=\"compilation_metrics.html#"));
+    // Except for when each report was generated
+    let metadata = PathBuf::from(tlparse::REPORT_METADATA_PATH);
+    returned.remove(&metadata);
+    sent.remove(&metadata);
+    assert_eq!(returned, sent);
+}
+
+#[test]
+fn test_inductor_kernel_annotations() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let code = &map[&PathBuf::from("-_4_0_0/inductor_output_code_30.html")];
+    assert!(code.contains("<h2>Kernels</h2>"));
+    assert!(code.contains(
+        "<td><code>triton_poi_fused_mul_relu_0</code></td>\n        <td>triton</td>\n        <td><code>y</code> <code>z</code> </td>\n        <td><code>aten.mul</code> <code>aten.relu</code> </td>"
+    ));
+    assert!(code.contains("Estimated runtime: <code>0.012 ms</code>"));
+    // Launched twice, and the kernel's own def in its source doesn't count
+    assert!(code.contains("<br></td>\n        <td>2</td>"));
+    assert!(code.contains("<li><code>buf2 = buf0; del buf0  # reuse</code></li>"));
+    // The multi-line comment sections aren't annotations
+    assert!(!code.contains("Graph fragment: <code>"));
+}
+
+#[test]
+fn test_render_budget() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    // Out of time before the first line, so the code is written as plain text
+    let config = tlparse::ParseConfig {
+        render_budget_s: Some(0.0),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map.contains_key(&PathBuf::from("-_4_0_0/inductor_output_code_30.html")));
+    let code = &map[&PathBuf::from("-_4_0_0/inductor_output_code_30.txt")];
+    assert!(code.contains("triton_poi_fused_mul_relu_0"));
+    assert!(map[&PathBuf::from("index.html")].contains("inductor_output_code_30.txt"));
+}
+
+#[test]
+fn test_inductor_output_code_source() {
+    // The same tmp file is written to by two compilations
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let code: Vec<String> = map
+        .keys()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .filter(|p| p.starts_with("inductor_output_code"))
+        .collect();
+    assert_eq!(code.len(), 3);
+    // Named by the compile id directory they're in, not by the tmp file
+    assert!(code.iter().all(|p| {
+        p.strip_prefix("inductor_output_code_")
+            .and_then(|p| p.strip_suffix(".html"))
+            .is_some_and(|n| n.parse::<u32>().is_ok())
+    }));
+    // which is kept as a tooltip
+    let source = "/tmp/tmpsn77tlsm/sm/csmb7f23sz2oa7mngdcoprpgrrbziqzirwhkjffdmxv6qhdf3e57.py";
+    let index = &map[&PathBuf::from("index.html")];
+    assert_eq!(index.matches(&format!("title=\"{source}\"")).count(), 2);
+}
+
+#[test]
+fn test_abandoned_attempts() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    // [0/0] restarted, and [0/0_1] finished the compilation
+    assert!(index.contains("<li id=\"cid-0-0-0\" class=\"abandoned-attempt\">"));
+    assert!(index.contains("abandoned attempt, restarted as <a href='#cid-0-0-1'>[0/0_1]</a>"));
+    assert!(index.contains("<li id=\"cid-0-0-1\" data-backend=\"other\"><a id=\"compile--_0_0_1\"></a><a id=\"[0/0_1]\">[0/0_1]</a>"));
+    assert!(!index.contains("<summary>Artifacts of the abandoned attempt</summary>"));
+
+    // Nothing to collapse without artifacts
+    let config = tlparse::ParseConfig {
+        strict: true,
+        hide_abandoned_attempts: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("<details><summary>Artifacts"));
+
+    // [5/0] logged an output graph before it restarted
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        hide_abandoned_attempts: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let abandoned = &index[index.find("<li id=\"cid-5-0-0\"").unwrap()..];
+    let (abandoned, last) = abandoned.split_at(abandoned.find("<li id=\"cid-5-0-1\"").unwrap());
+    assert!(abandoned.contains("<details><summary>Artifacts of the abandoned attempt</summary>"));
+    assert!(abandoned.contains("-_5_0_0/dynamo_output_graph_32.txt"));
+    // The final attempt's artifacts stay visible
+    assert!(last.contains("-_5_0_1/dynamo_output_graph_"));
+    assert!(!last.contains("<details>"));
+}
+
+#[test]
+fn test_guards_by_source() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let guards = &map[&PathBuf::from("-_5_0_1/dynamo_guards_37.html")];
+    assert!(guards.contains("<h2>Guards by Source</h2>"));
+    // Attribute chains and method calls are attributed to the model attribute
+    assert!(guards.contains(
+        "<td><code>L[&#39;self&#39;].module</code></td>\n<td>3</td>\n<td><a href='#guard-0'>#0</a> <a href='#guard-1'>#1</a> <a href='#guard-4'>#4</a> </td>"
+    ));
+    assert!(guards.contains("<td><code>L[&#39;self&#39;]._modules[&#39;layer1&#39;]</code></td>"));
+    assert!(guards.contains("<td><code>L[&#39;x&#39;]</code></td>\n<td>1</td>"));
+    // Most guarded first
+    let module = guards.find("L[&#39;self&#39;].module</code>").unwrap();
+    let x = guards.find("<td><code>L[&#39;x&#39;]</code>").unwrap();
+    assert!(module < x);
+}
+
+#[test]
+fn test_top_guard_sources() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let table = &index[index.find("<h2> Top Guard Sources </h2>").unwrap()..];
+    let table = &table[..table.find("</table>").unwrap()];
+    // Size guards on L['x'] across every recompile count against L['x'] itself
+    assert!(table.contains("<tr><td><code>L[&#39;x&#39;]</code></td><td>14</td><td>5</td></tr>"));
+    assert!(table.contains(
+        "<tr><td><code>L[&#39;self&#39;].module.weight</code></td><td>2</td><td>1</td></tr>"
+    ));
+    assert!(!table.contains("size()"));
+}
+
+#[test]
+fn test_specialization_recompiles() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")]
+        .contains("<a href=\"specialization_recompiles.html\">2 recompile(s)</a>"));
+    // Frame 6 specializes x.size(0) = 8 in [6/0], which fails with 16 and causes [6/1]; that in turn
+    // specializes x.size(1) = 4, whose shape env guard fails and causes [6/2]
+    let page = &map[&PathBuf::from("specialization_recompiles.html")];
+    assert!(page.contains("<h2>Frame [6/-]</h2>"));
+    let first = page.find("<code>s0 = 8</code>").unwrap();
+    let second = page.find("<code>s1 = 4</code>").unwrap();
+    assert!(first < second);
+    assert!(page[first..second].contains("guard failed with <code>16</code>"));
+    assert!(page[first..second].contains("<a href=\"-_6_1_0/index.html\">[6/1]</a>"));
+    assert!(page[second..].contains("<a href=\"-_6_2_0/index.html\">[6/2]</a>"));
+    // [0/1] recompiled on x.size(0), which [0/0] never specialized
+    assert!(!page.contains("[0/"));
+}
+
+#[test]
+fn test_frame_history() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(
+        map[&PathBuf::from("index.html")].contains("<a href=\"frame_0.html\">[frame history]</a>")
+    );
+    assert!(map[&PathBuf::from("-_0_1_0/index.html")]
+        .contains("<a href=\"../frame_0.html\">frame history</a>"));
+    let page = &map[&PathBuf::from("frame_0.html")];
+    assert!(page.contains("<h1>Frame [0/-]: <code>forward</code></h1>"));
+    // Compile ids in order, with their compile time and guard counts
+    let rows: Vec<&str> = page.split("<tr>").skip(2).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].contains("<a href=\"-_0_0_0/index.html\">[0/0]</a>"));
+    assert!(rows[1].contains("<td>1.2s</td>"));
+    assert!(rows[1].contains("<a href=\"-_0_1_0/dynamo_guards_8.html\">6</a>"));
+    // What changed, and how the guards differ from the previous compile id
+    assert!(rows[1].contains("L[&#39;x&#39;].size()[0] changed 8 → 16"));
+    assert!(rows[1].contains("+3, -1"));
+    assert!(rows[2].contains("+2, -0"));
+    assert!(rows[2].contains("<code>L[&#39;y&#39;].size()[0] == 3</code>"));
+    // Attempts are listed too
+    let page = &map[&PathBuf::from("frame_5.html")];
+    assert!(page.contains("[5/0_1]"));
+}
+
+#[test]
+fn test_restart_time_waste() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    // Both frames restarted once; the one that wasted the most time comes first
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("This run spent <strong>9.3ms</strong> on analysis"));
+    let first =
+        index.find("<tr><td>[0/-]</td><td><code>fn</code></td><td>6.7ms</td><td>1</td></tr>");
+    let second = index.find("<tr><td>[1/-]</td>");
+    assert!(first.is_some() && second.is_some() && first < second);
+    assert!(map[&PathBuf::from("print.html")]
+        .contains("<li>Time wasted on restarted analysis: 9.3ms</li>"));
+
+    // Nothing is shown for a log without restarts
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("Time Wasted on Restarted Analysis"));
+}
+
+#[test]
+fn test_metrics_columns() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let csv = &map[&PathBuf::from("compilation_metrics.csv")];
+    assert!(csv.starts_with("compile_id,status,entire_frame_compile_time_s,backend_compile_time_s,graph_op_count,guard_count,fail_type\n"));
+    assert_eq!(csv.lines().count(), 4);
+    assert!(map[&PathBuf::from("index.html")].contains("<th>graph_op_count</th>"));
+
+    // Chosen columns, in the order given; lists are quoted since they contain commas
+    let config = tlparse::ParseConfig {
+        strict: true,
+        metrics_columns: Some(vec!["co_name".to_string(), "restart_reasons".to_string()]),
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let csv = &map[&PathBuf::from("compilation_metrics.csv")];
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "compile_id,status,co_name,restart_reasons");
+    assert!(lines[1].starts_with("[0/0],restarted,fn,\"[\"\"'skip function graph_break"));
+    assert_eq!(lines[3], "[2/0],ok,torch_dynamo_resume_in_fn_at_9553,[]");
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<th>co_name</th><th>restart_reasons</th></tr>"));
+    assert!(!index.contains("<th>graph_op_count</th>"));
+
+    // Unknown fields are rejected
+    let config = tlparse::ParseConfig {
+        metrics_columns: Some(vec!["bogus".to_string()]),
+        ..Default::default()
+    };
+    let err = tlparse::parse_path(&path, config).err().unwrap();
+    assert!(err.to_string().contains("Unknown metrics column bogus"));
+}
+
+#[test]
+fn test_graph_count_mismatches() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    // Only [7/0], whose output graph was cut off, is flagged; [5/0] restarted, so its metrics
+    // don't describe the graph that was logged
+    let index = &map[&PathBuf::from("index.html")];
+    let section = &index[index.find("<h2> Graph Size Mismatches </h2>").unwrap()..];
+    let section = &section[..section.find("</table>").unwrap()];
+    assert_eq!(section.matches("<tr><td>").count(), 1);
+    assert!(section.contains(
+        "<tr><td><a href=\"-_7_0_0/index.html\">[7/0]</a></td><td>3</td><td>2</td><td>5</td><td>3</td></tr>"
+    ));
+
+    // Metrics and graphs from torch agree
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(!map[&PathBuf::from("index.html")].contains("Graph Size Mismatches"));
+}
+
+#[test]
+fn test_artifact_kinds() {
+    let kind = |log: &str, name: &str| {
+        let path = Path::new(log).to_path_buf();
+        let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+        let map: HashMap<PathBuf, String> = output.into_iter().collect();
+        let index = &map[&PathBuf::from("index.html")];
+        // The build products listing, after e.g. the external links table
+        let index = &index[index.find("<li data-kind=\"").unwrap()..];
+        let end = index.find(&format!("\">{name}")).unwrap();
+        let start = index[..end].rfind("<li data-kind=\"").unwrap() + "<li data-kind=\"".len();
+        index[start..start + index[start..].find('"').unwrap()].to_string()
+    };
+    let log = "tests/inputs/cache_hit_miss.log";
+    assert_eq!(kind(log, "-_0_0_0/dynamo_output_graph_0.txt"), "graph");
+    assert_eq!(kind(log, "-_0_0_0/compilation_metrics_3.html"), "metrics");
+    assert_eq!(kind(log, "-_1_0_0/fx_graph_runnable_8.txt"), "graph");
+    assert_eq!(kind(log, "-_1_0_0/inductor_output_code_"), "code");
+    assert_eq!(kind(log, "-_1_0_0/fx_graph_cache_miss_11.json"), "cache");
+    let log = "tests/inputs/links.log";
+    assert_eq!(kind(log, "-_0_0_0/dynamo_guards_6.html"), "guards");
+    assert_eq!(kind(log, "-_0_0_0/dynamo_cpp_guards_str_8.txt"), "guards");
+    assert_eq!(kind(log, "manifold_url"), "link");
+}
+
+#[test]
+fn test_external_links() {
+    let log = fs::read_to_string("tests/inputs/links.log").unwrap();
+    let link = r#"{"link": {"name": "manifold_url", "url": "https://www.google.com"}"#;
+    assert!(log.contains(link));
+    let log = log.replace(
+        link,
+        r#"{"link": {"name": "manifold_url", "url": "https://www.google.com"}, "frame_id": 0, "frame_compile_id": 0, "attempt": 0}
+V0516 11:47:27.931000 139733182882816 torch/_functorch/aot_autograd.py:887] {"link": {"name": "trace", "url": "https://example.com/trace", "category": "profiler trace", "description": "Kineto trace of the first run"}"#,
+    );
+    let output = tlparse::parse_reader(log.as_bytes(), tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let links = &index[index.find("<h2> External Links </h2>").unwrap()..];
+    let links = &links[..links.find("</table>").unwrap()];
+    // Categorized links first
+    let trace = links
+        .find("<span class=\"link-category\">profiler trace</span></td><td><a href=\"https://example.com/trace\">trace</a>: Kineto trace of the first run</td><td><a href=\"-_0_0_0/index.html\">[0/0]</a></td>")
+        .unwrap();
+    assert!(trace < links.find("manifold_url").unwrap());
+    // and on the compile id's own page
+    let landing = &map[&PathBuf::from("-_0_0_0/index.html")];
+    assert!(landing.contains("<h2>External Links</h2>"));
+    assert!(landing.contains("<li><a href=\"https://www.google.com\">manifold_url</a></li>"));
+
+    // Logs without links don't get the section
+    let output = tlparse::parse_path(
+        &PathBuf::from("tests/inputs/simple.log"),
+        tlparse::ParseConfig::default(),
+    )
+    .unwrap();
+    assert!(!output
+        .files
+        .iter()
+        .any(|(p, c)| p == Path::new("index.html") && c.contains("External Links")));
+}
+
+#[test]
+fn test_link_rewrites() {
+    let path = Path::new("tests/inputs/links.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        custom_header_html: "<a href='https://www.google.com/dash'>Dashboard</a> \
+            <img src=\"https://example.com/logo.png\">"
+            .to_string(),
+        link_rewrites: vec![
+            tlparse::LinkRewrite {
+                pattern: r"^https://www\.google\.com(/.*)?$".to_string(),
+                replacement: "https://proxy.example.com/google$1".to_string(),
+            },
+            // Applied after the first
+            tlparse::LinkRewrite {
+                pattern: r"^https://proxy\.example\.com/".to_string(),
+                replacement: "https://proxy2.example.com/".to_string(),
+            },
+        ],
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<a href=\"https://proxy2.example.com/google\">manifold_url</a>"));
+    assert!(index.contains("<a href='https://proxy2.example.com/google/dash'>Dashboard</a>"));
+    // Links that don't match are left alone
+    assert!(index.contains("<img src=\"https://example.com/logo.png\">"));
+    assert!(!index.contains("https://www.google.com"));
+    let landing = &map[&PathBuf::from("-_0_0_0/index.html")];
+    assert!(landing.contains("<a href=\"https://proxy2.example.com/google\">manifold_url</a>"));
+
+    let config = tlparse::ParseConfig {
+        link_rewrites: vec![tlparse::LinkRewrite {
+            pattern: "(".to_string(),
+            replacement: "".to_string(),
+        }],
+        ..Default::default()
+    };
+    assert!(tlparse::parse_path(&path, config).is_err());
+}
+
+#[test]
+fn test_phase_groups() {
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    // The last compile id
+    let entry = &index[index.find("<li id=\"cid-1-0-0\"").unwrap()..];
+    let pos = |s: &str| entry.find(s).unwrap();
+    // Headings in pipeline order
+    let headings: Vec<&str> = entry
+        .split("<span class=\"phase-heading\">")
+        .skip(1)
+        .map(|h| &h[..h.find('<').unwrap()])
+        .collect();
+    assert_eq!(headings, ["Dynamo", "AOTAutograd", "Inductor", "Caching"]);
+    // Files are grouped by phase rather than listed in the order they were written
+    assert!(pos("compilation_metrics_42.html") < pos("aot_inference_graph_7.txt"));
+    assert!(pos("inductor_output_code_") < pos("fx_graph_cache_miss_11.json"));
+    // but keep that order within a phase
+    assert!(pos("fx_graph_cache_miss_11.json") < pos("fx_graph_cache_hit_24.json"));
+}
+
+#[test]
+fn test_compile_id_summary() {
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let summary: serde_json::Value =
+        serde_json::from_str(&map[&PathBuf::from("-_1_0_0/summary.json")]).unwrap();
+    assert_eq!(summary["compile_id"], "[1/0]");
+    assert_eq!(summary["status"], "ok");
+    assert_eq!(summary["co_name"], "fn2");
+    assert_eq!(summary["graph_op_count"], 2);
+    assert_eq!(summary["guard_count"], 91);
+    // Artifact urls are relative to the compile id directory
+    let artifacts = summary["artifacts"].as_array().unwrap();
+    let cache_miss = artifacts
+        .iter()
+        .find(|a| a["url"] == "fx_graph_cache_miss_11.json")
+        .unwrap();
+    assert_eq!(cache_miss["kind"], "cache");
+    assert_eq!(cache_miss["phase"], "caching");
+    assert!(map.contains_key(&PathBuf::from("-_1_0_0/fx_graph_cache_miss_11.json")));
+    // Every compile id has one
+    assert!(map.contains_key(&PathBuf::from("-_0_0_0/summary.json")));
+}
+
+#[test]
+fn test_json_output() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    assert!(!output
+        .files
+        .iter()
+        .any(|(p, _)| p == Path::new(tlparse::JSON_OUTPUT_PATH)));
+
+    let json = tlparse::parse_path_json(&path, tlparse::ParseConfig::default()).unwrap();
+    assert_eq!(json["stats"]["fail_json"], 0);
+    let compile_ids = json["compile_ids"].as_array().unwrap();
+    assert_eq!(compile_ids.len(), 1);
+    let cid = &compile_ids[0];
+    assert_eq!(cid["compile_id"], "[0/0]");
+    assert_eq!(cid["dir"], "-_0_0_0");
+    assert_eq!(cid["status"], "failed");
+    assert!(cid["metrics"][0]["fail_type"].is_string());
+    assert!(cid["artifacts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|a| a["url"] == "dynamo_error_2.txt"));
+    let failures = json["failures"].as_array().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["kind"], "failure");
+    assert_eq!(failures[0]["compile_id"], "[0/0]");
+    assert_eq!(failures[0]["fail_type"], cid["fail_type"]);
+
+    // Guards are included in full
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let json = tlparse::parse_path_json(&path, tlparse::ParseConfig::default()).unwrap();
+    let cid = json["compile_ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["compile_id"] == "[2/0]")
+        .unwrap();
+    let guards = cid["guards"].as_array().unwrap();
+    assert_eq!(guards.len(), 3);
+    assert!(guards[0]["code"].is_string());
+}
+
+#[test]
+fn test_max_compiles() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let parse = |max_compiles| {
+        let config = tlparse::ParseConfig {
+            max_compiles,
+            ..Default::default()
+        };
+        let output = tlparse::parse_path(&path, config).unwrap();
+        output.into_iter().collect::<HashMap<PathBuf, String>>()
+    };
+    let full = parse(None);
+    let sampled = parse(Some(2));
+    let artifacts = |map: &HashMap<PathBuf, String>, dir: &str| {
+        map.keys()
+            .filter(|p| p.starts_with(dir))
+            .filter(|p| !p.ends_with("index.html") && !p.ends_with("summary.json"))
+            .count()
+    };
+    // The first two compile ids are rendered as usual
+    assert_eq!(artifacts(&sampled, "-_0_0_0"), artifacts(&full, "-_0_0_0"));
+    assert_eq!(artifacts(&sampled, "-_0_0_1"), artifacts(&full, "-_0_0_1"));
+    // the rest only get their landing pages
+    assert!(artifacts(&full, "-_1_0_1") > 0);
+    assert_eq!(artifacts(&sampled, "-_1_0_1"), 0);
+    assert!(sampled.contains_key(&PathBuf::from("-_1_0_1/index.html")));
+    let index = &sampled[&PathBuf::from("index.html")];
+    assert!(index.contains("Only the artifacts of the first 2 compile ids were rendered"));
+    assert!(index.contains("3 compile ids below have no artifacts"));
+    assert!(!full[&PathBuf::from("index.html")].contains("unrendered-compiles"));
+    // Failures still cover every compilation, linking to the landing page when there is no
+    // metrics page
+    let failures = &sampled[&PathBuf::from("failures_and_restarts.html")];
+    assert!(failures.contains("<a href='-_1_0_1/index.html'>[1/0_1]</a>"));
+    assert_eq!(
+        sampled[&PathBuf::from(tlparse::METRICS_CSV_PATH)]
+            .lines()
+            .count(),
+        full[&PathBuf::from(tlparse::METRICS_CSV_PATH)]
+            .lines()
+            .count()
+    );
+}
+
+#[test]
+fn test_inline_previews() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let item = |name: &str| {
+        let start = index.find(&format!("<a href=\"{name}\"")).unwrap();
+        &index[start..start + index[start..].find("</li>").unwrap()]
+    };
+    // Small text outputs are previewed inline
+    assert!(item("-_0_1_0/recompile_reasons_5.json").contains(
+        "<details class=\"inline-preview\"><summary>preview</summary><pre>[\n  &quot;tensor &#39;L[&#39;x&#39;]&#39; size mismatch"
+    ));
+    assert!(item("-_0_0_0/dynamo_output_graph_0.txt").contains("inline-preview"));
+    // HTML outputs are not, since they are pages in their own right
+    assert!(!item("-_0_0_0/dynamo_guards_2.html").contains("inline-preview"));
+    // Nor are outputs that are too big to be worth inlining
+    let inductor = map
+        .keys()
+        .find(|k| {
+            k.to_string_lossy()
+                .starts_with("-_4_0_0/inductor_output_code")
+        })
+        .unwrap();
+    assert!(map[inductor].len() > 1024);
+    assert!(!item(&inductor.to_string_lossy()).contains("inline-preview"));
+}
+
+#[test]
+fn test_tensor_catalog() {
+    let path = Path::new("tests/inputs/dynamic_shapes.log").to_path_buf();
+    let config = tlparse::ParseConfig {
+        strict: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    assert!(output.is_ok());
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")]
+        .contains("<a href=\"tensor_catalog.html\">7 distinct shape(s) and dtype(s)</a>"));
+    let catalog = &map[&PathBuf::from("tensor_catalog.html")];
+    // f32[8, 4] was an input of four compilations, in three different frames, and is the most common
+    let rows: Vec<&str> = catalog.split("<tr>").skip(2).collect();
+    assert!(rows[0].contains("<code>[8, 4]</code>"));
+    assert!(rows[0].contains("<td>4</td>"));
+    // x's shape varied within frames 0, 2 and 6, but each frame is listed on its own
+    let varying = &catalog[catalog.find("Inputs With Varying Shapes").unwrap()..];
+    assert_eq!(varying.matches("<code>L[&#39;x&#39;]</code>").count(), 3);
+    assert!(varying.contains("<a href=\"frame_2.html\">[2/-]</a>"));
+    assert!(varying.contains(
+        "<div><code>torch.float32[8, 4]</code></div><div><code>torch.float32[16, 4]</code></div>"
+    ));
+    // y only ever had one shape
+    assert!(!varying.contains("L[&#39;y&#39;]"));
+}
+
+#[test]
+fn test_no_verify_payload() {
+    // A payload whose md5 doesn't match
+    let log = fs::read_to_string("tests/inputs/simple.log").unwrap();
+    let start = log.find("\"has_payload\": \"").unwrap() + "\"has_payload\": \"".len();
+    let log = format!("{}{}{}", &log[..start], "0".repeat(32), &log[start + 32..]);
+    let fail_payload_md5 = |output: &[(PathBuf, String)]| {
+        let (_, report) = output
+            .iter()
+            .find(|(p, _)| p == Path::new(tlparse::REPORT_METADATA_PATH))
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(report).unwrap();
+        report["stats"]["fail_payload_md5"].as_u64().unwrap()
+    };
+    let output = tlparse::parse_reader(log.as_bytes(), tlparse::ParseConfig::default()).unwrap();
+    assert_eq!(fail_payload_md5(&output.files), 1);
+    let verified: HashMap<PathBuf, String> = output.into_iter().collect();
+
+    let config = tlparse::ParseConfig {
+        no_verify_payload: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_reader(log.as_bytes(), config).unwrap();
+    assert_eq!(fail_payload_md5(&output.files), 0);
+    // Otherwise the report is the same
+    let unverified: HashMap<PathBuf, String> = output.into_iter().collect();
+    let metadata = PathBuf::from(tlparse::REPORT_METADATA_PATH);
+    assert_eq!(
+        verified.keys().filter(|p| **p != metadata).count(),
+        unverified.keys().filter(|p| **p != metadata).count()
+    );
+    assert_eq!(
+        verified[&PathBuf::from("index.html")],
+        unverified[&PathBuf::from("index.html")]
+    );
+}
+
+#[test]
+fn test_stitched_payload() {
+    let log = fs::read_to_string("tests/inputs/simple.log").unwrap();
+    let stats = |output: &[(PathBuf, String)]| {
+        let (_, report) = output
+            .iter()
+            .find(|(p, _)| p == Path::new(tlparse::REPORT_METADATA_PATH))
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(report).unwrap();
+        report["stats"].clone()
+    };
+    let graph = |output: &[(PathBuf, String)]| {
+        output
+            .iter()
+            .find(|(p, _)| p.starts_with("-_0_0_0") && p.ends_with("dynamo_output_graph_0.txt"))
+            .map(|(_, contents)| contents.clone())
+            .unwrap()
+    };
+    let expected = tlparse::parse_reader(log.as_bytes(), tlparse::ParseConfig::default()).unwrap();
+    // Interrupt the output graph's payload after its third line
+    let interrupt = |lines: &[&str]| {
+        let start = log.find("{\"dynamo_output_graph\"").unwrap();
+        let mut at = start;
+        for _ in 0..4 {
+            at += log[at..].find('\n').unwrap() + 1;
+        }
+        format!("{}{}{}", &log[..at], lines.join(""), &log[at..])
+    };
+    let artifact = "V1206 15:18:20.000000 1500233 torch/_dynamo/utils.py:1288] {\"artifact\": \
+        {\"name\": \"interleaved\", \"encoding\": \"string\"}, \"frame_id\": 0, \
+        \"frame_compile_id\": 0, \"attempt\": 0}\n";
+
+    let output = tlparse::parse_reader(
+        interrupt(&[artifact]).as_bytes(),
+        tlparse::ParseConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(stats(&output.files)["fail_payload_md5"], 0);
+    assert_eq!(stats(&output.files)["stitched_payload"], 1);
+    assert_eq!(graph(&output.files), graph(&expected.files));
+    // The interrupting line is parsed as usual
+    assert!(output
+        .files
+        .iter()
+        .any(|(p, _)| p.to_string_lossy().contains("interleaved")));
+
+    // Too many lines in between to look past
+    let output = tlparse::parse_reader(
+        interrupt(&[artifact; 9]).as_bytes(),
+        tlparse::ParseConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(stats(&output.files)["fail_payload_md5"], 1);
+    assert_eq!(stats(&output.files)["stitched_payload"], 0);
+}
+
+#[test]
+fn test_verify_payload_min_bytes() {
+    // A payload whose md5 doesn't match, as in test_no_verify_payload
+    let log = fs::read_to_string("tests/inputs/simple.log").unwrap();
+    let start = log.find("\"has_payload\": \"").unwrap() + "\"has_payload\": \"".len();
+    let log = format!("{}{}{}", &log[..start], "0".repeat(32), &log[start + 32..]);
+    // Size of that payload, from its tab indented lines
+    let payload_len = log[start..]
+        .lines()
+        .skip(1)
+        .take_while(|l| l.starts_with('\t'))
+        .map(|l| l.len())
+        .sum::<usize>()
+        - 1;
+    let fail_payload_md5 = |min_bytes: usize| {
+        let config = tlparse::ParseConfig {
+            verify_payload_min_bytes: Some(min_bytes),
+            ..Default::default()
+        };
+        let output = tlparse::parse_reader(log.as_bytes(), config).unwrap();
+        let (_, report) = output
+            .files
+            .iter()
+            .find(|(p, _)| p == Path::new(tlparse::REPORT_METADATA_PATH))
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(report).unwrap();
+        assert_eq!(report["config"]["verify_payload_min_bytes"], min_bytes);
+        report["stats"]["fail_payload_md5"].as_u64().unwrap()
+    };
+    assert_eq!(fail_payload_md5(payload_len), 1);
+    assert_eq!(fail_payload_md5(payload_len + 1), 0);
+}
+
+#[test]
+fn test_missing_interned_strs() {
+    let log = |missing: u32, late: u32| {
+        let log = fs::read_to_string("tests/inputs/simple.log")
+            .unwrap()
+            .replace("\"filename\": 1}", &format!("\"filename\": {missing}}}"))
+            .replace("\"filename\": 2}", &format!("\"filename\": {late}}}"));
+        format!(
+            "{log}\nV1206 15:18:30.000000 1500233 torch/_logging/structured.py:22] \
+             {{\"str\": [\"/data/users/xmfan/a/pytorch/test/late_test_case.py\", {late}]}}\n"
+        )
+    };
+
+    let output =
+        tlparse::parse_reader(log(9000001, 9000002).as_bytes(), Default::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("Missing Interned Strings"));
+    assert!(index.contains("<tr><td>9000001</td>"));
+    assert!(!index.contains("<tr><td>9000002</td>"));
+    assert!(index.contains("1 file names were referred to by stack frames before"));
+    assert!(index.contains("(unknown)"));
+    // The first reference is shown in raw.html
+    assert!(map.contains_key(&PathBuf::from("raw.html")));
+
+    let path = std::env::temp_dir().join("tlparse_test_missing_interned_strs.log");
+    fs::write(&path, log(9000003, 9000004)).unwrap();
+    let config = tlparse::ParseConfig {
+        two_pass: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config).unwrap();
+    fs::remove_file(&path).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<tr><td>9000003</td>"));
+    // The late str entry was read ahead of the frames referring to it
+    assert!(!index.contains("before their <code>str</code> entry"));
+    assert!(index.contains("late_test_case.py"));
+}
+
+#[test]
+fn test_log_dir() {
+    let dir = std::env::temp_dir().join("tlparse_test_log_dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // Neither log has ranks in its entries, so they are told apart by their file names
+    fs::copy(
+        "tests/inputs/simple.log",
+        dir.join("dedicated_log_torch_trace_rank_0_abc.log"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/inputs/cache_hit_miss.log",
+        dir.join("dedicated_log_torch_trace_rank_1_def.log"),
+    )
+    .unwrap();
+    fs::write(dir.join("notes.txt"), "not a log").unwrap();
+    let result = tlparse::parse_path_with_stats(&dir, Default::default());
+    fs::remove_dir_all(&dir).unwrap();
+    let result = result.unwrap();
+
+    let expected = |path: &str| {
+        tlparse::parse_path_with_stats(&PathBuf::from(path), Default::default())
+            .unwrap()
+            .output
+            .stats
+            .ok
+    };
+    assert_eq!(
+        result.output.stats.ok,
+        expected("tests/inputs/simple.log") + expected("tests/inputs/cache_hit_miss.log")
+    );
+    let map: HashMap<PathBuf, String> = result.output.into_iter().collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("This log has entries from 2 ranks"));
+    assert!(map.contains_key(&PathBuf::from("rank_0/-_0_0_0/index.html")));
+    assert!(map.contains_key(&PathBuf::from("rank_1/-_1_0_0/index.html")));
+    assert!(!map.contains_key(&PathBuf::from("no_rank/index.html")));
+    // Both ranks have [0/0], which is listed once, linking to each
+    let row = index
+        .lines()
+        .find(|l| l.contains("<td>[0/0]</td> <td>"))
+        .unwrap();
+    assert!(row.contains(r#"<a href="rank_0/-_0_0_0/index.html""#));
+    assert!(row.contains(r#"<a href="rank_1/-_0_0_0/index.html""#));
+}
+
+#[test]
+fn test_log_dir_segments() {
+    use std::io::Write;
+    let config = || tlparse::ParseConfig {
+        all_ranks: true,
+        ..Default::default()
+    };
+    let multi_rank =
+        tlparse::parse_path_with_stats(&PathBuf::from("tests/inputs/multi_rank.log"), config())
+            .unwrap();
+    let rank_0_ok = multi_rank.output.ranks[&Some(0)].stats.ok;
+    let rank_2_ok = multi_rank.output.ranks[&Some(2)].stats.ok;
+    let files: HashMap<PathBuf, String> = multi_rank.output.into_iter().collect();
+
+    let dir = temp_path("log_dir_segments");
+    fs::create_dir_all(&dir).unwrap();
+    // Rank 0's own log doesn't end with a newline, and is followed by rank 0's entries from a
+    // compressed log of every rank
+    let rank_0_log = &files[&PathBuf::from("rank_0/raw.log")];
+    fs::write(dir.join("a_rank_0.log"), rank_0_log.trim_end()).unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&fs::read("tests/inputs/multi_rank.log").unwrap())
+        .unwrap();
+    fs::write(dir.join("b.log.gz"), encoder.finish().unwrap()).unwrap();
+    let result = tlparse::parse_path_with_stats(&dir, config());
+    fs::remove_dir_all(&dir).unwrap();
+    let result = result.unwrap();
+
+    let rank_0 = &result.output.ranks[&Some(0)].stats;
+    assert_eq!(rank_0.ok, 2 * rank_0_ok);
+    assert_eq!(rank_0.fail_glog + rank_0.fail_json, 0);
+    assert_eq!(result.output.ranks[&Some(2)].stats.ok, rank_2_ok);
+}
+
+#[test]
+fn test_two_pass() {
+    // Log [0/1]'s specialization after its compilation metrics
+    let log = fs::read_to_string("tests/inputs/dynamic_shapes.log").unwrap();
+    let mut lines: Vec<&str> = log.lines().collect();
+    let specialization = lines.remove(32);
+    assert!(specialization.contains("symbolic_shape_specialization"));
+    let metrics = lines
+        .iter()
+        .position(|l| l.contains("compilation_metrics") && l.contains("\"frame_compile_id\": 1"))
+        .unwrap();
+    lines.insert(metrics + 1, specialization);
+    let path = std::env::temp_dir().join("tlparse_test_two_pass.log");
+    fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+    let metrics_page = |two_pass: bool| {
+        let config = tlparse::ParseConfig {
+            two_pass,
+            ..Default::default()
+        };
+        let output = tlparse::parse_path(&path, config).unwrap();
+        let (_, contents) = output
+            .into_iter()
+            .find(|(p, _)| {
+                p.starts_with("-_0_1_0")
+                    && p.to_string_lossy().contains("compilation_metrics")
+                    && p.extension().is_some_and(|ext| ext == "html")
+            })
+            .unwrap();
+        contents
+    };
+    let one_pass = metrics_page(false);
+    let two_pass = metrics_page(true);
+    fs::remove_file(&path).unwrap();
+    assert!(!one_pass.contains("evaluate_expr"));
+    assert!(two_pass.contains("evaluate_expr"));
+
+    // Reading logs from a stream only allows for a single pass
+    let config = tlparse::ParseConfig {
+        two_pass: true,
+        ..Default::default()
+    };
+    assert!(tlparse::parse_reader(log.as_bytes(), config).is_err());
+}
+
+#[test]
+fn test_two_pass_entries_and_ranks() {
+    // A line that isn't an entry, though it looks like one after its "] ", defining a file name only
+    // rank 0's frame refers to, and rank 1 without str entries of its own
+    let garbage = "[rank0]: oops] {\"str\": [\"/data/users/dev/bogus.py\", 7], \"rank\": 0}";
+    let log: String = fs::read_to_string("tests/inputs/multi_rank.log")
+        .unwrap()
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| *i != 6 && *i != 7)
+        .map(|(i, line)| match i {
+            1 => format!("{line}\n{garbage}\n"),
+            2 => line.replace("\"filename\": 1}]", "\"filename\": 7}]") + "\n",
+            _ => line.to_string() + "\n",
+        })
+        .collect();
+    let path = temp_path("two_pass_entries_and_ranks.log");
+    fs::write(&path, log).unwrap();
+    let config = tlparse::ParseConfig {
+        two_pass: true,
+        all_ranks: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    fs::remove_file(&path).unwrap();
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    let rank0 = &map[&PathBuf::from("rank_0/index.html")];
+    assert!(rank0.contains("train.py:100 in train_step"));
+    assert!(rank0.contains("(unknown):465 in _fn"));
+    assert!(!rank0.contains("bogus.py"));
+    // Rank 0's str entries don't stand in for the ones rank 1 never logged
+    let rank1 = &map[&PathBuf::from("rank_1/index.html")];
+    assert!(!rank1.contains("train.py"));
+    assert!(rank1.contains("(unknown):100 in train_step"));
+}
+
+#[test]
+fn test_compile_divergence() {
+    // Rank 1 ends up with more guards on its first compile id
+    let log: String = fs::read_to_string("tests/inputs/multi_rank.log")
+        .unwrap()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 9 {
+                line.replace("\"guard_count\": 5", "\"guard_count\": 7")
+            } else {
+                line.to_string()
+            };
+            line + "\n"
+        })
+        .collect();
+    let check = |map: &HashMap<PathBuf, String>| {
+        let index = &map[&PathBuf::from("index.html")];
+        assert!(index.contains("compile divergence report</a></strong> found 1\n"));
+        let divergence = &map[&PathBuf::from("compile_divergence.html")];
+        assert!(divergence.contains("<td>[0/0]</td>"));
+        assert!(divergence.contains("5 (ranks 0, 2); 7 (ranks 1)"));
+        assert!(!divergence.contains("<td>[1/0]</td>"));
+        assert!(!divergence.contains("different numbers of compile ids"));
+    };
+
+    let output = tlparse::parse_reader(log.as_bytes(), Default::default()).unwrap();
+    check(&output.into_iter().collect());
+
+    let path = std::env::temp_dir().join("tlparse_test_compile_divergence.log");
+    fs::write(&path, &log).unwrap();
+    let config = tlparse::ParseConfig {
+        all_ranks: true,
+        ..Default::default()
+    };
+    let output = tlparse::parse_path(&path, config);
+    fs::remove_file(&path).unwrap();
+    let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+    check(&map);
+    // Each rank's own report only has the one rank
+    assert!(!map.contains_key(&PathBuf::from("rank_0/compile_divergence.html")));
+
+    // Logs from a single rank have nothing to compare
+    let output = tlparse::parse_path(
+        &PathBuf::from("tests/inputs/simple.log"),
+        Default::default(),
+    )
+    .unwrap();
+    assert!(!output
+        .files
+        .iter()
+        .any(|(p, _)| p == Path::new("compile_divergence.html")));
+}
+
+#[test]
+fn test_json_stack_frames() {
+    let path = Path::new("tests/inputs/comp_metrics.log").to_path_buf();
+    let json = tlparse::parse_path_json(&path, tlparse::ParseConfig::default()).unwrap();
+    let frames: Vec<&serde_json::Value> = json["compile_ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|cid| cid["guards"].as_array().unwrap())
+        .filter_map(|guard| guard["stack"].as_array())
+        .flatten()
+        .collect();
+    assert!(!frames.is_empty());
+    for frame in frames {
+        // The intern id is kept, next to the file name it stands for
+        assert!(frame["filename"].is_u64());
+        let raw = frame["raw_filename"].as_str().unwrap();
+        let simplified = frame["simplified_filename"].as_str().unwrap();
+        assert!(raw.ends_with(simplified));
+    }
+}
+
+#[test]
+fn test_hide_frames() {
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let index = |hide_frames: Vec<String>| {
+        let config = tlparse::ParseConfig {
+            hide_frames,
+            ..Default::default()
+        };
+        let (_, index) = tlparse::parse_path(&path, config)
+            .unwrap()
+            .into_iter()
+            .find(|(p, _)| p == Path::new("index.html"))
+            .unwrap();
+        index
+    };
+    let shown = index(Vec::new());
+    assert!(shown.contains("unittest/main.py"));
+    let hidden = index(vec!["python3\\.11/unittest/".to_string()]);
+    assert!(!hidden.contains("unittest/main.py"));
+    // The compile ids at the bottom of the hidden frames are still in the trie
+    assert!(hidden.contains("<a href='#cid-0-0-0'"));
+    assert!(hidden.contains("test_torchinductor.py"));
+
+    let config = tlparse::ParseConfig {
+        hide_frames: vec!["(".to_string()],
+        ..Default::default()
+    };
+    assert!(tlparse::parse_path(&path, config).is_err());
+}
+
+#[test]
+fn test_max_stack_depth() {
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let index = |max_stack_depth: Option<usize>| {
+        let config = tlparse::ParseConfig {
+            max_stack_depth,
+            ..Default::default()
+        };
+        let (_, index) = tlparse::parse_path(&path, config)
+            .unwrap()
+            .into_iter()
+            .find(|(p, _)| p == Path::new("index.html"))
+            .unwrap();
+        index
+    };
+    assert!(!index(None).contains("more frames"));
+    let limited = index(Some(3));
+    let (_, more) = limited
+        .split_once("<details class='more-frames'><summary>\u{2026} ")
+        .unwrap();
+    let (num_hidden, _) = more.split_once(" more frames").unwrap();
+    assert!(num_hidden.parse::<usize>().unwrap() > 0);
+    // Every frame is still there, some behind the expander, and so are the compile ids
+    assert!(limited.contains("unittest/main.py"));
+    assert!(limited.contains("<a href='#cid-0-0-0'"));
+    // Generous limits change nothing
+    assert_eq!(index(Some(1000)), index(None));
+}
+
+#[test]
+fn test_glog_prefix_after_launcher_prefix() {
+    let log = fs::read_to_string("tests/inputs/simple.log").unwrap();
+    // torchrun --tee adds its own prefix before glog's
+    let prefixed: String = log
+        .lines()
+        .map(|line| {
+            if line.starts_with('\t') {
+                format!("{line}\n")
+            } else {
+                format!("[rank0]:{line}\n")
+            }
+        })
+        .collect();
+    let stats = |log: &str| {
+        let output = tlparse::parse_reader(log.as_bytes(), Default::default()).unwrap();
+        let (_, report) = output
+            .into_iter()
+            .find(|(p, _)| p == Path::new(tlparse::REPORT_METADATA_PATH))
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        report["stats"].clone()
+    };
+    let expected = stats(&log);
+    assert_eq!(stats(&prefixed), expected);
+    assert_eq!(expected["fail_glog"], 0);
+}
+
+#[test]
+fn test_compiles_by_module() {
+    // Frame 1 compiles a helper called from the forward instead of the forward itself
+    let log: String = fs::read_to_string("tests/inputs/dynamic_shapes.log")
+        .unwrap()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = match i {
+                84 => line.replace(
+                    "\"filename\": 4}]",
+                    "\"filename\": 4}, {\"line\": 14, \"name\": \"forward\", \"filename\": 0}]",
+                ),
+                107 => line
+                    .replace("\"co_name\": \"forward\"", "\"co_name\": \"helper\"")
+                    .replace("\"co_firstlineno\": 12", "\"co_firstlineno\": 30"),
+                _ => line.to_string(),
+            };
+            line + "\n"
+        })
+        .collect();
+    let output = tlparse::parse_reader(log.as_bytes(), Default::default()).unwrap();
+    let map: HashMap<PathBuf, String> = output.into_iter().collect();
+    assert!(map[&PathBuf::from("index.html")].contains("1 module(s)</a></strong>"));
+    let modules = &map[&PathBuf::from("modules.html")];
+    assert!(modules.contains("test_dynamic_shapes.py:12</code>"));
+    assert!(modules.contains("<code>forward</code> <code>helper</code>"));
+    assert!(modules.contains("<td>15</td>"));
+    assert!(modules.contains("<a href=\"index.html#cid-1-0-0\">[1/0]</a>"));
+}
+
+#[test]
+fn test_compile_id_backend() {
+    // [1/0] ran inductor, while [0/0] only has a dynamo graph
+    let path = Path::new("tests/inputs/cache_hit_miss.log").to_path_buf();
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, tlparse::ParseConfig::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<li id=\"cid-0-0-0\" data-backend=\"other\"><a id=\"compile--_0_0_0\"></a><a id=\"[0/0]\">[0/0]</a>"));
+    assert!(index.contains("<li id=\"cid-1-0-0\" data-backend=\"inductor\"><a id=\"compile--_1_0_0\"></a><a id=\"[1/0]\">[1/0]</a>"));
+    assert!(index.contains("<span class=\"backend\" title=\"Backend\">inductor</span>"));
+    // Mixed backends can be filtered
+    assert!(index.contains("<option>other</option><option>inductor</option>"));
+    let landing = &map[&PathBuf::from("-_1_0_0/index.html")];
+    assert!(landing.contains("<p>Backend: <code>inductor</code></p>"));
+    let summary: serde_json::Value =
+        serde_json::from_str(&map[&PathBuf::from("-_1_0_0/summary.json")]).unwrap();
+    assert_eq!(summary["backend"], "inductor");
+
+    // A logged backend name wins, and a single backend needs no filter
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log")
+        .unwrap()
+        .replace(
+            "{\"compilation_metrics\": {",
+            "{\"compilation_metrics\": {\"backend_name\": \"my_backend\", ",
+        );
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<li id=\"cid-0-0-1\" data-backend=\"my_backend\"><a id=\"compile--_0_0_1\"></a><a id=\"[0/0_1]\">[0/0_1]</a>"));
+    assert!(!index.contains("filterBackend(this)"));
+
+    // Unknown for a compilation that failed
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, tlparse::ParseConfig::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(map[&PathBuf::from("index.html")]
+        .contains("<li id=\"cid-0-0-0\"><a id=\"compile--_0_0_0\"></a><a id=\"[0/0]\">[0/0]</a>"));
+}
+
+#[test]
+fn test_non_compliant_ops() {
+    // [0/0] of simple.log uses test::baz
+    let path = Path::new("tests/inputs/simple.log").to_path_buf();
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, tlparse::ParseConfig::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let index = &map[&PathBuf::from("index.html")];
+    assert!(index.contains("<a href=\"non_compliant_ops.html\">1 custom op(s)</a>"));
+    let ops = &map[&PathBuf::from("non_compliant_ops.html")];
+    assert!(ops.contains("<td><code>test::baz</code></td>\n        <td>1</td>"));
+    assert!(ops.contains("<a href=\"frame_0.html\">forward ("));
+    assert!(ops.contains("<a href=\"index.html#cid-0-0-0\">[0/0]</a>"));
+
+    // The first compilation also uses test::baz, and every one uses test::qux
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log")
+        .unwrap()
+        .replacen(
+            "\"non_compliant_ops\": []",
+            "\"non_compliant_ops\": [\"test::baz\", \"test::qux\"]",
+            1,
+        )
+        .replace(
+            "\"non_compliant_ops\": []",
+            "\"non_compliant_ops\": [\"test::qux\"]",
+        );
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let ops = &map[&PathBuf::from("non_compliant_ops.html")];
+    // The op in the most graphs comes first
+    let qux = ops.find("<code>test::qux</code>").unwrap();
+    let baz = ops.find("<code>test::baz</code>").unwrap();
+    assert!(qux < baz);
+    assert!(ops.contains("<td><code>test::baz</code></td>\n        <td>1</td>"));
+    // No page for a log without non-compliant ops
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, tlparse::ParseConfig::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(!map.contains_key(&PathBuf::from("non_compliant_ops.html")));
+}
+
+#[test]
+fn test_compressed_logs() {
+    use std::io::Write;
+    let log = fs::read("tests/inputs/comp_metrics.log").unwrap();
+    let expected = tlparse::parse_path_with_stats(
+        &PathBuf::from("tests/inputs/comp_metrics.log"),
+        Default::default(),
+    )
+    .unwrap();
+    let expected: HashMap<PathBuf, String> = expected.output.into_iter().collect();
+
+    let gz = std::env::temp_dir().join("tlparse_test_compressed_logs.log.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&log).unwrap();
+    fs::write(&gz, encoder.finish().unwrap()).unwrap();
+    let zst = std::env::temp_dir().join("tlparse_test_compressed_logs.log.zst");
+    fs::write(&zst, zstd::encode_all(&log[..], 0).unwrap()).unwrap();
+
+    for path in [&gz, &zst] {
+        let output = tlparse::parse_path(path, Default::default());
+        let incremental = tlparse::parse_path_incremental(path, Default::default(), None);
+        fs::remove_file(path).unwrap();
+        let map: HashMap<PathBuf, String> = output.unwrap().into_iter().collect();
+        assert_eq!(map.len(), expected.len());
+        for (path, contents) in &map {
+            // Records when it was parsed
+            if path != Path::new("tlparse_report.json") {
+                assert!(*contents == expected[path], "{} differs", path.display());
+            }
+        }
+        assert!(incremental.is_err());
+    }
+}
+
+#[test]
+fn test_parse_reader_all_ranks() {
+    let path = PathBuf::from("tests/inputs/multi_rank.log");
+    let config = || tlparse::ParseConfig {
+        all_ranks: true,
+        ..Default::default()
+    };
+    let from_path = tlparse::parse_path_with_stats(&path, config()).unwrap();
+    let log = fs::read(&path).unwrap();
+    let from_reader = tlparse::parse_reader_with_stats(&log[..], config()).unwrap();
+    assert_eq!(from_path.output.stats.ok, from_reader.output.stats.ok);
+    let mut from_path: HashMap<PathBuf, String> = from_path.output.into_iter().collect();
+    let mut from_reader: HashMap<PathBuf, String> = from_reader.output.into_iter().collect();
+    assert!(from_reader.contains_key(&PathBuf::from("rank_1/index.html")));
+    // Except for when each report was generated
+    from_path.retain(|p, _| !p.ends_with(tlparse::REPORT_METADATA_PATH));
+    from_reader.retain(|p, _| !p.ends_with(tlparse::REPORT_METADATA_PATH));
+    assert_eq!(from_path, from_reader);
+
+    // Logs that can only be read once can't be parsed in two passes
+    let config = tlparse::ParseConfig {
+        two_pass: true,
+        ..Default::default()
+    };
+    assert!(tlparse::parse_reader(&log[..], config).is_err());
+}
+
+#[test]
+fn test_stack_trie_exports() {
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let map: HashMap<PathBuf, String> = tlparse::parse_path(&path, Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(map[&PathBuf::from("index.html")].contains("<a href=\"stack_trie.folded\">"));
+
+    let folded = &map[&PathBuf::from("stack_trie.folded")];
+    assert_eq!(folded.lines().count(), 3);
+    assert!(folded.lines().all(|line| line.ends_with(" 1")));
+    assert!(folded
+        .lines()
+        .last()
+        .unwrap()
+        .ends_with("test_misc.py:9551 in fn;/data/users/jjwu/a/pytorch/test/dynamo/test_misc.py:9553 in torch_dynamo_resume_in_fn_at_9551 1"));
+    let folded_time = &map[&PathBuf::from("stack_trie_time_us.folded")];
+    let total_us: u64 = folded_time
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(total_us, 23031);
+
+    let speedscope: serde_json::Value =
+        serde_json::from_str(&map[&PathBuf::from("stack_trie.speedscope.json")]).unwrap();
+    let profiles = speedscope["profiles"].as_array().unwrap();
+    assert_eq!(profiles.len(), 2);
+    assert_eq!(profiles[0]["samples"].as_array().unwrap().len(), 3);
+    assert_eq!(profiles[0]["endValue"], 3.0);
+    let frames = speedscope["shared"]["frames"].as_array().unwrap();
+    let innermost = profiles[0]["samples"][2]
+        .as_array()
+        .unwrap()
+        .last()
+        .unwrap();
+    assert_eq!(
+        frames[innermost.as_u64().unwrap() as usize]["name"],
+        "torch_dynamo_resume_in_fn_at_9551"
+    );
+}
+
+#[test]
+fn test_log_listing() {
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let listing = tlparse::LogListing::new(tlparse::parse_entries(&path).unwrap());
+    assert_eq!(listing.compile_ids.len(), 5);
+    let summary = listing.summary();
+    assert!(
+        summary.contains("[0/0_1]: 3 entries, 2.2 KiB\n  dynamo_output_graph      1      268 B\n")
+    );
+    assert!(summary.ends_with("5 compile ids, 12 entries, 6.9 KiB of payloads\n"));
+
+    // Artifacts are listed by name
+    let path = PathBuf::from("tests/inputs/artifacts.log");
+    let listing = tlparse::LogListing::new(tlparse::parse_entries(&path).unwrap());
+    assert!(listing
+        .compile_ids
+        .values()
+        .any(|kinds| kinds.contains_key("fx_graph_cache_hash")));
+}
+
+#[test]
+fn test_query() {
+    let run = |query: &str, log: &str| -> Vec<serde_json::Value> {
+        let query = tlparse::Query::parse(query).unwrap();
+        let path = PathBuf::from(format!("tests/inputs/{log}"));
+        tlparse::parse_entries(&path)
+            .unwrap()
+            .flat_map(|entry| query.run(tlparse::entry_value(&entry)))
+            .collect()
+    };
+    let fail_reasons = run(
+        "compilation_metrics | select(.fail_type) | .fail_reason",
+        "comp_failure.log",
+    );
+    assert_eq!(fail_reasons.len(), 1);
+    assert!(fail_reasons[0].as_str().unwrap().contains("broken backend"));
+
+    assert_eq!(
+        run("compilation_metrics | .guard_count", "comp_metrics.log"),
+        [9, 9, 6]
+    );
+    assert_eq!(
+        run(
+            "compilation_metrics | select(.guard_count > 6) | .guard_count",
+            "comp_metrics.log"
+        ),
+        [9, 9]
+    );
+    // The compile id is flattened into the entry, and [] iterates
+    let names = run(
+        "select(.frame_id == 0) | dynamo_start | .stack[].name",
+        "comp_metrics.log",
+    );
+    assert_eq!(names[0], "<module>");
+    assert!(names.len() > 1);
+
+    for invalid in ["", "a |", "select(.a ~ 1)", ".a[", "select(.a == \"b)"] {
+        assert!(tlparse::Query::parse(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_parse_entries() {
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let entries: Vec<tlparse::ParsedEnvelope> = tlparse::parse_entries(&path).unwrap().collect();
+    assert!(!entries.is_empty());
+    // str entries are only used to resolve file names
+    assert!(entries.iter().all(|e| e.envelope.str.is_none()));
+    assert!(entries.iter().all(|e| !e.payload_md5_mismatch));
+    assert!(entries.windows(2).all(|w| w[0].lineno < w[1].lineno));
+
+    let start = entries
+        .iter()
+        .find(|e| e.envelope.dynamo_start.is_some())
+        .unwrap();
+    let compile_id = start.envelope.compile_id.as_ref().unwrap();
+    assert_eq!(
+        (compile_id.frame_id, compile_id.frame_compile_id),
+        (Some(0), Some(0))
+    );
+    let stack = start
+        .envelope
+        .dynamo_start
+        .as_ref()
+        .unwrap()
+        .stack
+        .as_ref()
+        .unwrap();
+    assert!(stack
+        .iter()
+        .all(|frame| frame.uninterned_filename.is_some()));
+    assert!(start.timestamp_us.is_some());
+
+    let with_payload = entries.iter().find(|e| e.payload.is_some()).unwrap();
+    assert!(!with_payload.payload.as_ref().unwrap().is_empty());
+
+    // Entries serialize like the log's own JSON, e.g., for shipping them elsewhere
+    let json = serde_json::to_value(start).unwrap();
+    assert_eq!(json["frame_id"], 0);
+    assert!(json["dynamo_start"]["stack"][0]["uninterned_filename"].is_string());
+}
+
+#[test]
+fn test_stack_trie_times() {
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let index = |stack_trie_times| {
+        let config = tlparse::ParseConfig {
+            stack_trie_times,
+            ..Default::default()
+        };
+        let mut map: HashMap<PathBuf, String> = tlparse::parse_path(&path, config)
+            .unwrap()
+            .into_iter()
+            .collect();
+        map.remove(&PathBuf::from("index.html")).unwrap()
+    };
+    assert!(!index(false).contains("<span class='trie-time'>"));
+    let index = index(true);
+    // Everything is compiled under the outermost frame
+    assert!(index.contains("<li><span class='trie-time'>23.0ms (100%)</span> "));
+    // Frames count the compilations under them along with their own
+    assert!(index.contains("<span class='trie-time'>23.0ms (100%)</span> <a href='#cid-0-0-0'"));
+    assert!(index.contains("<span class='trie-time'>10.6ms (46%)</span> <a href='#cid-1-0-0'"));
+    assert!(index.contains("<span class='trie-time'>3.4ms (15%)</span> <a href='#cid-2-0-0'"));
+}
+
+#[test]
+fn test_compiled_autograd_badge() {
+    // Frame 1 becomes a compiled autograd compilation
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log")
+        .unwrap()
+        .replace(
+            "\"frame_id\": 1,",
+            "\"compiled_autograd_id\": 0, \"frame_id\": 1,",
+        );
+    let map: HashMap<PathBuf, String> = tlparse::parse_reader(log.as_bytes(), Default::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let index = &map[&PathBuf::from("index.html")];
+    let badge = "<span class='compiled-autograd' title='Compiled autograd (backward)'>CA</span>";
+    assert_eq!(index.matches(badge).count(), 1);
+    assert!(index.contains(&format!("class='status-break'>[!0/1/0]</a> {badge}")));
+    // Both attempts in the list of compile ids
+    let badge =
+        "<span class=\"compiled-autograd\" title=\"Compiled autograd (backward)\">CA</span>";
+    assert_eq!(index.matches(badge).count(), 2);
+    assert!(index.contains(&format!(
+        "<li id=\"cid-ca0-1-0-0\" class=\"abandoned-attempt\"><a id=\"compile-0_1_0_0\"></a><a id=\"[!0/1/0]\">[!0/1/0]</a>\n    {badge}"
+    )));
+}
+
+#[test]
+fn test_progress_sink() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Recorder {
+        size: RefCell<Option<u64>>,
+        bytes_read: RefCell<u64>,
+        warnings: RefCell<Vec<String>>,
+        finished: RefCell<Option<tlparse::Stats>>,
+    }
+
+    impl tlparse::ProgressSink for Recorder {
+        fn start(&self, size: Option<u64>) {
+            *self.size.borrow_mut() = size;
+        }
+        fn bytes_read(&self, bytes: u64) {
+            *self.bytes_read.borrow_mut() = bytes;
+        }
+        fn warn(&self, message: &str) {
+            self.warnings.borrow_mut().push(message.to_string());
+        }
+        fn finish(&self, stats: &tlparse::Stats) {
+            *self.finished.borrow_mut() = Some(stats.clone());
+        }
+    }
+
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let recorder = Rc::new(Recorder::default());
+    let config = tlparse::ParseConfig {
+        progress: Some(recorder.clone()),
+        ..Default::default()
+    };
+    let result = tlparse::parse_path_with_stats(&path, config).unwrap();
+    let size = fs::metadata(&path).unwrap().len();
+    assert_eq!(*recorder.size.borrow(), Some(size));
+    // Newlines aren't counted
+    assert!(*recorder.bytes_read.borrow() > size / 2);
+    assert!(recorder
+        .warnings
+        .borrow()
+        .contains(&"Detected rank: None".to_string()));
+    assert_eq!(
+        recorder.finished.borrow().as_ref().unwrap().ok,
+        result.output.stats.ok
+    );
+}
+
+#[test]
+fn test_structured_output() {
+    let path = Path::new("tests/inputs/comp_failure.log").to_path_buf();
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    assert!(output
+        .files
+        .iter()
+        .any(|(p, _)| p == Path::new("index.html")));
+    assert!(output.stats.ok > 0);
+    // The same failures as failures_and_restarts.html
+    assert!(!output.failures.is_empty());
+    assert!(output.failures.iter().all(|f| f.kind == "failure"));
+    let failed = output
+        .metrics
+        .values()
+        .flatten()
+        .filter(|m| m.fail_type.is_some())
+        .count();
+    assert_eq!(failed, output.failures.len());
+    // Every compilation with metrics has files in the directory
+    for cid in output.metrics.keys() {
+        let files = &output.directory[cid];
+        assert!(files.iter().any(|f| f.url.contains("compilation_metrics")));
+    }
+}
+
+#[test]
+fn test_output_writer() {
+    use std::io::Read;
+
+    #[derive(Default)]
+    struct Recorder {
+        files: HashMap<PathBuf, Vec<u8>>,
+        dirs: Vec<PathBuf>,
+        finished: usize,
+    }
+
+    impl tlparse::OutputWriter for Recorder {
+        fn write_file(&mut self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+            // Directories come first
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                assert!(self.dirs.iter().any(|d| d == dir));
+            }
+            self.files.insert(path.to_path_buf(), contents.to_vec());
+            Ok(())
+        }
+        fn create_dir(&mut self, path: &Path) -> anyhow::Result<()> {
+            self.dirs.push(path.to_path_buf());
+            Ok(())
+        }
+        fn finish(&mut self) -> anyhow::Result<()> {
+            self.finished += 1;
+            Ok(())
+        }
+    }
+
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let expected: HashMap<PathBuf, String> = output.files.iter().cloned().collect();
+    let mut recorder = Recorder::default();
+    tlparse::write_outputs(&mut recorder, output).unwrap();
+    assert_eq!(recorder.finished, 1);
+    assert_eq!(recorder.files.len(), expected.len());
+    for (path, contents) in &expected {
+        assert_eq!(&recorder.files[path], contents.as_bytes());
+    }
+    // Each directory is created once
+    let mut dirs = recorder.dirs.clone();
+    dirs.sort();
+    dirs.dedup();
+    assert_eq!(dirs.len(), recorder.dirs.len());
+    assert!(dirs.contains(&PathBuf::from("-_0_0_0")));
+
+    // Written to disk, gzipped but for index.html
+    let root = std::env::temp_dir().join(format!("tlparse_test_writer_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let mut writer = tlparse::DirectoryWriter::new(root.clone(), true).unwrap();
+    tlparse::write_outputs(&mut writer, expected.clone()).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("index.html")).unwrap(),
+        expected[&PathBuf::from("index.html")]
+    );
+    let mut raw = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(root.join("raw.log.gz")).unwrap())
+        .read_to_string(&mut raw)
+        .unwrap();
+    assert_eq!(raw, expected[&PathBuf::from("raw.log")]);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_gzip_output_cli() {
+    use std::io::Read;
+
+    let log = "tests/inputs/comp_metrics.log";
+    let plain = temp_path("gzip_cli_plain");
+    let gzipped = temp_path("gzip_cli_gzipped");
+    for (out, extra) in [(&plain, None), (&gzipped, Some("--gzip-output"))] {
+        let status = tlparse_cli()
+            .args([log, "--no-browser", "-o"])
+            .arg(out)
+            .args(extra)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    // Every file but the index is gzipped, with the same contents
+    let mut dirs = vec![PathBuf::new()];
+    let mut checked = 0;
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(plain.join(&dir)).unwrap() {
+            let path = dir.join(entry.unwrap().file_name());
+            if plain.join(&path).is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            // The metadata has the time of the run
+            if path == Path::new("index.html") || path == Path::new(tlparse::REPORT_METADATA_PATH) {
+                continue;
+            }
+            let mut gz_path = gzipped.join(&path).into_os_string();
+            gz_path.push(".gz");
+            let mut contents = Vec::new();
+            flate2::read::GzDecoder::new(fs::File::open(gz_path).unwrap())
+                .read_to_end(&mut contents)
+                .unwrap();
+            assert_eq!(contents, fs::read(plain.join(&path)).unwrap());
+            checked += 1;
+        }
+    }
+    assert!(checked > 10);
+    assert!(gzipped.join("index.html").is_file());
+    assert!(!gzipped.join("index.html.gz").exists());
+    fs::remove_dir_all(&plain).unwrap();
+    fs::remove_dir_all(&gzipped).unwrap();
+}
+
+#[test]
+fn test_parse_only_cli() {
+    let out = temp_path("parse_only_cli");
+    let output = tlparse_cli()
+        .args([
+            "tests/inputs/comp_failure.log",
+            "--parse-only",
+            "--no-browser",
+            "-o",
+        ])
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // Only the statistics and a summary of what would be written, and no files
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Stats { ok: 13,"), "{stderr}");
+    assert!(stderr.contains("Would write 20 files"), "{stderr}");
+    assert!(output.stdout.is_empty());
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_fail_on_cli() {
+    // A log with a line that isn't a glog line
+    let log = temp_path("fail_on_cli.log");
+    let mut contents = fs::read_to_string("tests/inputs/comp_metrics.log").unwrap();
+    contents.push_str("not a glog line\n");
+    fs::write(&log, contents).unwrap();
+    let out = temp_path("fail_on_cli");
+    let run = |fail_on: &str| {
+        tlparse_cli()
+            .arg(&log)
+            .args(["--no-browser", "--overwrite", "--fail-on", fail_on, "-o"])
+            .arg(&out)
+            .output()
+            .unwrap()
+    };
+    // The report is written either way, but only selected problems fail the run
+    let output = run("md5,malformed");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Log had problems selected by --fail-on: malformed"));
+    assert!(out.join("index.html").is_file());
+    for fail_on in ["md5,unknown", "none"] {
+        fs::remove_dir_all(&out).unwrap();
+        assert_eq!(run(fail_on).status.code(), Some(0), "{fail_on}");
+        assert!(out.join("index.html").is_file());
+    }
+    fs::remove_dir_all(&out).unwrap();
+    fs::remove_file(&log).unwrap();
+}
+
+#[test]
+fn test_print_url_only_and_headless_cli() {
+    let out = temp_path("print_url_cli");
+    let url = || {
+        format!(
+            "file://{}",
+            fs::canonicalize(out.join("index.html")).unwrap().display()
+        )
+    };
+    // Only the URL is printed, for scripts
+    let output = tlparse_cli()
+        .arg("tests/inputs/simple.log")
+        .args(["--print-url-only", "-o"])
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), url() + "\n");
+
+    // Without a display, there is no browser to open, so say where the report is instead
+    if cfg!(not(any(target_os = "macos", windows))) {
+        let output = tlparse_cli()
+            .arg("tests/inputs/simple.log")
+            .args(["--overwrite", "-o"])
+            .arg(&out)
+            .env_remove("DISPLAY")
+            .env_remove("WAYLAND_DISPLAY")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(&format!("The report is at {}\n", url())));
+        assert!(stderr.contains("pass --serve"));
+    }
+    fs::remove_dir_all(&out).unwrap();
+}
+
+#[test]
+fn test_stdin_cli() {
+    let out = temp_path("stdin_cli");
+    let output = tlparse_cli()
+        .args(["-", "--no-browser", "-o"])
+        .arg(&out)
+        .stdin(fs::File::open("tests/inputs/simple.log").unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<li id=\"cid-0-0-0\""));
+    fs::remove_dir_all(&out).unwrap();
+}
+
+#[test]
+fn test_watch_cli() {
+    use std::io::{BufRead, Write};
+    let dir = temp_path("watch_cli");
+    fs::create_dir(&dir).unwrap();
+    let log_path = dir.join("log.txt");
+    let out = dir.join("out");
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log").unwrap();
+    // Frame 1 starts on line 32
+    let split = log.match_indices('\n').nth(30).unwrap().0 + 1;
+    fs::write(&log_path, &log[..split]).unwrap();
+
+    let mut child = tlparse_cli()
+        .arg(&log_path)
+        .args([
+            "--watch",
+            "--watch-interval-s",
+            "0.1",
+            "--watch-rounds",
+            "2",
+        ])
+        .args(["--no-browser", "-o"])
+        .arg(&out)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = std::io::BufReader::new(child.stderr.take().unwrap()).lines();
+    // The first round is done once it waits for the log to grow
+    assert!(stderr.any(|l| l.unwrap().contains("waiting for")));
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<li id=\"cid-0-0-1\""));
+    assert!(!index.contains("<li id=\"cid-1-0-0\""));
+    // What is appended to the log shows up in the report of the second round, the last
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&log_path)
+        .unwrap()
+        .write_all(&log.as_bytes()[split..])
+        .unwrap();
+    assert!(stderr.any(|l| l.unwrap().contains("stopping")));
+    assert!(child.wait().unwrap().success());
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<li id=\"cid-2-0-0\""));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_partial_line_cli() {
+    use std::io::{BufRead, Write};
+    let dir = temp_path("watch_partial_line_cli");
+    fs::create_dir(&dir).unwrap();
+    let log_path = dir.join("log.txt");
+    let out = dir.join("out");
+    let log = fs::read_to_string("tests/inputs/comp_metrics.log").unwrap();
+    // Frame 1 starts on line 32, and the second round ends partway through line 42
+    let split = log.match_indices('\n').nth(30).unwrap().0 + 1;
+    let complete = log.match_indices('\n').nth(40).unwrap().0 + 1;
+    let partial = complete + 10;
+    fs::write(&log_path, &log[..split]).unwrap();
+    let append = |text: &str| {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .unwrap()
+            .write_all(text.as_bytes())
+            .unwrap()
+    };
+    let offset = || {
+        let checkpoint = fs::read(out.join(".tlparse_checkpoint.json")).unwrap();
+        serde_json::from_slice::<serde_json::Value>(&checkpoint).unwrap()["offset"].clone()
+    };
+
+    let mut child = tlparse_cli()
+        .arg(&log_path)
+        .args([
+            "--watch",
+            "--watch-interval-s",
+            "0.1",
+            "--watch-rounds",
+            "3",
+        ])
+        .args(["--no-browser", "-o"])
+        .arg(&out)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = std::io::BufReader::new(child.stderr.take().unwrap()).lines();
+    assert!(stderr.any(|l| l.unwrap().contains("waiting for")));
+    append(&log[split..partial]);
+    // The line still being written is left for the next round, which resumes from before it
+    assert!(stderr.any(|l| l.unwrap().contains("waiting for")));
+    assert_eq!(offset(), complete);
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<li id=\"cid-1-0-0\""));
+    assert!(!dir.join("out.tlparse_staging").exists());
+    append(&log[partial..]);
+    assert!(stderr.any(|l| l.unwrap().contains("stopping")));
+    assert!(child.wait().unwrap().success());
+    assert_eq!(offset(), log.len());
+
+    // The same report as parsing the whole log at once
+    let full: HashMap<PathBuf, String> = tlparse::parse_path(
+        &PathBuf::from("tests/inputs/comp_metrics.log"),
+        Default::default(),
+    )
+    .unwrap()
+    .into_iter()
+    .collect();
+    for name in ["index.html", "failures_and_restarts.html"] {
+        if let Some(contents) = full.get(Path::new(name)) {
+            assert_eq!(
+                &fs::read_to_string(out.join(name)).unwrap(),
+                contents,
+                "{name}"
+            );
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_config_file() {
+    let dir = temp_path("config_file");
+    fs::create_dir(&dir).unwrap();
+    let log = dir.join("malformed.log");
+    let mut contents = fs::read_to_string("tests/inputs/comp_metrics.log").unwrap();
+    contents.push_str("not a glog line\n");
+    fs::write(&log, contents).unwrap();
+    fs::write(
+        dir.join("tlparse.toml"),
+        r#"
+out = "report"
+print_url_only = true
+fail_on = ["malformed"]
+custom_header_html = '<a href="http://internal/docs">docs</a>'
+rewrite_link = ["http://internal/(.*)", "https://proxy/$1"]
+"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        tlparse_cli()
+            .current_dir(&dir)
+            .arg("malformed.log")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    // tlparse.toml in the current directory is picked up
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("file://"), "{stdout}");
+    assert!(stdout.trim_end().ends_with("report/index.html"), "{stdout}");
+    let index = fs::read_to_string(dir.join("report/index.html")).unwrap();
+    assert!(index.contains(r#"<a href="https://proxy/docs">docs</a>"#));
+    // Options on the command line take precedence
+    let output = run(&["--overwrite", "--fail-on", "none", "-o", "other"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .trim_end()
+        .ends_with("other/index.html"));
+    // Patterns without a replacement are rejected
+    fs::write(
+        dir.join("bad.toml"),
+        r#"rewrite_link = ["http://internal/"]"#,
+    )
+    .unwrap();
+    let output = run(&["--config", "bad.toml"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("needs a replacement for every pattern"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_zip_output() {
+    use std::io::Read;
+
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let output = tlparse::parse_path(&path, tlparse::ParseConfig::default()).unwrap();
+    let expected: HashMap<PathBuf, String> = output.files.iter().cloned().collect();
+    let zip_path =
+        std::env::temp_dir().join(format!("tlparse_test_zip_{}.zip", std::process::id()));
+    let mut writer = tlparse::ZipWriter::new(&zip_path).unwrap();
+    tlparse::write_outputs(&mut writer, output).unwrap();
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    // Every file, and the directories they are in
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    assert!(names.contains(&"-_0_0_0/".to_string()));
+    assert_eq!(
+        names.iter().filter(|n| !n.ends_with('/')).count(),
+        expected.len()
+    );
+    for (path, contents) in &expected {
+        let mut file = archive.by_name(&path.to_string_lossy()).unwrap();
+        let mut read = String::new();
+        file.read_to_string(&mut read).unwrap();
+        assert_eq!(&read, contents);
+    }
+    fs::remove_file(&zip_path).unwrap();
+}
+
+#[test]
+fn test_serve_report() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    let path = PathBuf::from("tests/inputs/comp_metrics.log");
+    let config = tlparse::ParseConfig {
+        json: true,
+        ..Default::default()
+    };
+    let files: HashMap<PathBuf, String> = tlparse::parse_path(&path, config)
+        .unwrap()
+        .into_iter()
+        .collect();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let lookup_files = files.clone();
+    std::thread::spawn(move || {
+        tlparse::serve_report(listener, move |file| {
+            lookup_files.get(file).map(|c| c.as_bytes().to_vec())
+        })
+    });
+
+    let get = |request: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "{request} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    let index = get("GET /");
+    assert!(index.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(index.contains("Content-Type: text/html; charset=utf-8\r\n"));
+    assert!(index.ends_with(&files[Path::new("index.html")]));
+
+    let json = get("GET /parsed.json?x=1");
+    assert!(json.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(json.contains("Content-Type: application/json\r\n"));
+    assert!(json.ends_with(&files[Path::new("parsed.json")]));
+
+    let head = get("HEAD /index.html");
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.ends_with("\r\n\r\n"));
+
+    // A directory without its trailing slash is sent on to it, keeping the query
+    let redirect = get("GET /-_0_0_0?x=1");
+    assert!(redirect.starts_with("HTTP/1.1 301 Moved Permanently\r\n"));
+    assert!(redirect.contains("Location: /-_0_0_0/?x=1\r\n"));
+    assert!(get("GET /-_0_0_0/").ends_with(&files[Path::new("-_0_0_0/index.html")]));
+
+    assert!(get("GET /missing.html").starts_with("HTTP/1.1 404"));
+    assert!(get("GET /missing").starts_with("HTTP/1.1 404"));
+    assert!(get("GET /../Cargo.toml").starts_with("HTTP/1.1 404"));
+    assert!(get("POST /index.html").starts_with("HTTP/1.1 405"));
+}